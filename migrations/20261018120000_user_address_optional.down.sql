@@ -0,0 +1,2 @@
+alter table organization alter column primary_address_id set not null;
+alter table "user" alter column address_id set not null;
@@ -0,0 +1,5 @@
+-- Users and organizations reference their address with `on delete set null`, which can
+-- only work if the column is nullable. This also lets us create users (such as admins
+-- from the command line) before we know where they live.
+alter table "user" alter column address_id drop not null;
+alter table organization alter column primary_address_id drop not null;
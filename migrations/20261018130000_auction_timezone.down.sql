@@ -0,0 +1 @@
+alter table auction drop column timezone;
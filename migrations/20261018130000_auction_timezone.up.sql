@@ -0,0 +1,3 @@
+-- Each auction is run by a sanctuary somewhere, and its dates are entered and shown in that
+-- sanctuary's local time. This is an IANA name such as 'America/Los_Angeles'.
+alter table auction add column timezone text not null default 'UTC';
@@ -0,0 +1,7 @@
+drop index if exists article_search_vector_gin;
+drop index if exists auction_item_search_vector_gin;
+drop trigger if exists set_search_vector on article;
+drop trigger if exists set_search_vector on auction_item;
+drop function if exists set_search_vector();
+alter table article drop column if exists search_vector;
+alter table auction_item drop column if exists search_vector;
@@ -0,0 +1,37 @@
+-- FULL-TEXT SEARCH --
+-- Titles count most, then tags, then descriptions: see `setweight` below.
+-- `search_vector` is kept up to date by a trigger so that nothing writing to these tables
+-- has to remember it.
+alter table auction_item add column search_vector tsvector;
+alter table article add column search_vector tsvector;
+
+create or replace function set_search_vector()
+    returns trigger as
+$$
+begin
+    NEW.search_vector =
+        setweight(to_tsvector('english', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', array_to_string(NEW.tag_list, ' ')), 'B') ||
+        setweight(to_tsvector('english', coalesce(NEW.description, '')), 'C');
+    return NEW;
+end;
+$$ language plpgsql;
+
+create trigger set_search_vector
+    before insert or update of title, description, tag_list
+    on auction_item
+    for each row
+execute function set_search_vector();
+
+create trigger set_search_vector
+    before insert or update of title, description, tag_list
+    on article
+    for each row
+execute function set_search_vector();
+
+-- fill in rows written before the trigger existed
+update auction_item set title = title;
+update article set title = title;
+
+create index auction_item_search_vector_gin on auction_item using gin (search_vector);
+create index article_search_vector_gin on article using gin (search_vector);
@@ -0,0 +1,12 @@
+-- WATCHLIST TABLE --
+-- Items a user has chosen to follow
+create table watchlist
+(
+    user_id          uuid not null references "user" (user_id) on delete cascade,
+    auction_item_id  uuid not null references auction_item (auction_item_id) on delete cascade,
+    created_at       timestamptz not null default now(),
+    primary key (user_id, auction_item_id)
+);
+
+-- This should speed up finding everyone watching an item.
+create index watchlist_auction_item_ids on watchlist using btree (auction_item_id);
@@ -0,0 +1,2 @@
+alter table "user" drop column notify_by_sms;
+alter table "user" drop column notify_by_email;
@@ -0,0 +1,4 @@
+-- How each user wants to hear about bids. Email is on by default because every user has an
+-- email address; texts are opt-in.
+alter table "user" add column notify_by_email boolean not null default true;
+alter table "user" add column notify_by_sms boolean not null default false;
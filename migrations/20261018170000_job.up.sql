@@ -0,0 +1,29 @@
+-- JOB TABLE --
+-- Work to do outside of a request, such as sending notifications. Jobs are inserted in the same
+-- transaction as the change that calls for them, so a job exists if and only if that change
+-- was committed.
+create table job
+(
+    job_id       uuid primary key default uuid_generate_v1mc(),
+    -- the job's `kind` tag is repeated from `payload` for the admin view
+    kind         text not null,
+    payload      jsonb not null,
+    -- `dead` jobs have used up their attempts and will only run again if retried by an admin.
+    status       text not null default 'pending'
+        check (status in ('pending', 'running', 'done', 'dead')),
+    attempts     int not null default 0,
+    max_attempts int not null default 8,
+    -- a pending job runs once this has passed; failed jobs are pushed back here
+    run_at       timestamptz not null default now(),
+    -- when a worker claimed it: a job left `running` for too long has lost its worker
+    locked_at    timestamptz,
+    last_error   text,
+    -- defaults
+    created_at   timestamptz not null default now(),
+    updated_at   timestamptz not null default now()
+);
+
+select trigger_updated_at('job');
+
+-- Workers only ever look for jobs that are ready to run.
+create index job_ready on job using btree (run_at) where status in ('pending', 'running');
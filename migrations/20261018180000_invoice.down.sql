@@ -0,0 +1,2 @@
+drop table invoice_line;
+drop table invoice;
@@ -0,0 +1,40 @@
+-- INVOICE TABLES --
+-- One invoice per winning bidder per auction, grouping everything they won.
+create table invoice
+(
+    invoice_id     uuid primary key default uuid_generate_v1mc(),
+    -- what people see and quote back to us: INV-000042
+    invoice_number bigint generated always as identity unique,
+    auction_id     uuid not null references auction (auction_id) on delete cascade,
+    user_id        uuid not null references "user" (user_id) on delete cascade,
+    -- Drafts are rebuilt whenever winners are recalculated; once issued an invoice is only ever
+    -- paid or voided.
+    status         text not null default 'draft'
+        check (status in ('draft', 'issued', 'paid', 'void')),
+    issued_at      timestamptz,
+    paid_at        timestamptz,
+    voided_at      timestamptz,
+    -- defaults
+    created_at     timestamptz not null default now(),
+    updated_at     timestamptz not null default now()
+);
+
+select trigger_updated_at('invoice');
+
+-- A voided invoice can be replaced, but a bidder never has two live invoices for one auction.
+create unique index invoice_live_per_bidder on invoice (auction_id, user_id) where status <> 'void';
+create index invoice_user_ids on invoice using btree (user_id);
+
+create table invoice_line
+(
+    invoice_line_id     uuid primary key default uuid_generate_v1mc(),
+    invoice_id          uuid not null references invoice (invoice_id) on delete cascade,
+    auction_item_id     uuid references auction_item (auction_item_id) on delete set null,
+    auction_item_bid_id uuid references auction_item_bid (auction_item_bid_id) on delete set null,
+    kind                text not null check (kind in ('item', 'shipping')),
+    description         text not null,
+    amount              decimal(15, 6) not null,
+    created_at          timestamptz not null default now()
+);
+
+create index invoice_line_invoice_ids on invoice_line using btree (invoice_id);
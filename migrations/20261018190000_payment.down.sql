@@ -0,0 +1,9 @@
+drop table if exists ledger_entry;
+drop table if exists ledger_transaction;
+drop table if exists payment_event;
+drop table if exists payment;
+
+alter table invoice drop column if exists refunded_at;
+alter table invoice drop constraint invoice_status_check;
+alter table invoice add constraint invoice_status_check
+    check (status in ('draft', 'issued', 'paid', 'void'));
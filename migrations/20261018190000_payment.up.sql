@@ -0,0 +1,71 @@
+-- PAYMENT TABLES --
+-- Invoices can now be refunded in full once paid.
+alter table invoice drop constraint invoice_status_check;
+alter table invoice add constraint invoice_status_check
+    check (status in ('draft', 'issued', 'paid', 'void', 'refunded'));
+alter table invoice add column refunded_at timestamptz;
+
+-- One payment intent with the payment provider. A bidder has at most one pending intent per
+-- invoice at a time, so an invoice can't be paid twice.
+create table payment
+(
+    payment_id     uuid primary key default uuid_generate_v1mc(),
+    invoice_id     uuid not null references invoice (invoice_id) on delete cascade,
+    -- which gateway took it, and the gateway's id for it
+    provider       text not null,
+    provider_ref   text not null,
+    checkout_url   text not null,
+    amount         decimal(15, 6) not null,
+    -- the provider's processing fee, known once the payment succeeds
+    fee            decimal(15, 6) not null default 0,
+    status         text not null default 'pending'
+        check (status in ('pending', 'succeeded', 'failed', 'expired', 'refunded')),
+    failure_reason text,
+    completed_at   timestamptz,
+    refunded_at    timestamptz,
+    -- defaults
+    created_at     timestamptz not null default now(),
+    updated_at     timestamptz not null default now(),
+    unique (provider, provider_ref)
+);
+
+select trigger_updated_at('payment');
+
+create unique index payment_pending_per_invoice on payment (invoice_id) where status = 'pending';
+
+-- Every callback we've acted on. Providers deliver callbacks at least once, so a repeated
+-- event id is acknowledged and otherwise ignored.
+create table payment_event
+(
+    provider    text not null,
+    event_id    text not null,
+    payment_id  uuid not null references payment (payment_id) on delete cascade,
+    payload     jsonb not null,
+    received_at timestamptz not null default now(),
+    primary key (provider, event_id)
+);
+
+-- Double-entry bookkeeping: the entries of one ledger transaction always sum to zero. Debits
+-- are positive and credits negative.
+create table ledger_transaction
+(
+    ledger_transaction_id uuid primary key default uuid_generate_v1mc(),
+    memo                  text not null,
+    invoice_id            uuid references invoice (invoice_id) on delete set null,
+    payment_id            uuid references payment (payment_id) on delete set null,
+    created_at            timestamptz not null default now()
+);
+
+create index ledger_transaction_invoice_ids on ledger_transaction using btree (invoice_id);
+
+create table ledger_entry
+(
+    ledger_entry_id       uuid primary key default uuid_generate_v1mc(),
+    ledger_transaction_id uuid not null
+        references ledger_transaction (ledger_transaction_id) on delete cascade,
+    account               text not null check (account in ('cash', 'fees', 'proceeds')),
+    amount                decimal(15, 6) not null,
+    invoice_line_id       uuid references invoice_line (invoice_line_id) on delete set null
+);
+
+create index ledger_entry_transaction_ids on ledger_entry using btree (ledger_transaction_id);
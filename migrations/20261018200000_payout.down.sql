@@ -0,0 +1,10 @@
+-- Payouts have nowhere to go once their table is dropped.
+delete from ledger_transaction where payout_id is not null;
+
+alter table ledger_entry drop constraint ledger_entry_account_check;
+alter table ledger_entry add constraint ledger_entry_account_check
+    check (account in ('cash', 'fees', 'proceeds'));
+alter table ledger_entry drop column if exists organization_id;
+alter table ledger_transaction drop column if exists payout_id;
+
+drop table if exists payout;
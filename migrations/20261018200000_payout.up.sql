@@ -0,0 +1,73 @@
+-- PAYOUT TABLES --
+-- Money paid out to the organizations we raise it for.
+create table payout
+(
+    payout_id       uuid primary key default uuid_generate_v1mc(),
+    organization_id uuid not null references organization (organization_id) on delete restrict,
+    amount          decimal(15, 6) not null check (amount > 0),
+    -- a cheque number or bank transfer reference
+    reference       text not null default '',
+    paid_at         timestamptz not null default now(),
+    created_at      timestamptz not null default now()
+);
+
+create index payout_organization_ids on payout using btree (organization_id);
+
+alter table ledger_transaction
+    add column payout_id uuid references payout (payout_id) on delete set null;
+
+-- Everything but cash now belongs to an organization: proceeds are what we've taken for it,
+-- fees what processing cost it, and payouts what we've since paid it.
+alter table ledger_entry
+    add column organization_id uuid references organization (organization_id) on delete set null;
+alter table ledger_entry drop constraint ledger_entry_account_check;
+alter table ledger_entry add constraint ledger_entry_account_check
+    check (account in ('cash', 'fees', 'proceeds', 'payouts'));
+
+create index ledger_entry_organization_ids on ledger_entry using btree (organization_id);
+
+-- An invoice line benefits its item's organization, or failing that the auction's.
+update ledger_entry e
+set organization_id = coalesce(ai.benefits_organization_id, a.benefits_organization_id)
+from invoice_line il
+inner join auction_item ai
+on ai.auction_item_id = il.auction_item_id
+inner join auction a
+on a.auction_id = ai.auction_id
+where il.invoice_line_id = e.invoice_line_id;
+
+-- Split each existing fee between the organizations in its payment, in proportion to their
+-- proceeds. Shares are rounded to the cent and the largest takes up the remainder.
+with share as (
+    select
+        f.ledger_entry_id,
+        f.ledger_transaction_id,
+        f.amount fee,
+        p.organization_id,
+        round(f.amount * sum(p.amount) / sum(sum(p.amount)) over (partition by f.ledger_entry_id), 2)
+            rounded,
+        row_number() over (partition by f.ledger_entry_id order by sum(p.amount)) rank
+    from ledger_entry f
+    inner join ledger_entry p
+    on p.ledger_transaction_id = f.ledger_transaction_id
+    and p.account = 'proceeds'
+    where f.account = 'fees'
+    and f.organization_id is null
+    group by f.ledger_entry_id, f.ledger_transaction_id, f.amount, p.organization_id
+),
+split as (
+    select
+        *,
+        case
+            when rank = 1 then fee - sum(rounded) over (partition by ledger_entry_id) + rounded
+            else rounded
+        end amount
+    from share
+),
+inserted as (
+    insert into ledger_entry (ledger_transaction_id, account, amount, organization_id)
+    select ledger_transaction_id, 'fees', amount, organization_id
+    from split
+)
+delete from ledger_entry
+where ledger_entry_id in (select ledger_entry_id from share);
@@ -0,0 +1,9 @@
+drop index if exists auction_item_delivery_statuses;
+alter table auction_item_delivery drop column if exists status_changed_at;
+alter table auction_item_delivery drop column if exists picked_up_at;
+alter table auction_item_delivery drop column if exists exception_at;
+alter table auction_item_delivery drop column if exists ready_at;
+alter table auction_item_delivery drop column if exists status;
+
+delete from auction_item_delivery where shipping_address is null;
+alter table auction_item_delivery alter column shipping_address set not null;
@@ -0,0 +1,34 @@
+-- DELIVERY STATUS --
+-- A delivery moves through an explicit lifecycle, enforced by `crate::db::deliveries`:
+--
+--   pending_address -> ready_to_ship -> shipped -> delivered
+--                                          |  ^
+--                                          v  |
+--                                        exception
+--
+-- An item collected in person goes straight to picked_up from pending_address or
+-- ready_to_ship. A delivery can now be recorded before we have an address for it.
+alter table auction_item_delivery alter column shipping_address drop not null;
+alter table auction_item_delivery add column status text not null default 'pending_address'
+    check (status in (
+        'pending_address', 'ready_to_ship', 'shipped', 'delivered', 'exception', 'picked_up'
+    ));
+-- when each status was last entered: shipped_datetime and delivered already cover shipping
+-- and delivery
+alter table auction_item_delivery add column ready_at timestamptz;
+alter table auction_item_delivery add column exception_at timestamptz;
+alter table auction_item_delivery add column picked_up_at timestamptz;
+alter table auction_item_delivery add column status_changed_at timestamptz not null default now();
+
+update auction_item_delivery
+set status = case
+        when delivered is not null then 'delivered'
+        when shipping_exception is not null then 'exception'
+        when shipped_datetime is not null then 'shipped'
+        else 'ready_to_ship'
+    end,
+    ready_at = created_at,
+    exception_at = case when shipping_exception is not null then updated_at end,
+    status_changed_at = coalesce(delivered, updated_at);
+
+create index auction_item_delivery_statuses on auction_item_delivery using btree (status);
@@ -0,0 +1,6 @@
+alter table auction_item_delivery drop constraint if exists auction_item_delivery_bid_key;
+alter table auction_item_delivery drop constraint if exists auction_item_delivery_pkey;
+alter table auction_item_delivery drop column if exists delivery_id;
+alter table auction_item_delivery add primary key (auction_item_bid_id, user_id);
+-- the old foreign key to auction_item isn't put back: no real delivery could satisfy it
+alter table auction_item_delivery drop constraint if exists auction_item_delivery_user_id_fkey;
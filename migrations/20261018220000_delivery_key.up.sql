@@ -0,0 +1,27 @@
+-- DELIVERY KEY --
+-- `user_id` referenced auction_item rather than "user", so a delivery for a real bidder
+-- couldn't be saved. Point it at the bidder, and give each delivery its own key. A winning bid
+-- still has at most one delivery.
+-- The broken foreign key may already have been dropped by hand to get deliveries saved.
+alter table auction_item_delivery drop constraint if exists auction_item_delivery_user_id_fkey;
+alter table auction_item_delivery drop constraint auction_item_delivery_pkey;
+
+-- the delivery belongs to whoever made the bid
+update auction_item_delivery d
+set user_id = aib.user_id
+from auction_item_bid aib
+where aib.auction_item_bid_id = d.auction_item_bid_id
+and d.user_id <> aib.user_id;
+
+-- keep the most recently changed delivery for any bid with more than one
+delete from auction_item_delivery d
+using auction_item_delivery newer
+where newer.auction_item_bid_id = d.auction_item_bid_id
+and (newer.updated_at, newer.ctid) > (d.updated_at, d.ctid);
+
+alter table auction_item_delivery add constraint auction_item_delivery_user_id_fkey
+    foreign key (user_id) references "user" (user_id) on delete cascade;
+alter table auction_item_delivery add column delivery_id uuid not null default uuid_generate_v1mc();
+alter table auction_item_delivery add primary key (delivery_id);
+alter table auction_item_delivery add constraint auction_item_delivery_bid_key
+    unique (auction_item_bid_id);
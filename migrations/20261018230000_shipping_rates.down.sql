@@ -0,0 +1,3 @@
+alter table auction_item_delivery drop column if exists local_pickup;
+alter table auction_item drop column if exists size_class;
+alter table auction_item drop column if exists weight_class;
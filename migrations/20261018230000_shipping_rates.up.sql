@@ -0,0 +1,10 @@
+-- SHIPPING RATES --
+-- Shipping fees are worked out by `crate::shipping` from how far an item travels and how
+-- heavy and big it is, so each item is given a weight class and a size class.
+alter table auction_item add column weight_class text not null default 'medium'
+    check (weight_class in ('light', 'medium', 'heavy', 'freight'));
+alter table auction_item add column size_class text not null default 'medium'
+    check (size_class in ('small', 'medium', 'large', 'oversized'));
+
+-- A winner may collect their item instead, which costs nothing and needs no address.
+alter table auction_item_delivery add column local_pickup boolean not null default false;
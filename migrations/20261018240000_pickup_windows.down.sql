@@ -0,0 +1,2 @@
+alter table auction_item_delivery drop column if exists pickup_window_id;
+drop table pickup_window;
@@ -0,0 +1,30 @@
+-- PICKUP WINDOWS --
+-- Times when winners can collect their items in person, set up per auction. Each window is
+-- one slot at one place, and takes at most `capacity` bookings.
+create table pickup_window
+(
+    pickup_window_id uuid primary key default uuid_generate_v1mc(),
+    auction_id       uuid not null references auction (auction_id) on delete cascade,
+    -- where to collect from, usually the sanctuary
+    address_id       uuid not null references address (address_id),
+    starts_at        timestamptz not null,
+    ends_at          timestamptz not null,
+    capacity         integer not null check (capacity > 0),
+    -- defaults
+    created_at       timestamptz not null default now(),
+    updated_at       timestamptz not null default now(),
+    check (ends_at > starts_at)
+);
+
+select trigger_updated_at('pickup_window');
+
+create index pickup_window_starts on pickup_window using btree (auction_id, starts_at);
+
+-- A pickup is booked by pointing its delivery at a window. A window can't be removed while
+-- anyone is booked into it.
+alter table auction_item_delivery add column pickup_window_id uuid
+    constraint auction_item_delivery_pickup_window_id_fkey
+    references pickup_window (pickup_window_id);
+
+create index auction_item_delivery_pickup_windows
+    on auction_item_delivery using btree (pickup_window_id);
@@ -0,0 +1,17 @@
+-- ORGANIZATION MEMBERS --
+-- Users who look after an organization: they see its items, proceeds and deliveries in the
+-- organization portal. Managers can also edit its profile.
+create table organization_member
+(
+    organization_id uuid not null references organization (organization_id) on delete cascade,
+    user_id         uuid not null references "user" (user_id) on delete cascade,
+    role            text not null default 'member' check (role in ('manager', 'member')),
+    -- defaults
+    created_at      timestamptz not null default now(),
+    updated_at      timestamptz not null default now(),
+    primary key (organization_id, user_id)
+);
+
+select trigger_updated_at('organization_member');
+
+create index organization_member_user_ids on organization_member using btree (user_id);
@@ -0,0 +1,7 @@
+drop table item_donation;
+-- items which never made it into an auction have nowhere to go
+delete from auction_item where auction_id is null;
+drop index auction_item_pending;
+alter table auction_item drop constraint auction_item_approved_in_auction;
+alter table auction_item drop column approval_status;
+alter table auction_item alter column auction_id set not null;
@@ -0,0 +1,29 @@
+-- ITEM DONATIONS --
+-- Items offered through the public donation form start out as draft auction items, pending
+-- until an admin approves them into an auction. Only approved items are in an auction, and
+-- every item made before now was approved.
+alter table auction_item alter column auction_id drop not null;
+alter table auction_item add column approval_status text not null default 'approved'
+    check (approval_status in ('pending', 'approved', 'declined'));
+alter table auction_item add constraint auction_item_approved_in_auction
+    check ((approval_status = 'approved') = (auction_id is not null));
+
+create index auction_item_pending on auction_item using btree (created_at)
+    where approval_status = 'pending';
+
+-- Who offered a donated item, so we can tell them how it's going.
+create table item_donation
+(
+    auction_item_id uuid primary key references auction_item (auction_item_id) on delete cascade,
+    donor_name      text not null,
+    donor_email     text collate "case_insensitive" not null,
+    donor_phone     text,
+    -- set when an admin approves or declines the item, with the reason for declining it
+    decided_at      timestamptz,
+    decline_reason  text,
+    -- defaults
+    created_at      timestamptz not null default now(),
+    updated_at      timestamptz not null default now()
+);
+
+select trigger_updated_at('item_donation');
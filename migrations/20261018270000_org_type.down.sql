@@ -0,0 +1,15 @@
+drop trigger check_beneficiary_becomes_business on organization;
+drop function check_beneficiary_becomes_business();
+drop trigger check_beneficiary_type on auction_item;
+drop trigger check_beneficiary_type on auction;
+drop function check_beneficiary_type();
+
+alter table organization drop constraint organization_org_type;
+
+update organization
+set org_type = case org_type
+    when 'business' then 'Business'
+    when 'farm_animal_sanctuary' then 'FarmAnimalSanctuary'
+    when 'non_profit' then 'NonProfit'
+    else org_type
+end;
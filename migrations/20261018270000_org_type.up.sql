@@ -0,0 +1,74 @@
+-- ORGANIZATION TYPES --
+-- `org_type` holds the snake_case name of `OrgType`. Rows written with the variant names
+-- are renamed to match, and anything else is rejected from now on.
+update organization
+set org_type = case org_type
+    when 'Business' then 'business'
+    when 'FarmAnimalSanctuary' then 'farm_animal_sanctuary'
+    when 'NonProfit' then 'non_profit'
+    else org_type
+end;
+
+alter table organization
+    add constraint organization_org_type
+        check (org_type in ('business', 'farm_animal_sanctuary', 'non_profit'));
+
+-- Proceeds only go to non-profits and sanctuaries: a business can donate items, but can't be
+-- an auction's or an item's beneficiary. The error names a constraint, as a check would, so
+-- callers can pick it out.
+create or replace function check_beneficiary_type()
+    returns trigger as
+$$
+begin
+    if exists (
+        select 1
+        from organization
+        where organization_id = NEW.benefits_organization_id
+        and org_type = 'business'
+    ) then
+        raise exception 'a business can''t be the beneficiary of %', TG_TABLE_NAME
+            using errcode = 'check_violation',
+                constraint = TG_TABLE_NAME || '_beneficiary_type';
+    end if;
+    return NEW;
+end;
+$$ language plpgsql;
+
+create trigger check_beneficiary_type
+    before insert or update of benefits_organization_id
+    on auction
+    for each row
+    when (NEW.benefits_organization_id is not null)
+execute function check_beneficiary_type();
+
+create trigger check_beneficiary_type
+    before insert or update of benefits_organization_id
+    on auction_item
+    for each row
+    when (NEW.benefits_organization_id is not null)
+execute function check_beneficiary_type();
+
+-- and an organization which benefits from anything can't become a business
+create or replace function check_beneficiary_becomes_business()
+    returns trigger as
+$$
+begin
+    if exists (
+        select 1 from auction where benefits_organization_id = NEW.organization_id
+        union all
+        select 1 from auction_item where benefits_organization_id = NEW.organization_id
+    ) then
+        raise exception 'organization % is a beneficiary, so can''t be a business', NEW.organization_id
+            using errcode = 'check_violation',
+                constraint = 'organization_beneficiary_type';
+    end if;
+    return NEW;
+end;
+$$ language plpgsql;
+
+create trigger check_beneficiary_becomes_business
+    before update of org_type
+    on organization
+    for each row
+    when (NEW.org_type = 'business' and OLD.org_type <> 'business')
+execute function check_beneficiary_becomes_business();
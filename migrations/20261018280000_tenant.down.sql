@@ -0,0 +1,36 @@
+create or replace function check_beneficiary_type()
+    returns trigger as
+$$
+begin
+    if exists (
+        select 1
+        from organization
+        where organization_id = NEW.benefits_organization_id
+        and org_type = 'business'
+    ) then
+        raise exception 'a business can''t be the beneficiary of %', TG_TABLE_NAME
+            using errcode = 'check_violation',
+                constraint = TG_TABLE_NAME || '_beneficiary_type';
+    end if;
+    return NEW;
+end;
+$$ language plpgsql;
+
+alter table "user" add column role text default 'member';
+update "user" u
+set role = tu.role
+from tenant_user tu
+where tu.user_id = u.user_id
+and tu.tenant_id = default_tenant_id();
+drop table tenant_user;
+
+alter table address drop column tenant_id;
+alter table organization drop column tenant_id;
+alter table article
+    drop column tenant_id,
+    add constraint article_slug_key unique (slug);
+alter table auction_item drop column tenant_id;
+alter table auction drop column tenant_id;
+
+drop function default_tenant_id();
+drop table tenant;
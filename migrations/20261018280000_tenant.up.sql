@@ -0,0 +1,104 @@
+-- TENANTS --
+-- One deployment can host auctions for several partner sanctuaries. Each is a tenant, found
+-- by the host a request was made to, or by a `/t/<slug>` prefix on its path. Auctions,
+-- items, organizations, addresses and articles belong to one tenant. User accounts are
+-- shared, but a user's role is per tenant.
+create table tenant
+(
+    tenant_id   uuid primary key default uuid_generate_v1mc(),
+    -- in the path prefix, and naming the tenant's `templates/tenants/` and `static/tenants/`
+    slug        text not null unique check (slug ~ '^[a-z0-9][a-z0-9-]*$'),
+    name        text not null,
+    host        text collate "case_insensitive" unique,
+    -- requests for any other host go to the default tenant
+    is_default  boolean not null default false,
+    -- defaults
+    created_at  timestamptz not null default now(),
+    updated_at  timestamptz not null default now()
+);
+select trigger_updated_at('tenant');
+create unique index tenant_one_default on tenant (is_default) where is_default;
+
+-- everything so far belongs to the original site
+insert into tenant (slug, name, is_default)
+values ('hooksaurus', 'Hooksaurus Auctions', true);
+
+-- Rows written without a tenant go to the default one, so a single-tenant deployment
+-- needn't know about tenants at all.
+create or replace function default_tenant_id()
+    returns uuid as
+$$
+    select tenant_id from tenant where is_default;
+$$ language sql stable;
+
+alter table auction
+    add column tenant_id uuid not null default default_tenant_id() references tenant (tenant_id),
+    add constraint auction_tenant_key unique (auction_id, tenant_id);
+create index auction_tenant_id on auction (tenant_id);
+
+-- an item is in its auction's tenant, and a pending donation with no auction yet still has one
+alter table auction_item
+    add column tenant_id uuid not null default default_tenant_id() references tenant (tenant_id),
+    add constraint auction_item_auction_tenant_fkey
+        foreign key (auction_id, tenant_id) references auction (auction_id, tenant_id);
+create index auction_item_tenant_id on auction_item (tenant_id);
+
+-- two sanctuaries can each have an article called "thank-you"
+alter table article
+    add column tenant_id uuid not null default default_tenant_id() references tenant (tenant_id),
+    add constraint article_auction_tenant_fkey
+        foreign key (auction_id, tenant_id) references auction (auction_id, tenant_id),
+    drop constraint article_slug_key,
+    add constraint article_tenant_slug_key unique (tenant_id, slug);
+
+alter table organization
+    add column tenant_id uuid not null default default_tenant_id() references tenant (tenant_id);
+create index organization_tenant_id on organization (tenant_id);
+
+alter table address
+    add column tenant_id uuid not null default default_tenant_id() references tenant (tenant_id);
+create index address_tenant_id on address (tenant_id);
+
+-- A user's role in each tenant they've used, which replaces `"user".role`.
+create table tenant_user
+(
+    tenant_id   uuid not null references tenant (tenant_id) on delete cascade,
+    user_id     uuid not null references "user" (user_id) on delete cascade,
+    role        text not null default 'member' check (role in ('member', 'admin')),
+    -- defaults
+    created_at  timestamptz not null default now(),
+    updated_at  timestamptz not null default now(),
+    primary key (tenant_id, user_id)
+);
+select trigger_updated_at('tenant_user');
+create index tenant_user_user_id on tenant_user (user_id);
+
+insert into tenant_user (tenant_id, user_id, role)
+select default_tenant_id(), user_id, case when role = 'admin' then 'admin' else 'member' end
+from "user";
+
+alter table "user" drop column role;
+
+-- Beneficiaries also have to be in the same tenant as what they benefit from.
+create or replace function check_beneficiary_type()
+    returns trigger as
+$$
+declare
+    beneficiary organization;
+begin
+    select * into beneficiary
+    from organization
+    where organization_id = NEW.benefits_organization_id;
+    if beneficiary.org_type = 'business' then
+        raise exception 'a business can''t be the beneficiary of %', TG_TABLE_NAME
+            using errcode = 'check_violation',
+                constraint = TG_TABLE_NAME || '_beneficiary_type';
+    end if;
+    if beneficiary.tenant_id <> NEW.tenant_id then
+        raise exception 'the beneficiary of % belongs to another tenant', TG_TABLE_NAME
+            using errcode = 'check_violation',
+                constraint = TG_TABLE_NAME || '_beneficiary_tenant';
+    end if;
+    return NEW;
+end;
+$$ language plpgsql;
@@ -0,0 +1,5 @@
+drop index article_tenant_published_idx;
+
+alter table article
+    alter column featured_image_filepath drop default,
+    drop column published_at;
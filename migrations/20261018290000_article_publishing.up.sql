@@ -0,0 +1,10 @@
+-- ARTICLE PUBLISHING --
+-- Articles are news and updates written in markdown. A draft has no `published_at` and is
+-- only seen by admins. Articles written before now were already public, so they stay that way.
+alter table article
+    add column published_at timestamptz,
+    alter column featured_image_filepath set default '';
+
+update article set published_at = created_at;
+
+create index article_tenant_published_idx on article (tenant_id, published_at desc);
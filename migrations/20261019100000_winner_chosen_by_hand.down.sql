@@ -0,0 +1 @@
+alter table auction_item_bid drop column winner_chosen_by_hand;
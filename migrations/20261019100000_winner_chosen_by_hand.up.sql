@@ -0,0 +1,5 @@
+-- WINNERS CHOSEN BY HAND --
+-- An admin can give an item to a bidder other than the high bidder, e.g. the runner-up when the
+-- winner backs out. Calculating winners again leaves an item with a hand-chosen winner alone.
+alter table auction_item_bid
+    add column winner_chosen_by_hand boolean not null default false;
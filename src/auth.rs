@@ -0,0 +1,40 @@
+use anyhow::Context;
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, PasswordHash};
+
+use crate::error::{Error, Result};
+
+/// Hash a password with Argon2 using a freshly-generated salt.
+///
+/// Argon2 is deliberately slow, so the work happens on the blocking thread pool
+/// instead of stalling the async executor.
+pub async fn hash_password(password: String) -> Result<String> {
+    tokio::task::spawn_blocking(move || -> Result<String> {
+        let salt = SaltString::generate(rand::thread_rng());
+        Ok(
+            PasswordHash::generate(Argon2::default(), password.as_bytes(), salt.as_str())
+                .map_err(|e| anyhow::anyhow!("failed to generate password hash: {}", e))?
+                .to_string(),
+        )
+    })
+    .await
+    .context("panic in generating password hash")?
+}
+
+/// Check `password` against a hash produced by `hash_password()`.
+///
+/// Returns `Error::Unauthorized` if the password does not match.
+pub async fn verify_password(password: String, password_hash: String) -> Result<()> {
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let hash = PasswordHash::new(&password_hash)
+            .map_err(|e| anyhow::anyhow!("invalid password hash: {}", e))?;
+
+        hash.verify_password(&[&Argon2::default()], password)
+            .map_err(|e| match e {
+                argon2::password_hash::Error::Password => Error::Unauthorized,
+                _ => anyhow::anyhow!("failed to verify password hash: {}", e).into(),
+            })
+    })
+    .await
+    .context("panic in verifying password hash")?
+}
@@ -0,0 +1,326 @@
+//! Management subcommands for the `hooksaurus-auctions` binary.
+//!
+//! Apart from `serve`, these all run straight against `DATABASE_URL` so that operators can
+//! do routine maintenance without going through the web UI or writing raw SQL.
+use anyhow::Context;
+use clap::Parser;
+use sqlx::migrate::Migrate;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::auth;
+use crate::config::{Config, DatabaseConfig};
+use crate::db::{self, tables::Table};
+use crate::endpoints;
+use crate::error::Error;
+
+#[derive(clap::Parser)]
+#[clap(about, version)]
+pub struct Cli {
+    /// Defaults to `serve` when no subcommand is given
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+// parsed once, so the size of `Serve` doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Run any pending migrations, then start the web server
+    Serve(Config),
+    /// Apply, revert, or list database migrations
+    Migrate {
+        #[clap(subcommand)]
+        action: MigrateAction,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+    /// Create an admin user, or promote an existing user to admin
+    CreateAdmin {
+        #[clap(long)]
+        email: String,
+        #[clap(long, env = "ADMIN_PASSWORD")]
+        password: String,
+        #[clap(long)]
+        first_name: Option<String>,
+        #[clap(long)]
+        last_name: Option<String>,
+        /// Slug of the tenant to administer, the default tenant if omitted
+        #[clap(long)]
+        tenant: Option<String>,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+    /// Add a tenant, served under `/t/<slug>` and at `--host` if given
+    CreateTenant {
+        #[clap(long)]
+        slug: String,
+        #[clap(long)]
+        name: String,
+        /// Requests for this host, without a port, are served for the tenant
+        #[clap(long)]
+        host: Option<String>,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+    /// Stop bidding on an auction and all of its items now, and queue calculating its winners
+    CloseAuction {
+        auction_id: Uuid,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+    /// Recalculate the winning bid for every item whose bidding has closed
+    RecomputeWinners {
+        /// Only recalculate items in this auction
+        #[clap(long)]
+        auction_id: Option<Uuid>,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+    /// Make a bid the winner of its item, which recalculating winners will then leave alone
+    ChooseWinner {
+        auction_item_bid_id: Uuid,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+    /// Export tables as JSON, to stdout or to one `<table>.json` file per table
+    Export {
+        /// Table to export, by its URL name (e.g. `auction-item`). Exports all tables if omitted.
+        #[clap(long)]
+        table: Option<Table>,
+        /// Directory to write files into
+        #[clap(long)]
+        output: Option<PathBuf>,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+    /// Fill the database with demo data
+    Seed {
+        /// RNG seed: the same seed always produces the same data
+        #[clap(long, default_value = "0")]
+        seed: u64,
+        #[clap(flatten)]
+        db: DatabaseConfig,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum MigrateAction {
+    /// Apply all pending migrations
+    Up,
+    /// Revert the most recently applied migration
+    Down,
+    /// List migrations and whether each has been applied
+    Status,
+}
+
+pub async fn run(cli: Cli) -> anyhow::Result<()> {
+    match cli
+        .command
+        .unwrap_or_else(|| Command::Serve(Config::parse()))
+    {
+        Command::Serve(config) => serve(config).await,
+        Command::Migrate { action, db } => {
+            let db = connect(&db).await?;
+            match action {
+                MigrateAction::Up => migrate_up(&db).await,
+                MigrateAction::Down => migrate_down(&db).await,
+                MigrateAction::Status => migrate_status(&db).await,
+            }
+        }
+        Command::CreateAdmin {
+            email,
+            password,
+            first_name,
+            last_name,
+            tenant,
+            db,
+        } => {
+            let db = connect(&db).await?;
+            let tenant = match tenant {
+                Some(slug) => db::tenants::get_by_slug(&slug, &db)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("no tenant with slug {}", slug))?,
+                None => db::tenants::get_default(&db).await?,
+            };
+            let password_hash = auth::hash_password(password).await?;
+            let user_id = db::users::upsert_admin(
+                &email,
+                &password_hash,
+                first_name.as_deref(),
+                last_name.as_deref(),
+                tenant.tenant_id,
+                &db,
+            )
+            .await?;
+            println!("Admin {} of {} has user_id {}", email, tenant.name, user_id);
+            Ok(())
+        }
+        Command::CreateTenant {
+            slug,
+            name,
+            host,
+            db,
+        } => {
+            let db = connect(&db).await?;
+            let tenant = match db::tenants::create(&slug, &name, host.as_deref(), &db).await {
+                Err(Error::UnprocessableEntity { errors }) => {
+                    anyhow::bail!("can't create tenant: {:?}", errors)
+                }
+                result => result?,
+            };
+            println!("Tenant {} has tenant_id {}", tenant.slug, tenant.tenant_id);
+            Ok(())
+        }
+        Command::CloseAuction { auction_id, db } => {
+            let db = connect(&db).await?;
+            match db::bidding::close_auction(auction_id, &db).await {
+                Err(Error::NotFound) => anyhow::bail!("no auction with id {}", auction_id),
+                result => result?,
+            };
+            println!(
+                "Closed auction {}; winners will be calculated by the job worker",
+                auction_id
+            );
+            Ok(())
+        }
+        Command::RecomputeWinners { auction_id, db } => {
+            let db = connect(&db).await?;
+            let winners = db::bidding::recompute_winners(auction_id, &db).await?;
+            println!("{} winning bids", winners);
+            Ok(())
+        }
+        Command::ChooseWinner {
+            auction_item_bid_id,
+            db,
+        } => {
+            let db = connect(&db).await?;
+            match db::bidding::choose_winner(auction_item_bid_id, &db).await {
+                Err(Error::NotFound) => anyhow::bail!("no bid with id {}", auction_item_bid_id),
+                Err(Error::UnprocessableEntity { errors }) => {
+                    anyhow::bail!("can't choose this bid: {:?}", errors)
+                }
+                result => result?,
+            };
+            println!("Bid {} now wins its item", auction_item_bid_id);
+            Ok(())
+        }
+        Command::Export { table, output, db } => {
+            let db = connect(&db).await?;
+            export(table, output, &db).await
+        }
+        Command::Seed { seed, db } => {
+            let db = connect(&db).await?;
+            let summary = db::seed::seed(seed, &db).await?;
+            println!("Seeded with {}: {:?}", seed, summary);
+            Ok(())
+        }
+    }
+}
+
+async fn connect(config: &DatabaseConfig) -> anyhow::Result<PgPool> {
+    PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&config.database_url)
+        .await
+        .context("could not connect to database_url")
+}
+
+async fn serve(config: Config) -> anyhow::Result<()> {
+    let db = PgPoolOptions::new()
+        .max_connections(50)
+        .connect(&config.database_url)
+        .await
+        .context("could not connect to database_url")?;
+
+    db::MIGRATOR.run(&db).await?;
+
+    endpoints::serve(config, db).await
+}
+
+async fn migrate_up(db: &PgPool) -> anyhow::Result<()> {
+    db::MIGRATOR.run(db).await?;
+    migrate_status(db).await
+}
+
+/// `Migrator` can only run migrations forward, so reverting goes through `Migrate` directly.
+async fn migrate_down(db: &PgPool) -> anyhow::Result<()> {
+    let mut conn = db.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let latest = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .max();
+    let latest = match latest {
+        Some(version) => version,
+        None => {
+            println!("No migrations have been applied");
+            return Ok(());
+        }
+    };
+    let migration = db::MIGRATOR
+        .iter()
+        .find(|m| m.version == latest && m.migration_type.is_down_migration())
+        .with_context(|| format!("no down migration for version {}", latest))?;
+
+    conn.lock().await?;
+    let elapsed = conn.revert(migration).await;
+    conn.unlock().await?;
+    println!(
+        "Applied {}/revert {} ({:?})",
+        migration.version, migration.description, elapsed?
+    );
+    Ok(())
+}
+
+async fn migrate_status(db: &PgPool) -> anyhow::Result<()> {
+    let mut conn = db.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied: HashSet<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect();
+    for migration in db::MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+    {
+        let status = if applied.contains(&migration.version) {
+            "installed"
+        } else {
+            "pending"
+        };
+        println!("{}/{} {}", migration.version, status, migration.description);
+    }
+    Ok(())
+}
+
+async fn export(table: Option<Table>, output: Option<PathBuf>, db: &PgPool) -> anyhow::Result<()> {
+    let tables = table.map_or_else(Table::get_table_list, |t| vec![t]);
+    let mut exported = BTreeMap::new();
+    for table in tables {
+        let rows = db::export::export_table(&table, db).await?;
+        match output {
+            Some(ref dir) => {
+                let path = dir.join(format!("{}.json", table.to_url_name()));
+                tokio::fs::write(&path, serde_json::to_vec_pretty(&rows)?)
+                    .await
+                    .with_context(|| format!("could not write {}", path.display()))?;
+                println!("Wrote {}", path.display());
+            }
+            None => {
+                exported.insert(table.to_url_name().to_string(), rows);
+            }
+        }
+    }
+    if output.is_none() {
+        println!("{}", serde_json::to_string_pretty(&exported)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,205 @@
+//! Writing and publishing news articles.
+//!
+//! An article starts out as a draft, which only admins see, until it's published. Its slug is
+//! its address under `/articles/`, unique within the tenant: one made from the title gets a
+//! number on the end when another article already has it, while one typed in by an admin has
+//! to be free already.
+use std::collections::HashSet;
+
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tables::article::{slugify, Article, ArticleFromForm};
+use crate::error::{Error, Result};
+use crate::ResultExt;
+
+fn validate(article: &ArticleFromForm) -> Result<()> {
+    let mut errors = vec![];
+    if article.title.trim().is_empty() {
+        errors.push(("title", "give the article a title"));
+    }
+    if article.body.trim().is_empty() {
+        errors.push(("body", "the article needs something to say"));
+    }
+    if article
+        .slug
+        .as_deref()
+        .is_some_and(|slug| slugify(slug).is_empty())
+    {
+        errors.push(("slug", "use some letters or numbers in the slug"));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::unprocessable_entity(errors))
+    }
+}
+
+/// Constraint violations, as the form fields they're about.
+fn save_errors<T>(e: sqlx::Error) -> Result<T> {
+    Err(e)
+        .on_constraint("article_tenant_slug_key", |_| {
+            Error::unprocessable_entity([("slug", "another article has this slug")])
+        })
+        .on_constraint("article_auction_id_fkey", |_| {
+            Error::unprocessable_entity([("auction_id", "no such auction")])
+        })
+        .on_constraint("article_auction_tenant_fkey", |_| {
+            Error::unprocessable_entity([("auction_id", "no such auction")])
+        })
+}
+
+/// Save a new draft by `user_id`.
+#[instrument(skip(db))]
+pub async fn create(
+    article: &ArticleFromForm,
+    user_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Article> {
+    validate(article)?;
+    // two drafts with the same title at once can still collide, which is reported like any
+    // other taken slug
+    let slug = match &article.slug {
+        Some(slug) => slugify(slug),
+        None => free_slug(&article.title, tenant_id, db).await?,
+    };
+    sqlx::query_as!(
+        Article,
+        r#"
+            insert into article (
+                auction_id, user_id, slug, title, description, body, tag_list,
+                featured_image_filepath, tenant_id, etag
+            )
+            values ($1, $2, $3, $4, $5, $6, $7, $8, $9, uuid_generate_v1mc())
+            returning article_id, auction_id, user_id, slug, title, description, body, tag_list,
+                featured_image_filepath, published_at, created_at, updated_at
+        "#,
+        article.auction_id,
+        user_id,
+        slug,
+        article.title.trim(),
+        article.description.trim(),
+        article.body,
+        &article.tag_list(),
+        article.featured_image_filepath.trim(),
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .or_else(save_errors)
+}
+
+/// Change an article, keeping its slug unless a new one is given. Publishing is separate,
+/// see `publish`.
+#[instrument(skip(db))]
+pub async fn update(
+    article_id: Uuid,
+    article: &ArticleFromForm,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<Article>> {
+    validate(article)?;
+    sqlx::query_as!(
+        Article,
+        r#"
+            update article
+            set auction_id = $3,
+                slug = coalesce($4, slug),
+                title = $5,
+                description = $6,
+                body = $7,
+                tag_list = $8,
+                featured_image_filepath = $9
+            where article_id = $1
+            and tenant_id = $2
+            returning article_id, auction_id, user_id, slug, title, description, body, tag_list,
+                featured_image_filepath, published_at, created_at, updated_at
+        "#,
+        article_id,
+        tenant_id,
+        article.auction_id,
+        article.slug.as_deref().map(slugify),
+        article.title.trim(),
+        article.description.trim(),
+        article.body,
+        &article.tag_list(),
+        article.featured_image_filepath.trim()
+    )
+    .fetch_optional(db)
+    .await
+    .or_else(save_errors)
+}
+
+/// Publish a draft, or take a published article back to being a draft. Publishing again
+/// doesn't change when it was first published.
+#[instrument(skip(db))]
+pub async fn publish(
+    article_id: Uuid,
+    published: bool,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<Article>> {
+    sqlx::query_as!(
+        Article,
+        r#"
+            update article
+            set published_at = case when $3 then coalesce(published_at, now()) end
+            where article_id = $1
+            and tenant_id = $2
+            returning article_id, auction_id, user_id, slug, title, description, body, tag_list,
+                featured_image_filepath, published_at, created_at, updated_at
+        "#,
+        article_id,
+        tenant_id,
+        published
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// A slug made from `title` which no other article in the tenant has, like `spring-fling`,
+/// or else `spring-fling-2`, `spring-fling-3` and so on.
+async fn free_slug(title: &str, tenant_id: Uuid, db: &PgPool) -> Result<String> {
+    let base = match slugify(title) {
+        slug if slug.is_empty() => "article".to_string(),
+        slug => slug,
+    };
+    let taken: HashSet<String> = sqlx::query_scalar!(
+        r#"
+            select slug
+            from article
+            where tenant_id = $1
+            and (slug = $2 or starts_with(slug, $2 || '-'))
+        "#,
+        tenant_id,
+        base
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .collect();
+    Ok(next_free_slug(&base, &taken))
+}
+
+fn next_free_slug(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|slug| !taken.contains(slug))
+        .unwrap()
+}
+
+#[test]
+fn test_next_free_slug() {
+    let taken: HashSet<String> = ["open-house", "open-house-2", "open-house-party"]
+        .iter()
+        .map(|slug| slug.to_string())
+        .collect();
+    assert_eq!(next_free_slug("goat-yoga", &taken), "goat-yoga");
+    assert_eq!(next_free_slug("open-house", &taken), "open-house-3");
+}
@@ -0,0 +1,399 @@
+use sqlx::{types::Decimal, PgPool, Postgres, Transaction};
+use std::collections::BTreeSet;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::jobs::{self, Job};
+
+/// Flag the winning bid on every item whose bidding window has closed, which is at its own
+/// `active_end_date` or the auction's `end_date`, whichever comes first.
+///
+/// The winner is the highest bid at or above the item's `minimum_bid_amount`, with ties going
+/// to whoever bid first. Any other bid on a closed item is un-flagged, so running this again
+/// after an admin has edited bids simply recalculates from scratch. Items whose winner was
+/// chosen with `choose_winner` are left alone.
+///
+/// Bidders whose bids have just become winning are sent a `Job::Won`, and the draft invoices of
+/// any auction whose winners changed are rebuilt.
+///
+/// Pass an `auction_id` to limit the work to a single auction. Returns the number of winning bids.
+#[instrument(skip(db))]
+pub async fn recompute_winners(auction_id: Option<Uuid>, db: &PgPool) -> Result<i64> {
+    let mut tx = db.begin().await?;
+    let changed = sqlx::query!(
+        r#"
+            with closed_item as (
+                select ai.auction_item_id, ai.auction_id, ai.minimum_bid_amount
+                from auction_item ai
+                inner join auction a
+                on a.auction_id = ai.auction_id
+                where least(ai.active_end_date, a.end_date) <= now()
+                and ($1::uuid is null or ai.auction_id = $1)
+                and not exists (
+                    select 1
+                    from auction_item_bid chosen
+                    where chosen.auction_item_id = ai.auction_item_id
+                    and chosen.winner_chosen_by_hand
+                )
+            ),
+            best_bid as (
+                select distinct on (aib.auction_item_id) aib.auction_item_bid_id
+                from auction_item_bid aib
+                inner join closed_item ci
+                on ci.auction_item_id = aib.auction_item_id
+                where aib.amount >= ci.minimum_bid_amount
+                order by aib.auction_item_id, aib.amount desc, aib.created_at asc
+            ),
+            changed as (
+                update auction_item_bid aib
+                set is_winning_bid = not aib.is_winning_bid
+                where aib.auction_item_id in (select auction_item_id from closed_item)
+                and aib.is_winning_bid <> (
+                    aib.auction_item_bid_id in (select auction_item_bid_id from best_bid)
+                )
+                returning aib.auction_item_bid_id, aib.auction_item_id, aib.is_winning_bid
+            )
+            select
+                c.auction_item_bid_id "auction_item_bid_id!",
+                c.is_winning_bid "is_winning_bid!",
+                ci.auction_id "auction_id!"
+            from changed c
+            inner join closed_item ci
+            on ci.auction_item_id = c.auction_item_id
+        "#,
+        auction_id
+    )
+    .fetch_all(&mut tx)
+    .await?;
+    for bid in changed.iter().filter(|bid| bid.is_winning_bid) {
+        let won = Job::Won {
+            auction_item_bid_id: bid.auction_item_bid_id,
+        };
+        jobs::enqueue(&won, &mut tx).await?;
+    }
+    let auction_ids: BTreeSet<Uuid> = changed.iter().map(|bid| bid.auction_id).collect();
+    for auction_id in auction_ids {
+        jobs::enqueue(&Job::GenerateInvoices { auction_id }, &mut tx).await?;
+    }
+
+    let winners = sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            where aib.is_winning_bid
+            and ($1::uuid is null or ai.auction_id = $1)
+        "#,
+        auction_id
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    tx.commit().await?;
+    Ok(winners)
+}
+
+/// Queue a winner calculation for when the last of the auction's items closes. Call this
+/// whenever an auction's or its items' end dates change: a job queued for an earlier end date
+/// finds nothing new to do.
+pub async fn schedule_winners(auction_id: Uuid, tx: &mut Transaction<'_, Postgres>) -> Result<()> {
+    let closes_at = sqlx::query_scalar!(
+        r#"
+            select coalesce(max(least(ai.active_end_date, a.end_date)), a.end_date) "closes_at!"
+            from auction a
+            left join auction_item ai
+            on ai.auction_id = a.auction_id
+            where a.auction_id = $1
+            group by a.auction_id
+        "#,
+        auction_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(Error::NotFound)?;
+    jobs::enqueue_at(&Job::RecomputeWinners { auction_id }, closes_at, &mut *tx).await?;
+    Ok(())
+}
+
+/// Give an item to the bidder of `auction_item_bid_id` instead of whoever `recompute_winners`
+/// picked, e.g. the runner-up when the winner backs out. The choice sticks: calculating winners
+/// again leaves the item alone.
+///
+/// Returns `Error::NotFound` if there is no such bid, and `Error::UnprocessableEntity` while
+/// the item is still open for bidding.
+#[instrument(skip(db))]
+pub async fn choose_winner(auction_item_bid_id: Uuid, db: &PgPool) -> Result<()> {
+    let mut tx = db.begin().await?;
+    // locked like place_bid does, so nobody's bid lands in the middle of this
+    let bid = sqlx::query!(
+        r#"
+            select
+                aib.auction_item_id,
+                aib.is_winning_bid,
+                ai.auction_id "auction_id!",
+                least(ai.active_end_date, a.end_date) <= now() "is_closed!"
+            from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            where aib.auction_item_bid_id = $1
+            for update of ai
+        "#,
+        auction_item_bid_id
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or(Error::NotFound)?;
+    if !bid.is_closed {
+        return Err(Error::unprocessable_entity([(
+            "auction_item_bid_id",
+            "bidding on this item hasn't closed yet",
+        )]));
+    }
+    sqlx::query!(
+        r#"
+            update auction_item_bid
+            set is_winning_bid = auction_item_bid_id = $1,
+                winner_chosen_by_hand = auction_item_bid_id = $1
+            where auction_item_id = $2
+        "#,
+        auction_item_bid_id,
+        bid.auction_item_id
+    )
+    .execute(&mut tx)
+    .await?;
+    if !bid.is_winning_bid {
+        let won = Job::Won {
+            auction_item_bid_id,
+        };
+        jobs::enqueue(&won, &mut tx).await?;
+    }
+    let auction_id = bid.auction_id;
+    jobs::enqueue(&Job::GenerateInvoices { auction_id }, &mut tx).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// End an auction now: the auction and any of its items still open stop taking bids,
+/// and a `RecomputeWinners` job is queued for the whole auction.
+///
+/// Returns `Error::NotFound` if there is no such auction.
+#[instrument(skip(db))]
+pub async fn close_auction(auction_id: Uuid, db: &PgPool) -> Result<()> {
+    let mut tx = db.begin().await?;
+    let closed = sqlx::query!(
+        r#"
+            update auction
+            set end_date = least(end_date, now())
+            where auction_id = $1
+        "#,
+        auction_id
+    )
+    .execute(&mut tx)
+    .await?;
+    if closed.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+    sqlx::query!(
+        r#"
+            update auction_item
+            set active_end_date = least(active_end_date, now())
+            where auction_id = $1
+        "#,
+        auction_id
+    )
+    .execute(&mut tx)
+    .await?;
+    schedule_winners(auction_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The smallest raise a proxy bid makes over a competing bid.
+pub const BID_INCREMENT: Decimal = Decimal::ONE;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BidOutcome {
+    Leading,
+    Outbid,
+}
+
+/// Place a bid on an item that is currently open for bidding.
+///
+/// A bid may carry a `max_bid_amount`, in which case it is a proxy bid: whenever someone else
+/// bids below that maximum we automatically bid for its owner, `BID_INCREMENT` above the
+/// competing maximum, or the whole maximum if that is all that's left. A proxy only answers
+/// bids below its maximum, so matching someone's maximum is enough to take the lead.
+///
+/// Whoever loses the high bid to this one is sent a `Job::Outbid`.
+///
+/// Returns whether the bidder holds the high bid once the proxies have had their turn.
+#[instrument(skip(db))]
+pub async fn place_bid(
+    auction_item_id: Uuid,
+    user_id: Uuid,
+    amount: Decimal,
+    max_bid_amount: Option<Decimal>,
+    db: &PgPool,
+) -> Result<BidOutcome> {
+    let mut tx = db.begin().await?;
+    // Locking the item serializes bids on it, so the high bid can't move underneath us.
+    let item = sqlx::query!(
+        r#"
+            select
+                ai.minimum_bid_amount,
+                ai.basket_id,
+                now() >= greatest(ai.active_start_date, a.start_date)
+                    and now() < least(ai.active_end_date, a.end_date) "is_open!"
+            from auction_item ai
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            where ai.auction_item_id = $1
+            for update of ai
+        "#,
+        auction_item_id
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or(Error::NotFound)?;
+
+    if item.basket_id.is_some() {
+        return Err(Error::unprocessable_entity([(
+            "amount",
+            "this item is part of a basket: bid on the basket instead",
+        )]));
+    }
+    if !item.is_open {
+        return Err(Error::unprocessable_entity([(
+            "amount",
+            "bidding is closed for this item",
+        )]));
+    }
+    if amount < item.minimum_bid_amount {
+        return Err(Error::unprocessable_entity([(
+            "amount",
+            format!("bid must be at least ${:.2}", item.minimum_bid_amount),
+        )]));
+    }
+    let max_bid = max_bid_amount.unwrap_or(amount);
+    if max_bid < amount {
+        return Err(Error::unprocessable_entity([(
+            "max_bid_amount",
+            "maximum bid cannot be less than the bid",
+        )]));
+    }
+
+    let leader = high_bid(auction_item_id, &mut tx).await?;
+    if let Some(leader) = &leader {
+        if amount <= leader.amount {
+            return Err(Error::unprocessable_entity([(
+                "amount",
+                format!(
+                    "bid must be more than the current high bid of ${:.2}",
+                    leader.amount
+                ),
+            )]));
+        }
+    }
+
+    insert_bid(auction_item_id, user_id, amount, max_bid_amount, &mut tx).await?;
+
+    let previous_leader = leader.as_ref().map(|leader| leader.user_id);
+    if let Some(leader) = leader.filter(|leader| leader.user_id != user_id) {
+        if leader.max_bid_amount > amount {
+            let answer = leader.max_bid_amount.min(max_bid + BID_INCREMENT);
+            insert_bid(
+                auction_item_id,
+                leader.user_id,
+                answer,
+                Some(leader.max_bid_amount),
+                &mut tx,
+            )
+            .await?;
+            if max_bid > answer {
+                insert_bid(
+                    auction_item_id,
+                    user_id,
+                    max_bid.min(answer + BID_INCREMENT),
+                    max_bid_amount,
+                    &mut tx,
+                )
+                .await?;
+            }
+        }
+    }
+
+    let outcome = match high_bid(auction_item_id, &mut tx).await? {
+        Some(high) if high.user_id == user_id => BidOutcome::Leading,
+        _ => BidOutcome::Outbid,
+    };
+    if let Some(previous_leader) = previous_leader {
+        if previous_leader != user_id && outcome == BidOutcome::Leading {
+            let outbid = Job::Outbid {
+                user_id: previous_leader,
+                auction_item_id,
+            };
+            jobs::enqueue(&outbid, &mut tx).await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(outcome)
+}
+
+struct HighBid {
+    user_id: Uuid,
+    amount: Decimal,
+    max_bid_amount: Decimal,
+}
+
+async fn high_bid(
+    auction_item_id: Uuid,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Option<HighBid>> {
+    sqlx::query_as!(
+        HighBid,
+        r#"
+            select
+                user_id,
+                amount,
+                coalesce(max_bid_amount, amount) "max_bid_amount!"
+            from auction_item_bid
+            where auction_item_id = $1
+            order by amount desc, created_at asc
+            limit 1
+        "#,
+        auction_item_id
+    )
+    .fetch_optional(tx)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+async fn insert_bid(
+    auction_item_id: Uuid,
+    user_id: Uuid,
+    amount: Decimal,
+    max_bid_amount: Option<Decimal>,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<()> {
+    // `now()` is fixed for the whole transaction, but proxy answers need to sort after the bid
+    // they answer.
+    sqlx::query!(
+        r#"
+            insert into auction_item_bid (
+                auction_item_id, user_id, amount, max_bid_amount, created_at, etag
+            )
+            values ($1, $2, $3, $4, clock_timestamp(), uuid_generate_v1mc())
+        "#,
+        auction_item_id,
+        user_id,
+        amount,
+        max_bid_amount
+    )
+    .execute(tx)
+    .await?;
+    Ok(())
+}
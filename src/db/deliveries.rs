@@ -0,0 +1,819 @@
+//! Getting won items to their winners.
+//!
+//! Each winning bid has at most one `auction_item_delivery`, which moves through the statuses
+//! of `DeliveryStatus`. Only the transitions in `DeliveryStatus::can_become` are allowed, each
+//! one records when it happened, and the bidder is told about it by a background job.
+//!
+//! Admins can also add, correct and remove deliveries directly, which leaves the status alone
+//! apart from the one a new delivery starts in.
+//!
+//! Winners say how they want their item themselves, with `choose_delivery`: shipped to an
+//! address, with the fee worked out by a `ShippingRateCalculator`, or picked up for free in
+//! one of the auction's pickup windows, see `crate::db::pickups`.
+//!
+//! Once an item has shipped, its carrier's tracking moves it along too, see `record_tracking`.
+use sqlx::types::Decimal;
+use sqlx::{PgConnection, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::{pickups, tables};
+use crate::error::{Error, Result};
+use crate::jobs::{self, Job};
+use crate::shipping::{Coordinates, Shipment, ShippingRateCalculator, SizeClass, WeightClass};
+use crate::tracking::TrackingUpdate;
+use crate::ResultExt;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Won, but we don't know where to send it yet
+    PendingAddress,
+    ReadyToShip,
+    /// With the carrier
+    Shipped,
+    Delivered,
+    /// The carrier couldn't deliver it
+    Exception,
+    /// Collected in person, so never shipped
+    PickedUp,
+}
+
+impl DeliveryStatus {
+    pub const ALL: [DeliveryStatus; 6] = [
+        DeliveryStatus::PendingAddress,
+        DeliveryStatus::ReadyToShip,
+        DeliveryStatus::Shipped,
+        DeliveryStatus::Delivered,
+        DeliveryStatus::Exception,
+        DeliveryStatus::PickedUp,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::PendingAddress => "pending_address",
+            DeliveryStatus::ReadyToShip => "ready_to_ship",
+            DeliveryStatus::Shipped => "shipped",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Exception => "exception",
+            DeliveryStatus::PickedUp => "picked_up",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|candidate| candidate.as_str() == status)
+    }
+
+    /// An exception is resolved by the carrier delivering after all, by shipping it again, or
+    /// by the item coming back to us to be sent out afresh.
+    pub fn can_become(&self, to: DeliveryStatus) -> bool {
+        use DeliveryStatus::*;
+        matches!(
+            (self, to),
+            (PendingAddress, ReadyToShip)
+                | (PendingAddress, PickedUp)
+                | (ReadyToShip, Shipped)
+                | (ReadyToShip, PickedUp)
+                | (Shipped, Delivered)
+                | (Shipped, Exception)
+                | (Exception, Shipped)
+                | (Exception, ReadyToShip)
+                | (Exception, Delivered)
+        )
+    }
+
+    /// Where a delivery can go from here.
+    pub fn next(&self) -> Vec<DeliveryStatus> {
+        Self::ALL
+            .iter()
+            .copied()
+            .filter(|to| self.can_become(*to))
+            .collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, DeliveryStatus::Delivered | DeliveryStatus::PickedUp)
+    }
+}
+
+/// What a clerk fills in along with a new status. Shipping needs a carrier and tracking number,
+/// an exception needs to say what went wrong, and a pickup who collected the item.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Transition {
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub carrier: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub tracking_number: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub shipping_exception: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub signed_for_by: Option<String>,
+}
+
+impl Transition {
+    fn check(&self, to: DeliveryStatus, has_address: bool) -> Result<()> {
+        let mut errors = vec![];
+        if to == DeliveryStatus::ReadyToShip && !has_address {
+            errors.push(("shipping_address", "add a shipping address first"));
+        }
+        if to == DeliveryStatus::Shipped {
+            if self.carrier.is_none() {
+                errors.push(("carrier", "say which carrier has it"));
+            }
+            if self.tracking_number.is_none() {
+                errors.push(("tracking_number", "add the tracking number"));
+            }
+        }
+        if to == DeliveryStatus::Exception && self.shipping_exception.is_none() {
+            errors.push(("shipping_exception", "say what went wrong"));
+        }
+        if to == DeliveryStatus::PickedUp && self.signed_for_by.is_none() {
+            errors.push(("signed_for_by", "say who collected it"));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::unprocessable_entity(errors))
+        }
+    }
+}
+
+/// Move the delivery for a winning bid on one of the tenant's items to `to`, and queue a
+/// message to the bidder.
+///
+/// Returns `Error::NotFound` for a bid with no delivery or from another tenant, and
+/// `Error::UnprocessableEntity` for a transition which isn't allowed or is missing details.
+#[instrument(skip(db))]
+pub async fn transition(
+    auction_item_bid_id: Uuid,
+    to: DeliveryStatus,
+    details: &Transition,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<()> {
+    let mut tx = db.begin().await?;
+    let delivery = sqlx::query!(
+        r#"
+            -- an item being picked up can be made ready without an address
+            select d.status, (d.shipping_address is not null or d.local_pickup) "has_address!"
+            from auction_item_delivery d
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = d.auction_item_bid_id
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            where d.auction_item_bid_id = $1
+            and ai.tenant_id = $2
+            for update of d
+        "#,
+        auction_item_bid_id,
+        tenant_id
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or(Error::NotFound)?;
+    let from = DeliveryStatus::parse(&delivery.status)
+        .ok_or_else(|| anyhow::anyhow!("unknown delivery status {}", delivery.status))?;
+    if !from.can_become(to) {
+        return Err(Error::unprocessable_entity([(
+            "status",
+            format!(
+                "a delivery can't go from {} to {}",
+                from.as_str().replace('_', " "),
+                to.as_str().replace('_', " ")
+            ),
+        )]));
+    }
+    details.check(to, delivery.has_address)?;
+
+    sqlx::query!(
+        r#"
+            update auction_item_delivery
+            set status = $2,
+                status_changed_at = now(),
+                ready_at = case when $2 = 'ready_to_ship' then now() else ready_at end,
+                shipped_datetime = case when $2 = 'shipped' then now() else shipped_datetime end,
+                delivered = case when $2 = 'delivered' then now() else delivered end,
+                exception_at = case when $2 = 'exception' then now() else exception_at end,
+                picked_up_at = case when $2 = 'picked_up' then now() else picked_up_at end,
+                carrier = coalesce($3, carrier),
+                tracking_number = coalesce($4, tracking_number),
+                -- an exception only stands until the delivery moves on
+                shipping_exception = case when $2 = 'exception' then $5 end,
+                signed_for_by = coalesce($6, signed_for_by)
+            where auction_item_bid_id = $1
+        "#,
+        auction_item_bid_id,
+        to.as_str(),
+        details.carrier,
+        details.tracking_number,
+        details.shipping_exception,
+        details.signed_for_by
+    )
+    .execute(&mut tx)
+    .await?;
+    jobs::enqueue(
+        &Job::DeliveryStatusChanged {
+            auction_item_bid_id,
+            status: to,
+        },
+        &mut tx,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A shipped delivery, for looking up with its carrier.
+#[derive(Debug)]
+pub struct InTransit {
+    pub auction_item_bid_id: Uuid,
+    pub carrier: String,
+    pub tracking_number: String,
+}
+
+/// Deliveries with a carrier which haven't arrived yet, the longest unchanged first.
+#[instrument(skip(db))]
+pub async fn list_in_transit(db: &PgPool) -> Result<Vec<InTransit>> {
+    sqlx::query_as!(
+        InTransit,
+        r#"
+            select auction_item_bid_id, carrier "carrier!", tracking_number "tracking_number!"
+            from auction_item_delivery
+            where status in ('shipped', 'exception')
+            and carrier is not null
+            and tracking_number is not null
+            order by status_changed_at
+        "#
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Record what a carrier says about a delivery in transit. The carrier's own times replace
+/// the ones stamped when a clerk marked it, and a change of status is passed on to the bidder
+/// like any other. Returns the new status when it changed.
+///
+/// Only shipped deliveries and exceptions are tracked: anything else has been dealt with by
+/// hand, and tracking leaves it alone.
+#[instrument(skip(db))]
+pub async fn record_tracking(
+    auction_item_bid_id: Uuid,
+    update: &TrackingUpdate,
+    db: &PgPool,
+) -> Result<Option<DeliveryStatus>> {
+    let mut tx = db.begin().await?;
+    let status = sqlx::query_scalar!(
+        r#"
+            select status
+            from auction_item_delivery
+            where auction_item_bid_id = $1
+            for update
+        "#,
+        auction_item_bid_id
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or(Error::NotFound)?;
+    let from = DeliveryStatus::parse(&status)
+        .ok_or_else(|| anyhow::anyhow!("unknown delivery status {}", status))?;
+    if !matches!(from, DeliveryStatus::Shipped | DeliveryStatus::Exception) {
+        return Ok(None);
+    }
+    let to = update
+        .status
+        .delivery_status()
+        .filter(|to| *to != from && from.can_become(*to));
+    let exception = match to {
+        Some(DeliveryStatus::Exception) => Some(
+            update
+                .exception
+                .clone()
+                .unwrap_or_else(|| "the carrier reported a problem".to_string()),
+        ),
+        _ => None,
+    };
+
+    sqlx::query!(
+        r#"
+            update auction_item_delivery
+            set status = coalesce($2, status),
+                status_changed_at = case when $2 is not null then now() else status_changed_at end,
+                shipped_datetime = coalesce($3, shipped_datetime),
+                delivered = case when $2 = 'delivered' then coalesce($4, now()) else delivered end,
+                exception_at = case when $2 = 'exception' then now() else exception_at end,
+                shipping_exception = case
+                    when $2 = 'exception' then $5
+                    when $2 is not null then null
+                    else shipping_exception
+                end
+            where auction_item_bid_id = $1
+        "#,
+        auction_item_bid_id,
+        to.map(|to| to.as_str()),
+        update.shipped_at,
+        update.delivered_at,
+        exception
+    )
+    .execute(&mut tx)
+    .await?;
+    if let Some(status) = to {
+        jobs::enqueue(
+            &Job::DeliveryStatusChanged {
+                auction_item_bid_id,
+                status,
+            },
+            &mut tx,
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(to)
+}
+
+/// How a winner wants to get their item.
+#[derive(Debug)]
+pub enum DeliveryChoice {
+    Ship(tables::address::AddressId),
+    /// To an address we don't have yet, which is saved along with the choice
+    ShipToNew(tables::address::AddressFromForm),
+    /// In one of the auction's pickup windows
+    Pickup {
+        pickup_window_id: Uuid,
+    },
+}
+
+/// What we need to know about a winning bid to deliver it.
+struct WinningBid {
+    auction_item_bid_id: Uuid,
+    auction_id: Uuid,
+    // a new address for the item is the tenant's
+    tenant_id: Uuid,
+    email: String,
+    weight_class: String,
+    size_class: String,
+    origin_latitude: Option<f64>,
+    origin_longitude: Option<f64>,
+    // on an invoice which has gone out to the bidder
+    invoiced: bool,
+}
+
+/// Items are sent from the organization they benefit: the item's own beneficiary, otherwise
+/// the auction's.
+async fn get_winning_bid(
+    user_id: Uuid,
+    auction_item_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<WinningBid> {
+    sqlx::query_as!(
+        WinningBid,
+        r#"
+            select
+                aib.auction_item_bid_id,
+                ai.auction_id "auction_id!",
+                ai.tenant_id,
+                u.email,
+                ai.weight_class,
+                ai.size_class,
+                origin.latitude "origin_latitude?",
+                origin.longitude "origin_longitude?",
+                exists(
+                    select 1
+                    from invoice_line il
+                    inner join invoice i
+                    on i.invoice_id = il.invoice_id
+                    where il.auction_item_bid_id = aib.auction_item_bid_id
+                    and i.status not in ('draft', 'void')
+                ) "invoiced!"
+            from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            inner join "user" u
+            on u.user_id = aib.user_id
+            left join organization o
+            on o.organization_id = coalesce(
+                ai.benefits_organization_id,
+                a.benefits_organization_id
+            )
+            left join address origin
+            on origin.address_id = o.primary_address_id
+            where aib.auction_item_id = $1
+            and aib.user_id = $2
+            and aib.is_winning_bid
+        "#,
+        auction_item_id,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await?
+    .ok_or(Error::NotFound)
+}
+
+async fn shipment(
+    bid: &WinningBid,
+    choice: &DeliveryChoice,
+    conn: &mut PgConnection,
+) -> Result<Shipment> {
+    let destination = match choice {
+        DeliveryChoice::Ship(address_id) => {
+            let address = sqlx::query!(
+                r#"
+                    select latitude, longitude
+                    from address
+                    where address_id = $1
+                "#,
+                address_id.0
+            )
+            .fetch_optional(conn)
+            .await?
+            .ok_or_else(|| {
+                Error::unprocessable_entity([("shipping_address", "no such address")])
+            })?;
+            Coordinates::new(address.latitude, address.longitude)
+        }
+        DeliveryChoice::ShipToNew(address) => Coordinates::new(
+            address.latitude.as_ref().and_then(|n| n.parse().ok()),
+            address.longitude.as_ref().and_then(|n| n.parse().ok()),
+        ),
+        DeliveryChoice::Pickup { .. } => None,
+    };
+    Ok(Shipment {
+        origin: Coordinates::new(bid.origin_latitude, bid.origin_longitude),
+        destination,
+        weight_class: WeightClass::parse(&bid.weight_class)
+            .ok_or_else(|| anyhow::anyhow!("unknown weight class {}", bid.weight_class))?,
+        size_class: SizeClass::parse(&bid.size_class)
+            .ok_or_else(|| anyhow::anyhow!("unknown size class {}", bid.size_class))?,
+        local_pickup: matches!(choice, DeliveryChoice::Pickup { .. }),
+    })
+}
+
+/// What a winner would pay to get their item `choice`'s way, without choosing it.
+#[instrument(skip(rates, db))]
+pub async fn quote(
+    user_id: Uuid,
+    auction_item_id: Uuid,
+    choice: &DeliveryChoice,
+    rates: &dyn ShippingRateCalculator,
+    db: &PgPool,
+) -> Result<Decimal> {
+    let mut conn = db.acquire().await?;
+    let bid = get_winning_bid(user_id, auction_item_id, &mut conn).await?;
+    let shipment = shipment(&bid, choice, &mut conn).await?;
+    Ok(rates.rate(&shipment).await?)
+}
+
+/// Record how a winner wants their item, with the shipping fee from `rates`, and make it ready
+/// to go. The auction's draft invoices are rebuilt to charge the new fee. Returns the fee.
+///
+/// Returns `Error::NotFound` unless `user_id` has the winning bid on the item. The choice can
+/// be changed until the item is on its way or the bidder has been sent their invoice, and a
+/// pickup window can only be booked while it has room.
+#[instrument(skip(rates, db))]
+pub async fn choose_delivery(
+    user_id: Uuid,
+    auction_item_id: Uuid,
+    choice: &DeliveryChoice,
+    rates: &dyn ShippingRateCalculator,
+    db: &PgPool,
+) -> Result<Decimal> {
+    let mut tx = db.begin().await?;
+    let bid = get_winning_bid(user_id, auction_item_id, &mut tx).await?;
+    let status = sqlx::query_scalar!(
+        r#"
+            select status
+            from auction_item_delivery
+            where auction_item_bid_id = $1
+            for update
+        "#,
+        bid.auction_item_bid_id
+    )
+    .fetch_optional(&mut tx)
+    .await?;
+    if let Some(status) = status {
+        if !matches!(
+            DeliveryStatus::parse(&status),
+            Some(DeliveryStatus::PendingAddress | DeliveryStatus::ReadyToShip)
+        ) {
+            return Err(Error::unprocessable_entity([(
+                "delivery",
+                "this item is already on its way",
+            )]));
+        }
+    }
+    if bid.invoiced {
+        return Err(Error::unprocessable_entity([(
+            "delivery",
+            "your invoice has been sent, so please contact us to change how this item gets to you",
+        )]));
+    }
+    let shipment = shipment(&bid, choice, &mut tx).await?;
+    let fee = rates.rate(&shipment).await?;
+    let (shipping_address, pickup_window_id) = match choice {
+        DeliveryChoice::Ship(address_id) => (Some(address_id.0), None),
+        DeliveryChoice::ShipToNew(address) => (
+            Some(insert_address(address, bid.tenant_id, &mut tx).await?),
+            None,
+        ),
+        DeliveryChoice::Pickup { pickup_window_id } => {
+            pickups::hold_window(
+                *pickup_window_id,
+                bid.auction_id,
+                bid.auction_item_bid_id,
+                &mut tx,
+            )
+            .await?;
+            (None, Some(*pickup_window_id))
+        }
+    };
+
+    sqlx::query!(
+        r#"
+            insert into auction_item_delivery (
+                auction_item_bid_id, user_id, shipping_address, local_pickup, pickup_window_id,
+                shipping_fee, email_contact, status, ready_at, etag
+            )
+            values ($1, $2, $3, $4, $5, $6, $7, 'ready_to_ship', now(), uuid_generate_v1mc())
+            on conflict (auction_item_bid_id) do update
+            set shipping_address = excluded.shipping_address,
+                local_pickup = excluded.local_pickup,
+                pickup_window_id = excluded.pickup_window_id,
+                shipping_fee = excluded.shipping_fee,
+                email_contact = coalesce(auction_item_delivery.email_contact, excluded.email_contact),
+                status = excluded.status,
+                ready_at = case
+                    when auction_item_delivery.status = 'ready_to_ship'
+                    then auction_item_delivery.ready_at
+                    else now()
+                end,
+                status_changed_at = case
+                    when auction_item_delivery.status = 'ready_to_ship'
+                    then auction_item_delivery.status_changed_at
+                    else now()
+                end
+        "#,
+        bid.auction_item_bid_id,
+        user_id,
+        shipping_address,
+        shipment.local_pickup,
+        pickup_window_id,
+        fee,
+        bid.email
+    )
+    .execute(&mut tx)
+    .await?;
+    jobs::enqueue(
+        &Job::GenerateInvoices {
+            auction_id: bid.auction_id,
+        },
+        &mut tx,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(fee)
+}
+
+pub(crate) async fn insert_address(
+    address: &tables::address::AddressFromForm,
+    tenant_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<Uuid> {
+    sqlx::query_scalar!(
+        r#"
+            insert into address (
+                street_address1, street_address2, street_address3,
+                city, state_province_county, postal_code,
+                country_code, latitude, longitude, tenant_id
+            )
+            values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            returning address_id
+        "#,
+        address.street_address1,
+        address.street_address2,
+        address.street_address3,
+        address.city,
+        address.state_province_county,
+        address.postal_code,
+        address.country_code,
+        address
+            .latitude
+            .as_ref()
+            .and_then(|n| n.parse::<f64>().ok()),
+        address
+            .longitude
+            .as_ref()
+            .and_then(|n| n.parse::<f64>().ok()),
+        tenant_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Add the delivery for a winning bid, for the bidder who made it. A delivery with an address
+/// starts out ready to ship, and one without waits for an address.
+#[instrument(skip(db))]
+pub async fn insert_delivery(
+    delivery: &tables::auction::AuctionItemDeliveryFromForm,
+    db: &PgPool,
+) -> Result<tables::auction::AuctionItemDelivery> {
+    let status = match delivery.shipping_address {
+        Some(_) => DeliveryStatus::ReadyToShip,
+        None => DeliveryStatus::PendingAddress,
+    };
+    sqlx::query_as!(
+        tables::auction::AuctionItemDelivery,
+        r#"
+            insert into auction_item_delivery (
+                auction_item_bid_id, user_id, shipping_address, shipping_fee,
+                sms_updates_number, email_contact, signature_name, carrier, tracking_number,
+                status, ready_at, etag
+            )
+            select
+                aib.auction_item_bid_id, aib.user_id, $2, coalesce($3::decimal, 0), $4, $5, $6, $7, $8,
+                $9, case when $9 = 'ready_to_ship' then now() end, uuid_generate_v1mc()
+            from auction_item_bid aib
+            where aib.auction_item_bid_id = $1
+            returning
+                delivery_id "delivery_id: tables::auction::AuctionItemDeliveryId",
+                auction_item_bid_id "auction_item_bid_id: tables::auction::AuctionItemBidId",
+                user_id,
+                shipping_address "shipping_address: tables::address::AddressId",
+                shipping_fee, shipped_datetime, delivered, shipping_exception,
+                sms_updates_number, email_contact, signature_name, signed_for_by,
+                carrier, tracking_number, local_pickup, pickup_window_id, status, ready_at, exception_at, picked_up_at,
+                status_changed_at, created_at, updated_at,
+                etag "etag: tables::Etag"
+        "#,
+        delivery.auction_item_bid_id.0,
+        delivery.shipping_address.as_ref().map(|a| a.0),
+        delivery.shipping_fee,
+        delivery.sms_updates_number,
+        delivery.email_contact,
+        delivery.signature_name,
+        delivery.carrier,
+        delivery.tracking_number,
+        status.as_str()
+    )
+    .fetch_optional(db)
+    .await
+    .on_constraint("auction_item_delivery_bid_key", |_| {
+        Error::unprocessable_entity([("auction_item_bid_id", "this bid already has a delivery")])
+    })
+    .on_constraint("auction_item_delivery_shipping_address_fkey", |_| {
+        Error::unprocessable_entity([("shipping_address", "no such address")])
+    })?
+    .ok_or_else(|| Error::unprocessable_entity([("auction_item_bid_id", "no such bid")]))
+}
+
+#[instrument(skip(db))]
+pub async fn get_delivery(
+    delivery_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<tables::auction::AuctionItemDelivery>> {
+    sqlx::query_as!(
+        tables::auction::AuctionItemDelivery,
+        r#"
+            select
+                delivery_id "delivery_id: tables::auction::AuctionItemDeliveryId",
+                auction_item_bid_id "auction_item_bid_id: tables::auction::AuctionItemBidId",
+                user_id,
+                shipping_address "shipping_address: tables::address::AddressId",
+                shipping_fee, shipped_datetime, delivered, shipping_exception,
+                sms_updates_number, email_contact, signature_name, signed_for_by,
+                carrier, tracking_number, local_pickup, pickup_window_id, status, ready_at, exception_at, picked_up_at,
+                status_changed_at, created_at, updated_at,
+                etag "etag: tables::Etag"
+            from auction_item_delivery
+            where delivery_id = $1
+        "#,
+        delivery_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Correct a delivery's address, fee and contact or carrier details. A delivery stays with its
+/// bid, and only one which is still waiting for an address, or is being picked up, can lose it.
+#[instrument(skip(db))]
+pub async fn update_delivery(
+    delivery_id: Uuid,
+    delivery: &tables::auction::AuctionItemDeliveryFromForm,
+    db: &PgPool,
+) -> Result<Option<tables::auction::AuctionItemDelivery>> {
+    let mut tx = db.begin().await?;
+    let current = sqlx::query!(
+        r#"
+            select status, local_pickup
+            from auction_item_delivery
+            where delivery_id = $1
+            for update
+        "#,
+        delivery_id
+    )
+    .fetch_optional(&mut tx)
+    .await?;
+    let (status, local_pickup) = match current {
+        Some(current) => (current.status, current.local_pickup),
+        None => return Ok(None),
+    };
+    let needs_address = !local_pickup
+        && !matches!(
+            DeliveryStatus::parse(&status),
+            Some(DeliveryStatus::PendingAddress | DeliveryStatus::PickedUp)
+        );
+    if needs_address && delivery.shipping_address.is_none() {
+        return Err(Error::unprocessable_entity([(
+            "shipping_address",
+            format!(
+                "a delivery which is {} needs a shipping address",
+                status.replace('_', " ")
+            ),
+        )]));
+    }
+    let updated = sqlx::query_as!(
+        tables::auction::AuctionItemDelivery,
+        r#"
+            update auction_item_delivery
+            set shipping_address = $2, shipping_fee = coalesce($3::decimal, 0), sms_updates_number = $4,
+                email_contact = $5, signature_name = $6, carrier = $7, tracking_number = $8
+            where delivery_id = $1
+            returning
+                delivery_id "delivery_id: tables::auction::AuctionItemDeliveryId",
+                auction_item_bid_id "auction_item_bid_id: tables::auction::AuctionItemBidId",
+                user_id,
+                shipping_address "shipping_address: tables::address::AddressId",
+                shipping_fee, shipped_datetime, delivered, shipping_exception,
+                sms_updates_number, email_contact, signature_name, signed_for_by,
+                carrier, tracking_number, local_pickup, pickup_window_id, status, ready_at, exception_at, picked_up_at,
+                status_changed_at, created_at, updated_at,
+                etag "etag: tables::Etag"
+        "#,
+        delivery_id,
+        delivery.shipping_address.as_ref().map(|a| a.0),
+        delivery.shipping_fee,
+        delivery.sms_updates_number,
+        delivery.email_contact,
+        delivery.signature_name,
+        delivery.carrier,
+        delivery.tracking_number
+    )
+    .fetch_optional(&mut tx)
+    .await
+    .on_constraint("auction_item_delivery_shipping_address_fkey", |_| {
+        Error::unprocessable_entity([("shipping_address", "no such address")])
+    })?;
+    tx.commit().await?;
+    Ok(updated)
+}
+
+/// Returns whether there was a delivery to delete.
+#[instrument(skip(db))]
+pub async fn delete_delivery(delivery_id: Uuid, db: &PgPool) -> Result<bool> {
+    let deleted = sqlx::query!(
+        r#"
+            delete from auction_item_delivery
+            where delivery_id = $1
+        "#,
+        delivery_id
+    )
+    .execute(db)
+    .await?;
+    Ok(deleted.rows_affected() > 0)
+}
+
+#[test]
+fn test_delivery_transitions() {
+    use DeliveryStatus::*;
+    assert_eq!(PendingAddress.next(), vec![ReadyToShip, PickedUp]);
+    assert_eq!(Shipped.next(), vec![Delivered, Exception]);
+    assert!(Exception.can_become(Shipped));
+    assert!(!ReadyToShip.can_become(Delivered));
+    assert!(Delivered.next().is_empty() && Delivered.is_finished());
+    assert!(PickedUp.next().is_empty() && PickedUp.is_finished());
+    for status in DeliveryStatus::ALL {
+        assert_eq!(DeliveryStatus::parse(status.as_str()), Some(status));
+    }
+}
+
+#[test]
+fn test_transition_details() {
+    let missing = Transition::default();
+    assert!(missing.check(DeliveryStatus::Shipped, true).is_err());
+    assert!(missing.check(DeliveryStatus::Exception, true).is_err());
+    assert!(missing.check(DeliveryStatus::ReadyToShip, false).is_err());
+    assert!(missing.check(DeliveryStatus::PickedUp, true).is_err());
+    assert!(missing.check(DeliveryStatus::Delivered, true).is_ok());
+    let shipped = Transition {
+        carrier: Some("USPS".to_string()),
+        tracking_number: Some("9400100000000000000000".to_string()),
+        ..Transition::default()
+    };
+    assert!(shipped.check(DeliveryStatus::Shipped, true).is_ok());
+}
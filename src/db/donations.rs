@@ -0,0 +1,354 @@
+//! Items offered through the public donation form.
+//!
+//! A donation is a draft `auction_item` with `approval_status` pending, and no auction, along
+//! with an `item_donation` row saying who offered it. It stays out of every auction until an
+//! admin approves it into one with `approve`, which sets its minimum bid and dates, or declines
+//! it with `decline`. The donor is emailed at each step, see `crate::notify`.
+//!
+//! Donations are made to, and decided on by, one tenant: its items, organizations and
+//! auctions are the only ones they see.
+use sqlx::types::Decimal;
+use sqlx::{PgConnection, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tables;
+use crate::db::{bidding, deliveries};
+use crate::error::{Error, Result};
+use crate::jobs::{self, Job};
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DonationStatus {
+    /// Waiting for an admin to look at it
+    Pending,
+    Approved,
+    Declined,
+}
+
+impl DonationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DonationStatus::Pending => "pending",
+            DonationStatus::Approved => "approved",
+            DonationStatus::Declined => "declined",
+        }
+    }
+}
+
+/// What a donor tells us about themselves and their item. The address of a new organization
+/// comes in the same form, but separately, see `submit`.
+#[derive(Debug, serde::Deserialize)]
+pub struct DonationFromForm {
+    #[serde(default)]
+    pub donor_name: String,
+    #[serde(default)]
+    pub donor_email: String,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub donor_phone: Option<String>,
+    // the business or organization giving the item, when it isn't the donor's own
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub organization_name: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub organization_website: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub expected_retail_value: Option<Decimal>,
+    // comma separated
+    #[serde(default)]
+    pub tags: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ApprovalFromForm {
+    pub auction_id: Uuid,
+    pub minimum_bid_amount: Decimal,
+}
+
+/// Save a donation as a draft item, waiting for approval, and let the donor know we have it.
+///
+/// A named organization is matched to an existing one by name, or else created, along with
+/// `address` when one is given. Returns the draft's `auction_item_id`.
+#[instrument(skip(db))]
+pub async fn submit(
+    donation: &DonationFromForm,
+    address: Option<&tables::address::AddressFromForm>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Uuid> {
+    let mut errors = vec![];
+    if donation.donor_name.trim().is_empty() {
+        errors.push(("donor_name", "tell us who you are"));
+    }
+    if !donation.donor_email.contains('@') {
+        errors.push(("donor_email", "enter an email address we can reach you at"));
+    }
+    if donation.title.trim().is_empty() {
+        errors.push(("title", "tell us what you're donating"));
+    }
+    if donation
+        .expected_retail_value
+        .is_some_and(|value| value < Decimal::ZERO)
+    {
+        errors.push(("expected_retail_value", "the value can't be negative"));
+    }
+    if !errors.is_empty() {
+        return Err(Error::unprocessable_entity(errors));
+    }
+
+    let mut tx = db.begin().await?;
+    let organization_id = match &donation.organization_name {
+        Some(name) => {
+            Some(find_or_insert_organization(name, donation, address, tenant_id, &mut tx).await?)
+        }
+        None => None,
+    };
+    let tags: Vec<String> = donation
+        .tags
+        .split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    // a draft isn't open for bidding, so its dates are placeholders until it's approved
+    let auction_item_id = sqlx::query_scalar!(
+        r#"
+            insert into auction_item (
+                auction_id, approval_status, title, description, expected_retail_value,
+                featured_image_filepath, image_dir, tag_list, donated_by_organization_id,
+                active_end_date, tenant_id, etag
+            )
+            values (
+                null, 'pending', $1, $2, coalesce($3::decimal, 0), '', '', $4, $5, now(), $6,
+                uuid_generate_v1mc()
+            )
+            returning auction_item_id
+        "#,
+        donation.title.trim(),
+        donation.description.trim(),
+        donation.expected_retail_value,
+        &tags,
+        organization_id,
+        tenant_id
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    sqlx::query!(
+        r#"
+            insert into item_donation (auction_item_id, donor_name, donor_email, donor_phone)
+            values ($1, $2, $3, $4)
+        "#,
+        auction_item_id,
+        donation.donor_name.trim(),
+        donation.donor_email.trim(),
+        donation.donor_phone
+    )
+    .execute(&mut tx)
+    .await?;
+    jobs::enqueue(
+        &Job::DonationStatusChanged {
+            auction_item_id,
+            status: DonationStatus::Pending,
+        },
+        &mut tx,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(auction_item_id)
+}
+
+async fn find_or_insert_organization(
+    name: &str,
+    donation: &DonationFromForm,
+    address: Option<&tables::address::AddressFromForm>,
+    tenant_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<Uuid> {
+    let existing = sqlx::query_scalar!(
+        r#"
+            select organization_id
+            from organization
+            where lower(name) = lower($1)
+            and tenant_id = $2
+            order by created_at
+            limit 1
+        "#,
+        name.trim(),
+        tenant_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(organization_id) = existing {
+        return Ok(organization_id);
+    }
+    let address_id = match address {
+        Some(address) => Some(deliveries::insert_address(address, tenant_id, &mut *conn).await?),
+        None => None,
+    };
+    sqlx::query_scalar!(
+        r#"
+            insert into organization (
+                name, email, website, contact_name, phone_number, primary_address_id, tenant_id
+            )
+            values ($1, $2, coalesce($3, ''), $4, $5, $6, $7)
+            returning organization_id
+        "#,
+        name.trim(),
+        donation.donor_email.trim(),
+        donation.organization_website,
+        donation.donor_name.trim(),
+        donation.donor_phone,
+        address_id,
+        tenant_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Lock a donation to the tenant for deciding on, checking it hasn't been decided already.
+async fn pending_donation(
+    auction_item_id: Uuid,
+    tenant_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<()> {
+    let status = sqlx::query_scalar!(
+        r#"
+            select ai.approval_status
+            from auction_item ai
+            inner join item_donation d
+            on d.auction_item_id = ai.auction_item_id
+            where ai.auction_item_id = $1
+            and ai.tenant_id = $2
+            for update of ai
+        "#,
+        auction_item_id,
+        tenant_id
+    )
+    .fetch_optional(conn)
+    .await?
+    .ok_or(Error::NotFound)?;
+    if status != DonationStatus::Pending.as_str() {
+        return Err(Error::unprocessable_entity([(
+            "approval_status",
+            format!("this donation has already been {}", status),
+        )]));
+    }
+    Ok(())
+}
+
+/// Put a pending donation into an auction of the same tenant which hasn't ended, open for
+/// bidding for as long as the auction is, and tell the donor.
+#[instrument(skip(db))]
+pub async fn approve(
+    auction_item_id: Uuid,
+    approval: &ApprovalFromForm,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<()> {
+    if approval.minimum_bid_amount < Decimal::ZERO {
+        return Err(Error::unprocessable_entity([(
+            "minimum_bid_amount",
+            "the minimum bid can't be negative",
+        )]));
+    }
+    let mut tx = db.begin().await?;
+    pending_donation(auction_item_id, tenant_id, &mut tx).await?;
+    let approved = sqlx::query!(
+        r#"
+            update auction_item ai
+            set auction_id = a.auction_id,
+                approval_status = 'approved',
+                minimum_bid_amount = $3,
+                active_start_date = a.start_date,
+                active_end_date = a.end_date
+            from auction a
+            where ai.auction_item_id = $1
+            and a.auction_id = $2
+            and a.tenant_id = ai.tenant_id
+            and a.end_date > now()
+        "#,
+        auction_item_id,
+        approval.auction_id,
+        approval.minimum_bid_amount
+    )
+    .execute(&mut tx)
+    .await?;
+    if approved.rows_affected() == 0 {
+        return Err(Error::unprocessable_entity([(
+            "auction_id",
+            "choose an auction which hasn't ended",
+        )]));
+    }
+    decide(auction_item_id, DonationStatus::Approved, None, &mut tx).await?;
+    bidding::schedule_winners(approval.auction_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Turn down a pending donation, telling the donor why.
+#[instrument(skip(db))]
+pub async fn decline(
+    auction_item_id: Uuid,
+    reason: &str,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<()> {
+    if reason.trim().is_empty() {
+        return Err(Error::unprocessable_entity([(
+            "decline_reason",
+            "let the donor know why",
+        )]));
+    }
+    let mut tx = db.begin().await?;
+    pending_donation(auction_item_id, tenant_id, &mut tx).await?;
+    sqlx::query!(
+        r#"
+            update auction_item
+            set approval_status = 'declined'
+            where auction_item_id = $1
+        "#,
+        auction_item_id
+    )
+    .execute(&mut tx)
+    .await?;
+    decide(
+        auction_item_id,
+        DonationStatus::Declined,
+        Some(reason.trim()),
+        &mut tx,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn decide(
+    auction_item_id: Uuid,
+    status: DonationStatus,
+    decline_reason: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            update item_donation
+            set decided_at = now(), decline_reason = $2
+            where auction_item_id = $1
+        "#,
+        auction_item_id,
+        decline_reason
+    )
+    .execute(&mut *conn)
+    .await?;
+    jobs::enqueue(
+        &Job::DonationStatusChanged {
+            auction_item_id,
+            status,
+        },
+        &mut *conn,
+    )
+    .await?;
+    Ok(())
+}
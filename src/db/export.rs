@@ -0,0 +1,26 @@
+use sqlx::PgPool;
+use tracing::instrument;
+
+use crate::db::tables::Table;
+use crate::error::{Error, Result};
+
+/// Dump every row of `table` as a JSON array.
+///
+/// Password hashes are stripped out of the export: they're never needed off-server.
+/// Search vectors are too, since they are derived from the other columns.
+#[instrument(skip(db))]
+pub async fn export_table(table: &Table, db: &PgPool) -> Result<serde_json::Value> {
+    // Table names come from our own `Table` enum, never from user input,
+    // so it's safe to build this query with `format!`.
+    let query = format!(
+        r#"
+            select coalesce(json_agg(to_jsonb(t) - 'password_hash' - 'search_vector'), '[]'::json)
+            from "{}" t
+        "#,
+        table.to_postgres_name()
+    );
+    sqlx::query_scalar::<_, serde_json::Value>(&query)
+        .fetch_one(db)
+        .await
+        .map_err(Error::Sqlx)
+}
@@ -0,0 +1,161 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::jobs::{self, Job};
+
+/// Build the draft invoices for an auction from its winning bids: one per winning bidder, with
+/// a line for each item they won and another for its shipping fee, if any.
+///
+/// Drafts are rebuilt from scratch each time, so this is safe to run again after winners are
+/// recalculated. Issued, paid and voided invoices are left alone. Returns the number of drafts.
+#[instrument(skip(db))]
+pub async fn generate_invoices(auction_id: Uuid, db: &PgPool) -> Result<i64> {
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        r#"
+            insert into invoice (auction_id, user_id)
+            select distinct ai.auction_id, aib.user_id
+            from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            where ai.auction_id = $1
+            and aib.is_winning_bid
+            on conflict (auction_id, user_id) where status <> 'void' do nothing
+        "#,
+        auction_id
+    )
+    .execute(&mut tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+            delete from invoice_line
+            where invoice_id in (
+                select invoice_id
+                from invoice
+                where auction_id = $1
+                and status = 'draft'
+            )
+        "#,
+        auction_id
+    )
+    .execute(&mut tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+            insert into invoice_line (
+                invoice_id, auction_item_id, auction_item_bid_id, kind, description, amount
+            )
+            select i.invoice_id, ai.auction_item_id, aib.auction_item_bid_id, line.kind,
+                line.description, line.amount
+            from invoice i
+            inner join auction_item_bid aib
+            on aib.user_id = i.user_id
+            and aib.is_winning_bid
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            and ai.auction_id = i.auction_id
+            cross join lateral (
+                select 'item' kind, ai.title description, aib.amount
+                union all
+                select 'shipping', 'Shipping: ' || ai.title, d.shipping_fee
+                from auction_item_delivery d
+                where d.auction_item_bid_id = aib.auction_item_bid_id
+                and d.shipping_fee > 0
+            ) line
+            where i.auction_id = $1
+            and i.status = 'draft'
+        "#,
+        auction_id
+    )
+    .execute(&mut tx)
+    .await?;
+
+    // someone whose bid stopped winning has nothing left to pay for
+    sqlx::query!(
+        r#"
+            delete from invoice i
+            where i.auction_id = $1
+            and i.status = 'draft'
+            and not exists (
+                select 1
+                from invoice_line il
+                where il.invoice_id = i.invoice_id
+            )
+        "#,
+        auction_id
+    )
+    .execute(&mut tx)
+    .await?;
+
+    let drafts = sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from invoice
+            where auction_id = $1
+            and status = 'draft'
+        "#,
+        auction_id
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    tx.commit().await?;
+    Ok(drafts)
+}
+
+/// Send a draft invoice to its bidder.
+///
+/// Returns `Error::NotFound` unless the invoice exists and is a draft.
+#[instrument(skip(db))]
+pub async fn issue_invoice(invoice_id: Uuid, db: &PgPool) -> Result<()> {
+    let mut tx = db.begin().await?;
+    let issued = sqlx::query!(
+        r#"
+            update invoice
+            set status = 'issued', issued_at = now()
+            where invoice_id = $1
+            and status = 'draft'
+        "#,
+        invoice_id
+    )
+    .execute(&mut tx)
+    .await?;
+    if issued.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+    jobs::enqueue(&Job::InvoiceIssued { invoice_id }, &mut tx).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Cancel an invoice which hasn't been paid. A new draft can then be generated in its place.
+///
+/// Returns `Error::NotFound` unless the invoice exists, is a draft or issued, and the bidder
+/// isn't part way through paying it.
+#[instrument(skip(db))]
+pub async fn void_invoice(invoice_id: Uuid, db: &PgPool) -> Result<()> {
+    let voided = sqlx::query!(
+        r#"
+            update invoice
+            set status = 'void', voided_at = now()
+            where invoice_id = $1
+            and status in ('draft', 'issued')
+            and not exists (
+                select 1
+                from payment p
+                where p.invoice_id = invoice.invoice_id
+                and p.status = 'pending'
+            )
+        "#,
+        invoice_id
+    )
+    .execute(db)
+    .await?;
+    if voided.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+    Ok(())
+}
@@ -0,0 +1,298 @@
+//! Double-entry bookkeeping for the money that moves through the site.
+//!
+//! Every movement is a ledger transaction: a set of entries which sum to zero. Debits are
+//! positive and credits negative, so `cash` goes up with a debit and `proceeds` (what the site
+//! has taken on behalf of the organizations) goes up with a credit.
+//!
+//! Everything except cash is kept per organization, so what we owe an organization is the
+//! negated balance of its proceeds, fees and payouts.
+use sqlx::types::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Account {
+    /// Money held by the site
+    Cash,
+    /// What the payment provider charged for processing
+    Fees,
+    /// Money taken for auction items and shipping
+    Proceeds,
+    /// Money paid out to organizations
+    Payouts,
+}
+
+impl Account {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Account::Cash => "cash",
+            Account::Fees => "fees",
+            Account::Proceeds => "proceeds",
+            Account::Payouts => "payouts",
+        }
+    }
+}
+
+/// One side of a ledger transaction.
+#[derive(Debug)]
+pub struct Posting {
+    pub account: Account,
+    pub amount: Decimal,
+    pub organization_id: Option<Uuid>,
+    pub invoice_line_id: Option<Uuid>,
+}
+
+impl Posting {
+    pub fn debit(account: Account, amount: Decimal) -> Self {
+        Self {
+            account,
+            amount,
+            organization_id: None,
+            invoice_line_id: None,
+        }
+    }
+
+    pub fn credit(account: Account, amount: Decimal) -> Self {
+        Self::debit(account, -amount)
+    }
+
+    pub fn for_org(self, organization_id: Option<Uuid>) -> Self {
+        Self {
+            organization_id,
+            ..self
+        }
+    }
+
+    pub fn for_line(self, invoice_line_id: Uuid) -> Self {
+        Self {
+            invoice_line_id: Some(invoice_line_id),
+            ..self
+        }
+    }
+}
+
+/// What a ledger transaction is for.
+#[derive(Debug, Default)]
+pub struct Source {
+    pub invoice_id: Option<Uuid>,
+    pub payment_id: Option<Uuid>,
+    pub payout_id: Option<Uuid>,
+}
+
+/// Record `postings` as one ledger transaction.
+///
+/// Refuses postings which don't balance rather than write a ledger that doesn't add up.
+pub async fn post(
+    memo: &str,
+    source: Source,
+    postings: &[Posting],
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Uuid> {
+    check_balanced(postings)?;
+    let ledger_transaction_id = sqlx::query_scalar!(
+        r#"
+            insert into ledger_transaction (memo, invoice_id, payment_id, payout_id)
+            values ($1, $2, $3, $4)
+            returning ledger_transaction_id
+        "#,
+        memo,
+        source.invoice_id,
+        source.payment_id,
+        source.payout_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    for posting in postings.iter().filter(|p| !p.amount.is_zero()) {
+        sqlx::query!(
+            r#"
+                insert into ledger_entry (
+                    ledger_transaction_id, account, amount, organization_id, invoice_line_id
+                )
+                values ($1, $2, $3, $4, $5)
+            "#,
+            ledger_transaction_id,
+            posting.account.as_str(),
+            posting.amount,
+            posting.organization_id,
+            posting.invoice_line_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    Ok(ledger_transaction_id)
+}
+
+fn check_balanced(postings: &[Posting]) -> anyhow::Result<()> {
+    let total: Decimal = postings.iter().map(|p| p.amount).sum();
+    if !total.is_zero() {
+        anyhow::bail!("ledger postings are off by {}: {:?}", total, postings);
+    }
+    Ok(())
+}
+
+/// Share a payment's fee between the organizations it was for, in proportion to what each
+/// was paid. Shares are rounded to the cent and the largest share takes up the remainder.
+pub fn split_fee(fee: Decimal, shares: &[(Option<Uuid>, Decimal)]) -> Vec<(Option<Uuid>, Decimal)> {
+    let mut totals: Vec<(Option<Uuid>, Decimal)> = vec![];
+    for (organization_id, amount) in shares {
+        match totals.iter_mut().find(|(org, _)| org == organization_id) {
+            Some((_, total)) => *total += *amount,
+            None => totals.push((*organization_id, *amount)),
+        }
+    }
+    let paid: Decimal = totals.iter().map(|(_, amount)| *amount).sum();
+    if paid.is_zero() || totals.is_empty() {
+        return vec![(None, fee)];
+    }
+    let mut split: Vec<(Option<Uuid>, Decimal)> = totals
+        .iter()
+        .map(|(org, amount)| (*org, (fee * *amount / paid).round_dp(2)))
+        .collect();
+    let remainder = fee - split.iter().map(|(_, share)| *share).sum::<Decimal>();
+    let largest = (0..totals.len()).fold(0, |largest, i| {
+        if totals[i].1 > totals[largest].1 {
+            i
+        } else {
+            largest
+        }
+    });
+    split[largest].1 += remainder;
+    split
+}
+
+/// Record money paid out to one of the tenant's organizations.
+///
+/// Returns `Error::UnprocessableEntity` for an amount which isn't positive or is more than we
+/// owe them, and for another tenant's organization. The organization stays locked until the
+/// payout is in the ledger, so two payouts at once can't both be paid from the same balance.
+#[instrument(skip(db))]
+pub async fn record_payout(
+    organization_id: Uuid,
+    amount: Decimal,
+    reference: &str,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Uuid> {
+    if amount <= Decimal::ZERO {
+        return Err(Error::unprocessable_entity([(
+            "amount",
+            "a payout must be more than $0.00",
+        )]));
+    }
+    let mut tx = db.begin().await?;
+    // anything else posting for the organization waits on this lock too, since its entries
+    // reference the organization
+    sqlx::query!(
+        r#"
+            select organization_id
+            from organization
+            where organization_id = $1
+            and tenant_id = $2
+            for update
+        "#,
+        organization_id,
+        tenant_id
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or_else(|| {
+        Error::unprocessable_entity([("organization_id", "choose an organization we owe")])
+    })?;
+    let owed = sqlx::query_scalar!(
+        r#"
+            select coalesce(-sum(amount), 0) "owed!"
+            from ledger_entry
+            where organization_id = $1
+            and account <> 'cash'
+        "#,
+        organization_id
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    if amount > owed {
+        return Err(Error::unprocessable_entity([(
+            "amount",
+            format!("we only owe them ${:.2}", owed),
+        )]));
+    }
+    let payout_id = sqlx::query_scalar!(
+        r#"
+            insert into payout (organization_id, amount, reference)
+            values ($1, $2, $3)
+            returning payout_id
+        "#,
+        organization_id,
+        amount,
+        reference
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    post(
+        "Payout",
+        Source {
+            payout_id: Some(payout_id),
+            ..Source::default()
+        },
+        &[
+            Posting::debit(Account::Payouts, amount).for_org(Some(organization_id)),
+            Posting::credit(Account::Cash, amount),
+        ],
+        &mut tx,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(payout_id)
+}
+
+#[test]
+fn test_check_balanced() {
+    let postings = [
+        Posting::debit(Account::Cash, Decimal::new(9680, 2)),
+        Posting::debit(Account::Fees, Decimal::new(320, 2)),
+        Posting::credit(Account::Proceeds, Decimal::new(8000, 2)).for_line(Uuid::nil()),
+        Posting::credit(Account::Proceeds, Decimal::new(2000, 2)).for_line(Uuid::nil()),
+    ];
+    assert!(check_balanced(&postings).is_ok());
+    assert!(check_balanced(&postings[1..]).is_err());
+}
+
+#[test]
+fn test_split_fee() {
+    let sanctuary = Some(Uuid::from_u128(1));
+    let rescue = Some(Uuid::from_u128(2));
+    let split = split_fee(
+        Decimal::new(100, 2),
+        &[
+            (sanctuary, Decimal::new(1000, 2)),
+            (rescue, Decimal::new(1000, 2)),
+            (sanctuary, Decimal::new(1000, 2)),
+        ],
+    );
+    // $0.666... and $0.333... round to 67 and 33 cents
+    assert_eq!(
+        split,
+        vec![
+            (sanctuary, Decimal::new(67, 2)),
+            (rescue, Decimal::new(33, 2))
+        ]
+    );
+
+    let split = split_fee(
+        Decimal::new(100, 2),
+        &[
+            (sanctuary, Decimal::new(100, 2)),
+            (rescue, Decimal::new(100, 2)),
+            (None, Decimal::new(100, 2)),
+        ],
+    );
+    assert_eq!(
+        split.iter().map(|(_, fee)| *fee).sum::<Decimal>(),
+        Decimal::new(100, 2)
+    );
+    assert_eq!(split[0], (sanctuary, Decimal::new(34, 2)));
+
+    assert_eq!(split_fee(Decimal::ZERO, &[]), vec![(None, Decimal::ZERO)]);
+}
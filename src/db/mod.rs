@@ -1 +1,19 @@
+use sqlx::migrate::Migrator;
+
+pub mod articles;
+pub mod bidding;
+pub mod deliveries;
+pub mod donations;
+pub mod export;
+pub mod invoices;
+pub mod ledger;
+pub mod organizations;
+pub mod payments;
+pub mod pickups;
+pub mod seed;
 pub mod tables;
+pub mod tenants;
+pub mod users;
+
+/// Every migration in `./migrations`, embedded into the binary at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
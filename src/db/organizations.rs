@@ -0,0 +1,191 @@
+//! Who looks after each organization.
+//!
+//! Admins add users to an organization as `organization_member`s. Members see the
+//! organization's items, proceeds and deliveries in its portal, and managers can also edit its
+//! profile. Everything in the portal goes through `require_member` first, which treats a user
+//! from any other organization as if the organization didn't exist.
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tables;
+use crate::error::{Error, Result};
+use crate::ResultExt;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    /// Can edit the organization's profile, as well as see everything a member can
+    Manager,
+    Member,
+}
+
+impl OrgRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrgRole::Manager => "manager",
+            OrgRole::Member => "member",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "manager" => Some(OrgRole::Manager),
+            "member" => Some(OrgRole::Member),
+            _ => None,
+        }
+    }
+}
+
+/// The parts of an organization its members look after themselves.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct OrganizationProfile {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub website: String,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub contact_name: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub phone_number: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub alt_phone_number: Option<String>,
+}
+
+/// The user's role in the organization. Returns `Error::NotFound` when they aren't a member,
+/// whether or not the organization exists, so the portal gives nothing away about other
+/// organizations.
+#[instrument(skip(db))]
+pub async fn require_member(organization_id: Uuid, user_id: Uuid, db: &PgPool) -> Result<OrgRole> {
+    let role = sqlx::query_scalar!(
+        r#"
+            select role
+            from organization_member
+            where organization_id = $1
+            and user_id = $2
+        "#,
+        organization_id,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?
+    .ok_or(Error::NotFound)?;
+    OrgRole::parse(&role)
+        .ok_or_else(|| anyhow::anyhow!("unknown organization role {}", role).into())
+}
+
+/// Add the user with `email` to the organization, or change the role they already have.
+#[instrument(skip(db))]
+pub async fn add_member(
+    organization_id: Uuid,
+    email: &str,
+    role: OrgRole,
+    db: &PgPool,
+) -> Result<()> {
+    let added = sqlx::query!(
+        r#"
+            insert into organization_member (organization_id, user_id, role)
+            select $1, user_id, $3
+            from "user"
+            where email = $2
+            on conflict (organization_id, user_id) do update
+            set role = excluded.role
+        "#,
+        organization_id,
+        email.trim(),
+        role.as_str()
+    )
+    .execute(db)
+    .await
+    .on_constraint("organization_member_organization_id_fkey", |_| {
+        Error::NotFound
+    })?;
+    if added.rows_affected() == 0 {
+        return Err(Error::unprocessable_entity([(
+            "email",
+            "nobody has registered with that email yet",
+        )]));
+    }
+    Ok(())
+}
+
+/// Returns whether the user was a member.
+#[instrument(skip(db))]
+pub async fn remove_member(organization_id: Uuid, user_id: Uuid, db: &PgPool) -> Result<bool> {
+    let removed = sqlx::query!(
+        r#"
+            delete from organization_member
+            where organization_id = $1
+            and user_id = $2
+        "#,
+        organization_id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+    Ok(removed.rows_affected() > 0)
+}
+
+#[instrument(skip(db))]
+pub async fn get_profile(organization_id: Uuid, db: &PgPool) -> Result<OrganizationProfile> {
+    sqlx::query_as!(
+        OrganizationProfile,
+        r#"
+            select
+                name, description, email, website, contact_name, phone_number, alt_phone_number
+            from organization
+            where organization_id = $1
+        "#,
+        organization_id
+    )
+    .fetch_optional(db)
+    .await?
+    .ok_or(Error::NotFound)
+}
+
+/// Save the profile for one of the organization's managers. Other members get
+/// `Error::Forbidden`, and anyone else `Error::NotFound`.
+#[instrument(skip(db))]
+pub async fn update_profile(
+    organization_id: Uuid,
+    user_id: Uuid,
+    profile: &OrganizationProfile,
+    db: &PgPool,
+) -> Result<()> {
+    if require_member(organization_id, user_id, db).await? != OrgRole::Manager {
+        return Err(Error::Forbidden);
+    }
+    let mut errors = vec![];
+    if profile.name.trim().is_empty() {
+        errors.push(("name", "the organization needs a name"));
+    }
+    if !profile.email.contains('@') {
+        errors.push(("email", "enter an email address we can reach you at"));
+    }
+    if !errors.is_empty() {
+        return Err(Error::unprocessable_entity(errors));
+    }
+    sqlx::query!(
+        r#"
+            update organization
+            set name = $2, description = $3, email = $4, website = $5, contact_name = $6,
+                phone_number = $7, alt_phone_number = $8
+            where organization_id = $1
+        "#,
+        organization_id,
+        profile.name.trim(),
+        profile.description,
+        profile.email.trim(),
+        profile.website.trim(),
+        profile.contact_name,
+        profile.phone_number,
+        profile.alt_phone_number
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
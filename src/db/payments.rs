@@ -0,0 +1,384 @@
+use sqlx::types::time::OffsetDateTime;
+use sqlx::types::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::ledger::{self, Account, Posting, Source};
+use crate::error::{Error, Result, ResultExt};
+use crate::jobs::{self, Job};
+use crate::payments::{Outcome, PaymentEvent, PaymentIntent};
+
+/// How long to leave a bidder at the checkout before asking the provider how it went.
+const CHECK_AFTER: time::Duration = time::Duration::minutes(15);
+
+#[derive(Debug)]
+pub struct PayableInvoice {
+    pub invoice_id: Uuid,
+    pub reference: String,
+    pub user_id: Uuid,
+    pub status: String,
+    pub auction_title: String,
+    pub total: Decimal,
+}
+
+#[derive(Debug)]
+pub struct PaymentRef {
+    pub payment_id: Uuid,
+    pub provider_ref: String,
+    pub checkout_url: String,
+    pub amount: Decimal,
+    pub status: String,
+}
+
+#[instrument(skip(db))]
+pub async fn get_payable_invoice(invoice_id: Uuid, db: &PgPool) -> Result<Option<PayableInvoice>> {
+    sqlx::query_as!(
+        PayableInvoice,
+        r#"
+            select
+                i.invoice_id,
+                'INV-' || lpad(i.invoice_number::text, 6, '0') "reference!",
+                i.user_id,
+                i.status,
+                a.title auction_title,
+                (
+                    select coalesce(sum(amount), 0)
+                    from invoice_line il
+                    where il.invoice_id = i.invoice_id
+                ) "total!"
+            from invoice i
+            inner join auction a
+            on a.auction_id = i.auction_id
+            where i.invoice_id = $1
+        "#,
+        invoice_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_payment(payment_id: Uuid, db: &PgPool) -> Result<Option<PaymentRef>> {
+    sqlx::query_as!(
+        PaymentRef,
+        r#"
+            select payment_id, provider_ref, checkout_url, amount, status
+            from payment
+            where payment_id = $1
+        "#,
+        payment_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_pending_payment(invoice_id: Uuid, db: &PgPool) -> Result<Option<PaymentRef>> {
+    sqlx::query_as!(
+        PaymentRef,
+        r#"
+            select payment_id, provider_ref, checkout_url, amount, status
+            from payment
+            where invoice_id = $1
+            and status = 'pending'
+        "#,
+        invoice_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The succeeded payment for a paid invoice, locked until `tx` ends so that only one refund of
+/// it can be in flight.
+#[instrument(skip(tx))]
+pub async fn lock_refundable_payment(
+    invoice_id: Uuid,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Option<PaymentRef>> {
+    sqlx::query_as!(
+        PaymentRef,
+        r#"
+            select p.payment_id, p.provider_ref, p.checkout_url, p.amount, p.status
+            from payment p
+            inner join invoice i
+            on i.invoice_id = p.invoice_id
+            where p.invoice_id = $1
+            and p.status = 'succeeded'
+            and i.status = 'paid'
+            for update of p
+        "#,
+        invoice_id
+    )
+    .fetch_optional(tx)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Record a new payment intent, and schedule a check on it in case the provider never calls
+/// back.
+#[instrument(skip(db))]
+pub async fn insert_payment(
+    invoice_id: Uuid,
+    provider: &str,
+    intent: &PaymentIntent,
+    amount: Decimal,
+    db: &PgPool,
+) -> Result<Uuid> {
+    let mut tx = db.begin().await?;
+    let payment_id = sqlx::query_scalar!(
+        r#"
+            insert into payment (invoice_id, provider, provider_ref, checkout_url, amount)
+            values ($1, $2, $3, $4, $5)
+            returning payment_id
+        "#,
+        invoice_id,
+        provider,
+        intent.provider_ref,
+        intent.checkout_url,
+        amount
+    )
+    .fetch_one(&mut tx)
+    .await
+    .on_constraint("payment_pending_per_invoice", |_| {
+        Error::unprocessable_entity([("invoice", "a payment for this invoice is in progress")])
+    })?;
+    jobs::enqueue_at(
+        &Job::CheckPayment { payment_id },
+        OffsetDateTime::now_utc() + CHECK_AFTER,
+        &mut tx,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(payment_id)
+}
+
+#[instrument(skip(db))]
+pub async fn schedule_check(payment_id: Uuid, db: &PgPool) -> Result<()> {
+    jobs::enqueue_at(
+        &Job::CheckPayment { payment_id },
+        OffsetDateTime::now_utc() + CHECK_AFTER,
+        db,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Apply something the payment provider told us about one of our payments.
+///
+/// Each event is only acted on once: returns `false` for an event we've already seen, or one
+/// which no longer applies (a failure reported after the payment succeeded, say). Returns
+/// `Error::NotFound` for a payment we don't know about. Nothing is saved until `tx` commits.
+#[instrument(skip(tx))]
+pub async fn apply_event(
+    provider: &str,
+    event: &PaymentEvent,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<bool> {
+    let payment = sqlx::query!(
+        r#"
+            select payment_id, invoice_id, amount, status
+            from payment
+            where provider = $1
+            and provider_ref = $2
+            for update
+        "#,
+        provider,
+        event.provider_ref
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(Error::NotFound)?;
+
+    let recorded = sqlx::query!(
+        r#"
+            insert into payment_event (provider, event_id, payment_id, payload)
+            values ($1, $2, $3, $4)
+            on conflict do nothing
+        "#,
+        provider,
+        event.event_id,
+        payment.payment_id,
+        serde_json::to_value(event).map_err(anyhow::Error::from)?
+    )
+    .execute(&mut *tx)
+    .await?;
+    if recorded.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let applied = match (&event.outcome, payment.status.as_str()) {
+        (Outcome::Succeeded { fee }, "pending") => {
+            sqlx::query!(
+                r#"
+                    update payment
+                    set status = 'succeeded', fee = $2, completed_at = now()
+                    where payment_id = $1
+                "#,
+                payment.payment_id,
+                fee
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                r#"
+                    update invoice
+                    set status = 'paid', paid_at = now()
+                    where invoice_id = $1
+                    and status = 'issued'
+                "#,
+                payment.invoice_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            let lines = list_lines(payment.invoice_id, &mut *tx).await?;
+            let mut postings = vec![Posting::debit(Account::Cash, payment.amount - fee)];
+            let shares: Vec<_> = lines
+                .iter()
+                .map(|line| (line.organization_id, line.amount))
+                .collect();
+            for (organization_id, share) in ledger::split_fee(*fee, &shares) {
+                postings.push(Posting::debit(Account::Fees, share).for_org(organization_id));
+            }
+            for line in lines {
+                postings.push(
+                    Posting::credit(Account::Proceeds, line.amount)
+                        .for_org(line.organization_id)
+                        .for_line(line.invoice_line_id),
+                );
+            }
+            ledger::post(
+                "Payment received",
+                payment_source(payment.invoice_id, payment.payment_id),
+                &postings,
+                &mut *tx,
+            )
+            .await?;
+            true
+        }
+        (Outcome::Failed { reason }, "pending") => {
+            sqlx::query!(
+                r#"
+                    update payment
+                    set status = 'failed', failure_reason = $2, completed_at = now()
+                    where payment_id = $1
+                "#,
+                payment.payment_id,
+                reason
+            )
+            .execute(&mut *tx)
+            .await?;
+            true
+        }
+        (Outcome::Expired, "pending") => {
+            sqlx::query!(
+                r#"
+                    update payment
+                    set status = 'expired', completed_at = now()
+                    where payment_id = $1
+                "#,
+                payment.payment_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            true
+        }
+        (Outcome::Refunded { amount }, "succeeded") => {
+            if *amount != payment.amount {
+                return Err(anyhow::anyhow!(
+                    "refunded {} of a {} payment: only full refunds are supported",
+                    amount,
+                    payment.amount
+                )
+                .into());
+            }
+            sqlx::query!(
+                r#"
+                    update payment
+                    set status = 'refunded', refunded_at = now()
+                    where payment_id = $1
+                "#,
+                payment.payment_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                r#"
+                    update invoice
+                    set status = 'refunded', refunded_at = now()
+                    where invoice_id = $1
+                    and status = 'paid'
+                "#,
+                payment.invoice_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            // the provider keeps its fee, so the organizations still bear it
+            let mut postings = vec![Posting::credit(Account::Cash, *amount)];
+            for line in list_lines(payment.invoice_id, &mut *tx).await? {
+                postings.push(
+                    Posting::debit(Account::Proceeds, line.amount)
+                        .for_org(line.organization_id)
+                        .for_line(line.invoice_line_id),
+                );
+            }
+            ledger::post(
+                "Payment refunded",
+                payment_source(payment.invoice_id, payment.payment_id),
+                &postings,
+                &mut *tx,
+            )
+            .await?;
+            true
+        }
+        _ => false,
+    };
+    Ok(applied)
+}
+
+fn payment_source(invoice_id: Uuid, payment_id: Uuid) -> Source {
+    Source {
+        invoice_id: Some(invoice_id),
+        payment_id: Some(payment_id),
+        payout_id: None,
+    }
+}
+
+struct LineAmount {
+    invoice_line_id: Uuid,
+    organization_id: Option<Uuid>,
+    amount: Decimal,
+}
+
+/// Each line with the organization it benefits: the item's own beneficiary if it has one,
+/// otherwise the auction's.
+async fn list_lines(
+    invoice_id: Uuid,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Vec<LineAmount>> {
+    sqlx::query_as!(
+        LineAmount,
+        r#"
+            select
+                il.invoice_line_id,
+                coalesce(
+                    ai.benefits_organization_id,
+                    a.benefits_organization_id
+                ) organization_id,
+                il.amount
+            from invoice_line il
+            left join auction_item ai
+            on ai.auction_item_id = il.auction_item_id
+            left join auction a
+            on a.auction_id = ai.auction_id
+            where il.invoice_id = $1
+        "#,
+        invoice_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(Error::Sqlx)
+}
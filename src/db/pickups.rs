@@ -0,0 +1,171 @@
+//! Times winners can collect their items in person.
+//!
+//! An auction has any number of `pickup_window`s, each a slot at one address which takes at
+//! most `capacity` bookings. A winner books one with `crate::db::deliveries::choose_delivery`,
+//! which holds the window with `hold_window` while it counts the bookings already made, and
+//! clerks check each pickup off on the day with `crate::db::deliveries::transition`.
+use sqlx::types::time::OffsetDateTime;
+use sqlx::{PgConnection, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tables::{self, serialize_dt};
+use crate::error::{Error, Result};
+use crate::ResultExt;
+
+#[derive(Debug, serde::Serialize)]
+pub struct PickupWindow {
+    pub pickup_window_id: Uuid,
+    pub auction_id: Uuid,
+    pub address_id: Uuid,
+    #[serde(serialize_with = "serialize_dt")]
+    pub starts_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub ends_at: OffsetDateTime,
+    pub capacity: i32,
+    #[serde(serialize_with = "serialize_dt")]
+    pub created_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Times from the form are usually naive `datetime-local` values, which are read in the
+/// auction's timezone.
+#[derive(Debug, serde::Deserialize)]
+pub struct PickupWindowFromForm {
+    pub address_id: Uuid,
+    #[serde(deserialize_with = "tables::deserialize_local_dt")]
+    pub starts_at: tables::LocalDateTime,
+    #[serde(deserialize_with = "tables::deserialize_local_dt")]
+    pub ends_at: tables::LocalDateTime,
+    pub capacity: i32,
+}
+
+/// Returns `Error::NotFound` for an auction which doesn't exist.
+#[instrument(skip(db))]
+pub async fn insert_window(
+    auction_id: Uuid,
+    window: &PickupWindowFromForm,
+    db: &PgPool,
+) -> Result<PickupWindow> {
+    let timezone = sqlx::query_scalar!(
+        r#"
+            select timezone
+            from auction
+            where auction_id = $1
+        "#,
+        auction_id
+    )
+    .fetch_optional(db)
+    .await?
+    .ok_or(Error::NotFound)?;
+    let tz = tables::parse_timezone(&timezone).map_err(anyhow::Error::msg)?;
+    let (starts_at, ends_at) = (
+        window.starts_at.assume_timezone(&tz),
+        window.ends_at.assume_timezone(&tz),
+    );
+    let mut errors = vec![];
+    if ends_at <= starts_at {
+        errors.push(("ends_at", "a window has to end after it starts"));
+    }
+    if window.capacity < 1 {
+        errors.push(("capacity", "a window has to take at least one pickup"));
+    }
+    if !errors.is_empty() {
+        return Err(Error::unprocessable_entity(errors));
+    }
+    sqlx::query_as!(
+        PickupWindow,
+        r#"
+            insert into pickup_window (auction_id, address_id, starts_at, ends_at, capacity)
+            values ($1, $2, $3, $4, $5)
+            returning
+                pickup_window_id, auction_id, address_id, starts_at, ends_at, capacity,
+                created_at, updated_at
+        "#,
+        auction_id,
+        window.address_id,
+        starts_at,
+        ends_at,
+        window.capacity
+    )
+    .fetch_one(db)
+    .await
+    .on_constraint("pickup_window_address_id_fkey", |_| {
+        Error::unprocessable_entity([("address_id", "no such address")])
+    })
+}
+
+/// Returns whether there was a window to delete. A window with pickups booked into it stays
+/// until they've been moved elsewhere.
+#[instrument(skip(db))]
+pub async fn delete_window(auction_id: Uuid, pickup_window_id: Uuid, db: &PgPool) -> Result<bool> {
+    let deleted = sqlx::query!(
+        r#"
+            delete from pickup_window
+            where pickup_window_id = $1
+            and auction_id = $2
+        "#,
+        pickup_window_id,
+        auction_id
+    )
+    .execute(db)
+    .await
+    .on_constraint("auction_item_delivery_pickup_window_id_fkey", |_| {
+        Error::unprocessable_entity([(
+            "pickup_window_id",
+            "winners have booked this time, so it can't be removed",
+        )])
+    })?;
+    Ok(deleted.rows_affected() > 0)
+}
+
+/// Lock one of `auction_id`'s windows for the rest of the transaction, and check it has room
+/// for the delivery of `auction_item_bid_id`. A delivery already booked into the window is
+/// already counted, so keeping a booking always succeeds.
+pub(crate) async fn hold_window(
+    pickup_window_id: Uuid,
+    auction_id: Uuid,
+    auction_item_bid_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<()> {
+    let window = sqlx::query!(
+        r#"
+            select capacity, ends_at > now() "open!"
+            from pickup_window
+            where pickup_window_id = $1
+            and auction_id = $2
+            for update
+        "#,
+        pickup_window_id,
+        auction_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| Error::unprocessable_entity([("pickup_window_id", "no such pickup time")]))?;
+    if !window.open {
+        return Err(Error::unprocessable_entity([(
+            "pickup_window_id",
+            "that pickup time has passed",
+        )]));
+    }
+    let booked = sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from auction_item_delivery
+            where pickup_window_id = $1
+            and auction_item_bid_id <> $2
+        "#,
+        pickup_window_id,
+        auction_item_bid_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if booked >= i64::from(window.capacity) {
+        return Err(Error::unprocessable_entity([(
+            "pickup_window_id",
+            "that pickup time is fully booked, so please choose another",
+        )]));
+    }
+    Ok(())
+}
@@ -0,0 +1,713 @@
+//! Demo data for local development and the demo site.
+//!
+//! Seeding happens in two steps: `SeedPlan::generate()` draws every record from an RNG seeded
+//! by the caller, and `SeedPlan::insert()` writes the plan to the database. The plan holds no
+//! ids or wall-clock times (dates are stored as offsets from "now"), so the same seed always
+//! produces the same plan, and tests can assert on a plan without touching a database.
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use sqlx::types::time::OffsetDateTime;
+use sqlx::types::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use time::Duration;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::auth;
+use crate::db::bidding;
+use crate::db::deliveries::DeliveryStatus;
+use crate::db::tables::organization::OrgType;
+use crate::error::Result;
+
+/// Every seeded user can log in with this password.
+pub const DEMO_PASSWORD: &str = "hooksaurus-demo";
+
+const CITIES: &[(&str, &str, &str, f64, f64)] = &[
+    ("Sebastopol", "CA", "95472", 38.4021, -122.8239),
+    ("Poplar Grove", "IL", "61065", 42.3683, -88.8223),
+    ("Watkins Glen", "NY", "14891", 42.3806, -76.8733),
+    ("Austin", "TX", "78701", 30.2672, -97.7431),
+    ("Portland", "OR", "97205", 45.5152, -122.6784),
+    ("Asheville", "NC", "28801", 35.5951, -82.5515),
+];
+
+const STREETS: &[&str] = &[
+    "Hay Bale Rd",
+    "Barn Owl Ln",
+    "Clover Field Way",
+    "Pasture Ave",
+    "Old Mill St",
+    "Duck Pond Dr",
+];
+
+const ORGANIZATIONS: &[(OrgType, &str)] = &[
+    (OrgType::FarmAnimalSanctuary, "Hooksaurus Farm Sanctuary"),
+    (OrgType::FarmAnimalSanctuary, "Sunny Meadow Rescue Ranch"),
+    (OrgType::NonProfit, "Friends of the Pasture"),
+    (OrgType::Business, "Clover Field Bakery"),
+    (OrgType::Business, "Old Mill Pottery"),
+    (OrgType::Business, "Duck Pond Outfitters"),
+];
+
+const FIRST_NAMES: &[&str] = &[
+    "Alex", "Sam", "Jordan", "Riley", "Casey", "Morgan", "Jamie", "Taylor", "Quinn", "Avery",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Rivera",
+    "Chen",
+    "Okafor",
+    "Novak",
+    "Haddad",
+    "Lindqvist",
+    "Moreau",
+    "Tanaka",
+    "Silva",
+];
+
+const ITEM_NAMES: &[(&str, &[&str])] = &[
+    ("Hand-thrown Mug Set", &["pottery", "kitchen"]),
+    ("Weekend Farm Stay", &["experience", "travel"]),
+    ("Sourdough Baking Class", &["experience", "food"]),
+    ("Quilted Throw Blanket", &["home", "handmade"]),
+    ("Goat Yoga for Four", &["experience", "animals"]),
+    ("Vegan Cookbook Collection", &["books", "food"]),
+    (
+        "Sanctuary Tour with the Founder",
+        &["experience", "animals"],
+    ),
+    ("Camping Gear Bundle", &["outdoors"]),
+    ("Watercolor of Our Rooster", &["art", "animals"]),
+    ("Bakery Gift Card", &["food", "gift-card"]),
+    ("Hiking Backpack", &["outdoors"]),
+    ("Ceramic Planter", &["pottery", "home"]),
+];
+
+const CARRIERS: &[&str] = &["USPS", "UPS", "FedEx"];
+
+/// The states a seeded delivery can be in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeliveryState {
+    Pending,
+    Shipped,
+    Delivered,
+    Exception,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddressSeed {
+    pub street_address1: String,
+    pub city: &'static str,
+    pub state_province_county: &'static str,
+    pub postal_code: &'static str,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrganizationSeed {
+    pub org_type: OrgType,
+    pub name: &'static str,
+    pub address: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserSeed {
+    pub email: String,
+    pub first_name: &'static str,
+    pub last_name: &'static str,
+    pub address: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionSeed {
+    pub title: String,
+    pub description: String,
+    /// Minutes relative to the time of insert
+    pub start_offset: i64,
+    pub end_offset: i64,
+    pub benefits_organization: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemSeed {
+    pub auction: usize,
+    /// Index of the basket item this item rolls up to
+    pub basket: Option<usize>,
+    pub title: String,
+    pub description: String,
+    pub tag_list: Vec<String>,
+    pub expected_retail_value: Decimal,
+    pub minimum_bid_amount: Decimal,
+    pub buy_it_now_amount: Option<Decimal>,
+    pub donated_by_organization: Option<usize>,
+    pub benefits_organization: Option<usize>,
+    pub start_offset: i64,
+    pub end_offset: i64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BidSeed {
+    pub item: usize,
+    pub user: usize,
+    pub amount: Decimal,
+    pub max_bid_amount: Option<Decimal>,
+    pub is_winning_bid: bool,
+    pub created_offset: i64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliverySeed {
+    /// Index of the winning bid being delivered
+    pub bid: usize,
+    pub state: DeliveryState,
+    pub shipping_fee: Decimal,
+    pub carrier: Option<&'static str>,
+    pub tracking_number: Option<String>,
+}
+
+/// Everything the seeder will insert, in insert order.
+///
+/// Records point at each other by index into the other `Vec`s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeedPlan {
+    pub addresses: Vec<AddressSeed>,
+    pub organizations: Vec<OrganizationSeed>,
+    pub users: Vec<UserSeed>,
+    pub auctions: Vec<AuctionSeed>,
+    pub items: Vec<ItemSeed>,
+    pub bids: Vec<BidSeed>,
+    pub deliveries: Vec<DeliverySeed>,
+}
+
+/// What the seeder created, for reporting back to whoever ran it.
+#[derive(Debug, Default)]
+pub struct SeedSummary {
+    pub addresses: usize,
+    pub organizations: usize,
+    pub users: usize,
+    pub auctions: usize,
+    pub auction_items: usize,
+    pub bids: usize,
+    pub deliveries: usize,
+}
+
+/// Fill an (ideally empty) database with demo data generated from `seed`.
+#[instrument(skip(db))]
+pub async fn seed(seed: u64, db: &PgPool) -> Result<SeedSummary> {
+    SeedPlan::generate(seed).insert(db).await
+}
+
+impl SeedPlan {
+    pub fn generate(seed: u64) -> SeedPlan {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut plan = SeedPlan {
+            addresses: vec![],
+            organizations: vec![],
+            users: vec![],
+            auctions: vec![],
+            items: vec![],
+            bids: vec![],
+            deliveries: vec![],
+        };
+
+        for (org_type, name) in ORGANIZATIONS {
+            let address = plan.add_address(&mut rng);
+            plan.organizations.push(OrganizationSeed {
+                org_type: org_type.clone(),
+                name,
+                address,
+            });
+        }
+        for n in 0..12 {
+            let address = plan.add_address(&mut rng);
+            let first_name = *FIRST_NAMES.choose(&mut rng).unwrap();
+            let last_name = *LAST_NAMES.choose(&mut rng).unwrap();
+            plan.users.push(UserSeed {
+                email: format!(
+                    "{}.{}{}@example.com",
+                    first_name.to_lowercase(),
+                    last_name.to_lowercase(),
+                    n
+                ),
+                first_name,
+                last_name,
+                address,
+            });
+        }
+
+        // One auction that has closed, one running now, and one that hasn't started yet.
+        let windows = [
+            ("Spring", -30 * 24 * 60, -20 * 24 * 60),
+            ("Summer", -3 * 24 * 60, 7 * 24 * 60),
+            ("Autumn", 20 * 24 * 60, 30 * 24 * 60),
+        ];
+        let beneficiaries = plan.organization_indices(OrgType::can_benefit);
+        let donors = plan.organization_indices(|t| !t.can_benefit());
+        for (season, start_offset, end_offset) in windows {
+            let benefits_organization = *beneficiaries.choose(&mut rng).unwrap();
+            plan.auctions.push(AuctionSeed {
+                title: format!(
+                    "{} Auction for {}",
+                    season, plan.organizations[benefits_organization].name
+                ),
+                description: format!(
+                    "Every bid in our {} auction helps care for rescued farm animals.",
+                    season.to_lowercase()
+                ),
+                start_offset,
+                end_offset,
+                benefits_organization,
+            });
+            let auction = plan.auctions.len() - 1;
+            plan.add_items(&mut rng, auction, &donors, &beneficiaries);
+        }
+
+        for item in 0..plan.items.len() {
+            plan.add_bids(&mut rng, item);
+        }
+        plan.add_deliveries(&mut rng);
+        plan
+    }
+
+    fn add_address(&mut self, rng: &mut StdRng) -> usize {
+        let (city, state_province_county, postal_code, latitude, longitude) =
+            *CITIES.choose(rng).unwrap();
+        self.addresses.push(AddressSeed {
+            street_address1: format!(
+                "{} {}",
+                rng.gen_range(1..9999),
+                STREETS.choose(rng).unwrap()
+            ),
+            city,
+            state_province_county,
+            postal_code,
+            // Jitter so that addresses in the same town aren't stacked on top of each other
+            latitude: latitude + rng.gen_range(-0.05..0.05),
+            longitude: longitude + rng.gen_range(-0.05..0.05),
+        });
+        self.addresses.len() - 1
+    }
+
+    fn organization_indices(&self, f: impl Fn(&OrgType) -> bool) -> Vec<usize> {
+        self.organizations
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| f(&o.org_type))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Items close in a staggered fashion, fifteen minutes apart, so that the last few hours of
+    /// an auction aren't one big rush. The first item of each auction is a basket that the
+    /// next two items roll up to.
+    fn add_items(
+        &mut self,
+        rng: &mut StdRng,
+        auction: usize,
+        donors: &[usize],
+        beneficiaries: &[usize],
+    ) {
+        let AuctionSeed {
+            start_offset,
+            end_offset,
+            ..
+        } = self.auctions[auction];
+        let mut names: Vec<_> = ITEM_NAMES.iter().collect();
+        names.shuffle(rng);
+        let count = rng.gen_range(5..9);
+        let basket = self.items.len();
+        for (n, (title, tags)) in names.into_iter().take(count).enumerate() {
+            let value = rng.gen_range(20..400) * 100;
+            let (title, basket_id) = match n {
+                0 => (format!("{} Basket", title), None),
+                1 | 2 => (title.to_string(), Some(basket)),
+                _ => (title.to_string(), None),
+            };
+            self.items.push(ItemSeed {
+                auction,
+                basket: basket_id,
+                description: format!("{}, generously donated to support the animals.", title),
+                title,
+                tag_list: tags.iter().map(|t| t.to_string()).collect(),
+                expected_retail_value: Decimal::new(value, 2),
+                minimum_bid_amount: Decimal::new(value / 2, 2),
+                buy_it_now_amount: rng.gen_bool(0.25).then(|| Decimal::new(value * 2, 2)),
+                donated_by_organization: rng.gen_bool(0.8).then(|| *donors.choose(rng).unwrap()),
+                // Most items benefit whoever the auction benefits
+                benefits_organization: rng
+                    .gen_bool(0.2)
+                    .then(|| *beneficiaries.choose(rng).unwrap()),
+                start_offset,
+                end_offset: end_offset - 15 * n as i64,
+            });
+        }
+    }
+
+    /// Bidding opens at the minimum and climbs in one-dollar-or-more steps. Some bidders leave
+    /// a proxy bid: a `max_bid_amount` that lets their bid climb automatically.
+    fn add_bids(&mut self, rng: &mut StdRng, item: usize) {
+        let ItemSeed {
+            minimum_bid_amount,
+            start_offset,
+            end_offset,
+            ..
+        } = self.items[item];
+        // Nobody can bid on something that hasn't opened yet
+        if start_offset > 0 {
+            return;
+        }
+        let last_bid_offset = end_offset.min(0);
+        let count = rng.gen_range(0..7);
+        let mut amount = minimum_bid_amount;
+        let mut previous_user = None;
+        for n in 0..count {
+            let user = loop {
+                let user = rng.gen_range(0..self.users.len());
+                if Some(user) != previous_user {
+                    break user;
+                }
+            };
+            previous_user = Some(user);
+            let max_bid_amount = rng
+                .gen_bool(0.3)
+                .then(|| amount + Decimal::new(rng.gen_range(5..50) * 100, 2));
+            self.bids.push(BidSeed {
+                item,
+                user,
+                amount,
+                max_bid_amount,
+                is_winning_bid: false,
+                created_offset: start_offset
+                    + (last_bid_offset - start_offset) * (n + 1) / (count + 1),
+            });
+            // The next bidder has to beat this bid, including any proxy amount
+            amount = max_bid_amount.unwrap_or(amount) + Decimal::new(rng.gen_range(1..20) * 100, 2);
+        }
+        // Bidding is over: the last (and highest) bid wins
+        if end_offset < 0 && count > 0 {
+            self.bids.last_mut().unwrap().is_winning_bid = true;
+        }
+    }
+
+    fn add_deliveries(&mut self, rng: &mut StdRng) {
+        const STATES: [DeliveryState; 4] = [
+            DeliveryState::Pending,
+            DeliveryState::Shipped,
+            DeliveryState::Delivered,
+            DeliveryState::Exception,
+        ];
+        let winning_bids: Vec<usize> = self
+            .bids
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.is_winning_bid)
+            .map(|(i, _)| i)
+            .collect();
+        for (n, bid) in winning_bids.into_iter().enumerate() {
+            // Cycle through the states so every one of them shows up
+            let state = STATES[n % STATES.len()];
+            let (carrier, tracking_number) = match state {
+                DeliveryState::Pending => (None, None),
+                _ => (
+                    Some(*CARRIERS.choose(rng).unwrap()),
+                    Some(format!(
+                        "1Z{:016}",
+                        rng.gen_range(0..10_000_000_000_000_000u64)
+                    )),
+                ),
+            };
+            self.deliveries.push(DeliverySeed {
+                bid,
+                state,
+                shipping_fee: Decimal::new(rng.gen_range(5..25) * 100, 2),
+                carrier,
+                tracking_number,
+            });
+        }
+    }
+
+    /// Write the plan to the database in a single transaction.
+    #[instrument(skip(self, db))]
+    pub async fn insert(&self, db: &PgPool) -> Result<SeedSummary> {
+        let now = OffsetDateTime::now_utc();
+        let at = |offset: i64| now + Duration::minutes(offset);
+        let password_hash = auth::hash_password(DEMO_PASSWORD.to_string()).await?;
+        let mut tx = db.begin().await?;
+
+        let mut address_ids = vec![];
+        for address in &self.addresses {
+            address_ids.push(insert_address(address, &mut tx).await?);
+        }
+        let mut organization_ids = vec![];
+        for org in &self.organizations {
+            organization_ids
+                .push(insert_organization(org, address_ids[org.address], &mut tx).await?);
+        }
+        let mut user_ids = vec![];
+        for user in &self.users {
+            user_ids.push(
+                sqlx::query_scalar!(
+                    r#"
+                        insert into "user" (
+                            email, password_hash, first_name, last_name, address_id
+                        )
+                        values ($1, $2, $3, $4, $5)
+                        returning user_id
+                    "#,
+                    user.email,
+                    password_hash,
+                    user.first_name,
+                    user.last_name,
+                    address_ids[user.address]
+                )
+                .fetch_one(&mut tx)
+                .await?,
+            );
+        }
+        let mut auction_ids = vec![];
+        for auction in &self.auctions {
+            auction_ids.push(
+                sqlx::query_scalar!(
+                    r#"
+                        insert into auction (
+                            title, description, start_date, end_date,
+                            benefits_organization_id, etag
+                        )
+                        values ($1, $2, $3, $4, $5, uuid_generate_v1mc())
+                        returning auction_id
+                    "#,
+                    auction.title,
+                    auction.description,
+                    at(auction.start_offset),
+                    at(auction.end_offset),
+                    organization_ids[auction.benefits_organization]
+                )
+                .fetch_one(&mut tx)
+                .await?,
+            );
+        }
+        let mut item_ids: Vec<Uuid> = vec![];
+        for item in &self.items {
+            let auction_item_id = sqlx::query_scalar!(
+                r#"
+                    insert into auction_item (
+                        auction_id, basket_id,
+                        expected_retail_value, minimum_bid_amount, buy_it_now_amount,
+                        title, description, featured_image_filepath, image_dir, tag_list,
+                        donated_by_organization_id, benefits_organization_id,
+                        active_start_date, active_end_date, etag
+                    )
+                    values (
+                        $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
+                        uuid_generate_v1mc()
+                    )
+                    returning auction_item_id
+                "#,
+                auction_ids[item.auction],
+                item.basket.map(|b| item_ids[b]),
+                item.expected_retail_value,
+                item.minimum_bid_amount,
+                item.buy_it_now_amount,
+                item.title,
+                item.description,
+                "/static/imgs/elephant_planter_hooksaurus.jpg",
+                "/static/imgs",
+                &item.tag_list,
+                item.donated_by_organization.map(|o| organization_ids[o]),
+                item.benefits_organization.map(|o| organization_ids[o]),
+                at(item.start_offset),
+                at(item.end_offset)
+            )
+            .fetch_one(&mut tx)
+            .await?;
+            item_ids.push(auction_item_id);
+        }
+        let mut bid_ids = vec![];
+        for bid in &self.bids {
+            bid_ids.push(
+                sqlx::query_scalar!(
+                    r#"
+                        insert into auction_item_bid (
+                            auction_item_id, user_id, amount, max_bid_amount,
+                            is_winning_bid, created_at, updated_at, etag
+                        )
+                        values ($1, $2, $3, $4, $5, $6, $6, uuid_generate_v1mc())
+                        returning auction_item_bid_id
+                    "#,
+                    item_ids[bid.item],
+                    user_ids[bid.user],
+                    bid.amount,
+                    bid.max_bid_amount,
+                    bid.is_winning_bid,
+                    at(bid.created_offset)
+                )
+                .fetch_one(&mut tx)
+                .await?,
+            );
+        }
+        for &auction_id in &auction_ids {
+            bidding::schedule_winners(auction_id, &mut tx).await?;
+        }
+        for delivery in &self.deliveries {
+            let bid = &self.bids[delivery.bid];
+            let closed_at = at(self.items[bid.item].end_offset);
+            let (status, shipped_datetime, delivered, shipping_exception) = match delivery.state {
+                DeliveryState::Pending => (DeliveryStatus::ReadyToShip, None, None, None),
+                DeliveryState::Shipped => (
+                    DeliveryStatus::Shipped,
+                    Some(closed_at + Duration::days(2)),
+                    None,
+                    None,
+                ),
+                DeliveryState::Delivered => (
+                    DeliveryStatus::Delivered,
+                    Some(closed_at + Duration::days(2)),
+                    Some(closed_at + Duration::days(5)),
+                    None,
+                ),
+                DeliveryState::Exception => (
+                    DeliveryStatus::Exception,
+                    Some(closed_at + Duration::days(2)),
+                    None,
+                    Some("Address could not be found by the carrier"),
+                ),
+            };
+            // the exception turned up the day after shipping
+            let exception_at = shipping_exception.map(|_| closed_at + Duration::days(3));
+            let status_changed_at = delivered
+                .or(exception_at)
+                .or(shipped_datetime)
+                .unwrap_or(closed_at);
+            let user = &self.users[bid.user];
+            sqlx::query!(
+                r#"
+                    insert into auction_item_delivery (
+                        auction_item_bid_id, user_id, shipping_address, shipping_fee,
+                        shipped_datetime, delivered, shipping_exception,
+                        email_contact, carrier, tracking_number, etag,
+                        status, ready_at, exception_at, status_changed_at
+                    )
+                    values (
+                        $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, uuid_generate_v1mc(),
+                        $11, $12, $13, $14
+                    )
+                "#,
+                bid_ids[delivery.bid],
+                user_ids[bid.user],
+                address_ids[user.address],
+                delivery.shipping_fee,
+                shipped_datetime,
+                delivered,
+                shipping_exception,
+                user.email,
+                delivery.carrier,
+                delivery.tracking_number,
+                status.as_str(),
+                closed_at,
+                exception_at,
+                status_changed_at
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(SeedSummary {
+            addresses: self.addresses.len(),
+            organizations: self.organizations.len(),
+            users: self.users.len(),
+            auctions: self.auctions.len(),
+            auction_items: self.items.len(),
+            bids: self.bids.len(),
+            deliveries: self.deliveries.len(),
+        })
+    }
+}
+
+async fn insert_address(address: &AddressSeed, tx: &mut Transaction<'_, Postgres>) -> Result<Uuid> {
+    let address_id = sqlx::query_scalar!(
+        r#"
+            insert into address (
+                street_address1, city, state_province_county, postal_code,
+                country_code, latitude, longitude
+            )
+            values ($1, $2, $3, $4, 'US', $5, $6)
+            returning address_id
+        "#,
+        address.street_address1,
+        address.city,
+        address.state_province_county,
+        address.postal_code,
+        address.latitude,
+        address.longitude
+    )
+    .fetch_one(tx)
+    .await?;
+    Ok(address_id)
+}
+
+async fn insert_organization(
+    org: &OrganizationSeed,
+    address_id: Uuid,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Uuid> {
+    let domain = org.name.to_lowercase().replace(' ', "");
+    let organization_id = sqlx::query_scalar!(
+        r#"
+            insert into organization (
+                org_type, name, description, email, website, contact_name, primary_address_id
+            )
+            values ($1, $2, $3, $4, $5, $6, $7)
+            returning organization_id
+        "#,
+        org.org_type.clone() as _,
+        org.name,
+        format!("{} is a proud supporter of Hooksaurus Auctions.", org.name),
+        format!("hello@{}.org", domain),
+        format!("https://{}.org", domain),
+        "Front Desk",
+        address_id
+    )
+    .fetch_one(tx)
+    .await?;
+    Ok(organization_id)
+}
+
+#[test]
+fn test_seed_plan_is_reproducible() {
+    assert_eq!(SeedPlan::generate(42), SeedPlan::generate(42));
+    assert_ne!(SeedPlan::generate(42), SeedPlan::generate(43));
+}
+
+#[test]
+fn test_seed_plan_covers_demo_scenarios() {
+    let plan = SeedPlan::generate(7);
+
+    for org_type in [
+        OrgType::Business,
+        OrgType::FarmAnimalSanctuary,
+        OrgType::NonProfit,
+    ] {
+        assert!(plan.organizations.iter().any(|o| o.org_type == org_type));
+    }
+    assert!(plan.items.iter().any(|i| i.basket.is_some()));
+    assert!(plan.bids.iter().any(|b| b.max_bid_amount.is_some()));
+    for state in [
+        DeliveryState::Pending,
+        DeliveryState::Shipped,
+        DeliveryState::Delivered,
+        DeliveryState::Exception,
+    ] {
+        assert!(plan.deliveries.iter().any(|d| d.state == state));
+    }
+    // Every winning bid is the highest bid on its item
+    for winner in plan.bids.iter().filter(|b| b.is_winning_bid) {
+        assert!(plan
+            .bids
+            .iter()
+            .filter(|b| b.item == winner.item)
+            .all(|b| b.amount <= winner.amount));
+    }
+}
@@ -2,6 +2,7 @@ use sqlx::types::time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::db::tables::{deserialize_dt, serialize_dt};
+use crate::error::{Error, Result};
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize, sqlx::Type)]
 pub struct AddressId(pub Uuid);
@@ -11,6 +12,13 @@ impl std::fmt::Display for AddressId {
         write!(f, "{}", self.0)
     }
 }
+impl std::str::FromStr for AddressId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(AddressId)
+    }
+}
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Address {
@@ -43,3 +51,31 @@ pub struct AddressFromForm {
     pub latitude: Option<String>,
     pub longitude: Option<String>,
 }
+
+impl AddressFromForm {
+    /// Check a typed-in address has what we need to send something to it, and drop its empty
+    /// optional lines.
+    pub fn validated(self) -> Result<Self> {
+        let mut errors = vec![];
+        if self.street_address1.trim().is_empty() {
+            errors.push(("street_address1", "add the street address"));
+        }
+        if self.city.trim().is_empty() {
+            errors.push(("city", "add the city"));
+        }
+        if self.state_province_county.trim().is_empty() {
+            errors.push(("state_province_county", "add the state, province or county"));
+        }
+        if !errors.is_empty() {
+            return Err(Error::unprocessable_entity(errors));
+        }
+        let blank_to_none = |field: Option<String>| field.filter(|f| !f.trim().is_empty());
+        Ok(AddressFromForm {
+            street_address2: blank_to_none(self.street_address2),
+            street_address3: blank_to_none(self.street_address3),
+            postal_code: blank_to_none(self.postal_code),
+            country_code: blank_to_none(self.country_code),
+            ..self
+        })
+    }
+}
@@ -27,6 +27,8 @@ pub struct Auction {
     )]
     pub end_date: OffsetDateTime,
     pub benefits_organization_id: Option<super::organization::OrganizationId>,
+    // IANA timezone name that dates for this auction are entered and shown in
+    pub timezone: String,
     #[serde(
         deserialize_with = "tables::deserialize_dt",
         serialize_with = "tables::serialize_dt"
@@ -40,21 +42,20 @@ pub struct Auction {
     pub etag: super::Etag,
 }
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+/// Dates from the form are usually naive `datetime-local` values, which are read in the
+/// auction's `timezone`.
+#[derive(Clone, Debug, serde::Deserialize)]
 pub struct AuctionFromForm {
     pub title: String,
     pub description: String,
-    #[serde(
-        deserialize_with = "tables::deserialize_dt",
-        serialize_with = "tables::serialize_dt"
-    )]
-    pub start_date: OffsetDateTime,
-    #[serde(
-        deserialize_with = "tables::deserialize_dt",
-        serialize_with = "tables::serialize_dt"
-    )]
-    pub end_date: OffsetDateTime,
+    #[serde(deserialize_with = "tables::deserialize_local_dt")]
+    pub start_date: tables::LocalDateTime,
+    #[serde(deserialize_with = "tables::deserialize_local_dt")]
+    pub end_date: tables::LocalDateTime,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
     pub benefits_organization_id: Option<super::organization::OrganizationId>,
+    #[serde(deserialize_with = "tables::deserialize_timezone")]
+    pub timezone: String,
 }
 
 /// An Auction is composed of one or more AuctionItems
@@ -73,8 +74,10 @@ impl std::fmt::Display for AuctionItemId {
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct AuctionItem {
     pub auction_item_id: AuctionItemId,
-    // relates to this auction
-    pub auction_id: AuctionId,
+    // relates to this auction, once it's approved: a donation waits for one, see `crate::db::donations`
+    pub auction_id: Option<AuctionId>,
+    // "pending", "approved" or "declined"
+    pub approval_status: String,
     // This may be foreign-keyed to _another_ AuctionItem, which is called its "basket"
     pub basket_id: Option<AuctionItemId>,
 
@@ -91,6 +94,9 @@ pub struct AuctionItem {
     pub tag_list: Vec<String>,
     pub donated_by_organization_id: Option<super::organization::OrganizationId>,
     pub benefits_organization_id: Option<super::organization::OrganizationId>,
+    // what shipping it costs, see `crate::shipping`
+    pub weight_class: crate::shipping::WeightClass,
+    pub size_class: crate::shipping::SizeClass,
 
     #[serde(
         deserialize_with = "tables::deserialize_dt",
@@ -115,7 +121,8 @@ pub struct AuctionItem {
     pub etag: super::Etag,
 }
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+/// Active dates are read in the timezone of the auction the item belongs to.
+#[derive(Clone, Debug, serde::Deserialize)]
 pub struct AuctionItemFromForm {
     // relates to this auction
     pub auction_id: AuctionId,
@@ -135,17 +142,14 @@ pub struct AuctionItemFromForm {
     pub tag_list: Vec<String>,
     pub donated_by_organization_id: Option<super::organization::OrganizationId>,
     pub benefits_organization_id: Option<super::organization::OrganizationId>,
+    // what shipping it costs, see `crate::shipping`
+    pub weight_class: crate::shipping::WeightClass,
+    pub size_class: crate::shipping::SizeClass,
 
-    #[serde(
-        deserialize_with = "tables::deserialize_dt",
-        serialize_with = "tables::serialize_dt"
-    )]
-    pub active_start_date: OffsetDateTime,
-    #[serde(
-        deserialize_with = "tables::deserialize_dt",
-        serialize_with = "tables::serialize_dt"
-    )]
-    pub active_end_date: OffsetDateTime,
+    #[serde(deserialize_with = "tables::deserialize_local_dt")]
+    pub active_start_date: tables::LocalDateTime,
+    #[serde(deserialize_with = "tables::deserialize_local_dt")]
+    pub active_end_date: tables::LocalDateTime,
 }
 
 /// An AuctionItemBid represents a bid by a single person for a particular
@@ -202,17 +206,27 @@ pub struct AuctionItemBidFromForm {
     pub is_winning_bid: bool,
 }
 
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, sqlx::Type)]
+pub struct AuctionItemDeliveryId(pub Uuid);
+
+impl std::fmt::Display for AuctionItemDeliveryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// AuctionItemDelivery Represents a delivery request for this auction item
 /// It is expected that shipping will be calculated for the buyer's address
 /// This table foreign-keys to address as a result
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct AuctionItemDelivery {
+    pub delivery_id: AuctionItemDeliveryId,
     // Bid this delivery relates to
     pub auction_item_bid_id: AuctionItemBidId,
     // User who made this bid
     pub user_id: Uuid,
-    // Shipping address for delivery
-    pub shipping_address: super::address::AddressId,
+    // Shipping address for delivery, once we have one
+    pub shipping_address: Option<super::address::AddressId>,
     pub shipping_fee: Option<Decimal>,
     #[serde(
         deserialize_with = "tables::deserialize_optional_datetime",
@@ -232,6 +246,32 @@ pub struct AuctionItemDelivery {
     pub signed_for_by: Option<String>,
     pub carrier: Option<String>,
     pub tracking_number: Option<String>,
+    // collected in person rather than shipped, so it needs no address and costs nothing
+    pub local_pickup: bool,
+    // the pickup window booked for it, see `crate::db::pickups`
+    pub pickup_window_id: Option<Uuid>,
+    // one of the statuses in `crate::db::deliveries::DeliveryStatus`
+    pub status: String,
+    #[serde(
+        deserialize_with = "tables::deserialize_optional_datetime",
+        serialize_with = "tables::serialize_option_dt"
+    )]
+    pub ready_at: Option<OffsetDateTime>,
+    #[serde(
+        deserialize_with = "tables::deserialize_optional_datetime",
+        serialize_with = "tables::serialize_option_dt"
+    )]
+    pub exception_at: Option<OffsetDateTime>,
+    #[serde(
+        deserialize_with = "tables::deserialize_optional_datetime",
+        serialize_with = "tables::serialize_option_dt"
+    )]
+    pub picked_up_at: Option<OffsetDateTime>,
+    #[serde(
+        deserialize_with = "tables::deserialize_dt",
+        serialize_with = "tables::serialize_dt"
+    )]
+    pub status_changed_at: OffsetDateTime,
 
     #[serde(
         deserialize_with = "tables::deserialize_dt",
@@ -248,29 +288,23 @@ pub struct AuctionItemDelivery {
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct AuctionItemDeliveryFromForm {
-    // Bid this delivery relates to
+    // Bid this delivery relates to: the bidder is taken from the bid
     pub auction_item_bid_id: AuctionItemBidId,
-    // User who made this bid
-    pub user_id: Uuid,
-    // Shipping address for delivery
-    pub shipping_address: super::address::AddressId,
+    // Shipping address for delivery, once we have one
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub shipping_address: Option<super::address::AddressId>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
     pub shipping_fee: Option<Decimal>,
-    #[serde(
-        deserialize_with = "tables::deserialize_optional_datetime",
-        serialize_with = "tables::serialize_option_dt"
-    )]
-    pub shipped_datetime: Option<OffsetDateTime>,
-    #[serde(
-        deserialize_with = "tables::deserialize_optional_datetime",
-        serialize_with = "tables::serialize_option_dt"
-    )]
-    pub delivered: Option<OffsetDateTime>,
-    // columns below are relating to storing delivery info and exceptions
-    pub shipping_exception: Option<String>,
+    // Status and when each status was entered only change through
+    // `crate::db::deliveries::transition`, but the details may need correcting
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
     pub sms_updates_number: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
     pub email_contact: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
     pub signature_name: Option<String>,
-    pub signed_for_by: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
     pub carrier: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
     pub tracking_number: Option<String>,
 }
@@ -1,6 +1,10 @@
-use serde::de;
-use sqlx::types::time::OffsetDateTime;
+use chrono::{Offset, TimeZone};
+use chrono_tz::Tz;
+use serde::{de, Deserialize};
+use sqlx::types::time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
 use std::fmt;
+use std::str::FromStr;
+use time::Format;
 use uuid::Uuid;
 
 pub mod address;
@@ -12,7 +16,7 @@ pub mod user;
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize, sqlx::Type)]
 pub struct Etag(pub Uuid);
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum Table {
     Address,
@@ -21,6 +25,7 @@ pub enum Table {
     AuctionItem,
     AuctionItemBid,
     AuctionItemDelivery,
+    #[default]
     Organization,
     User,
 }
@@ -63,6 +68,18 @@ impl Table {
         }
     }
 }
+impl std::str::FromStr for Table {
+    type Err = String;
+
+    /// Parse a table from its URL name, e.g. `auction-item`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Table::get_table_list()
+            .into_iter()
+            .find(|t| t.to_url_name() == s)
+            .ok_or_else(|| format!("unknown table: {}", s))
+    }
+}
+
 impl fmt::Display for Table {
     // This trait requires `fmt` with this exact signature.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -79,15 +96,124 @@ impl fmt::Display for Table {
     }
 }
 
-impl Default for Table {
-    fn default() -> Self {
-        Table::Organization
+/// Datetimes without an offset we accept: HTML `datetime-local` inputs send the first two.
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+/// A datetime as it arrives from a form or JSON body, which may or may not carry an offset.
+///
+/// Naive values are wall-clock times: they only mean something once we know which timezone
+/// they were entered in (usually the auction's).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LocalDateTime {
+    Offset(OffsetDateTime),
+    Naive(PrimitiveDateTime),
+}
+
+impl LocalDateTime {
+    /// Parse RFC 3339 (`2022-03-01T18:30:00-08:00`), our own `2022-03-01 18:30:00Z` format,
+    /// or an HTML `datetime-local` value (`2022-03-01T18:30`).
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if let Ok(odt) = OffsetDateTime::parse(value, Format::Rfc3339) {
+            return Ok(LocalDateTime::Offset(odt));
+        }
+        if let Some(utc) = value.strip_suffix('Z') {
+            if let Ok(pdt) = PrimitiveDateTime::parse(utc, "%Y-%m-%d %H:%M:%S") {
+                return Ok(LocalDateTime::Offset(pdt.assume_utc()));
+            }
+        }
+        NAIVE_DATETIME_FORMATS
+            .iter()
+            .find_map(|format| PrimitiveDateTime::parse(value, format).ok())
+            .map(LocalDateTime::Naive)
+            .ok_or_else(|| format!("{} is not a recognized datetime", value))
+    }
+
+    /// Resolve a naive value as a wall-clock time in `tz`.
+    ///
+    /// For times that happen twice (when clocks go back) the earlier one wins. Times that never
+    /// happen (when clocks go forward) are read using the offset in effect before the gap.
+    pub fn assume_timezone(self, tz: &Tz) -> OffsetDateTime {
+        match self {
+            LocalDateTime::Offset(odt) => odt,
+            LocalDateTime::Naive(pdt) => {
+                let naive = to_chrono(pdt.assume_utc()).naive_utc();
+                // stepping back an hour at a time finds the wall-clock time just before a gap,
+                // which no timezone has made longer than a day
+                let offset = (0..=24)
+                    .find_map(|hours| {
+                        tz.from_local_datetime(&(naive - chrono::Duration::hours(hours)))
+                            .earliest()
+                    })
+                    .map_or_else(
+                        || tz.offset_from_utc_datetime(&naive).fix(),
+                        |local| local.offset().fix(),
+                    );
+                pdt.assume_offset(UtcOffset::seconds(offset.local_minus_utc()))
+            }
+        }
+    }
+
+    pub fn assume_utc(self) -> OffsetDateTime {
+        self.assume_timezone(&Tz::UTC)
+    }
+}
+
+/// Look up an IANA timezone name such as `America/Los_Angeles`.
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("{} is not a known timezone", name))
+}
+
+/// Format `dt` as wall-clock time in `tz`, using `chrono`'s `strftime` syntax.
+pub fn format_dt_in_timezone(dt: &OffsetDateTime, tz: &Tz, format: &str) -> String {
+    to_chrono(*dt).with_timezone(tz).format(format).to_string()
+}
+
+fn to_chrono(dt: OffsetDateTime) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(dt.unix_timestamp(), dt.nanosecond()).unwrap_or_default()
+}
+
+/// Deserialize an empty form field as `None` instead of failing to parse it.
+pub fn empty_string_as_none<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match Option::<String>::deserialize(d)?.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse().map(Some).map_err(de::Error::custom),
     }
 }
 
+/// Deserialize and validate an IANA timezone name.
+pub fn deserialize_timezone<'de, D>(d: D) -> Result<String, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let name = String::deserialize(d)?;
+    parse_timezone(&name).map_err(de::Error::custom)?;
+    Ok(name)
+}
+
+/// Deserialize a datetime that may not have an offset, see `LocalDateTime::parse()`.
+pub fn deserialize_local_dt<'de, D>(d: D) -> Result<LocalDateTime, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    LocalDateTime::parse(&String::deserialize(d)?).map_err(de::Error::custom)
+}
+
 // Custom datetime deserializer
 struct DateTimeFromCustomFormatVisitor;
 
+/// Accepts anything `LocalDateTime::parse()` does; values without an offset are read as UTC.
 pub fn deserialize_dt<'de, D>(d: D) -> Result<OffsetDateTime, D::Error>
 where
     D: de::Deserializer<'de>,
@@ -106,8 +232,8 @@ impl<'de> de::Visitor<'de> for DateTimeFromCustomFormatVisitor {
     where
         E: de::Error,
     {
-        match OffsetDateTime::parse(value, "%Y-%m-%d %H:%M:%SZ") {
-            Ok(odt) => Ok(odt),
+        match LocalDateTime::parse(value) {
+            Ok(ldt) => Ok(ldt.assume_utc()),
             Err(e) => Err(E::custom(format!("Parse error {} for {}", e, value))),
         }
     }
@@ -161,3 +287,90 @@ pub fn serialize_dt<S: serde::Serializer>(
 ) -> Result<S::Ok, S::Error> {
     serializer.serialize_str(dt.format("%Y-%m-%d %H:%M:%SZ").as_str())
 }
+
+pub fn serialize_dt_opt<S: serde::Serializer>(
+    dt: &Option<OffsetDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match dt {
+        Some(dt) => serialize_dt(dt, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[test]
+fn test_parse_local_datetime() {
+    let expected = PrimitiveDateTime::parse("2022-03-01 18:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+    assert_eq!(
+        LocalDateTime::parse("2022-03-01T18:30").unwrap(),
+        LocalDateTime::Naive(expected)
+    );
+    assert_eq!(
+        LocalDateTime::parse("2022-03-01T18:30:00").unwrap(),
+        LocalDateTime::Naive(expected)
+    );
+    assert_eq!(
+        LocalDateTime::parse("2022-03-01 18:30:00Z").unwrap(),
+        LocalDateTime::Offset(expected.assume_utc())
+    );
+    assert_eq!(
+        LocalDateTime::parse("2022-03-01T10:30:00-08:00")
+            .unwrap()
+            .assume_utc(),
+        expected.assume_utc()
+    );
+    assert!(LocalDateTime::parse("March 1st").is_err());
+}
+
+#[test]
+fn test_naive_datetime_in_timezone() {
+    let tz = parse_timezone("America/Los_Angeles").unwrap();
+    // Winter is UTC-8, summer is UTC-7
+    let winter = LocalDateTime::parse("2022-01-15T09:00").unwrap();
+    let summer = LocalDateTime::parse("2022-07-15T09:00").unwrap();
+
+    assert_eq!(winter.assume_timezone(&tz).hour(), 9);
+    assert_eq!(
+        winter.assume_timezone(&tz).to_offset(UtcOffset::UTC).hour(),
+        17
+    );
+    assert_eq!(
+        summer.assume_timezone(&tz).to_offset(UtcOffset::UTC).hour(),
+        16
+    );
+    assert_eq!(
+        format_dt_in_timezone(&summer.assume_timezone(&tz), &tz, "%H:%M %Z"),
+        "09:00 PDT"
+    );
+    assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+}
+
+#[test]
+fn test_naive_datetime_around_clock_changes() {
+    // Berlin is ahead of UTC: clocks go from 02:00 CET to 03:00 CEST, and back from 03:00 CEST
+    // to 02:00 CET
+    let tz = parse_timezone("Europe/Berlin").unwrap();
+    let in_gap = LocalDateTime::parse("2022-03-27T02:30").unwrap();
+    let twice = LocalDateTime::parse("2022-10-30T02:30").unwrap();
+
+    let resolved = in_gap.assume_timezone(&tz);
+    assert_eq!(resolved.to_offset(UtcOffset::UTC).hour(), 1);
+    assert_eq!(
+        format_dt_in_timezone(&resolved, &tz, "%H:%M %Z"),
+        "03:30 CEST"
+    );
+    let resolved = twice.assume_timezone(&tz);
+    assert_eq!(resolved.to_offset(UtcOffset::UTC).hour(), 0);
+    assert_eq!(
+        format_dt_in_timezone(&resolved, &tz, "%H:%M %Z"),
+        "02:30 CEST"
+    );
+
+    let tz = parse_timezone("America/Los_Angeles").unwrap();
+    let in_gap = LocalDateTime::parse("2022-03-13T02:30").unwrap();
+    assert_eq!(
+        format_dt_in_timezone(&in_gap.assume_timezone(&tz), &tz, "%H:%M %Z"),
+        "03:30 PDT"
+    );
+}
@@ -2,14 +2,25 @@ use crate::db::tables::{deserialize_dt, serialize_dt};
 use sqlx::types::time::OffsetDateTime;
 use uuid::Uuid;
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, sqlx::Type)]
-#[sqlx(type_name = "text")]
+/// Stored in `organization.org_type` as its snake_case name, e.g. `farm_animal_sanctuary`.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
 pub enum OrgType {
+    /// Can donate items, but can't receive proceeds
     Business,
     FarmAnimalSanctuary,
     NonProfit,
 }
 
+impl OrgType {
+    /// Whether auction proceeds can go to it, as an auction's or an item's
+    /// `benefits_organization_id`. The database holds to this too.
+    pub fn can_benefit(&self) -> bool {
+        !matches!(self, OrgType::Business)
+    }
+}
+
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize, sqlx::Type)]
 
 pub struct OrganizationId(pub Uuid);
@@ -18,6 +29,13 @@ impl std::fmt::Display for OrganizationId {
         write!(f, "{}", self.0)
     }
 }
+impl std::str::FromStr for OrganizationId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(OrganizationId)
+    }
+}
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Organization {
@@ -31,7 +49,7 @@ pub struct Organization {
     pub contact_name: Option<String>,
     pub phone_number: Option<String>,
     pub alt_phone_number: Option<String>,
-    pub primary_address_id: super::address::AddressId,
+    pub primary_address_id: Option<super::address::AddressId>,
     #[serde(deserialize_with = "deserialize_dt", serialize_with = "serialize_dt")]
     pub created_at: OffsetDateTime,
     #[serde(deserialize_with = "deserialize_dt", serialize_with = "serialize_dt")]
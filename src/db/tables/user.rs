@@ -44,17 +44,3 @@ pub struct User {
     pub role: String,
     pub image: Option<String>,
 }
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub struct ProfileInfo {
-    pub username: String,
-    pub bio: String,
-    pub image: Option<String>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub struct ProfileInfoWrapper {
-    pub profile: ProfileInfo,
-}
@@ -0,0 +1,135 @@
+//! The partner sanctuaries sharing this deployment.
+//!
+//! Each request is served for one tenant, see `crate::endpoints::tenant`. Auctions, items,
+//! organizations, addresses and articles belong to a tenant, and user accounts are shared,
+//! with a role in each tenant they've signed in to.
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::ResultExt;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Tenant {
+    pub tenant_id: Uuid,
+    /// Names the tenant in a `/t/<slug>` path prefix, and its template and static overrides
+    pub slug: String,
+    pub name: String,
+    /// Serves every host which isn't another tenant's, without a path prefix
+    pub is_default: bool,
+}
+
+/// The tenant serving requests for `host`, without its port.
+#[instrument(skip(db))]
+pub async fn get_by_host(host: &str, db: &PgPool) -> Result<Option<Tenant>> {
+    sqlx::query_as!(
+        Tenant,
+        r#"
+            select tenant_id, slug, name, is_default
+            from tenant
+            where host = $1
+        "#,
+        host
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_by_slug(slug: &str, db: &PgPool) -> Result<Option<Tenant>> {
+    sqlx::query_as!(
+        Tenant,
+        r#"
+            select tenant_id, slug, name, is_default
+            from tenant
+            where slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The tenant for every host that isn't another tenant's.
+#[instrument(skip(db))]
+pub async fn get_default(db: &PgPool) -> Result<Tenant> {
+    sqlx::query_as!(
+        Tenant,
+        r#"
+            select tenant_id, slug, name, is_default
+            from tenant
+            where is_default
+        "#
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Add a tenant, served at `host` when one is given as well as under `/t/<slug>`.
+#[instrument(skip(db))]
+pub async fn create(slug: &str, name: &str, host: Option<&str>, db: &PgPool) -> Result<Tenant> {
+    sqlx::query_as!(
+        Tenant,
+        r#"
+            insert into tenant (slug, name, host)
+            values ($1, $2, $3)
+            returning tenant_id, slug, name, is_default
+        "#,
+        slug,
+        name,
+        host
+    )
+    .fetch_one(db)
+    .await
+    .on_constraint("tenant_slug_check", |_| {
+        Error::unprocessable_entity([(
+            "slug",
+            "use lowercase letters, numbers and dashes, starting with a letter or number",
+        )])
+    })
+    .on_constraint("tenant_slug_key", |_| {
+        Error::unprocessable_entity([("slug", "another tenant has this slug")])
+    })
+    .on_constraint("tenant_host_key", |_| {
+        Error::unprocessable_entity([("host", "another tenant is served at this host")])
+    })
+}
+
+/// Make `user_id` a member of the tenant, if they aren't already something more.
+#[instrument(skip(db))]
+pub async fn join(tenant_id: Uuid, user_id: Uuid, db: &PgPool) -> Result<()> {
+    sqlx::query!(
+        r#"
+            insert into tenant_user (tenant_id, user_id)
+            values ($1, $2)
+            on conflict (tenant_id, user_id) do nothing
+        "#,
+        tenant_id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// The user's role in the tenant, `None` when they've never signed in to it.
+#[instrument(skip(db))]
+pub async fn get_role(tenant_id: Uuid, user_id: Uuid, db: &PgPool) -> Result<Option<String>> {
+    sqlx::query_scalar!(
+        r#"
+            select role
+            from tenant_user
+            where tenant_id = $1
+            and user_id = $2
+        "#,
+        tenant_id,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,51 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// Create an admin of the tenant, or promote an existing user with the same email to admin.
+///
+/// Emails are compared case-insensitively thanks to the collation on `"user".email`.
+#[instrument(skip(password_hash, db))]
+pub async fn upsert_admin(
+    email: &str,
+    password_hash: &str,
+    first_name: Option<&str>,
+    last_name: Option<&str>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Uuid> {
+    let mut tx = db.begin().await?;
+    let user_id = sqlx::query_scalar!(
+        r#"
+            insert into "user" (email, password_hash, first_name, last_name)
+            values ($1, $2, $3, $4)
+            on conflict (email) do update
+            set password_hash = excluded.password_hash,
+                first_name = coalesce(excluded.first_name, "user".first_name),
+                last_name = coalesce(excluded.last_name, "user".last_name)
+            returning user_id
+        "#,
+        email,
+        password_hash,
+        first_name,
+        last_name
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    sqlx::query!(
+        r#"
+            insert into tenant_user (tenant_id, user_id, role)
+            values ($1, $2, 'admin')
+            on conflict (tenant_id, user_id) do update
+            set role = 'admin'
+        "#,
+        tenant_id,
+        user_id
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+    Ok(user_id)
+}
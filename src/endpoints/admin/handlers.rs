@@ -1,23 +1,27 @@
 use axum::{
-    extract::{Extension, Form, Path, Query},
+    extract::{Extension, Path, Query},
     http::{header::HeaderMap, StatusCode},
     response::Html,
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use minijinja::context;
 use serde::Deserialize;
+use sqlx::PgPool;
 use tracing::{event, instrument, Level};
 use uuid::Uuid;
 
+use crate::db::deliveries;
 use crate::db::tables::{self, Table};
+use crate::db::tenants::Tenant;
 use crate::endpoints::admin::{AdminRow, Pagination, ToForm};
-use crate::endpoints::ApiContext;
-use crate::error::Result;
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+use crate::jobs;
 
 use super::queries;
 
-pub fn router() -> Router {
+pub fn admin_router() -> Router {
     Router::new()
         .route("/admin", get(admin_root))
         .route("/admin/tables", get(list_tables))
@@ -33,37 +37,37 @@ pub fn router() -> Router {
             get(get_insert_form).post(insert_table_record),
         )
         .route("/admin/tables/:table", get(list_table_records))
+        .route("/admin/jobs", get(list_jobs))
+        .route("/admin/jobs/:job_id/retry", post(retry_job))
 }
 
 #[instrument(skip(ctx))]
-async fn admin_root(ctx: Extension<ApiContext>) -> Html<String> {
+async fn admin_root(ctx: Extension<ApiContext>, tenant: Tenant) -> Html<String> {
     let template = ctx.template_env.get_template("admin.html").unwrap();
-    Html(
-        template
-            .render(context!(title => "Hooksaurus Auctions: Helping Animal Sanctuaries"))
-            .unwrap(),
-    )
+    let title = format!("{}: Helping Animal Sanctuaries", tenant.name);
+    Html(template.render(context!(title)).unwrap())
 }
 
 #[instrument(skip(ctx))]
-async fn list_tables(headers: HeaderMap, ctx: Extension<ApiContext>) -> Html<String> {
-    let template;
-    if headers.get("hx-request").is_some() {
-        template = ctx
-            .template_env
+async fn list_tables(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Html<String> {
+    let template = if headers.get("hx-request").is_some() {
+        ctx.template_env
             .get_template("fragments/list_all_tables.html")
-            .unwrap();
+            .unwrap()
     } else {
-        template = ctx
-            .template_env
+        ctx.template_env
             .get_template("completes/list_all_tables.html")
-            .unwrap();
-    }
+            .unwrap()
+    };
     let table_list: Vec<(String, String)> = Table::get_table_list()
         .iter()
         .map(|t| (t.to_url_name().to_string(), t.to_string()))
         .collect();
-    let ctx = context! { table_list };
+    let ctx = context! { table_list, is_operator => tenant.is_default };
     let rendered = template
         .render(ctx)
         .map_err(|e| {
@@ -77,40 +81,43 @@ async fn list_tables(headers: HeaderMap, ctx: Extension<ApiContext>) -> Html<Str
 async fn list_table_records(
     headers: HeaderMap,
     ctx: Extension<ApiContext>,
+    tenant: Tenant,
     Path(table): Path<Table>,
     pagination: Option<Query<Pagination>>,
 ) -> (StatusCode, Html<String>) {
-    let template;
-    if headers.get("hx-request").is_some() {
+    let template = if headers.get("hx-request").is_some() {
         event!(
             Level::INFO,
             event_msg = "Table list records called as fragment"
         );
-        template = ctx
-            .template_env
+        ctx.template_env
             .get_template("fragments/table_list_records.html")
-            .unwrap();
+            .unwrap()
     } else {
-        template = ctx
-            .template_env
+        ctx.template_env
             .get_template("completes/table_list_records.html")
-            .unwrap();
-    }
+            .unwrap()
+    };
     let Query(pagination) = pagination.unwrap_or_default();
     let next_page: usize = pagination.page + 1;
+    let (tenant_id, db) = (tenant.tenant_id, &ctx.db);
     let rows_result: Result<Vec<AdminRow>> = match table {
-        Table::Address => queries::get_address_admin_rows(&pagination, &ctx.db).await,
-        Table::Article => queries::get_article_admin_rows(&pagination, &ctx.db).await,
-        Table::Auction => queries::get_auction_admin_rows(&pagination, &ctx.db).await,
-        Table::AuctionItem => queries::get_auction_item_admin_rows(&pagination, &ctx.db).await,
+        Table::Address => queries::get_address_admin_rows(&pagination, tenant_id, db).await,
+        Table::Article => queries::get_article_admin_rows(&pagination, tenant_id, db).await,
+        Table::Auction => queries::get_auction_admin_rows(&pagination, tenant_id, db).await,
+        Table::AuctionItem => {
+            queries::get_auction_item_admin_rows(&pagination, tenant_id, db).await
+        }
         Table::AuctionItemBid => {
-            queries::get_auction_item_bid_admin_rows(&pagination, &ctx.db).await
+            queries::get_auction_item_bid_admin_rows(&pagination, tenant_id, db).await
         }
         Table::AuctionItemDelivery => {
-            queries::get_auction_item_delivery_admin_rows(&pagination, &ctx.db).await
+            queries::get_auction_item_delivery_admin_rows(&pagination, tenant_id, db).await
+        }
+        Table::Organization => {
+            queries::get_organization_admin_rows(&pagination, tenant_id, db).await
         }
-        Table::Organization => queries::get_organization_admin_rows(&pagination, &ctx.db).await,
-        Table::User => queries::get_user_admin_rows(&pagination, &ctx.db).await,
+        Table::User => queries::get_user_admin_rows(&pagination, tenant_id, db).await,
     };
     let rows = rows_result.unwrap_or_else(|_| vec![]);
 
@@ -131,25 +138,22 @@ async fn get_insert_form(
     ctx: Extension<ApiContext>,
     Path(table): Path<Table>,
 ) -> Html<String> {
-    let template;
-    if headers.get("hx-request").is_some() {
-        template = ctx
-            .template_env
+    let template = if headers.get("hx-request").is_some() {
+        ctx.template_env
             .get_template("fragments/form_insert_modal.html")
-            .unwrap();
+            .unwrap()
     } else {
-        template = ctx
-            .template_env
+        ctx.template_env
             .get_template("completes/form_insert_modal.html")
-            .unwrap();
-    }
+            .unwrap()
+    };
     let form = match table {
         Table::Address => tables::address::Address::to_empty_form(),
         Table::Article => todo!(),
         Table::Auction => tables::auction::Auction::to_empty_form(),
         Table::AuctionItem => todo!(),
         Table::AuctionItemBid => todo!(),
-        Table::AuctionItemDelivery => todo!(),
+        Table::AuctionItemDelivery => tables::auction::AuctionItemDelivery::to_empty_form(),
         Table::Organization => todo!(),
         Table::User => todo!(),
     };
@@ -158,7 +162,7 @@ async fn get_insert_form(
         .render(context!(
             table_name => table.to_string(),
             form => form,
-            insert_record_url => format!("/admin/tables/{}/insert", table.to_url_name()),
+            record_save_url => format!("/admin/tables/{}/insert", table.to_url_name()),
         ))
         .unwrap();
     Html(rendered)
@@ -170,47 +174,116 @@ struct TableDetailParams {
     pk: Uuid,
 }
 
-#[instrument(skip(ctx))]
-async fn insert_table_record(
-    headers: HeaderMap,
-    ctx: Extension<ApiContext>,
-    Path(table): Path<Table>,
-    form: Form<tables::address::AddressFromForm>,
-) -> (StatusCode, Html<String>) {
-    let address: tables::address::AddressFromForm = form.0;
-    // insert new address
-    event!(Level::INFO, event_msg = "Inserting new address", address=?address);
-    match queries::insert_address_from_form(address, &ctx.db).await {
-        Ok(_) => list_table_records(headers, ctx, Path(table), None).await,
-        Err(e) => {
-            event!(Level::ERROR, event_msg="Error inserting Address record", err=?e);
+async fn save_table_record(
+    table: &Table,
+    pk: Option<Uuid>,
+    body: &str,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<()> {
+    let saved = match (table, pk) {
+        (Table::Address, None) => {
+            queries::insert_address_from_form(parse_form(body)?, tenant_id, db)
+                .await
+                .map(|_| true)?
+        }
+        (Table::Address, Some(pk)) => {
+            queries::update_address_from_form(pk, parse_form(body)?, tenant_id, db)
+                .await?
+                .is_some()
+        }
+        (Table::Auction, None) => {
+            queries::insert_auction_from_form(parse_form(body)?, tenant_id, db)
+                .await
+                .map(|_| true)?
+        }
+        (Table::Auction, Some(pk)) => {
+            queries::update_auction_from_form(pk, parse_form(body)?, tenant_id, db)
+                .await?
+                .is_some()
+        }
+        (Table::AuctionItemDelivery, None) => {
+            let delivery: tables::auction::AuctionItemDeliveryFromForm = parse_form(body)?;
+            if !queries::bid_in_tenant(delivery.auction_item_bid_id.0, tenant_id, db).await? {
+                return Err(Error::unprocessable_entity([(
+                    "auction_item_bid_id",
+                    "no such bid",
+                )]));
+            }
+            deliveries::insert_delivery(&delivery, db)
+                .await
+                .map(|_| true)?
+        }
+        (Table::AuctionItemDelivery, Some(pk)) => {
+            queries::delivery_in_tenant(pk, tenant_id, db).await?
+                && deliveries::update_delivery(pk, &parse_form(body)?, db)
+                    .await?
+                    .is_some()
+        }
+        _ => todo!(),
+    };
+    if saved {
+        Ok(())
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+fn save_error_response(table: &Table, e: Error) -> (StatusCode, Html<String>) {
+    match e {
+        Error::UnprocessableEntity { errors } => {
+            let messages: Vec<String> = errors
+                .iter()
+                .map(|(field, errs)| format!("{}: {}", field, errs.join(", ")))
+                .collect();
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Html(messages.join("<br>")),
+            )
+        }
+        Error::NotFound => (StatusCode::NOT_FOUND, Html("".to_string())),
+        e => {
+            event!(Level::ERROR, event_msg="Error saving record", table=%table, err=?e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Html("An error occurred".to_string()),
             )
         }
     }
-    // send back listings again
+}
+
+#[instrument(skip(ctx, body))]
+async fn insert_table_record(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(table): Path<Table>,
+    body: String,
+) -> (StatusCode, Html<String>) {
+    event!(Level::INFO, event_msg = "Inserting new record", table=%table);
+    match save_table_record(&table, None, &body, tenant.tenant_id, &ctx.db).await {
+        // send back listings again
+        Ok(_) => list_table_records(headers, ctx, tenant, Path(table), None).await,
+        Err(e) => save_error_response(&table, e),
+    }
 }
 
 async fn get_table_record(
     headers: HeaderMap,
     ctx: Extension<ApiContext>,
+    tenant: Tenant,
     Path(TableDetailParams { table, pk }): Path<TableDetailParams>,
 ) -> (StatusCode, Html<String>) {
-    let template;
-    if headers.get("hx-request").is_some() {
-        template = ctx
-            .template_env
+    let template = if headers.get("hx-request").is_some() {
+        ctx.template_env
             .get_template("fragments/form_insert_modal.html")
-            .unwrap();
+            .unwrap()
     } else {
-        template = ctx
-            .template_env
+        ctx.template_env
             .get_template("completes/form_insert_modal.html")
-            .unwrap();
-    }
-    match queries::get_table_detail(&table, pk, &ctx.db).await {
+            .unwrap()
+    };
+    match queries::get_table_detail(&table, pk, tenant.tenant_id, &ctx.db).await {
         Err(e) => {
             event!(Level::ERROR, event_msg="Error retrieving Address record", err=?e);
             (
@@ -227,7 +300,11 @@ async fn get_table_record(
                         action => "Update",
                         table_name => table.to_string(),
                         form => form_thing.to_form(),
-                        record_save_url => format!("/admin/tables/{}/{}", table, pk),
+                        record_save_url => format!("/admin/tables/{}/{}", table.to_url_name(), pk),
+                        save_method => "put",
+                        // only deliveries can be deleted so far
+                        record_delete_url => matches!(table, Table::AuctionItemDelivery)
+                            .then(|| format!("/admin/tables/{}/{}", table.to_url_name(), pk)),
                     ))
                     .unwrap(),
             ),
@@ -235,18 +312,103 @@ async fn get_table_record(
     }
 }
 
+#[instrument(skip(ctx, body))]
 async fn update_table_record(
     Path(TableDetailParams { table, pk }): Path<TableDetailParams>,
     headers: HeaderMap,
     ctx: Extension<ApiContext>,
-) -> Html<String> {
-    todo!()
+    tenant: Tenant,
+    body: String,
+) -> (StatusCode, Html<String>) {
+    event!(Level::INFO, event_msg = "Updating record", table=%table, pk=%pk);
+    match save_table_record(&table, Some(pk), &body, tenant.tenant_id, &ctx.db).await {
+        Ok(_) => list_table_records(headers, ctx, tenant, Path(table), None).await,
+        Err(e) => save_error_response(&table, e),
+    }
 }
 
+#[instrument(skip(ctx))]
 async fn delete_table_record(
     Path(TableDetailParams { table, pk }): Path<TableDetailParams>,
     headers: HeaderMap,
     ctx: Extension<ApiContext>,
-) -> Html<String> {
-    todo!()
+    tenant: Tenant,
+) -> (StatusCode, Html<String>) {
+    event!(Level::INFO, event_msg = "Deleting record", table=%table, pk=%pk);
+    let deleted = match table {
+        Table::AuctionItemDelivery => {
+            match queries::delivery_in_tenant(pk, tenant.tenant_id, &ctx.db).await {
+                Ok(true) => deliveries::delete_delivery(pk, &ctx.db).await,
+                other => other.map(|_| false),
+            }
+        }
+        _ => todo!(),
+    };
+    match deleted {
+        Ok(true) => list_table_records(headers, ctx, tenant, Path(table), None).await,
+        Ok(false) => save_error_response(&table, Error::NotFound),
+        Err(e) => save_error_response(&table, e),
+    }
+}
+
+const JOB_STATUSES: [&str; 4] = ["dead", "pending", "running", "done"];
+const JOB_LIST_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+struct JobListParams {
+    status: Option<String>,
+}
+
+/// Jobs aren't any one tenant's, so only the admins of the default tenant, who run the
+/// deployment, look after them.
+fn require_operator(tenant: &Tenant) -> Result<()> {
+    if tenant.is_default {
+        Ok(())
+    } else {
+        Err(Error::Forbidden)
+    }
+}
+
+/// Background jobs by status, starting with the dead letters.
+#[instrument(skip(ctx))]
+async fn list_jobs(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Query(params): Query<JobListParams>,
+) -> Result<Html<String>> {
+    require_operator(&tenant)?;
+    let status = params.status.as_deref().unwrap_or("dead");
+    if !JOB_STATUSES.contains(&status) {
+        return Err(Error::NotFound);
+    }
+    let jobs = jobs::queries::list_jobs(status, JOB_LIST_LIMIT, &ctx.db).await?;
+    render_page(
+        &ctx,
+        &headers,
+        "admin_jobs.html",
+        context!(statuses => JOB_STATUSES, status => status, jobs => jobs, message => None::<String>),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn retry_job(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(job_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    require_operator(&tenant)?;
+    jobs::queries::retry_job(job_id, &ctx.db).await?;
+    event!(Level::INFO, event_msg = "Retrying dead job", job_id=%job_id);
+    let jobs = jobs::queries::list_jobs("dead", JOB_LIST_LIMIT, &ctx.db).await?;
+    render_template(
+        &ctx,
+        "fragments/admin_jobs.html",
+        context!(
+            statuses => JOB_STATUSES,
+            status => "dead",
+            jobs => jobs,
+            message => Some(format!("Job {} will run again shortly.", job_id)),
+        ),
+    )
 }
@@ -5,7 +5,7 @@ mod handlers;
 mod queries;
 
 use crate::db::tables::{self, serialize_dt};
-pub use handlers::router;
+pub use handlers::admin_router;
 
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct Pagination {
@@ -33,7 +33,27 @@ pub struct AdminRow {
 
 pub trait ToForm {
     fn to_form(&self) -> String;
-    fn to_empty_form() -> String;
+    fn to_empty_form() -> String
+    where
+        Self: Sized;
+}
+
+/// `<option>`s for every IANA timezone, with `selected` picked.
+fn timezone_options(selected: &str) -> String {
+    chrono_tz::TZ_VARIANTS
+        .iter()
+        .map(|tz| {
+            format!(
+                r#"<option value="{0}"{1}>{0}</option>"#,
+                tz.name(),
+                if tz.name() == selected {
+                    " selected"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect()
 }
 
 impl ToForm for tables::address::Address {
@@ -70,53 +90,65 @@ impl ToForm for tables::address::Address {
                 </div>
         "##,
             self.street_address1,
-            self.street_address2
-                .clone()
-                .unwrap_or_else(|| "".to_string()),
-            self.street_address3
-                .clone()
-                .unwrap_or_else(|| "".to_string()),
+            self.street_address2.clone().unwrap_or_default(),
+            self.street_address3.clone().unwrap_or_default(),
             self.city,
             self.state_province_county,
-            self.postal_code.clone().unwrap_or_else(|| "".to_string()),
-            self.country_code.clone().unwrap_or_else(|| "".to_string()),
-            self.latitude.clone().unwrap_or(0.0),
-            self.longitude.clone().unwrap_or(0.0),
+            self.postal_code.clone().unwrap_or_default(),
+            self.country_code.clone().unwrap_or_default(),
+            self.latitude.unwrap_or(0.0),
+            self.longitude.unwrap_or(0.0),
         )
     }
     fn to_empty_form() -> String {
         r##"
-            <div class="uk-margin">
-                <input class="uk-input" type="text" name="title" placeholder="Auction Title" required>
-            </div>
-            <div class="uk-margin">
-                <textarea class="uk-textarea" rows="5" placeholder="description" name="description"></textarea>
-            </div>
-            <div class="uk-margin">
-                <input data-uk-datepicker="{format:'DD.MM.YYYY'}" name="start_date_date" required>
-                <input data-uk-timepicker="{format:'12h'}" name="start_date_time" required>
-            </div>
-            <div class="uk-margin">
-                <input data-uk-datepicker="{format:'DD.MM.YYYY'}" name="end_date_date" required>
-                <input data-uk-timepicker="{format:'12h'}" name="end_date_time" required>
-            </div>
-            <div class="uk-margin">
-                <input class="uk-input" type="text" name="benefits_organization_id"
-                    placeholder="Shore Sanctuary" required>
-            </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="text" name="street_address1" placeholder="Street address Line 1" required>
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="text" name="street_address2" placeholder="Street address Line 2">
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="text" name="street_address3" placeholder="Street address Line 3">
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="text" name="city" placeholder="City" required>
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="text" name="state_province_county"
+                        placeholder="State, Province, or County" required>
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="text" name="postal_code" placeholder="Postal Code">
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="text" name="country_code" placeholder="Country">
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="number" name="latitude" placeholder="Latitude">
+                </div>
+                <div class="uk-margin">
+                    <input class="uk-input" type="number" name="longitude" placeholder="longitude">
+                </div>
         "##.to_string()
     }
 }
 
 impl ToForm for tables::auction::Auction {
     fn to_form(&self) -> String {
+        // `datetime-local` inputs show wall-clock time, so show it in the auction's timezone
+        let tz = tables::parse_timezone(&self.timezone).unwrap_or(chrono_tz::UTC);
         format!(
             r##"
             <div class="uk-margin">
                 <input class="uk-input" type="text" name="title" placeholder="Auction Title" required value="{}">
             </div>
             <div class="uk-margin">
-                <textarea class="uk-textarea" rows="5" placeholder="description" name="description" value="{}"></textarea>
+                <textarea class="uk-textarea" rows="5" placeholder="description" name="description">{}</textarea>
+            </div>
+            <div class="uk-margin">
+                <label class="uk-form-label">Timezone</label>
+                <select class="uk-select" name="timezone" required>{}</select>
             </div>
             <div class="uk-margin">
                 <label class="uk-form-label">Start Date</label>
@@ -129,13 +161,14 @@ impl ToForm for tables::auction::Auction {
             <div class="uk-margin">
                 <label class="uk-form-label">Auction Benefits Organization</label>
                 <input class="uk-input" type="text" name="benefits_organization_id"
-                    placeholder="Shore Sanctuary" required value="{}">
+                    placeholder="Shore Sanctuary" value="{}">
             </div>
         "##,
             self.title,
             self.description,
-            self.start_date.format("%Y-%m-%d %H:%M"),
-            self.end_date.format("%Y-%m-%d %H:%M"),
+            timezone_options(&self.timezone),
+            tables::format_dt_in_timezone(&self.start_date, &tz, "%Y-%m-%dT%H:%M"),
+            tables::format_dt_in_timezone(&self.end_date, &tz, "%Y-%m-%dT%H:%M"),
             self.benefits_organization_id
                 .as_ref()
                 .map(|t| t.to_string())
@@ -143,13 +176,18 @@ impl ToForm for tables::auction::Auction {
         )
     }
     fn to_empty_form() -> String {
-        r##"
+        format!(
+            r##"
             <div class="uk-margin">
                 <input class="uk-input" type="text" name="title" placeholder="Auction Title" required>
             </div>
             <div class="uk-margin">
                 <textarea class="uk-textarea" rows="5" placeholder="description" name="description"></textarea>
             </div>
+            <div class="uk-margin">
+                <label class="uk-form-label">Timezone</label>
+                <select class="uk-select" name="timezone" required>{}</select>
+            </div>
             <div class="uk-margin">
                 <label class="uk-form-label">Start Date</label>
                 <input class="uk-input" type="datetime-local" name="start_date" required>
@@ -161,8 +199,94 @@ impl ToForm for tables::auction::Auction {
             <div class="uk-margin">
                 <label class="uk-form-label">Auction Benefits Organization</label>
                 <input class="uk-input" type="text" name="benefits_organization_id"
-                    placeholder="Shore Sanctuary" required>
+                    placeholder="Shore Sanctuary">
             </div>
-        "##.to_string()
+        "##,
+            timezone_options("UTC"),
+        )
+    }
+}
+
+impl ToForm for tables::auction::AuctionItemDelivery {
+    fn to_form(&self) -> String {
+        format!(
+            r##"
+            <div class="uk-margin">
+                <label class="uk-form-label">Winning Bid</label>
+                <input class="uk-input" type="text" name="auction_item_bid_id" readonly value="{}">
+            </div>
+            <div class="uk-margin">
+                <label class="uk-form-label">Status</label>
+                <p>{} (<a href="/admin/fulfillment">change it in fulfillment</a>)</p>
+            </div>
+            <div class="uk-margin">
+                <label class="uk-form-label">Shipping Address</label>
+                <input class="uk-input" type="text" name="shipping_address" placeholder="Address id" value="{}">
+            </div>
+            <div class="uk-margin">
+                <label class="uk-form-label">Shipping Fee</label>
+                <input class="uk-input" type="number" step="0.01" min="0" name="shipping_fee" value="{}">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="email" name="email_contact" placeholder="Email for updates" value="{}">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="tel" name="sms_updates_number" placeholder="Phone for text updates" value="{}">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="text" name="signature_name" placeholder="Who should sign for it" value="{}">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="text" name="carrier" placeholder="Carrier" value="{}">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="text" name="tracking_number" placeholder="Tracking Number" value="{}">
+            </div>
+        "##,
+            self.auction_item_bid_id,
+            self.status.replace('_', " "),
+            self.shipping_address
+                .as_ref()
+                .map(|a| a.to_string())
+                .unwrap_or_default(),
+            self.shipping_fee.unwrap_or_default(),
+            self.email_contact.clone().unwrap_or_default(),
+            self.sms_updates_number.clone().unwrap_or_default(),
+            self.signature_name.clone().unwrap_or_default(),
+            self.carrier.clone().unwrap_or_default(),
+            self.tracking_number.clone().unwrap_or_default(),
+        )
+    }
+    fn to_empty_form() -> String {
+        r##"
+            <div class="uk-margin">
+                <label class="uk-form-label">Winning Bid</label>
+                <input class="uk-input" type="text" name="auction_item_bid_id" placeholder="Bid id" required>
+            </div>
+            <div class="uk-margin">
+                <label class="uk-form-label">Shipping Address</label>
+                <input class="uk-input" type="text" name="shipping_address" placeholder="Address id">
+            </div>
+            <div class="uk-margin">
+                <label class="uk-form-label">Shipping Fee</label>
+                <input class="uk-input" type="number" step="0.01" min="0" name="shipping_fee">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="email" name="email_contact" placeholder="Email for updates">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="tel" name="sms_updates_number" placeholder="Phone for text updates">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="text" name="signature_name" placeholder="Who should sign for it">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="text" name="carrier" placeholder="Carrier">
+            </div>
+            <div class="uk-margin">
+                <input class="uk-input" type="text" name="tracking_number" placeholder="Tracking Number">
+            </div>
+        "##
+        .to_string()
     }
 }
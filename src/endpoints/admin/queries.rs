@@ -2,12 +2,17 @@ use sqlx::PgPool;
 use tracing::instrument;
 use uuid::Uuid;
 
-use crate::{db::tables, error::Result, Error};
+use crate::db::{bidding, deliveries, tables};
+use crate::{error::Result, Error, ResultExt};
 
 use super::{AdminRow, Pagination, ToForm};
 
 #[instrument(skip(db))]
-pub async fn get_address_admin_rows(pagination: &Pagination, db: &PgPool) -> Result<Vec<AdminRow>> {
+pub async fn get_address_admin_rows(
+    pagination: &Pagination,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
         AdminRow,
         r#"
@@ -22,9 +27,11 @@ pub async fn get_address_admin_rows(pagination: &Pagination, db: &PgPool) -> Res
                 created_at,
                 updated_at
             from address
-            limit $1
-            offset $2
+            where tenant_id = $1
+            limit $2
+            offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -34,7 +41,11 @@ pub async fn get_address_admin_rows(pagination: &Pagination, db: &PgPool) -> Res
 }
 
 #[instrument(skip(db))]
-pub async fn get_article_admin_rows(pagination: &Pagination, db: &PgPool) -> Result<Vec<AdminRow>> {
+pub async fn get_article_admin_rows(
+    pagination: &Pagination,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
         AdminRow,
         r#"
@@ -44,9 +55,11 @@ pub async fn get_article_admin_rows(pagination: &Pagination, db: &PgPool) -> Res
                 created_at,
                 updated_at
             from article
-            limit $1
-            offset $2
+            where tenant_id = $1
+            limit $2
+            offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -56,7 +69,11 @@ pub async fn get_article_admin_rows(pagination: &Pagination, db: &PgPool) -> Res
 }
 
 #[instrument(skip(db))]
-pub async fn get_auction_admin_rows(pagination: &Pagination, db: &PgPool) -> Result<Vec<AdminRow>> {
+pub async fn get_auction_admin_rows(
+    pagination: &Pagination,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
         AdminRow,
         r#"
@@ -66,9 +83,11 @@ pub async fn get_auction_admin_rows(pagination: &Pagination, db: &PgPool) -> Res
                 created_at,
                 updated_at
             from auction
-            limit $1
-            offset $2
+            where tenant_id = $1
+            limit $2
+            offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -79,6 +98,7 @@ pub async fn get_auction_admin_rows(pagination: &Pagination, db: &PgPool) -> Res
 #[instrument(skip(db))]
 pub async fn get_auction_item_admin_rows(
     pagination: &Pagination,
+    tenant_id: Uuid,
     db: &PgPool,
 ) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
@@ -90,9 +110,11 @@ pub async fn get_auction_item_admin_rows(
                 created_at,
                 updated_at
             from auction_item
-            limit $1
-            offset $2
+            where tenant_id = $1
+            limit $2
+            offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -103,6 +125,7 @@ pub async fn get_auction_item_admin_rows(
 #[instrument(skip(db))]
 pub async fn get_auction_item_bid_admin_rows(
     pagination: &Pagination,
+    tenant_id: Uuid,
     db: &PgPool,
 ) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
@@ -114,11 +137,15 @@ pub async fn get_auction_item_bid_admin_rows(
                 aib.created_at "created_at!",
                 aib.updated_at "updated_at!"
             from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
             inner join "user" us
             on us.user_id = aib.user_id
-            limit $1
-            offset $2
+            where ai.tenant_id = $1
+            limit $2
+            offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -129,22 +156,30 @@ pub async fn get_auction_item_bid_admin_rows(
 #[instrument(skip(db))]
 pub async fn get_auction_item_delivery_admin_rows(
     pagination: &Pagination,
+    tenant_id: Uuid,
     db: &PgPool,
 ) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
         AdminRow,
         r#"
             select
-                aib.auction_item_bid_id as "pk!",
-                us.email as "name!",
-                aib.created_at "created_at!",
-                aib.updated_at "updated_at!"
-            from auction_item_bid aib
+                aid.delivery_id as pk,
+                concat(ai.title, ' for ', us.email) "name!",
+                aid.created_at,
+                aid.updated_at
+            from auction_item_delivery aid
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = aid.auction_item_bid_id
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
             inner join "user" us
-            on us.user_id = aib.user_id
-            limit $1
-            offset $2
+            on us.user_id = aid.user_id
+            where ai.tenant_id = $1
+            order by aid.created_at desc
+            limit $2
+            offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -155,6 +190,7 @@ pub async fn get_auction_item_delivery_admin_rows(
 #[instrument(skip(db))]
 pub async fn get_organization_admin_rows(
     pagination: &Pagination,
+    tenant_id: Uuid,
     db: &PgPool,
 ) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
@@ -166,9 +202,11 @@ pub async fn get_organization_admin_rows(
             created_at,
             updated_at
         from organization
-        limit $1
-        offset $2
+        where tenant_id = $1
+        limit $2
+        offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -177,19 +215,27 @@ pub async fn get_organization_admin_rows(
     .map_err(Error::Sqlx)
 }
 #[instrument(skip(db))]
-pub async fn get_user_admin_rows(pagination: &Pagination, db: &PgPool) -> Result<Vec<AdminRow>> {
+pub async fn get_user_admin_rows(
+    pagination: &Pagination,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<AdminRow>> {
     sqlx::query_as!(
         AdminRow,
         r#"
         select
-            user_id as pk,
-            email as name,
-            created_at,
-            updated_at
-        from "user"
-        limit $1
-        offset $2
+            us.user_id as pk,
+            us.email as name,
+            us.created_at,
+            us.updated_at
+        from "user" us
+        inner join tenant_user tu
+        on tu.user_id = us.user_id
+        where tu.tenant_id = $1
+        limit $2
+        offset $3
         "#,
+        tenant_id,
         i64::try_from(pagination.per_page).unwrap_or(30),
         i64::try_from(pagination.page * pagination.per_page).unwrap_or(0)
     )
@@ -201,6 +247,7 @@ pub async fn get_user_admin_rows(pagination: &Pagination, db: &PgPool) -> Result
 #[instrument(skip(db))]
 pub async fn insert_address_from_form(
     address: tables::address::AddressFromForm,
+    tenant_id: Uuid,
     db: &PgPool,
 ) -> Result<tables::address::Address> {
     sqlx::query_as!(
@@ -209,9 +256,9 @@ pub async fn insert_address_from_form(
         insert into address (
                 street_address1, street_address2, street_address3,
                 city, state_province_county, postal_code,
-                country_code, latitude, longitude
+                country_code, latitude, longitude, tenant_id
             )
-            values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             returning
                 address_id as "address_id: tables::address::AddressId",
                 street_address1, street_address2, street_address3,
@@ -226,34 +273,190 @@ pub async fn insert_address_from_form(
         address.state_province_county,
         address.postal_code,
         address.country_code,
-        address.latitude.map(|n| n.parse::<f64>().ok()).flatten(),
-        address.longitude.map(|n| n.parse::<f64>().ok()).flatten()
+        address.latitude.and_then(|n| n.parse::<f64>().ok()),
+        address.longitude.and_then(|n| n.parse::<f64>().ok()),
+        tenant_id
     )
     .fetch_one(db)
     .await
     .map_err(Error::Sqlx)
 }
 
+#[instrument(skip(db))]
+pub async fn update_address_from_form(
+    pk: Uuid,
+    address: tables::address::AddressFromForm,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<tables::address::Address>> {
+    sqlx::query_as!(
+        tables::address::Address,
+        r#"
+        update address
+            set street_address1 = $2, street_address2 = $3, street_address3 = $4,
+                city = $5, state_province_county = $6, postal_code = $7,
+                country_code = $8, latitude = $9, longitude = $10
+            where address_id = $1
+            and tenant_id = $11
+            returning
+                address_id as "address_id: tables::address::AddressId",
+                street_address1, street_address2, street_address3,
+                city, state_province_county, postal_code,
+                country_code, latitude, longitude, created_at, updated_at,
+                etag "etag: tables::Etag"
+        "#,
+        pk,
+        address.street_address1,
+        address.street_address2,
+        address.street_address3,
+        address.city,
+        address.state_province_county,
+        address.postal_code,
+        address.country_code,
+        address.latitude.and_then(|n| n.parse::<f64>().ok()),
+        address.longitude.and_then(|n| n.parse::<f64>().ok()),
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn insert_auction_from_form(
+    auction: tables::auction::AuctionFromForm,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<tables::auction::Auction> {
+    let tz = tables::parse_timezone(&auction.timezone)
+        .map_err(|e| Error::unprocessable_entity([("timezone", e)]))?;
+    let mut tx = db.begin().await?;
+    let inserted = sqlx::query_as!(
+        tables::auction::Auction,
+        r#"
+        insert into auction (
+                title, description, start_date, end_date,
+                benefits_organization_id, timezone, tenant_id, etag
+            )
+            values ($1, $2, $3, $4, $5, $6, $7, uuid_generate_v1mc())
+            returning
+                auction_id as "auction_id: tables::auction::AuctionId",
+                title, description, start_date, end_date,
+                benefits_organization_id as "benefits_organization_id: tables::organization::OrganizationId",
+                timezone, created_at, updated_at,
+                etag "etag: tables::Etag"
+        "#,
+        auction.title,
+        auction.description,
+        auction.start_date.assume_timezone(&tz),
+        auction.end_date.assume_timezone(&tz),
+        auction.benefits_organization_id.map(|o| o.0),
+        auction.timezone,
+        tenant_id
+    )
+    .fetch_one(&mut tx)
+    .await
+    .on_constraint("auction_beneficiary_type", business_beneficiary)
+    .on_constraint("auction_beneficiary_tenant", unknown_beneficiary)?;
+    bidding::schedule_winners(inserted.auction_id.0, &mut tx).await?;
+    tx.commit().await?;
+    Ok(inserted)
+}
+
+#[instrument(skip(db))]
+pub async fn update_auction_from_form(
+    pk: Uuid,
+    auction: tables::auction::AuctionFromForm,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<tables::auction::Auction>> {
+    let tz = tables::parse_timezone(&auction.timezone)
+        .map_err(|e| Error::unprocessable_entity([("timezone", e)]))?;
+    let mut tx = db.begin().await?;
+    let updated = sqlx::query_as!(
+        tables::auction::Auction,
+        r#"
+        update auction
+            set title = $2, description = $3, start_date = $4, end_date = $5,
+                benefits_organization_id = $6, timezone = $7
+            where auction_id = $1
+            and tenant_id = $8
+            returning
+                auction_id as "auction_id: tables::auction::AuctionId",
+                title, description, start_date, end_date,
+                benefits_organization_id as "benefits_organization_id: tables::organization::OrganizationId",
+                timezone, created_at, updated_at,
+                etag "etag: tables::Etag"
+        "#,
+        pk,
+        auction.title,
+        auction.description,
+        auction.start_date.assume_timezone(&tz),
+        auction.end_date.assume_timezone(&tz),
+        auction.benefits_organization_id.map(|o| o.0),
+        auction.timezone,
+        tenant_id
+    )
+    .fetch_optional(&mut tx)
+    .await
+    .on_constraint("auction_beneficiary_type", business_beneficiary)
+    .on_constraint("auction_beneficiary_tenant", unknown_beneficiary)?;
+    if let Some(updated) = &updated {
+        bidding::schedule_winners(updated.auction_id.0, &mut tx).await?;
+    }
+    tx.commit().await?;
+    Ok(updated)
+}
+
+fn business_beneficiary(_: Box<dyn sqlx::error::DatabaseError>) -> Error {
+    Error::unprocessable_entity([(
+        "benefits_organization_id",
+        "a business can donate items, but proceeds can only go to a non-profit or sanctuary",
+    )])
+}
+
+// another tenant's organization is as good as one that doesn't exist
+fn unknown_beneficiary(_: Box<dyn sqlx::error::DatabaseError>) -> Error {
+    Error::unprocessable_entity([("benefits_organization_id", "no such organization")])
+}
+
 #[instrument(skip(table, db))]
 pub async fn get_table_detail(
     table: &tables::Table,
     pk: Uuid,
+    tenant_id: Uuid,
     db: &PgPool,
-) -> Result<Option<impl ToForm>> {
-    match table {
-        tables::Table::Address => get_address_detail(pk, &db).await,
+) -> Result<Option<Box<dyn ToForm>>> {
+    Ok(match table {
+        tables::Table::Address => get_address_detail(pk, tenant_id, db)
+            .await?
+            .map(|r| Box::new(r) as Box<dyn ToForm>),
+        tables::Table::Auction => get_auction_detail(pk, tenant_id, db)
+            .await?
+            .map(|r| Box::new(r) as Box<dyn ToForm>),
         tables::Table::Article => todo!(),
-        tables::Table::Auction => todo!(),
         tables::Table::AuctionItem => todo!(),
         tables::Table::AuctionItemBid => todo!(),
-        tables::Table::AuctionItemDelivery => todo!(),
+        tables::Table::AuctionItemDelivery => {
+            if delivery_in_tenant(pk, tenant_id, db).await? {
+                deliveries::get_delivery(pk, db)
+                    .await?
+                    .map(|r| Box::new(r) as Box<dyn ToForm>)
+            } else {
+                None
+            }
+        }
         tables::Table::Organization => todo!(),
         tables::Table::User => todo!(),
-    }
+    })
 }
 
 #[instrument(skip(db))]
-pub async fn get_address_detail(pk: Uuid, db: &PgPool) -> Result<Option<tables::address::Address>> {
+pub async fn get_address_detail(
+    pk: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<tables::address::Address>> {
     sqlx::query_as!(
         tables::address::Address,
         r#"
@@ -273,10 +476,95 @@ pub async fn get_address_detail(pk: Uuid, db: &PgPool) -> Result<Option<tables::
                 etag "etag: tables::Etag"
             from address
             where address_id = $1
+            and tenant_id = $2
         "#,
-        pk
+        pk,
+        tenant_id
     )
     .fetch_optional(db)
     .await
     .map_err(Error::Sqlx)
 }
+
+#[instrument(skip(db))]
+pub async fn get_auction_detail(
+    pk: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<tables::auction::Auction>> {
+    sqlx::query_as!(
+        tables::auction::Auction,
+        r#"
+            select
+                auction_id "auction_id: tables::auction::AuctionId",
+                title,
+                description,
+                start_date,
+                end_date,
+                benefits_organization_id "benefits_organization_id: tables::organization::OrganizationId",
+                timezone,
+                created_at,
+                updated_at,
+                etag "etag: tables::Etag"
+            from auction
+            where auction_id = $1
+            and tenant_id = $2
+        "#,
+        pk,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Whether the delivery is of an item in the tenant. Deliveries are saved by
+/// `crate::db::deliveries`, which knows nothing of tenants, so check this first.
+#[instrument(skip(db))]
+pub async fn delivery_in_tenant(delivery_id: Uuid, tenant_id: Uuid, db: &PgPool) -> Result<bool> {
+    sqlx::query_scalar!(
+        r#"
+            select exists (
+                select 1
+                from auction_item_delivery aid
+                inner join auction_item_bid aib
+                on aib.auction_item_bid_id = aid.auction_item_bid_id
+                inner join auction_item ai
+                on ai.auction_item_id = aib.auction_item_id
+                where aid.delivery_id = $1
+                and ai.tenant_id = $2
+            ) "exists!"
+        "#,
+        delivery_id,
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Whether the bid is on an item in the tenant.
+#[instrument(skip(db))]
+pub async fn bid_in_tenant(
+    auction_item_bid_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<bool> {
+    sqlx::query_scalar!(
+        r#"
+            select exists (
+                select 1
+                from auction_item_bid aib
+                inner join auction_item ai
+                on ai.auction_item_id = aib.auction_item_id
+                where aib.auction_item_bid_id = $1
+                and ai.tenant_id = $2
+            ) "exists!"
+        "#,
+        auction_item_bid_id,
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,317 @@
+use axum::{
+    extract::{Extension, Path},
+    http::{StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::auth;
+use crate::db::bidding;
+use crate::db::organizations;
+use crate::db::tables::user::LoginUser;
+use crate::db::tenants::{self, Tenant};
+use crate::endpoints::auctions::{
+    self, AuctionSummary, BidFromForm, BidHistoryRow, ItemCard, ItemDetail,
+};
+use crate::endpoints::dashboard::{self, BidItem};
+use crate::endpoints::extractor::{AuthUser, MaybeAuthUser};
+use crate::endpoints::organizations::{self as portal, Membership, OrganizationItem};
+use crate::endpoints::{users, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{parse_json, queries, Me, OrganizationDetail, Page, PageParams, PlacedBid, Session};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/api/v1/users/login", post(login))
+        .route("/api/v1/users/me", get(get_me))
+        .route("/api/v1/users/me/bids", get(list_my_bids))
+        .route("/api/v1/auctions", get(list_auctions))
+        .route("/api/v1/auctions/:auction_id", get(get_auction))
+        .route("/api/v1/auctions/:auction_id/items", get(list_items))
+        .route(
+            "/api/v1/auctions/:auction_id/items/:auction_item_id",
+            get(get_item),
+        )
+        .route(
+            "/api/v1/auctions/:auction_id/items/:auction_item_id/bids",
+            get(list_bids).post(place_bid),
+        )
+        .route("/api/v1/organizations", get(list_organizations))
+        .route(
+            "/api/v1/organizations/:organization_id",
+            get(get_organization),
+        )
+        .route(
+            "/api/v1/organizations/:organization_id/items",
+            get(list_organization_items),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct AuctionItemParams {
+    auction_id: Uuid,
+    auction_item_id: Uuid,
+}
+
+/// `?page=` and `?per_page=`, reporting anything else in the query string as a 422.
+fn page_params(uri: &Uri) -> Result<PageParams> {
+    serde_urlencoded::from_str(uri.query().unwrap_or_default())
+        .map_err(|e| Error::unprocessable_entity([("query", e.to_string())]))
+}
+
+/// A token for `{"email": ..., "password": ...}`. A wrong password is a 401, like a missing
+/// or expired token.
+#[instrument(skip(ctx, body))]
+async fn login(ctx: Extension<ApiContext>, tenant: Tenant, body: String) -> Result<Json<Session>> {
+    let form: LoginUser = parse_json(&body)?;
+    let user = users::queries::get_user_login(&form.email, &ctx.db)
+        .await?
+        .ok_or(Error::Unauthorized)?;
+    auth::verify_password(form.password, user.password_hash).await?;
+    // accounts are shared, so this may be their first time at this tenant
+    tenants::join(tenant.tenant_id, user.user_id, &ctx.db).await?;
+    let auth_user = AuthUser {
+        user_id: user.user_id,
+    };
+    let me = queries::get_me(user.user_id, &ctx.db)
+        .await?
+        .ok_or(Error::Unauthorized)?;
+    Ok(Json(Session {
+        token: auth_user.to_jwt(&ctx, tenant.tenant_id),
+        user: me,
+    }))
+}
+
+#[instrument(skip(ctx))]
+async fn get_me(auth_user: AuthUser, ctx: Extension<ApiContext>) -> Result<Json<Me>> {
+    // a token outliving its user is as good as no token
+    let me = queries::get_me(auth_user.user_id, &ctx.db)
+        .await?
+        .ok_or(Error::Unauthorized)?;
+    Ok(Json(me))
+}
+
+/// Everything the user has bid on at this tenant, as their dashboard lists it.
+#[instrument(skip(ctx))]
+async fn list_my_bids(
+    auth_user: AuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+) -> Result<Json<Page<BidItem>>> {
+    let params = page_params(&uri)?;
+    let (limit, offset) = params.limit_offset()?;
+    let items = dashboard::queries::list_bid_items(
+        auth_user.user_id,
+        tenant.tenant_id,
+        Some(limit),
+        offset,
+        &ctx.db,
+    )
+    .await?;
+    let total =
+        dashboard::queries::count_bid_items(auth_user.user_id, tenant.tenant_id, &ctx.db).await?;
+    Ok(Json(Page::from_query(items, total, &params)?))
+}
+
+#[instrument(skip(ctx))]
+async fn list_auctions(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+) -> Result<Json<Page<AuctionSummary>>> {
+    let params = page_params(&uri)?;
+    let (limit, offset) = params.limit_offset()?;
+    let auctions =
+        auctions::queries::list_auctions(tenant.tenant_id, Some(limit), offset, &ctx.db).await?;
+    let total = auctions::queries::count_auctions(tenant.tenant_id, &ctx.db).await?;
+    Ok(Json(Page::from_query(auctions, total, &params)?))
+}
+
+#[instrument(skip(ctx))]
+async fn get_auction(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_id): Path<Uuid>,
+) -> Result<Json<AuctionSummary>> {
+    let auction = auctions::queries::get_auction(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    Ok(Json(auction))
+}
+
+/// The items that can be bid on, as in the auction's item grid.
+#[instrument(skip(ctx))]
+async fn list_items(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+    Path(auction_id): Path<Uuid>,
+) -> Result<Json<Page<ItemCard>>> {
+    let params = page_params(&uri)?;
+    auctions::queries::get_auction(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let (limit, offset) = params.limit_offset()?;
+    let items =
+        auctions::queries::list_auction_items(auction_id, Some(limit), offset, &ctx.db).await?;
+    let total = auctions::queries::count_auction_items(auction_id, &ctx.db).await?;
+    Ok(Json(Page::from_query(items, total, &params)?))
+}
+
+#[instrument(skip(ctx))]
+async fn get_item(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+) -> Result<Json<ItemDetail>> {
+    let item =
+        auctions::queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+            .await?
+            .ok_or(Error::NotFound)?;
+    Ok(Json(item))
+}
+
+/// The highest bids first, with bidders numbered as on the item's page.
+#[instrument(skip(ctx))]
+async fn list_bids(
+    auth_user: MaybeAuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+) -> Result<Json<Page<BidHistoryRow>>> {
+    let params = page_params(&uri)?;
+    auctions::queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let (limit, offset) = params.limit_offset()?;
+    let bids = auctions::queries::list_bid_history(
+        auction_item_id,
+        auth_user.user_id(),
+        Some(limit),
+        offset,
+        &ctx.db,
+    )
+    .await?;
+    let total = auctions::queries::count_bids(auction_item_id, &ctx.db).await?;
+    Ok(Json(Page::from_query(bids, total, &params)?))
+}
+
+/// `{"amount": "25.00"}`, with an optional `"max_bid_amount"` for a proxy bid. A bid which
+/// is too low or too late is a 422, as `bidding::place_bid` explains it.
+#[instrument(skip(ctx, body))]
+async fn place_bid(
+    auth_user: AuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+    body: String,
+) -> Result<Response> {
+    auctions::queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let bid: BidFromForm = parse_json(&body)?;
+    let outcome = bidding::place_bid(
+        auction_item_id,
+        auth_user.user_id,
+        bid.amount,
+        bid.max_bid_amount,
+        &ctx.db,
+    )
+    .await?;
+    event!(
+        Level::INFO,
+        event_msg = "Placed a bid through the API",
+        auction_item_id = %auction_item_id,
+        outcome = ?outcome
+    );
+    let item =
+        auctions::queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+            .await?
+            .ok_or(Error::NotFound)?;
+    Ok((StatusCode::CREATED, Json(PlacedBid { outcome, item })).into_response())
+}
+
+/// The organizations the user looks after at this tenant.
+#[instrument(skip(ctx))]
+async fn list_organizations(
+    auth_user: AuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+) -> Result<Json<Page<Membership>>> {
+    let params = page_params(&uri)?;
+    let (limit, offset) = params.limit_offset()?;
+    let memberships = portal::queries::list_memberships(
+        auth_user.user_id,
+        tenant.tenant_id,
+        Some(limit),
+        offset,
+        &ctx.db,
+    )
+    .await?;
+    let total =
+        portal::queries::count_memberships(auth_user.user_id, tenant.tenant_id, &ctx.db).await?;
+    Ok(Json(Page::from_query(memberships, total, &params)?))
+}
+
+/// Only for its members: anyone else gets a 404, as in the portal.
+#[instrument(skip(ctx))]
+async fn get_organization(
+    auth_user: AuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Json<OrganizationDetail>> {
+    let role = require_member(&ctx, &tenant, &auth_user, organization_id).await?;
+    Ok(Json(OrganizationDetail {
+        organization_id,
+        role,
+        profile: organizations::get_profile(organization_id, &ctx.db).await?,
+        proceeds: portal::queries::get_proceeds(organization_id, &ctx.db).await?,
+    }))
+}
+
+/// Items the organization donated or which raise money for it.
+#[instrument(skip(ctx))]
+async fn list_organization_items(
+    auth_user: AuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Json<Page<OrganizationItem>>> {
+    let params = page_params(&uri)?;
+    require_member(&ctx, &tenant, &auth_user, organization_id).await?;
+    let (limit, offset) = params.limit_offset()?;
+    let items = portal::queries::list_items(organization_id, Some(limit), offset, &ctx.db).await?;
+    let total = portal::queries::count_items(organization_id, &ctx.db).await?;
+    Ok(Json(Page::from_query(items, total, &params)?))
+}
+
+async fn require_member(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    auth_user: &AuthUser,
+    organization_id: Uuid,
+) -> Result<organizations::OrgRole> {
+    portal::queries::get_organization_name(organization_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    organizations::require_member(organization_id, auth_user.user_id, &ctx.db).await
+}
@@ -0,0 +1,186 @@
+//! The JSON API under `/api/v1`, for the mobile app and partner sites: auctions, their items
+//! and bids, the user's own account and bids, and the organizations they look after.
+//!
+//! Resources are the same `Serialize` types the pages render, and errors are `Error`'s own
+//! responses. Clients log in at `/api/v1/users/login` for a token to send as
+//! `Authorization: Bearer <token>`, which only the tenant that issued it accepts. Lists come
+//! in a `Page`, which `?page=` and `?per_page=` choose. Money is sent and returned as strings,
+//! like `"25.00"`.
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::bidding::BidOutcome;
+use crate::db::organizations::{OrgRole, OrganizationProfile};
+use crate::endpoints::auctions::ItemDetail;
+use crate::endpoints::organizations::Proceeds;
+use crate::error::{Error, Result};
+pub use handlers::router;
+
+const DEFAULT_PER_PAGE: usize = 25;
+const MAX_PER_PAGE: usize = 100;
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageParams {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+/// One page of a list, and where it is in the whole list.
+#[derive(Debug, serde::Serialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Pagination {
+    // counting from 1
+    pub page: usize,
+    pub per_page: usize,
+    pub total: usize,
+    pub total_pages: usize,
+}
+
+impl PageParams {
+    /// The page and page size asked for, or a 422 for ones we don't serve.
+    fn page_and_size(&self) -> Result<(usize, usize)> {
+        let page = self.page.unwrap_or(1);
+        let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if page < 1 {
+            return Err(Error::unprocessable_entity([("page", "pages start at 1")]));
+        }
+        if !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(Error::unprocessable_entity([(
+                "per_page",
+                format!("per_page must be between 1 and {}", MAX_PER_PAGE),
+            )]));
+        }
+        Ok((page, per_page))
+    }
+
+    /// `limit` and `offset` for a query which reads only the page asked for.
+    pub fn limit_offset(&self) -> Result<(i64, i64)> {
+        let (page, per_page) = self.page_and_size()?;
+        let offset = (page - 1)
+            .checked_mul(per_page)
+            .and_then(|offset| i64::try_from(offset).ok())
+            .ok_or_else(|| Error::unprocessable_entity([("page", "page is too large")]))?;
+        Ok((per_page as i64, offset))
+    }
+}
+
+impl<T> Page<T> {
+    /// A page read with `params.limit_offset()`, out of `total` rows in all.
+    pub fn from_query(data: Vec<T>, total: i64, params: &PageParams) -> Result<Self> {
+        let (page, per_page) = params.page_and_size()?;
+        Ok(Self::of(data, total.max(0) as usize, page, per_page))
+    }
+
+    fn of(data: Vec<T>, total: usize, page: usize, per_page: usize) -> Self {
+        Page {
+            data,
+            pagination: Pagination {
+                page,
+                per_page,
+                total,
+                total_pages: total.div_ceil(per_page),
+            },
+        }
+    }
+}
+
+/// The logged in user.
+#[derive(Debug, serde::Serialize)]
+pub struct Me {
+    pub user_id: Uuid,
+    pub email: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+/// What logging in returns: the token to send with later requests, and who it's for.
+#[derive(Debug, serde::Serialize)]
+pub struct Session {
+    pub token: String,
+    pub user: Me,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PlacedBid {
+    pub outcome: BidOutcome,
+    // with the high bid including this one
+    pub item: ItemDetail,
+}
+
+/// One of the user's organizations, as its portal shows it.
+#[derive(Debug, serde::Serialize)]
+pub struct OrganizationDetail {
+    pub organization_id: Uuid,
+    pub role: OrgRole,
+    pub profile: OrganizationProfile,
+    pub proceeds: Proceeds,
+}
+
+/// Decode a JSON body, reporting a bad one as a 422 like a bad form.
+fn parse_json<T: serde::de::DeserializeOwned>(body: &str) -> Result<T> {
+    serde_json::from_str(body).map_err(|e| Error::unprocessable_entity([("body", e.to_string())]))
+}
+
+#[test]
+fn test_page() {
+    let params = PageParams {
+        page: Some(2),
+        per_page: Some(2),
+    };
+    assert_eq!(params.limit_offset().unwrap(), (2, 2));
+    let page = Page::from_query(vec![3, 4], 5, &params).unwrap();
+    assert_eq!(page.data, vec![3, 4]);
+    assert_eq!(
+        page.pagination,
+        Pagination {
+            page: 2,
+            per_page: 2,
+            total: 5,
+            total_pages: 3
+        }
+    );
+
+    let page = Page::from_query(Vec::<i32>::new(), 0, &PageParams::default()).unwrap();
+    assert!(page.data.is_empty());
+    assert_eq!(page.pagination.total_pages, 0);
+
+    let past_the_end = PageParams {
+        page: Some(9),
+        per_page: None,
+    };
+    assert_eq!(
+        past_the_end.limit_offset().unwrap(),
+        (DEFAULT_PER_PAGE as i64, 8 * DEFAULT_PER_PAGE as i64)
+    );
+    for (page, per_page) in [(0, 10), (1, 0), (1, MAX_PER_PAGE + 1)] {
+        let params = PageParams {
+            page: Some(page),
+            per_page: Some(per_page),
+        };
+        assert!(matches!(
+            Page::from_query(vec![1], 1, &params),
+            Err(Error::UnprocessableEntity { .. })
+        ));
+        assert!(matches!(
+            params.limit_offset(),
+            Err(Error::UnprocessableEntity { .. })
+        ));
+    }
+
+    let too_far = PageParams {
+        page: Some(usize::MAX),
+        per_page: Some(MAX_PER_PAGE),
+    };
+    assert!(matches!(
+        too_far.limit_offset(),
+        Err(Error::UnprocessableEntity { .. })
+    ));
+}
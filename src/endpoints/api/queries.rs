@@ -0,0 +1,23 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::Me;
+
+#[instrument(skip(db))]
+pub async fn get_me(user_id: Uuid, db: &PgPool) -> Result<Option<Me>> {
+    sqlx::query_as!(
+        Me,
+        r#"
+            select user_id, email, first_name, last_name
+            from "user"
+            where user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
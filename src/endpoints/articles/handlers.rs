@@ -0,0 +1,283 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::header::HeaderMap,
+    response::Html,
+    routing::{get, post},
+    Router,
+};
+use minijinja::context;
+use std::collections::BTreeMap;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::articles;
+use crate::db::tables::article::{Article, ArticleFromForm};
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::{AuthUser, MaybeAuthUser};
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, ArticleListParams};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/articles", get(list_articles))
+        .route("/articles/:slug", get(get_article))
+}
+
+pub fn admin_router() -> Router {
+    Router::new()
+        .route("/admin/articles", get(get_admin_articles))
+        .route(
+            "/admin/articles/new",
+            get(get_new_article).post(create_article),
+        )
+        .route(
+            "/admin/articles/:article_id",
+            get(get_edit_article).put(update_article),
+        )
+        .route("/admin/articles/:article_id/publish", post(publish_article))
+        .route(
+            "/admin/articles/:article_id/unpublish",
+            post(unpublish_article),
+        )
+}
+
+#[instrument(skip(ctx))]
+async fn list_articles(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    params: Option<Query<ArticleListParams>>,
+) -> Result<Html<String>> {
+    let Query(params) = params.unwrap_or_default();
+    let tag = params.tag.as_deref();
+    let articles = queries::list_published(tag, tenant.tenant_id, &ctx.db).await?;
+    render_page(
+        &ctx,
+        &headers,
+        "article_list.html",
+        context!(
+            title => "News",
+            logged_in => auth_user.0.is_some(),
+            articles => articles,
+            tags => queries::list_tags(tenant.tenant_id, &ctx.db).await?,
+            tag => tag,
+        ),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn get_article(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(slug): Path<String>,
+) -> Result<Html<String>> {
+    let article = queries::get_published(&slug, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    render_page(
+        &ctx,
+        &headers,
+        "article.html",
+        context!(
+            title => article.title.clone(),
+            logged_in => auth_user.0.is_some(),
+            article => article,
+        ),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn get_admin_articles(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Result<Html<String>> {
+    render_admin_articles(&ctx, &tenant, Some(&headers), None).await
+}
+
+#[instrument(skip(ctx))]
+async fn get_new_article(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Result<Html<String>> {
+    render_article_form(&ctx, &tenant, Some(&headers), None, BTreeMap::new(), vec![]).await
+}
+
+/// Problems are rendered into the form, since htmx won't swap in an error response.
+#[instrument(skip(ctx, body))]
+async fn create_article(
+    auth_user: AuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    body: String,
+) -> Result<Html<String>> {
+    let created = match parse_form::<ArticleFromForm>(&body) {
+        Ok(form) => articles::create(&form, auth_user.user_id, tenant.tenant_id, &ctx.db).await,
+        Err(e) => Err(e),
+    };
+    match created {
+        Ok(article) => {
+            event!(
+                Level::INFO,
+                event_msg = "Saved a new article",
+                article_id = %article.article_id,
+                slug = %article.slug
+            );
+            let message = format!("Saved \"{}\" as a draft.", article.title);
+            render_admin_articles(&ctx, &tenant, None, Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            // what was typed so far, to fill the form back in
+            let typed: BTreeMap<String, String> = parse_form(&body)?;
+            render_article_form(&ctx, &tenant, None, None, typed, errors).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_edit_article(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(article_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    let article = queries::get_article(article_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let form = article_form(&article);
+    render_article_form(&ctx, &tenant, Some(&headers), Some(article), form, vec![]).await
+}
+
+#[instrument(skip(ctx, body))]
+async fn update_article(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(article_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    let updated = match parse_form::<ArticleFromForm>(&body) {
+        Ok(form) => articles::update(article_id, &form, tenant.tenant_id, &ctx.db).await,
+        Err(e) => Err(e),
+    };
+    match updated {
+        Ok(Some(article)) => {
+            event!(
+                Level::INFO,
+                event_msg = "Updated an article",
+                article_id = %article_id,
+                slug = %article.slug
+            );
+            let message = format!("Saved \"{}\".", article.title);
+            render_admin_articles(&ctx, &tenant, None, Some(message)).await
+        }
+        Ok(None) => Err(Error::NotFound),
+        Err(Error::UnprocessableEntity { errors }) => {
+            let article = queries::get_article(article_id, tenant.tenant_id, &ctx.db)
+                .await?
+                .ok_or(Error::NotFound)?;
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            let typed: BTreeMap<String, String> = parse_form(&body)?;
+            render_article_form(&ctx, &tenant, None, Some(article), typed, errors).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn publish_article(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(article_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    let article = articles::publish(article_id, true, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    event!(Level::INFO, event_msg = "Published an article", article_id = %article_id);
+    let message = format!(
+        "Published \"{}\" at /articles/{}.",
+        article.title, article.slug
+    );
+    render_admin_articles(&ctx, &tenant, None, Some(message)).await
+}
+
+#[instrument(skip(ctx))]
+async fn unpublish_article(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(article_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    let article = articles::publish(article_id, false, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    event!(Level::INFO, event_msg = "Unpublished an article", article_id = %article_id);
+    let message = format!("\"{}\" is a draft again.", article.title);
+    render_admin_articles(&ctx, &tenant, None, Some(message)).await
+}
+
+/// An article's fields as the form has them.
+fn article_form(article: &Article) -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("title".to_string(), article.title.clone()),
+        ("slug".to_string(), article.slug.clone()),
+        ("description".to_string(), article.description.clone()),
+        ("body".to_string(), article.body.clone()),
+        ("tags".to_string(), article.tag_list.join(", ")),
+        (
+            "auction_id".to_string(),
+            article
+                .auction_id
+                .map(|auction_id| auction_id.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "featured_image_filepath".to_string(),
+            article.featured_image_filepath.clone(),
+        ),
+    ])
+}
+
+/// The full page when `headers` are given, otherwise only the list fragment.
+async fn render_admin_articles(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let context = context!(
+        articles => queries::list_articles(tenant.tenant_id, &ctx.db).await?,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_articles.html", context),
+        None => render_template(ctx, "fragments/admin_articles.html", context),
+    }
+}
+
+/// The form for a new article, or for editing `article`.
+async fn render_article_form(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    article: Option<Article>,
+    form: BTreeMap<String, String>,
+    errors: Vec<String>,
+) -> Result<Html<String>> {
+    let context = context!(
+        article => article,
+        form => form,
+        errors => errors,
+        auctions => queries::list_auction_choices(tenant.tenant_id, &ctx.db).await?,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_article_form.html", context),
+        None => render_template(ctx, "fragments/admin_article_form.html", context),
+    }
+}
@@ -0,0 +1,64 @@
+//! News articles: the public listing and article pages, and the admin pages where articles
+//! are written and published. See `crate::db::articles`.
+//!
+//! Visitors only ever see published articles, so a draft's slug is as good as missing.
+use sqlx::types::time::OffsetDateTime;
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables::{self, serialize_dt, serialize_dt_opt};
+pub use handlers::{admin_router, router};
+
+#[derive(Debug, serde::Serialize)]
+pub struct ArticleSummary {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub tag_list: Vec<String>,
+    pub featured_image_filepath: String,
+    #[serde(serialize_with = "serialize_dt")]
+    pub published_at: OffsetDateTime,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ArticleDetail {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    // markdown, for the `markdown` filter
+    pub body: String,
+    pub tag_list: Vec<String>,
+    pub featured_image_filepath: String,
+    #[serde(serialize_with = "serialize_dt")]
+    pub published_at: OffsetDateTime,
+    pub auction_id: Option<Uuid>,
+    pub auction_title: Option<String>,
+}
+
+/// A published article or a draft, as listed for admins.
+#[derive(Debug, serde::Serialize)]
+pub struct AdminArticle {
+    pub article_id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub tag_list: Vec<String>,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub published_at: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// An auction an article can be about.
+#[derive(Debug, serde::Serialize)]
+pub struct AuctionChoice {
+    pub auction_id: Uuid,
+    pub title: String,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ArticleListParams {
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub tag: Option<String>,
+}
@@ -0,0 +1,151 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tables::article::Article;
+use crate::{error::Result, Error};
+
+use super::{AdminArticle, ArticleDetail, ArticleSummary, AuctionChoice};
+
+/// The newest first, only those with `tag` when one is given.
+#[instrument(skip(db))]
+pub async fn list_published(
+    tag: Option<&str>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<ArticleSummary>> {
+    sqlx::query_as!(
+        ArticleSummary,
+        r#"
+            select
+                slug,
+                title,
+                description,
+                tag_list,
+                featured_image_filepath,
+                published_at "published_at!"
+            from article
+            where tenant_id = $1
+            and published_at is not null
+            and ($2::text is null or $2 = any(tag_list))
+            order by published_at desc
+        "#,
+        tenant_id,
+        tag
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Every tag on a published article, for browsing by.
+#[instrument(skip(db))]
+pub async fn list_tags(tenant_id: Uuid, db: &PgPool) -> Result<Vec<String>> {
+    sqlx::query_scalar!(
+        r#"
+            select distinct tag "tag!"
+            from article, unnest(tag_list) tag
+            where tenant_id = $1
+            and published_at is not null
+            order by 1
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_published(
+    slug: &str,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<ArticleDetail>> {
+    sqlx::query_as!(
+        ArticleDetail,
+        r#"
+            select
+                ar.slug,
+                ar.title,
+                ar.description,
+                ar.body,
+                ar.tag_list,
+                ar.featured_image_filepath,
+                ar.published_at "published_at!",
+                ar.auction_id,
+                a.title "auction_title?"
+            from article ar
+            left join auction a
+            on a.auction_id = ar.auction_id
+            where ar.slug = $1
+            and ar.tenant_id = $2
+            and ar.published_at is not null
+        "#,
+        slug,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Drafts first, then the most recently published.
+#[instrument(skip(db))]
+pub async fn list_articles(tenant_id: Uuid, db: &PgPool) -> Result<Vec<AdminArticle>> {
+    sqlx::query_as!(
+        AdminArticle,
+        r#"
+            select article_id, slug, title, tag_list, published_at, updated_at
+            from article
+            where tenant_id = $1
+            order by published_at desc nulls first, updated_at desc
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// An article to edit, whether or not it's published.
+#[instrument(skip(db))]
+pub async fn get_article(
+    article_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<Article>> {
+    sqlx::query_as!(
+        Article,
+        r#"
+            select
+                article_id, auction_id, user_id, slug, title, description, body, tag_list,
+                featured_image_filepath, published_at, created_at, updated_at
+            from article
+            where article_id = $1
+            and tenant_id = $2
+        "#,
+        article_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_auction_choices(tenant_id: Uuid, db: &PgPool) -> Result<Vec<AuctionChoice>> {
+    sqlx::query_as!(
+        AuctionChoice,
+        r#"
+            select auction_id, title
+            from auction
+            where tenant_id = $1
+            order by start_date desc
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,292 @@
+use axum::{
+    extract::{Extension, Path},
+    http::header::HeaderMap,
+    response::Html,
+    routing::{get, post},
+    Router,
+};
+use minijinja::context;
+use serde::Deserialize;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::bidding::{self, BidOutcome};
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::{AuthUser, MaybeAuthUser};
+use crate::endpoints::{render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, BidFromForm, ItemDetail};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/auctions", get(list_auctions))
+        .route("/auctions/:auction_id", get(get_auction))
+        .route("/auctions/:auction_id/items", get(get_auction_item_grid))
+        .route(
+            "/auctions/:auction_id/items/:auction_item_id",
+            get(get_auction_item),
+        )
+        .route(
+            "/auctions/:auction_id/items/:auction_item_id/bids",
+            get(get_bid_panel).post(place_bid),
+        )
+        .route(
+            "/auctions/:auction_id/items/:auction_item_id/watch",
+            post(watch_item).delete(unwatch_item),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct AuctionItemParams {
+    auction_id: Uuid,
+    auction_item_id: Uuid,
+}
+
+#[instrument(skip(ctx))]
+async fn list_auctions(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Result<Html<String>> {
+    let auctions = queries::list_auctions(tenant.tenant_id, None, 0, &ctx.db).await?;
+    render_page(
+        &ctx,
+        &headers,
+        "auction_list.html",
+        context!(
+            title => "Auctions",
+            logged_in => auth_user.0.is_some(),
+            auctions => auctions,
+        ),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn get_auction(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    let auction = queries::get_auction(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let items = queries::list_auction_items(auction_id, None, 0, &ctx.db).await?;
+    render_page(
+        &ctx,
+        &headers,
+        "auction_detail.html",
+        context!(
+            title => auction.title,
+            logged_in => auth_user.0.is_some(),
+            auction => auction,
+            items => items,
+        ),
+    )
+}
+
+/// Only the item grid, which the auction page polls to keep high bids current.
+#[instrument(skip(ctx))]
+async fn get_auction_item_grid(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    let auction = queries::get_auction(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let items = queries::list_auction_items(auction_id, None, 0, &ctx.db).await?;
+    render_template(
+        &ctx,
+        "fragments/auction_item_grid.html",
+        context!(auction => auction, items => items),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn get_auction_item(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+) -> Result<Html<String>> {
+    let item = queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let basket_items = queries::list_basket_items(auction_item_id, &ctx.db).await?;
+    let bids =
+        queries::list_bid_history(auction_item_id, auth_user.user_id(), None, 0, &ctx.db).await?;
+    let watching = match auth_user.user_id() {
+        Some(user_id) => queries::is_watching(user_id, auction_item_id, &ctx.db).await?,
+        None => false,
+    };
+    render_page(
+        &ctx,
+        &headers,
+        "auction_item_detail.html",
+        context!(
+            title => item.title,
+            logged_in => auth_user.0.is_some(),
+            item => item,
+            basket_items => basket_items,
+            bids => bids,
+            watching => watching,
+        ),
+    )
+}
+
+/// The bid form and bid history, which the item page polls.
+#[instrument(skip(ctx))]
+async fn get_bid_panel(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    auth_user: MaybeAuthUser,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+) -> Result<Html<String>> {
+    let item = queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    render_bid_panel(&ctx, &auth_user, item, vec![], None).await
+}
+
+/// Bidding problems are rendered into the panel rather than returned as error statuses,
+/// because htmx will not swap in the body of an error response.
+#[instrument(skip(ctx, body))]
+async fn place_bid(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    auth_user: MaybeAuthUser,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+    body: String,
+) -> Result<Html<String>> {
+    let item = queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let user_id = match auth_user.user_id() {
+        Some(user_id) => user_id,
+        None => {
+            return render_bid_panel(
+                &ctx,
+                &auth_user,
+                item,
+                vec!["Log in to place a bid.".to_string()],
+                None,
+            )
+            .await
+        }
+    };
+    let result = match serde_urlencoded::from_str::<BidFromForm>(&body) {
+        Ok(bid) => {
+            bidding::place_bid(
+                auction_item_id,
+                user_id,
+                bid.amount,
+                bid.max_bid_amount,
+                &ctx.db,
+            )
+            .await
+        }
+        Err(_) => Err(Error::unprocessable_entity([(
+            "amount",
+            "enter bid amounts in dollars, e.g. 25.00",
+        )])),
+    };
+    let (errors, message) = match result {
+        Ok(BidOutcome::Leading) => (vec![], Some("You have the high bid!")),
+        Ok(BidOutcome::Outbid) => (
+            vec![],
+            Some("Your bid was placed, but another bidder's maximum is higher."),
+        ),
+        Err(Error::UnprocessableEntity { errors }) => (
+            errors.into_values().flatten().map(String::from).collect(),
+            None,
+        ),
+        Err(e) => {
+            event!(Level::ERROR, event_msg="Error placing bid", err=?e);
+            return Err(e);
+        }
+    };
+    // re-read the item so the high bid includes this bid
+    let item = queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .unwrap_or(item);
+    render_bid_panel(&ctx, &auth_user, item, errors, message).await
+}
+
+#[instrument(skip(ctx))]
+async fn watch_item(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    auth_user: AuthUser,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+) -> Result<Html<String>> {
+    let item = queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    queries::watch_item(auth_user.user_id, auction_item_id, &ctx.db).await?;
+    render_watch_button(&ctx, item, true)
+}
+
+#[instrument(skip(ctx))]
+async fn unwatch_item(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    auth_user: AuthUser,
+    Path(AuctionItemParams {
+        auction_id,
+        auction_item_id,
+    }): Path<AuctionItemParams>,
+) -> Result<Html<String>> {
+    let item = queries::get_auction_item(auction_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    queries::unwatch_item(auth_user.user_id, auction_item_id, &ctx.db).await?;
+    render_watch_button(&ctx, item, false)
+}
+
+fn render_watch_button(ctx: &ApiContext, item: ItemDetail, watching: bool) -> Result<Html<String>> {
+    render_template(
+        ctx,
+        "fragments/watch_button.html",
+        context!(item => item, watching => watching),
+    )
+}
+
+async fn render_bid_panel(
+    ctx: &ApiContext,
+    auth_user: &MaybeAuthUser,
+    item: ItemDetail,
+    errors: Vec<String>,
+    message: Option<&str>,
+) -> Result<Html<String>> {
+    let bids =
+        queries::list_bid_history(item.auction_item_id, auth_user.user_id(), None, 0, &ctx.db)
+            .await?;
+    render_template(
+        ctx,
+        "fragments/auction_item_bids.html",
+        context!(
+            logged_in => auth_user.0.is_some(),
+            item => item,
+            bids => bids,
+            errors => errors,
+            message => message,
+        ),
+    )
+}
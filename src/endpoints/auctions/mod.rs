@@ -0,0 +1,83 @@
+//! The public side of the site: browsing auctions and bidding on their items.
+//!
+//! These queries only ever expose what a visitor is allowed to see, so they are kept apart
+//! from the admin queries, which can see everything.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+pub(super) mod queries;
+
+use crate::db::tables::{self, serialize_dt};
+pub use handlers::router;
+
+#[derive(Debug, serde::Serialize)]
+pub struct AuctionSummary {
+    pub auction_id: Uuid,
+    pub title: String,
+    pub description: String,
+    #[serde(serialize_with = "serialize_dt")]
+    pub start_date: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub end_date: OffsetDateTime,
+    pub timezone: String,
+    // one of "upcoming", "open" or "closed"
+    pub status: String,
+    pub benefits_organization_name: Option<String>,
+    pub item_count: i64,
+}
+
+/// An item as shown in an auction's item grid.
+#[derive(Debug, serde::Serialize)]
+pub struct ItemCard {
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub title: String,
+    pub featured_image_filepath: String,
+    pub minimum_bid_amount: Decimal,
+    pub high_bid: Option<Decimal>,
+    pub bid_count: i64,
+    #[serde(serialize_with = "serialize_dt")]
+    pub active_end_date: OffsetDateTime,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ItemDetail {
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub auction_title: String,
+    pub timezone: String,
+    pub basket_id: Option<Uuid>,
+    pub title: String,
+    pub description: String,
+    pub featured_image_filepath: String,
+    pub expected_retail_value: Decimal,
+    pub minimum_bid_amount: Decimal,
+    pub buy_it_now_amount: Option<Decimal>,
+    pub high_bid: Option<Decimal>,
+    pub donated_by_organization_name: Option<String>,
+    pub benefits_organization_name: Option<String>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub active_start_date: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub active_end_date: OffsetDateTime,
+    pub is_open: bool,
+}
+
+/// A bid as other visitors see it: bidders are only numbered in the order they joined in.
+#[derive(Debug, serde::Serialize)]
+pub struct BidHistoryRow {
+    pub bidder_number: i64,
+    // whether the bid belongs to whoever is looking at it
+    pub is_viewer: bool,
+    pub amount: Decimal,
+    #[serde(serialize_with = "serialize_dt")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct BidFromForm {
+    pub amount: Decimal,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub max_bid_amount: Option<Decimal>,
+}
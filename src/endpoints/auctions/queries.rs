@@ -0,0 +1,361 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{AuctionSummary, BidHistoryRow, ItemCard, ItemDetail};
+
+/// Open and upcoming auctions first. `limit` and `offset` read a page of them, and a `None`
+/// limit all of them.
+#[instrument(skip(db))]
+pub async fn list_auctions(
+    tenant_id: Uuid,
+    limit: Option<i64>,
+    offset: i64,
+    db: &PgPool,
+) -> Result<Vec<AuctionSummary>> {
+    sqlx::query_as!(
+        AuctionSummary,
+        r#"
+            select
+                a.auction_id,
+                a.title,
+                a.description,
+                a.start_date,
+                a.end_date,
+                a.timezone,
+                case
+                    when now() < a.start_date then 'upcoming'
+                    when now() < a.end_date then 'open'
+                    else 'closed'
+                end "status!",
+                org.name "benefits_organization_name?",
+                (
+                    select count(*)
+                    from auction_item ai
+                    where ai.auction_id = a.auction_id
+                    and ai.basket_id is null
+                ) "item_count!"
+            from auction a
+            left join organization org
+            on org.organization_id = a.benefits_organization_id
+            where a.tenant_id = $1
+            order by
+                now() >= a.end_date,
+                a.start_date,
+                a.auction_id
+            limit $2
+            offset $3
+        "#,
+        tenant_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn count_auctions(tenant_id: Uuid, db: &PgPool) -> Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from auction
+            where tenant_id = $1
+        "#,
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_auction(
+    auction_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<AuctionSummary>> {
+    sqlx::query_as!(
+        AuctionSummary,
+        r#"
+            select
+                a.auction_id,
+                a.title,
+                a.description,
+                a.start_date,
+                a.end_date,
+                a.timezone,
+                case
+                    when now() < a.start_date then 'upcoming'
+                    when now() < a.end_date then 'open'
+                    else 'closed'
+                end "status!",
+                org.name "benefits_organization_name?",
+                (
+                    select count(*)
+                    from auction_item ai
+                    where ai.auction_id = a.auction_id
+                    and ai.basket_id is null
+                ) "item_count!"
+            from auction a
+            left join organization org
+            on org.organization_id = a.benefits_organization_id
+            where a.auction_id = $1
+            and a.tenant_id = $2
+        "#,
+        auction_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Items that can be bid on: anything in a basket is bid on through its basket. `limit`
+/// and `offset` read a page of them, and a `None` limit all of them.
+#[instrument(skip(db))]
+pub async fn list_auction_items(
+    auction_id: Uuid,
+    limit: Option<i64>,
+    offset: i64,
+    db: &PgPool,
+) -> Result<Vec<ItemCard>> {
+    sqlx::query_as!(
+        ItemCard,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                ai.title,
+                ai.featured_image_filepath,
+                ai.minimum_bid_amount,
+                bids.high_bid,
+                bids.bid_count "bid_count!",
+                ai.active_end_date
+            from auction_item ai
+            cross join lateral (
+                select max(amount) high_bid, count(*) bid_count
+                from auction_item_bid aib
+                where aib.auction_item_id = ai.auction_item_id
+            ) bids
+            where ai.auction_id = $1
+            and ai.basket_id is null
+            order by ai.active_end_date, ai.title, ai.auction_item_id
+            limit $2
+            offset $3
+        "#,
+        auction_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// How many items `list_auction_items` lists in all.
+#[instrument(skip(db))]
+pub async fn count_auction_items(auction_id: Uuid, db: &PgPool) -> Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from auction_item
+            where auction_id = $1
+            and basket_id is null
+        "#,
+        auction_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_basket_items(basket_id: Uuid, db: &PgPool) -> Result<Vec<ItemCard>> {
+    sqlx::query_as!(
+        ItemCard,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                ai.title,
+                ai.featured_image_filepath,
+                ai.minimum_bid_amount,
+                null::decimal "high_bid",
+                0::bigint "bid_count!",
+                ai.active_end_date
+            from auction_item ai
+            where ai.basket_id = $1
+            order by ai.title
+        "#,
+        basket_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_auction_item(
+    auction_id: Uuid,
+    auction_item_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<ItemDetail>> {
+    sqlx::query_as!(
+        ItemDetail,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                a.title auction_title,
+                a.timezone,
+                ai.basket_id,
+                ai.title,
+                ai.description,
+                ai.featured_image_filepath,
+                ai.expected_retail_value,
+                ai.minimum_bid_amount,
+                ai.buy_it_now_amount,
+                (
+                    select max(amount)
+                    from auction_item_bid aib
+                    where aib.auction_item_id = ai.auction_item_id
+                ) high_bid,
+                donor.name "donated_by_organization_name?",
+                beneficiary.name "benefits_organization_name?",
+                ai.active_start_date,
+                ai.active_end_date,
+                now() >= greatest(ai.active_start_date, a.start_date)
+                    and now() < least(ai.active_end_date, a.end_date) "is_open!"
+            from auction_item ai
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            left join organization donor
+            on donor.organization_id = ai.donated_by_organization_id
+            left join organization beneficiary
+            on beneficiary.organization_id = coalesce(
+                ai.benefits_organization_id,
+                a.benefits_organization_id
+            )
+            where ai.auction_id = $1
+            and ai.auction_item_id = $2
+            and a.tenant_id = $3
+        "#,
+        auction_id,
+        auction_item_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Bid history with bidders replaced by "Bidder 1", "Bidder 2"... in order of their first bid.
+/// Bidders are numbered across all the bids, whichever page `limit` and `offset` read.
+#[instrument(skip(db))]
+pub async fn list_bid_history(
+    auction_item_id: Uuid,
+    viewer: Option<Uuid>,
+    limit: Option<i64>,
+    offset: i64,
+    db: &PgPool,
+) -> Result<Vec<BidHistoryRow>> {
+    sqlx::query_as!(
+        BidHistoryRow,
+        r#"
+            select
+                dense_rank() over (order by first_bid_at, user_id) "bidder_number!",
+                coalesce(user_id = $2, false) "is_viewer!",
+                amount,
+                created_at
+            from (
+                select
+                    aib.*,
+                    min(aib.created_at) over (partition by aib.user_id) first_bid_at
+                from auction_item_bid aib
+                where aib.auction_item_id = $1
+            ) bids
+            order by amount desc, created_at asc, auction_item_bid_id
+            limit $3
+            offset $4
+        "#,
+        auction_item_id,
+        viewer,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn count_bids(auction_item_id: Uuid, db: &PgPool) -> Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from auction_item_bid
+            where auction_item_id = $1
+        "#,
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn is_watching(user_id: Uuid, auction_item_id: Uuid, db: &PgPool) -> Result<bool> {
+    sqlx::query_scalar!(
+        r#"
+            select exists(
+                select 1
+                from watchlist
+                where user_id = $1
+                and auction_item_id = $2
+            ) "exists!"
+        "#,
+        user_id,
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Watching an item twice is harmless.
+#[instrument(skip(db))]
+pub async fn watch_item(user_id: Uuid, auction_item_id: Uuid, db: &PgPool) -> Result<()> {
+    sqlx::query!(
+        r#"
+            insert into watchlist (user_id, auction_item_id)
+            values ($1, $2)
+            on conflict do nothing
+        "#,
+        user_id,
+        auction_item_id
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[instrument(skip(db))]
+pub async fn unwatch_item(user_id: Uuid, auction_item_id: Uuid, db: &PgPool) -> Result<()> {
+    sqlx::query!(
+        r#"
+            delete from watchlist
+            where user_id = $1
+            and auction_item_id = $2
+        "#,
+        user_id,
+        auction_item_id
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
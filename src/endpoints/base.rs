@@ -1,37 +1,52 @@
 use axum::{
+    body::{self, Body},
     extract::{Extension},
-    http::StatusCode,
-    response::Html,
-    routing::{get, get_service},
+    http::{Request, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::get,
     Router,
 };
 use minijinja::context;
+use tower::ServiceExt;
 use tower_http::services::fs::ServeDir;
 
 
-use crate::endpoints::ApiContext;
+use crate::db::tenants::Tenant;
+use crate::endpoints::{render_template, ApiContext, Result};
 
 
 pub fn router() -> Router {
-    // Serves files inside the `static` directory at `GET /static/*`
-        let serve_dir_service = get_service(ServeDir::new("static"))
-            .handle_error(|error: std::io::Error| async move {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Unhandled internal error: {}", error),
-                )
-            });
     Router::new()
         .route("/", get(index))
         .route("/health", get(health_check))
-        .nest("/static", serve_dir_service)
+        .route("/static/*path", get(static_file))
+}
+
+// Serves files inside the `static` directory at `GET /static/*`, or the tenant's own copy
+// from `static/tenants/<slug>/` when it has one
+async fn static_file(tenant: Tenant, mut req: Request<Body>) -> Response {
+    let path = req.uri().path().trim_start_matches("/static").to_string();
+    let tenant_dir = format!("static/tenants/{}", tenant.slug);
+    let overridden = !path.split('/').any(|part| part == "..")
+        && tokio::fs::metadata(format!("{}{}", tenant_dir, path))
+            .await
+            .is_ok_and(|metadata| metadata.is_file());
+    let root = if overridden { tenant_dir } else { "static".to_string() };
+    *req.uri_mut() = path.parse().unwrap_or_default();
+    match ServeDir::new(root).oneshot(req).await {
+        Ok(response) => response.map(body::boxed),
+        Err(error) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Unhandled internal error: {}", error),
+        )
+            .into_response(),
+    }
 }
 
 
 
-async fn index(ctx: Extension<ApiContext>) -> Html<String> {
-    let template = ctx.template_env.get_template("index.html").unwrap();
-    Html(template.render(context!(title => "Hooksaurus Auctions: Helping Animal Sanctuaries")).unwrap())
+async fn index(ctx: Extension<ApiContext>, tenant: Tenant) -> Result<Html<String>> {
+    render_template(&ctx, "index.html", context!(title => format!("{}: Helping Animal Sanctuaries", tenant.name)))
 }
 
 async fn health_check() -> &'static str {
@@ -0,0 +1,87 @@
+use axum::{
+    extract::Extension,
+    http::{header::HeaderMap, Uri},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, put},
+    Router,
+};
+use minijinja::context;
+use tracing::instrument;
+
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::{AuthUser, MaybeAuthUser};
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::Result;
+
+use super::{queries, BidItem, NotificationPreferences};
+
+pub fn router() -> Router {
+    Router::new().route("/dashboard", get(dashboard)).route(
+        "/dashboard/notifications",
+        put(update_notification_preferences),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn dashboard(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Result<Response> {
+    let user_id = match auth_user.user_id() {
+        Some(user_id) => user_id,
+        None => return Ok(Redirect::to(Uri::from_static("/login?next=/dashboard")).into_response()),
+    };
+    let watched = queries::list_watched_items(user_id, tenant.tenant_id, &ctx.db).await?;
+    let invoices = queries::list_invoices(user_id, tenant.tenant_id, &ctx.db).await?;
+    let statement_years = queries::list_statement_years(user_id, &ctx.db).await?;
+    let preferences = queries::get_notification_preferences(user_id, &ctx.db).await?;
+    let organizations = queries::list_organizations(user_id, tenant.tenant_id, &ctx.db).await?;
+    let (won, bids): (Vec<BidItem>, Vec<BidItem>) =
+        queries::list_bid_items(user_id, tenant.tenant_id, None, 0, &ctx.db)
+            .await?
+            .into_iter()
+            .partition(|item| item.status == "won");
+    let (needs_attention, won): (Vec<BidItem>, Vec<BidItem>) = won
+        .into_iter()
+        .partition(|item| item.needs_payment || item.needs_delivery_details);
+    Ok(render_page(
+        &ctx,
+        &headers,
+        "dashboard.html",
+        context!(
+            title => "My bids",
+            logged_in => true,
+            watched => watched,
+            bids => bids,
+            won => won,
+            needs_attention => needs_attention,
+            invoices => invoices,
+            statement_years => statement_years,
+            preferences => preferences,
+            organizations => organizations,
+        ),
+    )?
+    .into_response())
+}
+
+#[instrument(skip(ctx, body))]
+async fn update_notification_preferences(
+    ctx: Extension<ApiContext>,
+    auth_user: AuthUser,
+    body: String,
+) -> Result<Html<String>> {
+    let preferences: NotificationPreferences = parse_form(&body)?;
+    let (errors, message) = if preferences.notify_by_sms && preferences.phone_number.is_none() {
+        (vec!["Add a phone number to get text messages."], None)
+    } else {
+        queries::update_notification_preferences(auth_user.user_id, &preferences, &ctx.db).await?;
+        (vec![], Some("Saved."))
+    };
+    render_template(
+        &ctx,
+        "fragments/notification_preferences.html",
+        context!(preferences => preferences, errors => errors, message => message),
+    )
+}
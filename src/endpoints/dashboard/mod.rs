@@ -0,0 +1,75 @@
+//! A bidder's own view of the site: what they watch, what they've bid on and what they've won.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+pub(super) mod queries;
+
+use crate::db::tables::{self, serialize_dt, serialize_dt_opt};
+pub use handlers::router;
+
+#[derive(Debug, serde::Serialize)]
+pub struct WatchedItem {
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub title: String,
+    pub featured_image_filepath: String,
+    pub minimum_bid_amount: Decimal,
+    pub high_bid: Option<Decimal>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub end_date: OffsetDateTime,
+    pub is_open: bool,
+}
+
+/// An item the bidder has bid on, and where they stand.
+#[derive(Debug, serde::Serialize)]
+pub struct BidItem {
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub title: String,
+    pub featured_image_filepath: String,
+    // the bidder's own highest bid, and the highest they've said they'll go
+    pub my_bid: Decimal,
+    pub max_bid_amount: Option<Decimal>,
+    pub high_bid: Decimal,
+    #[serde(serialize_with = "serialize_dt")]
+    pub end_date: OffsetDateTime,
+    // one of "winning", "outbid", "won" or "lost"
+    pub status: String,
+    pub needs_payment: bool,
+    pub needs_delivery_details: bool,
+    // where the item has got to, once it's been won
+    pub delivery_status: Option<String>,
+}
+
+/// An invoice which has been issued to the bidder.
+#[derive(Debug, serde::Serialize)]
+pub struct MyInvoice {
+    pub invoice_id: Uuid,
+    pub reference: String,
+    pub auction_title: String,
+    // "issued", "paid" or "refunded"
+    pub status: String,
+    pub total: Decimal,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub issued_at: Option<OffsetDateTime>,
+}
+
+/// An organization the user looks after, which has a portal of its own.
+#[derive(Debug, serde::Serialize)]
+pub struct MyOrganization {
+    pub organization_id: Uuid,
+    pub name: String,
+}
+
+/// How a bidder wants to hear about being outbid or winning. Unchecked boxes are left out of
+/// a form entirely, hence the defaults.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct NotificationPreferences {
+    #[serde(default)]
+    pub notify_by_email: bool,
+    #[serde(default)]
+    pub notify_by_sms: bool,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub phone_number: Option<String>,
+}
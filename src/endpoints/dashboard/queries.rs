@@ -0,0 +1,294 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{BidItem, MyInvoice, MyOrganization, NotificationPreferences, WatchedItem};
+
+#[instrument(skip(db))]
+pub async fn list_watched_items(
+    user_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<WatchedItem>> {
+    sqlx::query_as!(
+        WatchedItem,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                ai.title,
+                ai.featured_image_filepath,
+                ai.minimum_bid_amount,
+                (
+                    select max(amount)
+                    from auction_item_bid aib
+                    where aib.auction_item_id = ai.auction_item_id
+                ) high_bid,
+                least(ai.active_end_date, a.end_date) "end_date!",
+                now() >= greatest(ai.active_start_date, a.start_date)
+                    and now() < least(ai.active_end_date, a.end_date) "is_open!"
+            from watchlist w
+            inner join auction_item ai
+            on ai.auction_item_id = w.auction_item_id
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            where w.user_id = $1
+            and ai.tenant_id = $2
+            order by least(ai.active_end_date, a.end_date) desc
+        "#,
+        user_id,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Every item the user has bid on, soonest to close first.
+///
+/// Closed items count as won when the user's bid is flagged as the winner. Until winners have
+/// been flagged for an item, the high bid wins if it meets the minimum, as in `recompute_winners`.
+/// `limit` and `offset` read a page of them, and a `None` limit all of them.
+#[instrument(skip(db))]
+pub async fn list_bid_items(
+    user_id: Uuid,
+    tenant_id: Uuid,
+    limit: Option<i64>,
+    offset: i64,
+    db: &PgPool,
+) -> Result<Vec<BidItem>> {
+    sqlx::query_as!(
+        BidItem,
+        r#"
+            with my_bid as (
+                select
+                    auction_item_id,
+                    max(amount) amount,
+                    max(max_bid_amount) max_bid_amount,
+                    bool_or(is_winning_bid) is_winner
+                from auction_item_bid
+                where user_id = $1
+                group by auction_item_id
+            ),
+            item as (
+                select
+                    ai.*,
+                    least(ai.active_end_date, a.end_date) end_date,
+                    now() < least(ai.active_end_date, a.end_date) is_open,
+                    exists(
+                        select 1
+                        from auction_item_bid aib
+                        where aib.auction_item_id = ai.auction_item_id
+                        and aib.is_winning_bid
+                    ) has_winner
+                from auction_item ai
+                inner join auction a
+                on a.auction_id = ai.auction_id
+                where ai.tenant_id = $2
+            )
+            select
+                item.auction_item_id,
+                item.auction_id "auction_id!",
+                item.title,
+                item.featured_image_filepath,
+                my_bid.amount "my_bid!",
+                my_bid.max_bid_amount,
+                top.amount high_bid,
+                item.end_date "end_date!",
+                case
+                    when item.is_open and top.user_id = $1 then 'winning'
+                    when item.is_open then 'outbid'
+                    when my_bid.is_winner then 'won'
+                    when not item.has_winner
+                        and top.user_id = $1
+                        and top.amount >= item.minimum_bid_amount then 'won'
+                    else 'lost'
+                end "status!",
+                not exists(
+                    select 1
+                    from invoice_line il
+                    inner join invoice i
+                    on i.invoice_id = il.invoice_id
+                    where il.auction_item_id = item.auction_item_id
+                    and il.kind = 'item'
+                    and i.user_id = $1
+                    and i.status in ('paid', 'refunded')
+                ) "needs_payment!",
+                coalesce(delivery.status = 'pending_address', true) "needs_delivery_details!",
+                delivery.status "delivery_status?"
+            from my_bid
+            inner join item
+            on item.auction_item_id = my_bid.auction_item_id
+            cross join lateral (
+                select user_id, amount
+                from auction_item_bid aib
+                where aib.auction_item_id = item.auction_item_id
+                order by amount desc, created_at asc
+                limit 1
+            ) top
+            left join lateral (
+                select aid.status
+                from auction_item_delivery aid
+                inner join auction_item_bid aib
+                on aib.auction_item_bid_id = aid.auction_item_bid_id
+                where aib.auction_item_id = item.auction_item_id
+                and aib.user_id = $1
+                limit 1
+            ) delivery on true
+            order by not item.is_open, item.end_date, item.auction_item_id
+            limit $3
+            offset $4
+        "#,
+        user_id,
+        tenant_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// How many items `list_bid_items` lists in all.
+#[instrument(skip(db))]
+pub async fn count_bid_items(user_id: Uuid, tenant_id: Uuid, db: &PgPool) -> Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+            select count(distinct aib.auction_item_id) "count!"
+            from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            where aib.user_id = $1
+            and ai.tenant_id = $2
+        "#,
+        user_id,
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Drafts and voided invoices are left out: the bidder has nothing to do with them.
+#[instrument(skip(db))]
+pub async fn list_invoices(user_id: Uuid, tenant_id: Uuid, db: &PgPool) -> Result<Vec<MyInvoice>> {
+    sqlx::query_as!(
+        MyInvoice,
+        r#"
+            select
+                i.invoice_id,
+                'INV-' || lpad(i.invoice_number::text, 6, '0') "reference!",
+                a.title auction_title,
+                i.status,
+                (
+                    select coalesce(sum(amount), 0)
+                    from invoice_line il
+                    where il.invoice_id = i.invoice_id
+                ) "total!",
+                i.issued_at
+            from invoice i
+            inner join auction a
+            on a.auction_id = i.auction_id
+            where i.user_id = $1
+            and a.tenant_id = $2
+            and i.status in ('issued', 'paid', 'refunded')
+            order by i.status <> 'issued', i.issued_at desc
+        "#,
+        user_id,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Years in which the bidder paid for something, for their donation statements.
+#[instrument(skip(db))]
+pub async fn list_statement_years(user_id: Uuid, db: &PgPool) -> Result<Vec<i32>> {
+    sqlx::query_scalar!(
+        r#"
+            select distinct extract(year from i.paid_at at time zone a.timezone)::int "year!"
+            from invoice i
+            inner join auction a
+            on a.auction_id = i.auction_id
+            where i.user_id = $1
+            and i.status = 'paid'
+            order by 1 desc
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_notification_preferences(
+    user_id: Uuid,
+    db: &PgPool,
+) -> Result<NotificationPreferences> {
+    sqlx::query_as!(
+        NotificationPreferences,
+        r#"
+            select notify_by_email, notify_by_sms, phone_number
+            from "user"
+            where user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn update_notification_preferences(
+    user_id: Uuid,
+    preferences: &NotificationPreferences,
+    db: &PgPool,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            update "user"
+            set
+                notify_by_email = $2,
+                notify_by_sms = $3,
+                phone_number = $4
+            where user_id = $1
+        "#,
+        user_id,
+        preferences.notify_by_email,
+        preferences.notify_by_sms,
+        preferences.phone_number
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[instrument(skip(db))]
+pub async fn list_organizations(
+    user_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<MyOrganization>> {
+    sqlx::query_as!(
+        MyOrganization,
+        r#"
+            select o.organization_id, o.name
+            from organization_member m
+            inner join organization o
+            on o.organization_id = m.organization_id
+            where m.user_id = $1
+            and o.tenant_id = $2
+            order by o.name
+        "#,
+        user_id,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
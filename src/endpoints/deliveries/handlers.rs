@@ -0,0 +1,193 @@
+use axum::{
+    extract::{Extension, Path},
+    http::{header::HeaderMap, Uri},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use minijinja::context;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::deliveries::{self, DeliveryChoice};
+use crate::db::tables::address::{AddressFromForm, AddressId};
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::MaybeAuthUser;
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, DeliveryFromForm, Method};
+
+pub fn router() -> Router {
+    Router::new().route(
+        "/dashboard/deliveries/:auction_item_id",
+        get(get_delivery_details).post(choose_delivery),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn get_delivery_details(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_item_id): Path<Uuid>,
+) -> Result<Response> {
+    let user_id = match auth_user.user_id() {
+        Some(user_id) => user_id,
+        None => {
+            let next = format!("/login?next=/dashboard/deliveries/{}", auction_item_id);
+            return match next.parse::<Uri>() {
+                Ok(uri) => Ok(Redirect::to(uri).into_response()),
+                Err(_) => Err(Error::Unauthorized),
+            };
+        }
+    };
+    render_delivery_details(
+        &ctx,
+        &tenant,
+        Some(&headers),
+        user_id,
+        auction_item_id,
+        vec![],
+        None,
+    )
+    .await
+    .map(IntoResponse::into_response)
+}
+
+/// Problems are rendered into the form, since htmx won't swap in an error response.
+#[instrument(skip(ctx, body))]
+async fn choose_delivery(
+    auth_user: MaybeAuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_item_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    let user_id = auth_user.user_id().ok_or(Error::Unauthorized)?;
+    queries::get_won_item(user_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let chosen = match choice_from_form(&ctx, user_id, &body).await {
+        Ok(choice) => {
+            deliveries::choose_delivery(
+                user_id,
+                auction_item_id,
+                &choice,
+                ctx.shipping.as_ref(),
+                &ctx.db,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    match chosen {
+        Ok(fee) => {
+            event!(
+                Level::INFO,
+                event_msg = "Winner chose a delivery",
+                auction_item_id = %auction_item_id,
+                shipping_fee = %fee
+            );
+            let message = if fee.is_zero() {
+                "Thanks! There's nothing to pay for delivery.".to_string()
+            } else {
+                format!(
+                    "Thanks! Shipping comes to ${:.2}, which will be on your invoice.",
+                    fee
+                )
+            };
+            render_delivery_details(
+                &ctx,
+                &tenant,
+                None,
+                user_id,
+                auction_item_id,
+                vec![],
+                Some(message),
+            )
+            .await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_delivery_details(&ctx, &tenant, None, user_id, auction_item_id, errors, None)
+                .await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn choice_from_form(ctx: &ApiContext, user_id: Uuid, body: &str) -> Result<DeliveryChoice> {
+    let form: DeliveryFromForm = parse_form(body)?;
+    match form.method {
+        Method::Home => {
+            let home = queries::get_home_address(user_id, &ctx.db)
+                .await?
+                .ok_or_else(|| {
+                    Error::unprocessable_entity([(
+                        "shipping_address",
+                        "add an address to send it to",
+                    )])
+                })?;
+            Ok(DeliveryChoice::Ship(AddressId(home.address_id)))
+        }
+        Method::Other => Ok(DeliveryChoice::ShipToNew(
+            parse_form::<AddressFromForm>(body)?.validated()?,
+        )),
+        Method::Pickup => match form.pickup_window_id {
+            Some(pickup_window_id) => Ok(DeliveryChoice::Pickup { pickup_window_id }),
+            None => Err(Error::unprocessable_entity([(
+                "pickup_window_id",
+                "choose when you'll pick it up",
+            )])),
+        },
+    }
+}
+
+/// The full page when `headers` are given, otherwise only the form fragment.
+async fn render_delivery_details(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    user_id: Uuid,
+    auction_item_id: Uuid,
+    errors: Vec<String>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let item = queries::get_won_item(user_id, auction_item_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let home = queries::get_home_address(user_id, &ctx.db).await?;
+    let home_quote = match &home {
+        Some(home) if item.can_choose() => Some(
+            deliveries::quote(
+                user_id,
+                auction_item_id,
+                &DeliveryChoice::Ship(AddressId(home.address_id)),
+                ctx.shipping.as_ref(),
+                &ctx.db,
+            )
+            .await?,
+        ),
+        _ => None,
+    };
+    let windows = if item.can_choose() {
+        queries::list_open_windows(item.auction_id, item.auction_item_bid_id, &ctx.db).await?
+    } else {
+        vec![]
+    };
+    let context = context!(
+        can_choose => item.can_choose(),
+        item => item,
+        home => home,
+        home_quote => home_quote,
+        windows => windows,
+        errors => errors,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "delivery_details.html", context),
+        None => render_template(ctx, "fragments/delivery_details.html", context),
+    }
+}
@@ -0,0 +1,83 @@
+//! Winners saying how they want their items: shipped to their own address or to another one,
+//! or picked up in one of the auction's pickup windows. The shipping fee is worked out as they choose, see
+//! `crate::db::deliveries::choose_delivery`.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables::{self, serialize_dt, serialize_dt_opt};
+pub use handlers::router;
+
+/// A won item, and how it's getting to the winner so far.
+#[derive(Debug, serde::Serialize)]
+pub struct WonItem {
+    pub auction_item_bid_id: Uuid,
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub title: String,
+    // no status until the winner has chosen, or an admin has added a delivery
+    pub status: Option<String>,
+    pub local_pickup: bool,
+    pub shipping_fee: Option<Decimal>,
+    // on an invoice which has gone out to the winner
+    pub invoiced: bool,
+    pub shipping_address: Option<String>,
+    // the auction's, for showing pickup times
+    pub timezone: String,
+    pub pickup_window_id: Option<Uuid>,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub pickup_starts_at: Option<OffsetDateTime>,
+    pub pickup_address: Option<String>,
+}
+
+impl WonItem {
+    /// The winner can change their mind until the item is on its way, or its shipping has been
+    /// invoiced.
+    pub fn can_choose(&self) -> bool {
+        !self.invoiced
+            && matches!(
+                self.status.as_deref(),
+                None | Some("pending_address") | Some("ready_to_ship")
+            )
+    }
+}
+
+/// The address on the winner's account, on one line.
+#[derive(Debug, serde::Serialize)]
+pub struct HomeAddress {
+    pub address_id: Uuid,
+    pub address: String,
+}
+
+/// A pickup window the winner could book.
+#[derive(Debug, serde::Serialize)]
+pub struct OpenWindow {
+    pub pickup_window_id: Uuid,
+    #[serde(serialize_with = "serialize_dt")]
+    pub starts_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub ends_at: OffsetDateTime,
+    pub address: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// To the address on their account
+    Home,
+    /// To an address they give us
+    Other,
+    /// In the pickup window they choose
+    Pickup,
+}
+
+/// The address fields for `Method::Other` are read from the same form as a
+/// `tables::address::AddressFromForm`.
+#[derive(Debug, serde::Deserialize)]
+pub struct DeliveryFromForm {
+    pub method: Method,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub pickup_window_id: Option<Uuid>,
+}
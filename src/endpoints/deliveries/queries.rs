@@ -0,0 +1,141 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{HomeAddress, OpenWindow, WonItem};
+
+/// An item of the tenant `user_id` has the winning bid on.
+#[instrument(skip(db))]
+pub async fn get_won_item(
+    user_id: Uuid,
+    auction_item_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<WonItem>> {
+    sqlx::query_as!(
+        WonItem,
+        r#"
+            select
+                aib.auction_item_bid_id,
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                ai.title,
+                d.status "status?",
+                coalesce(d.local_pickup, false) "local_pickup!",
+                d.shipping_fee "shipping_fee?",
+                exists(
+                    select 1
+                    from invoice_line il
+                    inner join invoice i
+                    on i.invoice_id = il.invoice_id
+                    where il.auction_item_bid_id = aib.auction_item_bid_id
+                    and i.status not in ('draft', 'void')
+                ) "invoiced!",
+                case when ad.address_id is not null then concat_ws(', ',
+                    ad.street_address1,
+                    ad.city,
+                    ad.state_province_county,
+                    ad.postal_code
+                ) end "shipping_address?",
+                a.timezone,
+                d.pickup_window_id "pickup_window_id?",
+                w.starts_at "pickup_starts_at?",
+                case when pa.address_id is not null then concat_ws(', ',
+                    pa.street_address1,
+                    pa.city,
+                    pa.state_province_county
+                ) end "pickup_address?"
+            from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            left join auction_item_delivery d
+            on d.auction_item_bid_id = aib.auction_item_bid_id
+            left join address ad
+            on ad.address_id = d.shipping_address
+            left join pickup_window w
+            on w.pickup_window_id = d.pickup_window_id
+            left join address pa
+            on pa.address_id = w.address_id
+            where aib.auction_item_id = $1
+            and aib.user_id = $2
+            and aib.is_winning_bid
+            and ai.tenant_id = $3
+        "#,
+        auction_item_id,
+        user_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_home_address(user_id: Uuid, db: &PgPool) -> Result<Option<HomeAddress>> {
+    sqlx::query_as!(
+        HomeAddress,
+        r#"
+            select
+                a.address_id,
+                concat_ws(', ',
+                    a.street_address1,
+                    a.city,
+                    a.state_province_county,
+                    a.postal_code
+                ) "address!"
+            from "user" u
+            inner join address a
+            on a.address_id = u.address_id
+            where u.user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The auction's pickup windows which are yet to end and have room, soonest first. The window
+/// `auction_item_bid_id` has already booked is always included.
+#[instrument(skip(db))]
+pub async fn list_open_windows(
+    auction_id: Uuid,
+    auction_item_bid_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<OpenWindow>> {
+    sqlx::query_as!(
+        OpenWindow,
+        r#"
+            select
+                w.pickup_window_id,
+                w.starts_at,
+                w.ends_at,
+                concat_ws(', ',
+                    a.street_address1,
+                    a.city,
+                    a.state_province_county
+                ) "address!"
+            from pickup_window w
+            inner join address a
+            on a.address_id = w.address_id
+            where w.auction_id = $1
+            and w.ends_at > now()
+            and (
+                select count(*)
+                from auction_item_delivery d
+                where d.pickup_window_id = w.pickup_window_id
+                and d.auction_item_bid_id <> $2
+            ) < w.capacity
+            order by w.starts_at
+        "#,
+        auction_id,
+        auction_item_bid_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,208 @@
+use axum::{
+    extract::{Extension, Path},
+    http::header::HeaderMap,
+    response::Html,
+    routing::{get, post},
+    Router,
+};
+use minijinja::context;
+use std::collections::BTreeMap;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::donations::{self, ApprovalFromForm, DonationFromForm};
+use crate::db::tables::address::AddressFromForm;
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::MaybeAuthUser;
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, DeclineFromForm};
+
+pub fn router() -> Router {
+    Router::new().route("/donate", get(get_donate).post(donate))
+}
+
+pub fn admin_router() -> Router {
+    Router::new()
+        .route("/admin/donations", get(get_donations))
+        .route(
+            "/admin/donations/:auction_item_id/approve",
+            post(approve_donation),
+        )
+        .route(
+            "/admin/donations/:auction_item_id/decline",
+            post(decline_donation),
+        )
+}
+
+/// Anyone can offer an item, whether or not they have an account.
+#[instrument(skip(ctx))]
+async fn get_donate(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+) -> Result<Html<String>> {
+    render_page(
+        &ctx,
+        &headers,
+        "donate.html",
+        context!(
+            title => "Donate an item",
+            logged_in => auth_user.user_id().is_some(),
+            form => BTreeMap::<String, String>::new(),
+            errors => Vec::<String>::new(),
+        ),
+    )
+}
+
+/// Problems are rendered into the form, since htmx won't swap in an error response.
+#[instrument(skip(ctx, body))]
+async fn donate(ctx: Extension<ApiContext>, tenant: Tenant, body: String) -> Result<Html<String>> {
+    let form: DonationFromForm = parse_form(&body)?;
+    let submitted = match donation_address(&body) {
+        Ok(address) => donations::submit(&form, address.as_ref(), tenant.tenant_id, &ctx.db).await,
+        Err(e) => Err(e),
+    };
+    match submitted {
+        Ok(auction_item_id) => {
+            event!(
+                Level::INFO,
+                event_msg = "Received an item donation",
+                auction_item_id = %auction_item_id
+            );
+            let message = format!(
+                "Thank you! We've emailed {} to say we have it, and will let you know once \
+                 we've decided which auction it fits.",
+                form.donor_email.trim()
+            );
+            render_template(
+                &ctx,
+                "fragments/donate.html",
+                context!(
+                    form => BTreeMap::<String, String>::new(),
+                    errors => Vec::<String>::new(),
+                    message => message,
+                ),
+            )
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors: Vec<String> = errors.into_values().flatten().map(String::from).collect();
+            // what was typed so far, to fill the form back in
+            let typed: BTreeMap<String, String> = parse_form(&body)?;
+            render_template(
+                &ctx,
+                "fragments/donate.html",
+                context!(form => typed, errors => errors),
+            )
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The address of a new organization, which is only filled in when there's one to give.
+fn donation_address(body: &str) -> Result<Option<AddressFromForm>> {
+    match parse_form::<AddressFromForm>(body) {
+        Ok(address) if !address.street_address1.trim().is_empty() => address.validated().map(Some),
+        _ => Ok(None),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_donations(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Result<Html<String>> {
+    render_donations(&ctx, &tenant, Some(&headers), vec![], None).await
+}
+
+#[instrument(skip(ctx, body))]
+async fn approve_donation(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_item_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    let approved = match parse_form::<ApprovalFromForm>(&body) {
+        Ok(form) => donations::approve(auction_item_id, &form, tenant.tenant_id, &ctx.db)
+            .await
+            .map(|()| form),
+        Err(e) => Err(e),
+    };
+    match approved {
+        Ok(form) => {
+            event!(
+                Level::INFO,
+                event_msg = "Approved an item donation",
+                auction_item_id = %auction_item_id,
+                auction_id = %form.auction_id
+            );
+            let message = "Approved, and we've let the donor know.".to_string();
+            render_donations(&ctx, &tenant, None, vec![], Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_donations(&ctx, &tenant, None, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument(skip(ctx, body))]
+async fn decline_donation(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_item_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    let declined = match parse_form::<DeclineFromForm>(&body) {
+        Ok(form) => {
+            donations::decline(
+                auction_item_id,
+                &form.decline_reason,
+                tenant.tenant_id,
+                &ctx.db,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    match declined {
+        Ok(()) => {
+            event!(
+                Level::INFO,
+                event_msg = "Declined an item donation",
+                auction_item_id = %auction_item_id
+            );
+            let message = "Declined, and we've let the donor know why.".to_string();
+            render_donations(&ctx, &tenant, None, vec![], Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_donations(&ctx, &tenant, None, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The full page when `headers` are given, otherwise only the donations fragment.
+async fn render_donations(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    errors: Vec<String>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let context = context!(
+        pending => queries::list_pending(tenant.tenant_id, &ctx.db).await?,
+        decided => queries::list_recently_decided(tenant.tenant_id, &ctx.db).await?,
+        auctions => queries::list_open_auctions(tenant.tenant_id, &ctx.db).await?,
+        errors => errors,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_donations.html", context),
+        None => render_template(ctx, "fragments/admin_donations.html", context),
+    }
+}
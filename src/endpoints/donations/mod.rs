@@ -0,0 +1,53 @@
+//! The public donation form, and the admin page where donations are approved into an auction
+//! or declined. See `crate::db::donations`.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables::{serialize_dt, serialize_dt_opt};
+pub use handlers::{admin_router, router};
+
+/// A donation waiting for an admin to decide on it.
+#[derive(Debug, serde::Serialize)]
+pub struct PendingDonation {
+    pub auction_item_id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub expected_retail_value: Decimal,
+    pub tags: Vec<String>,
+    pub donor_name: String,
+    pub donor_email: String,
+    pub donor_phone: Option<String>,
+    pub organization_name: Option<String>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DecidedDonation {
+    pub auction_item_id: Uuid,
+    pub title: String,
+    pub donor_name: String,
+    // "approved" or "declined"
+    pub approval_status: String,
+    pub auction_id: Option<Uuid>,
+    pub auction_title: Option<String>,
+    pub decline_reason: Option<String>,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub decided_at: Option<OffsetDateTime>,
+}
+
+/// An auction a donation can still go into.
+#[derive(Debug, serde::Serialize)]
+pub struct OpenAuction {
+    pub auction_id: Uuid,
+    pub title: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct DeclineFromForm {
+    #[serde(default)]
+    pub decline_reason: String,
+}
@@ -0,0 +1,89 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{DecidedDonation, OpenAuction, PendingDonation};
+
+/// The oldest first, so nobody waits too long to hear back.
+#[instrument(skip(db))]
+pub async fn list_pending(tenant_id: Uuid, db: &PgPool) -> Result<Vec<PendingDonation>> {
+    sqlx::query_as!(
+        PendingDonation,
+        r#"
+            select
+                ai.auction_item_id "auction_item_id!",
+                ai.title "title!",
+                ai.description "description!",
+                ai.expected_retail_value "expected_retail_value!",
+                ai.tag_list "tags!",
+                d.donor_name "donor_name!",
+                d.donor_email "donor_email!",
+                d.donor_phone,
+                o.name "organization_name?",
+                ai.created_at "created_at!"
+            from auction_item ai
+            inner join item_donation d
+            on d.auction_item_id = ai.auction_item_id
+            left join organization o
+            on o.organization_id = ai.donated_by_organization_id
+            where ai.approval_status = 'pending'
+            and ai.tenant_id = $1
+            order by ai.created_at
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Donations decided on in the last 30 days, the latest first.
+#[instrument(skip(db))]
+pub async fn list_recently_decided(tenant_id: Uuid, db: &PgPool) -> Result<Vec<DecidedDonation>> {
+    sqlx::query_as!(
+        DecidedDonation,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.title,
+                d.donor_name,
+                ai.approval_status,
+                a.auction_id "auction_id?",
+                a.title "auction_title?",
+                d.decline_reason,
+                d.decided_at
+            from auction_item ai
+            inner join item_donation d
+            on d.auction_item_id = ai.auction_item_id
+            left join auction a
+            on a.auction_id = ai.auction_id
+            where d.decided_at > now() - interval '30 days'
+            and ai.tenant_id = $1
+            order by d.decided_at desc
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_open_auctions(tenant_id: Uuid, db: &PgPool) -> Result<Vec<OpenAuction>> {
+    sqlx::query_as!(
+        OpenAuction,
+        r#"
+            select auction_id, title
+            from auction
+            where end_date > now()
+            and tenant_id = $1
+            order by start_date, title
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
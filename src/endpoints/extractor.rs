@@ -0,0 +1,277 @@
+use async_trait::async_trait;
+use axum::extract::{Extension, FromRequest, RequestParts};
+use axum::http::header::{AUTHORIZATION, COOKIE};
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use jwt::{SignWithKey, VerifyWithKey};
+use sha2::Sha384;
+use sqlx::types::time::OffsetDateTime;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::db::tenants::{self, Tenant};
+use crate::endpoints::ApiContext;
+use crate::error::Error;
+
+const DEFAULT_SESSION_LENGTH: time::Duration = time::Duration::weeks(2);
+
+// API clients send `Authorization: Token <jwt>`, or `Bearer <jwt>` as the JSON API documents
+// it, and browsers send the same token in a cookie.
+const SCHEME_PREFIXES: [&str; 2] = ["Token ", "Bearer "];
+pub const SESSION_COOKIE: &str = "hooksaurus_session";
+
+/// Add this as a parameter to a handler function to require the user to be logged in.
+///
+/// Parses a JWT from the `Authorization: Token <token>` or `Bearer <token>` header, or the
+/// session cookie. Tokens are for the tenant they were issued by, and aren't accepted by any
+/// other.
+#[derive(Clone, Debug)]
+pub struct AuthUser {
+    pub user_id: Uuid,
+}
+
+/// Add this as a parameter to a handler function to optionally check if the user is logged in.
+///
+/// An invalid `Authorization` header still rejects the request, but a stale session cookie is
+/// treated as being logged out so browsers can keep viewing public pages.
+#[derive(Clone, Debug)]
+pub struct MaybeAuthUser(pub Option<AuthUser>);
+
+/// Add this as a parameter to a handler function, or put it in front of a router with
+/// `extractor_middleware`, to require an admin of the request's tenant.
+///
+/// Someone who isn't logged in gets a 401, and anyone else who isn't the tenant's admin a 403.
+#[derive(Clone, Debug)]
+pub struct AdminUser;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct AuthUserClaims {
+    user_id: Uuid,
+    tenant_id: Uuid,
+    /// Standard JWT `exp` claim.
+    exp: i64,
+}
+
+impl AuthUser {
+    pub fn to_jwt(&self, ctx: &ApiContext, tenant_id: Uuid) -> String {
+        let hmac = Hmac::<Sha384>::new_from_slice(ctx.config.hmac_key.as_bytes())
+            .expect("HMAC-SHA-384 can accept any key length");
+
+        AuthUserClaims {
+            user_id: self.user_id,
+            tenant_id,
+            exp: (OffsetDateTime::now_utc() + DEFAULT_SESSION_LENGTH).unix_timestamp(),
+        }
+        .sign_with_key(&hmac)
+        .expect("HMAC signing should be infallible")
+    }
+
+    /// A `Set-Cookie` value carrying a fresh session token for the tenant.
+    pub fn to_session_cookie(&self, ctx: &ApiContext, tenant_id: Uuid) -> String {
+        format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+            SESSION_COOKIE,
+            self.to_jwt(ctx, tenant_id),
+            DEFAULT_SESSION_LENGTH.whole_seconds()
+        )
+    }
+
+    /// A `Set-Cookie` value which removes the session cookie.
+    pub fn clear_session_cookie() -> String {
+        format!(
+            "{}=; Path=/; Max-Age=0; HttpOnly; SameSite=Lax",
+            SESSION_COOKIE
+        )
+    }
+
+    /// Attempt to parse `Self` from the request headers.
+    ///
+    /// Returns `Ok(None)` if neither an `Authorization` header nor a valid session cookie was sent.
+    fn from_headers(
+        ctx: &ApiContext,
+        headers: &HeaderMap,
+        tenant_id: Uuid,
+    ) -> Result<Option<Self>, Error> {
+        match token_from_headers(headers)? {
+            Some(SessionToken::Header(token)) => Self::from_token(ctx, token, tenant_id).map(Some),
+            Some(SessionToken::Cookie(token)) => Ok(Self::from_token(ctx, token, tenant_id).ok()),
+            None => Ok(None),
+        }
+    }
+
+    fn from_token(ctx: &ApiContext, token: &str, tenant_id: Uuid) -> Result<Self, Error> {
+        let jwt =
+            jwt::Token::<jwt::Header, AuthUserClaims, _>::parse_unverified(token).map_err(|e| {
+                debug!("failed to parse session token: {}", e);
+                Error::Unauthorized
+            })?;
+
+        let hmac = Hmac::<Sha384>::new_from_slice(ctx.config.hmac_key.as_bytes())
+            .expect("HMAC-SHA-384 can accept any key length");
+
+        // When choosing a JWT implementation, be sure to check that it validates that the signing
+        // algorithm declared in the token matches the signing algorithm you're verifying with.
+        // The `jwt` crate does.
+        let jwt = jwt.verify_with_key(&hmac).map_err(|e| {
+            debug!("session token failed to verify: {}", e);
+            Error::Unauthorized
+        })?;
+
+        let (_header, claims) = jwt.into();
+
+        if claims.exp < OffsetDateTime::now_utc().unix_timestamp() {
+            debug!("session token expired");
+            return Err(Error::Unauthorized);
+        }
+
+        if claims.tenant_id != tenant_id {
+            debug!("session token is for another tenant");
+            return Err(Error::Unauthorized);
+        }
+
+        Ok(Self {
+            user_id: claims.user_id,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SessionToken<'a> {
+    Header(&'a str),
+    Cookie(&'a str),
+}
+
+fn token_from_headers(headers: &HeaderMap) -> Result<Option<SessionToken<'_>>, Error> {
+    if let Some(auth_header) = headers.get(AUTHORIZATION) {
+        let auth_header = auth_header.to_str().map_err(|_| {
+            debug!("Authorization header is not UTF-8");
+            Error::Unauthorized
+        })?;
+
+        let token = SCHEME_PREFIXES
+            .iter()
+            .find_map(|prefix| auth_header.strip_prefix(prefix));
+        return match token {
+            Some(token) => Ok(Some(SessionToken::Header(token))),
+            None => {
+                debug!(
+                    "Authorization header is using the wrong scheme: {:?}",
+                    auth_header
+                );
+                Err(Error::Unauthorized)
+            }
+        };
+    }
+
+    Ok(headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token)
+        .filter(|token| !token.is_empty())
+        .map(SessionToken::Cookie))
+}
+
+impl MaybeAuthUser {
+    pub fn user_id(&self) -> Option<Uuid> {
+        self.0.as_ref().map(|auth_user| auth_user.user_id)
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for AuthUser
+where
+    B: Send,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        MaybeAuthUser::from_request(req)
+            .await?
+            .0
+            .ok_or(Error::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for MaybeAuthUser
+where
+    B: Send,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let ctx: Extension<ApiContext> = Extension::from_request(req)
+            .await
+            .expect("BUG: ApiContext was not added as an extension");
+
+        // The `HeaderMap` extractor takes the headers out of the request, so this has to come
+        // before it in a handler's arguments.
+        let tenant = Tenant::from_request(req).await?;
+        let headers = req
+            .headers()
+            .ok_or_else(|| anyhow::anyhow!("BUG: headers were extracted before the session"))?;
+        Ok(Self(AuthUser::from_headers(
+            &ctx,
+            headers,
+            tenant.tenant_id,
+        )?))
+    }
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for AdminUser
+where
+    B: Send,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let auth_user = AuthUser::from_request(req).await?;
+        let tenant = Tenant::from_request(req).await?;
+        let ctx: Extension<ApiContext> = Extension::from_request(req)
+            .await
+            .expect("BUG: ApiContext was not added as an extension");
+
+        match tenants::get_role(tenant.tenant_id, auth_user.user_id, &ctx.db)
+            .await?
+            .as_deref()
+        {
+            Some("admin") => Ok(Self),
+            _ => Err(Error::Forbidden),
+        }
+    }
+}
+
+#[test]
+fn test_session_token_from_cookie() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        COOKIE,
+        format!("theme=dark; {}=abc.def.ghi", SESSION_COOKIE)
+            .parse()
+            .unwrap(),
+    );
+    assert_eq!(
+        token_from_headers(&headers).unwrap(),
+        Some(SessionToken::Cookie("abc.def.ghi"))
+    );
+
+    headers.insert(AUTHORIZATION, "Token from.the.header".parse().unwrap());
+    assert_eq!(
+        token_from_headers(&headers).unwrap(),
+        Some(SessionToken::Header("from.the.header"))
+    );
+
+    headers.insert(AUTHORIZATION, "Bearer from.an.app".parse().unwrap());
+    assert_eq!(
+        token_from_headers(&headers).unwrap(),
+        Some(SessionToken::Header("from.an.app"))
+    );
+
+    headers.insert(AUTHORIZATION, "Basic nope".parse().unwrap());
+    assert!(token_from_headers(&headers).is_err());
+}
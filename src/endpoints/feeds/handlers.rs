@@ -0,0 +1,208 @@
+use std::time::SystemTime;
+
+use axum::{
+    extract::{Extension, Path},
+    headers::{ETag, HeaderMapExt, IfModifiedSince, IfNoneMatch, LastModified},
+    http::header::{HeaderMap, HeaderValue, CONTENT_TYPE, ETAG},
+    http::{StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tables::article::render_markdown;
+use crate::db::tenants::Tenant;
+use crate::endpoints::ApiContext;
+use crate::error::{Error, Result};
+
+use super::{queries, ArticleEntry, Entry, Feed, FeedFormat, ItemEntry};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/feeds/:file", get(get_feed))
+        .route("/feeds/tags/:tag/:file", get(get_tag_feed))
+        .route("/feeds/auctions/:auction_id/:file", get(get_auction_feed))
+}
+
+/// `articles.atom` and `items.atom`, or `.rss`.
+#[instrument(skip(ctx))]
+async fn get_feed(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+    Path(file): Path<String>,
+) -> Result<Response> {
+    let site = site_url(&ctx, &tenant);
+    let (feed, format) = match FeedFormat::parse(&file) {
+        Some(("articles", format)) => {
+            let articles = queries::list_articles(None, None, tenant.tenant_id, &ctx.db).await?;
+            let feed = article_feed(
+                format!("{}: News", tenant.name),
+                format!("{}/articles", site),
+                &site,
+                &uri,
+                articles,
+            );
+            (feed, format)
+        }
+        Some(("items", format)) => {
+            let items = queries::list_opened_items(tenant.tenant_id, &ctx.db).await?;
+            let feed = Feed {
+                title: format!("{}: Newly listed items", tenant.name),
+                link: format!("{}/auctions", site),
+                feed_url: format!("{}{}", site, uri.path()),
+                entries: items
+                    .into_iter()
+                    .map(|item| item_entry(item, &site))
+                    .collect(),
+            };
+            (feed, format)
+        }
+        _ => return Err(Error::NotFound),
+    };
+    Ok(feed_response(&feed, format, &headers))
+}
+
+#[instrument(skip(ctx))]
+async fn get_tag_feed(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+    Path((tag, file)): Path<(String, String)>,
+) -> Result<Response> {
+    let format = match FeedFormat::parse(&file) {
+        Some(("articles", format)) => format,
+        _ => return Err(Error::NotFound),
+    };
+    let site = site_url(&ctx, &tenant);
+    let articles = queries::list_articles(Some(&tag), None, tenant.tenant_id, &ctx.db).await?;
+    let query = serde_urlencoded::to_string([("tag", &tag)]).map_err(anyhow::Error::from)?;
+    let feed = article_feed(
+        format!("{}: News tagged {}", tenant.name, tag),
+        format!("{}/articles?{}", site, query),
+        &site,
+        &uri,
+        articles,
+    );
+    Ok(feed_response(&feed, format, &headers))
+}
+
+/// Articles about one of the tenant's auctions.
+#[instrument(skip(ctx))]
+async fn get_auction_feed(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    uri: Uri,
+    Path((auction_id, file)): Path<(Uuid, String)>,
+) -> Result<Response> {
+    let format = match FeedFormat::parse(&file) {
+        Some(("articles", format)) => format,
+        _ => return Err(Error::NotFound),
+    };
+    let auction_title = queries::get_auction_title(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let site = site_url(&ctx, &tenant);
+    let articles =
+        queries::list_articles(None, Some(auction_id), tenant.tenant_id, &ctx.db).await?;
+    let feed = article_feed(
+        format!("{}: News about {}", tenant.name, auction_title),
+        format!("{}/auctions/{}", site, auction_id),
+        &site,
+        &uri,
+        articles,
+    );
+    Ok(feed_response(&feed, format, &headers))
+}
+
+/// Where the tenant's pages are, for the absolute links feeds need. Tenants other than the
+/// default one are under their path prefix, which works whatever host they're on.
+fn site_url(ctx: &ApiContext, tenant: &Tenant) -> String {
+    let site_url = ctx.config.notify.site_url.trim_end_matches('/');
+    if tenant.is_default {
+        site_url.to_string()
+    } else {
+        format!("{}/t/{}", site_url, tenant.slug)
+    }
+}
+
+fn article_feed(
+    title: String,
+    link: String,
+    site: &str,
+    uri: &Uri,
+    articles: Vec<ArticleEntry>,
+) -> Feed {
+    let entries = articles
+        .into_iter()
+        .map(|article| Entry {
+            link: format!("{}/articles/{}", site, article.slug),
+            title: article.title,
+            summary: article.description,
+            content: Some(render_markdown(&article.body)),
+            categories: article.tag_list,
+            published: article.published_at,
+            updated: article.updated_at,
+            etag: article.etag,
+        })
+        .collect();
+    Feed {
+        title,
+        link,
+        feed_url: format!("{}{}", site, uri.path()),
+        entries,
+    }
+}
+
+fn item_entry(item: ItemEntry, site: &str) -> Entry {
+    Entry {
+        link: format!(
+            "{}/auctions/{}/items/{}",
+            site, item.auction_id, item.auction_item_id
+        ),
+        summary: format!("In {}. {}", item.auction_title, item.description),
+        title: item.title,
+        content: None,
+        categories: item.tag_list,
+        published: item.opened_at,
+        updated: item.updated_at,
+        etag: item.etag,
+    }
+}
+
+/// The feed, or a 304 when the reader's copy is current. `If-None-Match` is checked in
+/// preference to `If-Modified-Since` when both are sent.
+fn feed_response(feed: &Feed, format: FeedFormat, headers: &HeaderMap) -> Response {
+    let etag = feed.etag(format);
+    let last_modified = feed.last_modified().map(SystemTime::from);
+    let not_modified = match headers.typed_get::<IfNoneMatch>() {
+        Some(if_none_match) => etag
+            .parse::<ETag>()
+            .is_ok_and(|etag| !if_none_match.precondition_passes(&etag)),
+        None => match (headers.typed_get::<IfModifiedSince>(), last_modified) {
+            (Some(since), Some(last_modified)) => !since.is_modified(last_modified),
+            _ => false,
+        },
+    };
+
+    let mut response_headers = HeaderMap::new();
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        response_headers.insert(ETAG, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        response_headers.typed_insert(LastModified::from(last_modified));
+    }
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+    response_headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    (response_headers, feed.render(format)).into_response()
+}
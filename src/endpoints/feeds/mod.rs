@@ -0,0 +1,298 @@
+//! Atom and RSS feeds of the tenant's published articles, all of them or those with a tag or
+//! about an auction, and of auction items as they open for bidding.
+//!
+//! Every feed is at `/feeds/.../<name>.atom` and `.rss`, with the same entries. Responses
+//! carry an `ETag` made from the entries' `etag`s, so it changes whenever an entry is edited,
+//! added or dropped, and a `Last-Modified` of the latest entry's `updated_at`. Feed readers
+//! sending either back get a 304 while nothing has changed.
+use sha2::{Digest, Sha256};
+use sqlx::types::time::{OffsetDateTime, UtcOffset};
+use time::Format;
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+pub use handlers::router;
+
+// how many of the newest entries a feed has
+const FEED_LENGTH: i64 = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    /// The feed named by the last part of its path, like `articles.atom`.
+    fn parse(file: &str) -> Option<(&str, FeedFormat)> {
+        let (name, extension) = file.rsplit_once('.')?;
+        match extension {
+            "atom" => Some((name, FeedFormat::Atom)),
+            "rss" => Some((name, FeedFormat::Rss)),
+            _ => None,
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ArticleEntry {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    // markdown
+    pub body: String,
+    pub tag_list: Vec<String>,
+    pub published_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub etag: Uuid,
+}
+
+#[derive(Debug)]
+pub struct ItemEntry {
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub auction_title: String,
+    pub tag_list: Vec<String>,
+    // when bidding opened, which is when it appears in the feed
+    pub opened_at: OffsetDateTime,
+    // the later of the item's and its auction's, or when it opened
+    pub updated_at: OffsetDateTime,
+    pub etag: Uuid,
+}
+
+pub struct Feed {
+    pub title: String,
+    // the page the feed follows, and the feed itself
+    pub link: String,
+    pub feed_url: String,
+    pub entries: Vec<Entry>,
+}
+
+pub struct Entry {
+    pub title: String,
+    pub link: String,
+    pub summary: String,
+    // HTML
+    pub content: Option<String>,
+    pub categories: Vec<String>,
+    pub published: OffsetDateTime,
+    pub updated: OffsetDateTime,
+    pub etag: Uuid,
+}
+
+impl Feed {
+    /// Quoted, for the `ETag` header.
+    pub fn etag(&self, format: FeedFormat) -> String {
+        let mut hash = Sha256::new();
+        hash.update(format!("{:?}", format));
+        for entry in &self.entries {
+            hash.update(entry.etag.as_bytes());
+        }
+        let hex: String = hash.finalize()[..16]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("\"{}\"", hex)
+    }
+
+    /// `None` for a feed without entries.
+    pub fn last_modified(&self) -> Option<OffsetDateTime> {
+        self.entries.iter().map(|entry| entry.updated).max()
+    }
+
+    pub fn render(&self, format: FeedFormat) -> String {
+        match format {
+            FeedFormat::Atom => self.atom(),
+            FeedFormat::Rss => self.rss(),
+        }
+    }
+
+    fn atom(&self) -> String {
+        let updated = self.last_modified().unwrap_or_else(OffsetDateTime::now_utc);
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+             <title>{title}</title>\n\
+             <id>{feed_url}</id>\n\
+             <link rel=\"self\" href=\"{feed_url}\"/>\n\
+             <link rel=\"alternate\" type=\"text/html\" href=\"{link}\"/>\n\
+             <updated>{updated}</updated>\n",
+            title = escape(&self.title),
+            feed_url = escape(&self.feed_url),
+            link = escape(&self.link),
+            updated = rfc3339(updated),
+        );
+        for entry in &self.entries {
+            xml.push_str(&format!(
+                "<entry>\n\
+                 <title>{title}</title>\n\
+                 <id>{link}</id>\n\
+                 <link rel=\"alternate\" type=\"text/html\" href=\"{link}\"/>\n\
+                 <published>{published}</published>\n\
+                 <updated>{updated}</updated>\n\
+                 <summary>{summary}</summary>\n",
+                title = escape(&entry.title),
+                link = escape(&entry.link),
+                published = rfc3339(entry.published),
+                updated = rfc3339(entry.updated),
+                summary = escape(&entry.summary),
+            ));
+            if let Some(content) = &entry.content {
+                xml.push_str(&format!(
+                    "<content type=\"html\">{}</content>\n",
+                    escape(content)
+                ));
+            }
+            for category in &entry.categories {
+                xml.push_str(&format!("<category term=\"{}\"/>\n", escape(category)));
+            }
+            xml.push_str("</entry>\n");
+        }
+        xml.push_str("</feed>\n");
+        xml
+    }
+
+    fn rss(&self) -> String {
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" \
+             xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n\
+             <channel>\n\
+             <title>{title}</title>\n\
+             <link>{link}</link>\n\
+             <description>{title}</description>\n\
+             <atom:link rel=\"self\" type=\"application/rss+xml\" href=\"{feed_url}\"/>\n",
+            title = escape(&self.title),
+            link = escape(&self.link),
+            feed_url = escape(&self.feed_url),
+        );
+        if let Some(updated) = self.last_modified() {
+            xml.push_str(&format!(
+                "<lastBuildDate>{}</lastBuildDate>\n",
+                rfc2822(updated)
+            ));
+        }
+        for entry in &self.entries {
+            xml.push_str(&format!(
+                "<item>\n\
+                 <title>{title}</title>\n\
+                 <link>{link}</link>\n\
+                 <guid isPermaLink=\"true\">{link}</guid>\n\
+                 <pubDate>{published}</pubDate>\n\
+                 <description>{summary}</description>\n",
+                title = escape(&entry.title),
+                link = escape(&entry.link),
+                published = rfc2822(entry.published),
+                summary = escape(&entry.summary),
+            ));
+            if let Some(content) = &entry.content {
+                xml.push_str(&format!(
+                    "<content:encoded>{}</content:encoded>\n",
+                    escape(content)
+                ));
+            }
+            for category in &entry.categories {
+                xml.push_str(&format!("<category>{}</category>\n", escape(category)));
+            }
+            xml.push_str("</item>\n");
+        }
+        xml.push_str("</channel>\n</rss>\n");
+        xml
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn rfc3339(dt: OffsetDateTime) -> String {
+    dt.to_offset(UtcOffset::UTC).format(Format::Rfc3339)
+}
+
+fn rfc2822(dt: OffsetDateTime) -> String {
+    dt.to_offset(UtcOffset::UTC)
+        .format("%a, %d %b %Y %H:%M:%S +0000")
+}
+
+#[cfg(test)]
+fn test_feed() -> Feed {
+    let published = OffsetDateTime::parse("2026-10-01T09:30:00+00:00", Format::Rfc3339).unwrap();
+    Feed {
+        title: "Hooksaurus Auctions: News".to_string(),
+        link: "http://localhost:8000/articles".to_string(),
+        feed_url: "http://localhost:8000/feeds/articles.atom".to_string(),
+        entries: vec![Entry {
+            title: "Hay & Feed Drive".to_string(),
+            link: "http://localhost:8000/articles/hay-feed-drive".to_string(),
+            summary: "Help us <fill> the barn".to_string(),
+            content: Some("<p>We need <strong>hay</strong>.</p>".to_string()),
+            categories: vec!["appeals".to_string()],
+            published,
+            updated: published + time::Duration::hours(2),
+            etag: Uuid::nil(),
+        }],
+    }
+}
+
+#[test]
+fn test_atom() {
+    let atom = test_feed().render(FeedFormat::Atom);
+    assert!(atom.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns"));
+    assert!(atom.contains("<title>Hay &amp; Feed Drive</title>"));
+    assert!(atom.contains("<published>2026-10-01T09:30:00+00:00</published>"));
+    assert!(atom.contains("<updated>2026-10-01T11:30:00+00:00</updated>"));
+    assert!(atom.contains("<summary>Help us &lt;fill&gt; the barn</summary>"));
+    assert!(atom.contains(
+        "<content type=\"html\">&lt;p&gt;We need &lt;strong&gt;hay&lt;/strong&gt;.&lt;/p&gt;</content>"
+    ));
+    assert!(atom.contains("<category term=\"appeals\"/>"));
+}
+
+#[test]
+fn test_rss() {
+    let rss = test_feed().render(FeedFormat::Rss);
+    assert!(rss.contains("<link>http://localhost:8000/articles</link>"));
+    assert!(rss.contains("<pubDate>Thu, 01 Oct 2026 09:30:00 +0000</pubDate>"));
+    assert!(rss.contains("<lastBuildDate>Thu, 01 Oct 2026 11:30:00 +0000</lastBuildDate>"));
+    assert!(rss.contains(
+        "<guid isPermaLink=\"true\">http://localhost:8000/articles/hay-feed-drive</guid>"
+    ));
+    assert!(rss.ends_with("</item>\n</channel>\n</rss>\n"));
+}
+
+#[test]
+fn test_feed_etag() {
+    let mut feed = test_feed();
+    let etag = feed.etag(FeedFormat::Atom);
+    assert_eq!(etag.len(), 34);
+    assert_ne!(etag, feed.etag(FeedFormat::Rss));
+    feed.entries.pop();
+    assert_ne!(etag, feed.etag(FeedFormat::Atom));
+    assert_eq!(
+        FeedFormat::parse("articles.rss"),
+        Some(("articles", FeedFormat::Rss))
+    );
+    assert_eq!(FeedFormat::parse("articles.json"), None);
+}
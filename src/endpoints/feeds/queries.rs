@@ -0,0 +1,102 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{ArticleEntry, ItemEntry, FEED_LENGTH};
+
+/// The newest published articles, with `tag` or about `auction_id` when they're given.
+#[instrument(skip(db))]
+pub async fn list_articles(
+    tag: Option<&str>,
+    auction_id: Option<Uuid>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<ArticleEntry>> {
+    sqlx::query_as!(
+        ArticleEntry,
+        r#"
+            select
+                slug,
+                title,
+                description,
+                body,
+                tag_list,
+                published_at "published_at!",
+                updated_at,
+                etag
+            from article
+            where tenant_id = $1
+            and published_at is not null
+            and ($2::text is null or $2 = any(tag_list))
+            and ($3::uuid is null or auction_id = $3)
+            order by published_at desc
+            limit $4
+        "#,
+        tenant_id,
+        tag,
+        auction_id,
+        FEED_LENGTH
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The items which opened for bidding most recently.
+#[instrument(skip(db))]
+pub async fn list_opened_items(tenant_id: Uuid, db: &PgPool) -> Result<Vec<ItemEntry>> {
+    sqlx::query_as!(
+        ItemEntry,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                ai.title,
+                ai.description,
+                a.title auction_title,
+                ai.tag_list,
+                greatest(ai.active_start_date, a.start_date) "opened_at!",
+                greatest(
+                    ai.updated_at, a.updated_at, ai.active_start_date, a.start_date
+                ) "updated_at!",
+                -- a new title for the auction changes the entry too
+                md5(ai.etag::text || a.etag::text)::uuid "etag!"
+            from auction_item ai
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            where ai.tenant_id = $1
+            and greatest(ai.active_start_date, a.start_date) <= now()
+            order by 7 desc, ai.title
+            limit $2
+        "#,
+        tenant_id,
+        FEED_LENGTH
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The auction's title, when it's one of the tenant's.
+#[instrument(skip(db))]
+pub async fn get_auction_title(
+    auction_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<String>> {
+    sqlx::query_scalar!(
+        r#"
+            select title
+            from auction
+            where auction_id = $1
+            and tenant_id = $2
+        "#,
+        auction_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
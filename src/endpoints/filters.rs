@@ -0,0 +1,87 @@
+//! Custom filters available in every template.
+use minijinja::value::Value;
+use minijinja::{Environment, Error, ErrorKind, State};
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use std::str::FromStr;
+
+use crate::db::tables;
+
+pub fn register(env: &mut Environment) {
+    env.add_filter("localtime", localtime);
+    env.add_filter("markdown", markdown);
+    env.add_filter("money", money);
+    env.add_filter("timeleft", timeleft);
+}
+
+fn invalid(detail: String) -> Error {
+    Error::new(ErrorKind::InvalidArguments, detail)
+}
+
+/// Render a serialized datetime in an IANA timezone:
+/// `{{ auction.start_date|localtime(auction.timezone) }}`
+// Filter signatures are dictated by minijinja.
+#[allow(clippy::result_large_err)]
+fn localtime(_: &State, value: String, tz: String) -> Result<String, Error> {
+    let dt = tables::LocalDateTime::parse(&value).map_err(invalid)?;
+    let tz = tables::parse_timezone(&tz).map_err(invalid)?;
+    Ok(tables::format_dt_in_timezone(
+        &dt.assume_utc(),
+        &tz,
+        "%b %-d, %Y %-I:%M %p %Z",
+    ))
+}
+
+/// Render markdown as HTML, cleaned of anything unsafe: `{{ article.body|markdown }}`
+#[allow(clippy::result_large_err)]
+fn markdown(_: &State, value: String) -> Result<Value, Error> {
+    Ok(Value::from_safe_string(tables::article::render_markdown(
+        &value,
+    )))
+}
+
+/// Render a serialized `Decimal` as dollars: `{{ item.high_bid|money }}`
+#[allow(clippy::result_large_err)]
+fn money(_: &State, value: String) -> Result<String, Error> {
+    let amount = Decimal::from_str(&value).map_err(|e| invalid(e.to_string()))?;
+    Ok(format!("${:.2}", amount))
+}
+
+/// Render how long until a serialized datetime, e.g. "2d 4h left" or "ended".
+#[allow(clippy::result_large_err)]
+fn timeleft(_: &State, value: String) -> Result<String, Error> {
+    let dt = tables::LocalDateTime::parse(&value).map_err(invalid)?;
+    Ok(format_time_left(
+        dt.assume_utc() - OffsetDateTime::now_utc(),
+    ))
+}
+
+fn format_time_left(left: time::Duration) -> String {
+    if left <= time::Duration::zero() {
+        return "ended".to_string();
+    }
+    let (days, hours, minutes) = (
+        left.whole_days(),
+        left.whole_hours() % 24,
+        left.whole_minutes() % 60,
+    );
+    if days > 0 {
+        format!("{}d {}h left", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m left", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m left", minutes)
+    } else {
+        "less than a minute left".to_string()
+    }
+}
+
+#[test]
+fn test_format_time_left() {
+    assert_eq!(format_time_left(time::Duration::seconds(-5)), "ended");
+    assert_eq!(
+        format_time_left(time::Duration::seconds(30)),
+        "less than a minute left"
+    );
+    assert_eq!(format_time_left(time::Duration::minutes(125)), "2h 5m left");
+    assert_eq!(format_time_left(time::Duration::hours(52)), "2d 4h left");
+}
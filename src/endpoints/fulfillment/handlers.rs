@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::header::HeaderMap,
+    response::{Html, Response},
+    routing::{get, post},
+    Router,
+};
+use minijinja::context;
+use std::collections::BTreeMap;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::deliveries::{self, DeliveryStatus};
+use crate::db::tenants::Tenant;
+use crate::endpoints::{
+    csv_response, parse_form, pdf_response, render_page, render_template, ApiContext,
+};
+use crate::error::{Error, Result};
+
+use super::{
+    label, labels_csv, packing_slips_pdf, queries, BatchParams, QueueEntry, QueueParams,
+    StatusFromForm,
+};
+
+pub fn admin_router() -> Router {
+    Router::new()
+        .route("/admin/fulfillment", get(get_queue))
+        .route("/admin/fulfillment/packing-slips", get(get_packing_slips))
+        .route("/admin/fulfillment/labels", get(get_labels))
+        .route(
+            "/admin/fulfillment/:auction_item_bid_id",
+            post(change_status),
+        )
+}
+
+#[instrument(skip(ctx))]
+async fn get_queue(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Query(params): Query<QueueParams>,
+) -> Result<Html<String>> {
+    render_queue(&ctx, &tenant, Some(&headers), params.status, vec![], None).await
+}
+
+/// Problems are rendered into the queue, since htmx won't swap in an error response.
+#[instrument(skip(ctx, body))]
+async fn change_status(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_item_bid_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    let form: StatusFromForm = parse_form(&body)?;
+    let changed = deliveries::transition(
+        auction_item_bid_id,
+        form.status,
+        &form.details,
+        tenant.tenant_id,
+        &ctx.db,
+    )
+    .await;
+    match changed {
+        Ok(()) => {
+            event!(
+                Level::INFO,
+                event_msg = "Changed delivery status",
+                auction_item_bid_id = %auction_item_bid_id,
+                status = form.status.as_str()
+            );
+            let message = format!("Marked {}: the bidder will be told.", label(form.status));
+            render_queue(&ctx, &tenant, None, form.filter, vec![], Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_queue(&ctx, &tenant, None, form.filter, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_packing_slips(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Query(params): Query<BatchParams>,
+) -> Result<Response> {
+    let shipments =
+        queries::list_ready_to_ship(params.auction_id, tenant.tenant_id, &ctx.db).await?;
+    let pdf = packing_slips_pdf(&shipments)?;
+    Ok(pdf_response("packing-slips.pdf", pdf))
+}
+
+#[instrument(skip(ctx))]
+async fn get_labels(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Query(params): Query<BatchParams>,
+) -> Result<Response> {
+    let shipments =
+        queries::list_ready_to_ship(params.auction_id, tenant.tenant_id, &ctx.db).await?;
+    let csv = labels_csv(&shipments)?;
+    Ok(csv_response("labels.csv", csv))
+}
+
+/// The full page when `headers` are given, otherwise only the queue fragment.
+async fn render_queue(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    filter: Option<String>,
+    errors: Vec<String>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let deliveries: Vec<QueueEntry> =
+        queries::list_deliveries(filter.as_deref(), tenant.tenant_id, &ctx.db)
+            .await?
+            .into_iter()
+            .map(|delivery| QueueEntry {
+                next: DeliveryStatus::parse(&delivery.status)
+                    .map(|status| status.next())
+                    .unwrap_or_default(),
+                delivery,
+            })
+            .collect();
+    let counts: BTreeMap<String, i64> = queries::count_by_status(tenant.tenant_id, &ctx.db)
+        .await?
+        .into_iter()
+        .map(|count| (count.status, count.count))
+        .collect();
+    let labels: BTreeMap<&str, &str> = DeliveryStatus::ALL
+        .iter()
+        .map(|status| (status.as_str(), label(*status)))
+        .collect();
+    let context = context!(
+        deliveries => deliveries,
+        statuses => DeliveryStatus::ALL.iter().map(|status| status.as_str()).collect::<Vec<_>>(),
+        labels => labels,
+        counts => counts,
+        filter => filter,
+        errors => errors,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_fulfillment.html", context),
+        None => render_template(ctx, "fragments/admin_fulfillment.html", context),
+    }
+}
@@ -0,0 +1,293 @@
+//! The clerks' fulfillment queue: every delivery still on its way to a winner, and the buttons
+//! which move it along. See `crate::db::deliveries` for the statuses and transitions.
+//!
+//! Everything ready to ship can also be printed as packing slips, one to a page, or exported
+//! as a label CSV for the shipping software to print postage from.
+use sqlx::types::time::OffsetDateTime;
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::deliveries::{DeliveryStatus, Transition};
+use crate::db::tables::{self, serialize_dt};
+use crate::pdf::{Cell, PdfBuilder, LEFT_EDGE};
+pub use handlers::admin_router;
+
+#[derive(Debug, serde::Serialize)]
+pub struct FulfillmentRow {
+    pub auction_item_bid_id: Uuid,
+    pub auction_id: Uuid,
+    pub auction_item_id: Uuid,
+    pub title: String,
+    pub auction_title: String,
+    pub bidder_name: String,
+    pub bidder_email: String,
+    // one line, or `None` while we wait for one
+    pub address: Option<String>,
+    pub local_pickup: bool,
+    pub status: String,
+    #[serde(serialize_with = "serialize_dt")]
+    pub status_changed_at: OffsetDateTime,
+    pub carrier: Option<String>,
+    pub tracking_number: Option<String>,
+    pub shipping_exception: Option<String>,
+    // whether the bidder has paid for the item yet
+    pub paid: bool,
+}
+
+/// A row of the queue, with the statuses it can move to.
+#[derive(Debug, serde::Serialize)]
+pub struct QueueEntry {
+    #[serde(flatten)]
+    pub delivery: FulfillmentRow,
+    pub next: Vec<DeliveryStatus>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct StatusCount {
+    pub status: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct QueueParams {
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub status: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct StatusFromForm {
+    pub status: DeliveryStatus,
+    // the queue's filter, to render the same list again
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub filter: Option<String>,
+    #[serde(flatten)]
+    pub details: Transition,
+}
+
+/// A delivery which is ready to ship, with everything the paperwork needs.
+#[derive(Debug)]
+pub struct ShipmentRow {
+    pub delivery_id: Uuid,
+    pub title: String,
+    pub auction_title: String,
+    pub bidder_name: String,
+    pub bidder_email: String,
+    pub sms_updates_number: Option<String>,
+    // who the winner asked to sign for it
+    pub signature_name: Option<String>,
+    pub street_address1: String,
+    pub street_address2: Option<String>,
+    pub street_address3: Option<String>,
+    pub city: String,
+    pub state_province_county: String,
+    pub postal_code: Option<String>,
+    pub country_code: Option<String>,
+    // the organization which donated the item, if one did
+    pub donor_name: Option<String>,
+    pub weight_class: String,
+    pub size_class: String,
+}
+
+impl ShipmentRow {
+    fn address_lines(&self) -> Vec<String> {
+        let mut lines = vec![self.street_address1.clone()];
+        lines.extend(self.street_address2.iter().cloned());
+        lines.extend(self.street_address3.iter().cloned());
+        lines.push(
+            [
+                Some(self.city.as_str()),
+                Some(self.state_province_county.as_str()),
+                self.postal_code.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", "),
+        );
+        lines.extend(self.country_code.iter().cloned());
+        lines
+    }
+}
+
+/// Limits a batch of paperwork to one auction.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct BatchParams {
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub auction_id: Option<Uuid>,
+}
+
+/// One packing slip to a page, to go in the box with the item.
+pub fn packing_slips_pdf(shipments: &[ShipmentRow]) -> anyhow::Result<Vec<u8>> {
+    let mut pdf = PdfBuilder::new();
+    if shipments.is_empty() {
+        pdf.heading("Packing slips")
+            .text("Nothing is ready to ship.");
+    }
+    for shipment in shipments {
+        pdf.page_break()
+            .heading("Packing slip")
+            .text("Hooksaurus Auctions")
+            .blank()
+            .subheading("Ship to");
+        pdf.text(&shipment.bidder_name);
+        for line in shipment.address_lines() {
+            pdf.text(&line);
+        }
+        pdf.blank()
+            .row(vec![
+                Cell::Left(LEFT_EDGE, "Item".to_string()),
+                Cell::Left(60.0, shipment.title.clone()),
+            ])
+            .row(vec![
+                Cell::Left(LEFT_EDGE, "Auction".to_string()),
+                Cell::Left(60.0, shipment.auction_title.clone()),
+            ])
+            .row(vec![
+                Cell::Left(LEFT_EDGE, "Won by".to_string()),
+                Cell::Left(
+                    60.0,
+                    format!("{} <{}>", shipment.bidder_name, shipment.bidder_email),
+                ),
+            ])
+            .row(vec![
+                Cell::Left(LEFT_EDGE, "Donated by".to_string()),
+                Cell::Left(
+                    60.0,
+                    shipment
+                        .donor_name
+                        .clone()
+                        .unwrap_or_else(|| "Hooksaurus Auctions".to_string()),
+                ),
+            ])
+            .row(vec![
+                Cell::Left(LEFT_EDGE, "Reference".to_string()),
+                Cell::Left(60.0, shipment.delivery_id.to_string()),
+            ]);
+        if let Some(signature_name) = &shipment.signature_name {
+            pdf.row(vec![
+                Cell::Left(LEFT_EDGE, "Signature by".to_string()),
+                Cell::Left(60.0, signature_name.clone()),
+            ]);
+        }
+        pdf.blank().text("Thank you for supporting the animals!");
+    }
+    pdf.render("Packing slips")
+}
+
+/// The label CSV's columns, which most shipping software can map on import.
+const LABEL_COLUMNS: [&str; 15] = [
+    "order_number",
+    "recipient_name",
+    "email",
+    "phone",
+    "address_line1",
+    "address_line2",
+    "address_line3",
+    "city",
+    "state",
+    "postal_code",
+    "country",
+    "item",
+    "weight_class",
+    "size_class",
+    "signature_name",
+];
+
+/// One row per shipment, under a header row even when there's nothing to ship.
+pub fn labels_csv(shipments: &[ShipmentRow]) -> anyhow::Result<Vec<u8>> {
+    let mut csv = csv::Writer::from_writer(vec![]);
+    csv.write_record(LABEL_COLUMNS)?;
+    for shipment in shipments {
+        let optional = |field: &Option<String>| field.clone().unwrap_or_default();
+        csv.write_record([
+            shipment.delivery_id.to_string(),
+            shipment.bidder_name.clone(),
+            shipment.bidder_email.clone(),
+            optional(&shipment.sms_updates_number),
+            shipment.street_address1.clone(),
+            optional(&shipment.street_address2),
+            optional(&shipment.street_address3),
+            shipment.city.clone(),
+            shipment.state_province_county.clone(),
+            optional(&shipment.postal_code),
+            optional(&shipment.country_code),
+            shipment.title.clone(),
+            shipment.weight_class.clone(),
+            shipment.size_class.clone(),
+            optional(&shipment.signature_name),
+        ])?;
+    }
+    Ok(csv.into_inner()?)
+}
+
+/// How each status reads in the admin.
+fn label(status: DeliveryStatus) -> &'static str {
+    match status {
+        DeliveryStatus::PendingAddress => "Needs address",
+        DeliveryStatus::ReadyToShip => "Ready to ship",
+        DeliveryStatus::Shipped => "Shipped",
+        DeliveryStatus::Delivered => "Delivered",
+        DeliveryStatus::Exception => "Exception",
+        DeliveryStatus::PickedUp => "Picked up",
+    }
+}
+
+#[cfg(test)]
+fn shipment(title: &str) -> ShipmentRow {
+    ShipmentRow {
+        delivery_id: Uuid::nil(),
+        title: title.to_string(),
+        auction_title: "Spring Fling".to_string(),
+        bidder_name: "Robin Farmer".to_string(),
+        bidder_email: "robin@example.com".to_string(),
+        sms_updates_number: None,
+        signature_name: None,
+        street_address1: "1 Barnyard Lane".to_string(),
+        street_address2: Some("Unit 2".to_string()),
+        street_address3: None,
+        city: "Petaluma".to_string(),
+        state_province_county: "CA".to_string(),
+        postal_code: Some("94952".to_string()),
+        country_code: None,
+        donor_name: None,
+        weight_class: "light".to_string(),
+        size_class: "small".to_string(),
+    }
+}
+
+#[test]
+fn test_labels_csv() {
+    let csv = labels_csv(&[shipment("Hay, \"fresh\" bales")]).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("order_number,recipient_name,email,phone,address_line1"));
+    assert_eq!(
+        lines.next().unwrap(),
+        "00000000-0000-0000-0000-000000000000,Robin Farmer,robin@example.com,,1 Barnyard Lane,\
+         Unit 2,,Petaluma,CA,94952,,\"Hay, \"\"fresh\"\" bales\",light,small,"
+    );
+    assert_eq!(lines.next(), None);
+    assert_eq!(
+        labels_csv(&[])
+            .unwrap()
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_packing_slips_pdf() {
+    assert_eq!(
+        shipment("Goat yoga").address_lines(),
+        vec!["1 Barnyard Lane", "Unit 2", "Petaluma, CA, 94952"]
+    );
+    let pdf = packing_slips_pdf(&[shipment("Goat yoga"), shipment("Hay bales")]).unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+}
@@ -0,0 +1,160 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{FulfillmentRow, ShipmentRow, StatusCount};
+
+/// The tenant's deliveries with the given status, or all those still in progress. Exceptions
+/// come first, then whatever has been waiting longest.
+#[instrument(skip(db))]
+pub async fn list_deliveries(
+    status: Option<&str>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<FulfillmentRow>> {
+    sqlx::query_as!(
+        FulfillmentRow,
+        r#"
+            select
+                d.auction_item_bid_id,
+                ai.auction_id "auction_id!",
+                ai.auction_item_id,
+                ai.title,
+                a.title auction_title,
+                coalesce(
+                    nullif(concat_ws(' ', u.first_name, u.last_name), ''),
+                    u.email
+                ) "bidder_name!",
+                u.email bidder_email,
+                case when ad.address_id is not null then
+                    concat_ws(
+                        ', ',
+                        ad.street_address1,
+                        nullif(ad.street_address2, ''),
+                        ad.city,
+                        ad.state_province_county,
+                        ad.postal_code
+                    )
+                end address,
+                d.local_pickup,
+                d.status,
+                d.status_changed_at,
+                d.carrier,
+                d.tracking_number,
+                d.shipping_exception,
+                exists(
+                    select 1
+                    from invoice_line il
+                    inner join invoice i
+                    on i.invoice_id = il.invoice_id
+                    where il.auction_item_bid_id = d.auction_item_bid_id
+                    and il.kind = 'item'
+                    and i.status = 'paid'
+                ) "paid!"
+            from auction_item_delivery d
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = d.auction_item_bid_id
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            inner join "user" u
+            on u.user_id = aib.user_id
+            left join address ad
+            on ad.address_id = d.shipping_address
+            where case
+                when $1::text is null then d.status not in ('delivered', 'picked_up')
+                else d.status = $1
+            end
+            and a.tenant_id = $2
+            order by d.status <> 'exception', d.status_changed_at
+        "#,
+        status,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn count_by_status(tenant_id: Uuid, db: &PgPool) -> Result<Vec<StatusCount>> {
+    sqlx::query_as!(
+        StatusCount,
+        r#"
+            select d.status, count(*) "count!"
+            from auction_item_delivery d
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = d.auction_item_bid_id
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            where ai.tenant_id = $1
+            group by d.status
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The tenant's deliveries ready to go out by carrier, in one auction or all of them, grouped
+/// by winner so a winner's items can go in one box.
+#[instrument(skip(db))]
+pub async fn list_ready_to_ship(
+    auction_id: Option<Uuid>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<ShipmentRow>> {
+    sqlx::query_as!(
+        ShipmentRow,
+        r#"
+            select
+                d.delivery_id,
+                ai.title,
+                a.title auction_title,
+                coalesce(
+                    nullif(concat_ws(' ', u.first_name, u.last_name), ''),
+                    u.email
+                ) "bidder_name!",
+                u.email bidder_email,
+                d.sms_updates_number,
+                d.signature_name,
+                ad.street_address1,
+                nullif(ad.street_address2, '') street_address2,
+                nullif(ad.street_address3, '') street_address3,
+                ad.city,
+                ad.state_province_county,
+                ad.postal_code,
+                ad.country_code,
+                donor.name "donor_name?",
+                ai.weight_class,
+                ai.size_class
+            from auction_item_delivery d
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = d.auction_item_bid_id
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            inner join "user" u
+            on u.user_id = aib.user_id
+            inner join address ad
+            on ad.address_id = d.shipping_address
+            left join organization donor
+            on donor.organization_id = ai.donated_by_organization_id
+            where d.status = 'ready_to_ship'
+            and not d.local_pickup
+            and ($1::uuid is null or ai.auction_id = $1)
+            and a.tenant_id = $2
+            order by "bidder_name!", u.user_id, ai.title
+        "#,
+        auction_id,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
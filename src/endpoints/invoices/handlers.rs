@@ -0,0 +1,268 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header::HeaderMap, Uri},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use minijinja::context;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::invoices;
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::MaybeAuthUser;
+use crate::endpoints::{pdf_response, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{invoice_pdf, queries, InvoiceDetail, InvoiceListParams, INVOICE_STATUSES};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/invoices/:invoice_id", get(get_invoice))
+        .route("/invoices/:invoice_id/pdf", get(get_invoice_pdf))
+}
+
+pub fn admin_router() -> Router {
+    Router::new()
+        .route("/admin/invoices", get(list_admin_invoices))
+        .route("/admin/invoices/:invoice_id", get(get_admin_invoice))
+        .route(
+            "/admin/invoices/:invoice_id/pdf",
+            get(get_admin_invoice_pdf),
+        )
+        .route("/admin/invoices/:invoice_id/issue", post(issue_invoice))
+        .route("/admin/invoices/:invoice_id/void", post(void_invoice))
+        .route("/admin/invoices/:invoice_id/refund", post(refund_invoice))
+        .route(
+            "/admin/auctions/:auction_id/invoices",
+            post(generate_invoices),
+        )
+}
+
+/// Bidders only see their own invoices, and not until they've been issued.
+async fn get_bidder_invoice(
+    auth_user: &MaybeAuthUser,
+    tenant: &Tenant,
+    invoice_id: Uuid,
+    ctx: &ApiContext,
+) -> Result<InvoiceDetail> {
+    let user_id = auth_user.user_id().ok_or(Error::Unauthorized)?;
+    queries::get_invoice(invoice_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .filter(|invoice| invoice.user_id == user_id && invoice.status != "draft")
+        .ok_or(Error::NotFound)
+}
+
+/// Admins can only act on their own tenant's invoices: anything else is `Error::NotFound`.
+async fn require_in_tenant(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    invoice_id: Uuid,
+) -> Result<InvoiceDetail> {
+    queries::get_invoice(invoice_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+fn login_redirect(invoice_id: Uuid) -> Response {
+    match format!("/login?next=/invoices/{}", invoice_id).parse::<Uri>() {
+        Ok(uri) => Redirect::to(uri).into_response(),
+        Err(_) => Error::Unauthorized.into_response(),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_invoice(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Response> {
+    if auth_user.0.is_none() {
+        return Ok(login_redirect(invoice_id));
+    }
+    let invoice = get_bidder_invoice(&auth_user, &tenant, invoice_id, &ctx).await?;
+    let lines = queries::list_invoice_lines(invoice_id, &ctx.db).await?;
+    let payments = queries::list_payments(invoice_id, &ctx.db).await?;
+    Ok(render_page(
+        &ctx,
+        &headers,
+        "invoice.html",
+        context!(
+            title => format!("Invoice {}", invoice.reference),
+            logged_in => true,
+            invoice => invoice,
+            lines => lines,
+            payment => payments.first(),
+            pdf_url => format!("/invoices/{}/pdf", invoice_id),
+            pay_url => ctx.payments.as_ref().map(|_| format!("/invoices/{}/pay", invoice_id)),
+            receipt_url => format!("/invoices/{}/receipt", invoice_id),
+        ),
+    )?
+    .into_response())
+}
+
+#[instrument(skip(ctx))]
+async fn get_invoice_pdf(
+    auth_user: MaybeAuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Response> {
+    if auth_user.0.is_none() {
+        return Ok(login_redirect(invoice_id));
+    }
+    let invoice = get_bidder_invoice(&auth_user, &tenant, invoice_id, &ctx).await?;
+    render_invoice_pdf(&ctx, invoice).await
+}
+
+async fn render_invoice_pdf(ctx: &ApiContext, invoice: InvoiceDetail) -> Result<Response> {
+    let lines = queries::list_invoice_lines(invoice.invoice_id, &ctx.db).await?;
+    let pdf = invoice_pdf(&invoice, &lines)?;
+    Ok(pdf_response(&format!("{}.pdf", invoice.reference), pdf))
+}
+
+#[instrument(skip(ctx))]
+async fn list_admin_invoices(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Query(params): Query<InvoiceListParams>,
+) -> Result<Html<String>> {
+    render_invoice_list(&ctx, &tenant, Some(&headers), params, None).await
+}
+
+/// The full page when `headers` are given, otherwise only the list fragment.
+async fn render_invoice_list(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    params: InvoiceListParams,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let invoices = queries::list_invoices(&params, tenant.tenant_id, &ctx.db).await?;
+    let auctions = queries::list_ended_auctions(tenant.tenant_id, &ctx.db).await?;
+    let context = context!(
+        invoices => invoices,
+        auctions => auctions,
+        statuses => INVOICE_STATUSES,
+        auction_id => params.auction_id,
+        status => params.status,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_invoice_list.html", context),
+        None => render_template(ctx, "fragments/admin_invoice_list.html", context),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_admin_invoice(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    render_admin_invoice(&ctx, &tenant, Some(&headers), invoice_id, None).await
+}
+
+async fn render_admin_invoice(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    invoice_id: Uuid,
+    message: Option<&str>,
+) -> Result<Html<String>> {
+    let invoice = require_in_tenant(ctx, tenant, invoice_id).await?;
+    let lines = queries::list_invoice_lines(invoice_id, &ctx.db).await?;
+    let payments = queries::list_payments(invoice_id, &ctx.db).await?;
+    let context = context!(
+        invoice => invoice,
+        lines => lines,
+        payments => payments,
+        pdf_url => format!("/admin/invoices/{}/pdf", invoice_id),
+        receipt_url => format!("/admin/invoices/{}/receipt", invoice_id),
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_invoice.html", context),
+        None => render_template(ctx, "fragments/admin_invoice.html", context),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_admin_invoice_pdf(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Response> {
+    let invoice = require_in_tenant(&ctx, &tenant, invoice_id).await?;
+    render_invoice_pdf(&ctx, invoice).await
+}
+
+#[instrument(skip(ctx))]
+async fn issue_invoice(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    require_in_tenant(&ctx, &tenant, invoice_id).await?;
+    invoices::issue_invoice(invoice_id, &ctx.db).await?;
+    event!(Level::INFO, event_msg = "Issued invoice", invoice_id=%invoice_id);
+    render_admin_invoice(
+        &ctx,
+        &tenant,
+        None,
+        invoice_id,
+        Some("Issued: the bidder has been told."),
+    )
+    .await
+}
+
+#[instrument(skip(ctx))]
+async fn void_invoice(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    require_in_tenant(&ctx, &tenant, invoice_id).await?;
+    invoices::void_invoice(invoice_id, &ctx.db).await?;
+    event!(Level::INFO, event_msg = "Voided invoice", invoice_id=%invoice_id);
+    render_admin_invoice(&ctx, &tenant, None, invoice_id, Some("Voided.")).await
+}
+
+#[instrument(skip(ctx))]
+async fn refund_invoice(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    require_in_tenant(&ctx, &tenant, invoice_id).await?;
+    ctx.payments
+        .as_ref()
+        .ok_or(Error::NotFound)?
+        .refund(invoice_id, &ctx.db)
+        .await?;
+    event!(Level::INFO, event_msg = "Refunded invoice", invoice_id=%invoice_id);
+    render_admin_invoice(&ctx, &tenant, None, invoice_id, Some("Refunded in full.")).await
+}
+
+#[instrument(skip(ctx))]
+async fn generate_invoices(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    queries::get_auction_title(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let drafts = invoices::generate_invoices(auction_id, &ctx.db).await?;
+    let params = InvoiceListParams {
+        auction_id: Some(auction_id),
+        status: None,
+    };
+    let message = format!("This auction has {} draft invoices.", drafts);
+    render_invoice_list(&ctx, &tenant, None, params, Some(message)).await
+}
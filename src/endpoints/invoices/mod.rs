@@ -0,0 +1,140 @@
+//! Invoices for winning bidders, as pages and as printable PDFs.
+//!
+//! Bidders can see their own invoices once they've been issued. Admins can see every invoice,
+//! build drafts for an auction, and issue or void them.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables::{self, serialize_dt, serialize_dt_opt};
+use crate::pdf::{Cell, PdfBuilder, LEFT_EDGE, RIGHT_EDGE};
+pub use handlers::{admin_router, router};
+
+const INVOICE_STATUSES: [&str; 5] = ["draft", "issued", "paid", "refunded", "void"];
+
+#[derive(Debug, serde::Serialize)]
+pub struct InvoiceDetail {
+    pub invoice_id: Uuid,
+    // e.g. INV-000042
+    pub reference: String,
+    pub auction_id: Uuid,
+    pub auction_title: String,
+    pub timezone: String,
+    pub user_id: Uuid,
+    pub bidder_name: String,
+    pub bidder_email: String,
+    pub status: String,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub issued_at: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub paid_at: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub refunded_at: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub created_at: OffsetDateTime,
+    pub total: Decimal,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct InvoiceLine {
+    pub auction_item_id: Option<Uuid>,
+    // "item" or "shipping"
+    pub kind: String,
+    pub description: String,
+    pub amount: Decimal,
+}
+
+/// An attempt to pay an invoice, newest first.
+#[derive(Debug, serde::Serialize)]
+pub struct InvoicePayment {
+    pub payment_id: Uuid,
+    pub provider: String,
+    pub provider_ref: String,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    // one of "pending", "succeeded", "failed", "expired" or "refunded"
+    pub status: String,
+    pub failure_reason: Option<String>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub created_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub completed_at: Option<OffsetDateTime>,
+}
+
+/// An invoice in a list.
+#[derive(Debug, serde::Serialize)]
+pub struct InvoiceRow {
+    pub invoice_id: Uuid,
+    pub reference: String,
+    pub auction_title: String,
+    pub bidder_email: String,
+    pub status: String,
+    pub line_count: i64,
+    pub total: Decimal,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub issued_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct AuctionChoice {
+    pub auction_id: Uuid,
+    pub title: String,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct InvoiceListParams {
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub auction_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub status: Option<String>,
+}
+
+/// The same invoice as the HTML page, laid out for printing.
+pub fn invoice_pdf(invoice: &InvoiceDetail, lines: &[InvoiceLine]) -> anyhow::Result<Vec<u8>> {
+    let tz = tables::parse_timezone(&invoice.timezone).map_err(anyhow::Error::msg)?;
+    let date = invoice.issued_at.unwrap_or(invoice.created_at);
+    let mut pdf = PdfBuilder::new();
+    pdf.heading(&format!("Invoice {}", invoice.reference))
+        .text("Hooksaurus Auctions")
+        .blank()
+        .row(vec![
+            Cell::Left(LEFT_EDGE, "Billed to".to_string()),
+            Cell::Left(
+                60.0,
+                format!("{} <{}>", invoice.bidder_name, invoice.bidder_email),
+            ),
+        ])
+        .row(vec![
+            Cell::Left(LEFT_EDGE, "Auction".to_string()),
+            Cell::Left(60.0, invoice.auction_title.clone()),
+        ])
+        .row(vec![
+            Cell::Left(LEFT_EDGE, "Date".to_string()),
+            Cell::Left(
+                60.0,
+                tables::format_dt_in_timezone(&date, &tz, "%B %-d, %Y"),
+            ),
+        ])
+        .row(vec![
+            Cell::Left(LEFT_EDGE, "Status".to_string()),
+            Cell::Left(60.0, invoice.status.to_uppercase()),
+        ])
+        .blank()
+        .bold_row(vec![
+            Cell::Left(LEFT_EDGE, "Description".to_string()),
+            Cell::Right(RIGHT_EDGE, "Amount".to_string()),
+        ]);
+    for line in lines {
+        pdf.row(vec![
+            Cell::Left(LEFT_EDGE, line.description.clone()),
+            Cell::Right(RIGHT_EDGE, format!("${:.2}", line.amount)),
+        ]);
+    }
+    pdf.blank().bold_row(vec![
+        Cell::Left(LEFT_EDGE, "Total".to_string()),
+        Cell::Right(RIGHT_EDGE, format!("${:.2}", invoice.total)),
+    ]);
+    pdf.render(&format!("Invoice {}", invoice.reference))
+}
@@ -0,0 +1,184 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{
+    AuctionChoice, InvoiceDetail, InvoiceLine, InvoiceListParams, InvoicePayment, InvoiceRow,
+};
+
+/// The invoice, if it's for one of the tenant's auctions.
+#[instrument(skip(db))]
+pub async fn get_invoice(
+    invoice_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<InvoiceDetail>> {
+    sqlx::query_as!(
+        InvoiceDetail,
+        r#"
+            select
+                i.invoice_id,
+                'INV-' || lpad(i.invoice_number::text, 6, '0') "reference!",
+                i.auction_id,
+                a.title auction_title,
+                a.timezone,
+                i.user_id,
+                coalesce(
+                    nullif(concat_ws(' ', u.first_name, u.last_name), ''),
+                    u.email
+                ) "bidder_name!",
+                u.email bidder_email,
+                i.status,
+                i.issued_at,
+                i.paid_at,
+                i.refunded_at,
+                i.created_at,
+                (
+                    select coalesce(sum(amount), 0)
+                    from invoice_line il
+                    where il.invoice_id = i.invoice_id
+                ) "total!"
+            from invoice i
+            inner join auction a
+            on a.auction_id = i.auction_id
+            inner join "user" u
+            on u.user_id = i.user_id
+            where i.invoice_id = $1
+            and a.tenant_id = $2
+        "#,
+        invoice_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Each item followed by its shipping.
+#[instrument(skip(db))]
+pub async fn list_invoice_lines(invoice_id: Uuid, db: &PgPool) -> Result<Vec<InvoiceLine>> {
+    sqlx::query_as!(
+        InvoiceLine,
+        r#"
+            select auction_item_id, kind, description, amount
+            from invoice_line
+            where invoice_id = $1
+            order by auction_item_id, kind, created_at
+        "#,
+        invoice_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_invoices(
+    params: &InvoiceListParams,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<InvoiceRow>> {
+    sqlx::query_as!(
+        InvoiceRow,
+        r#"
+            select
+                i.invoice_id,
+                'INV-' || lpad(i.invoice_number::text, 6, '0') "reference!",
+                a.title auction_title,
+                u.email bidder_email,
+                i.status,
+                lines.line_count "line_count!",
+                lines.total "total!",
+                i.issued_at
+            from invoice i
+            inner join auction a
+            on a.auction_id = i.auction_id
+            inner join "user" u
+            on u.user_id = i.user_id
+            cross join lateral (
+                select count(*) line_count, coalesce(sum(amount), 0) total
+                from invoice_line il
+                where il.invoice_id = i.invoice_id
+            ) lines
+            where ($1::uuid is null or i.auction_id = $1)
+            and ($2::text is null or i.status = $2)
+            and a.tenant_id = $3
+            order by i.invoice_number desc
+        "#,
+        params.auction_id,
+        params.status,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The title of one of the tenant's auctions.
+#[instrument(skip(db))]
+pub async fn get_auction_title(
+    auction_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<String>> {
+    sqlx::query_scalar!(
+        r#"
+            select title
+            from auction
+            where auction_id = $1
+            and tenant_id = $2
+        "#,
+        auction_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The tenant's auctions which have ended, and so can be invoiced.
+#[instrument(skip(db))]
+pub async fn list_ended_auctions(tenant_id: Uuid, db: &PgPool) -> Result<Vec<AuctionChoice>> {
+    sqlx::query_as!(
+        AuctionChoice,
+        r#"
+            select auction_id, title
+            from auction
+            where end_date <= now()
+            and tenant_id = $1
+            order by end_date desc
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_payments(invoice_id: Uuid, db: &PgPool) -> Result<Vec<InvoicePayment>> {
+    sqlx::query_as!(
+        InvoicePayment,
+        r#"
+            select
+                payment_id,
+                provider,
+                provider_ref,
+                amount,
+                fee,
+                status,
+                failure_reason,
+                created_at,
+                completed_at
+            from payment
+            where invoice_id = $1
+            order by created_at desc
+        "#,
+        invoice_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -1,16 +1,52 @@
 use crate::config::Config;
 use anyhow::Context;
-use axum::{extract::Extension, http::Method, Router};
+use axum::{
+    extract::{extractor_middleware, Extension},
+    http::{
+        header::{HeaderMap, HeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE},
+        Method,
+    },
+    response::{Html, IntoResponse, Response},
+    Router,
+};
 use minijinja::{Environment, Source};
 use sqlx::PgPool;
 use std::sync::Arc;
-use tower::ServiceBuilder;
+use tower::make::Shared;
+use tower::{Layer, ServiceBuilder};
 use tower_http::cors::{Any, CorsLayer, Origin};
 
-use crate::error::{Error, ResultExt};
+use crate::endpoints::extractor::AdminUser;
+use crate::error::Error;
+use crate::jobs;
+use crate::notify::Notifications;
+use crate::payments::Payments;
+use crate::shipping::{DistanceRates, ShippingRateCalculator};
+use crate::tracking::{CarrierTracker, HttpTracker};
 
 mod admin;
+mod api;
+mod articles;
+mod auctions;
 mod base;
+mod dashboard;
+mod deliveries;
+mod donations;
+mod extractor;
+mod feeds;
+mod filters;
+mod fulfillment;
+mod invoices;
+mod organizations;
+mod payments;
+mod payouts;
+mod pickups;
+mod receipts;
+mod search;
+mod tenant;
+mod users;
+
+pub use tenant::Tenants;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -21,21 +57,89 @@ pub struct ApiContext {
     config: Arc<Config>,
     db: PgPool,
     template_env: Environment<'static>,
+    // `None` without a payment gateway, when invoices can't be paid online
+    payments: Option<Payments>,
+    shipping: Arc<dyn ShippingRateCalculator>,
 }
 
-pub async fn serve(config: Config, db: PgPool) -> anyhow::Result<()> {
+/// Every template under `./templates`, with our filters registered. Pages are `.html`, and
+/// the plain text bodies of notifications are `.txt`.
+pub fn template_env() -> Environment<'static> {
     let mut env = Environment::new();
     let mut source = Source::new();
-    source.load_from_path("templates", &["html"]).unwrap();
+    source
+        .load_from_path("templates", &["html", "txt"])
+        .unwrap();
     env.set_source(source);
+    filters::register(&mut env);
+    env.add_global(
+        "tenant",
+        minijinja::value::Value::from_object(tenant::CurrentTenant),
+    );
+    env
+}
 
-    let app = api_router().layer(
+pub async fn serve(config: Config, db: PgPool) -> anyhow::Result<()> {
+    let env = template_env();
+    let notifications = Notifications::from_config(&config.notify, env.clone())?;
+    let payments = Payments::from_config(&config.payments, &config.notify.site_url);
+    let tracker: Option<Arc<dyn CarrierTracker>> = match &config.tracking.tracking_api_url {
+        Some(url) => Some(Arc::new(HttpTracker::new(
+            url,
+            config
+                .tracking
+                .tracking_api_key
+                .as_deref()
+                .unwrap_or_default(),
+        )?)),
+        None => None,
+    };
+    if tracker.is_some() {
+        jobs::ensure_queued(&jobs::Job::TrackShipments, &db).await?;
+    }
+    jobs::spawn_workers(
+        config.job_workers,
+        notifications,
+        payments.clone(),
+        tracker,
+        db.clone(),
+    );
+
+    let app = app_with_context(ApiContext {
+        config: Arc::new(config),
+        db,
+        template_env: env,
+        payments,
+        shipping: Arc::new(DistanceRates),
+    });
+    axum::Server::bind(&"0.0.0.0:8000".parse()?)
+        .serve(Shared::new(app))
+        .await
+        .context("error running HTTP server")
+}
+
+/// Every route, as `serve` serves them, but without starting the background job workers.
+pub fn app(config: Config, db: PgPool) -> Tenants<Router> {
+    let payments = Payments::from_config(&config.payments, &config.notify.site_url);
+    app_with_context(ApiContext {
+        config: Arc::new(config),
+        db,
+        template_env: template_env(),
+        payments,
+        shipping: Arc::new(DistanceRates),
+    })
+}
+
+fn app_with_context(ctx: ApiContext) -> Tenants<Router> {
+    let tenants = tenant::TenantLayer::new(ctx.db.clone());
+    let mut router = api_router();
+    // anyone can pay with the mock, so its checkout page is only there when it's in use
+    if ctx.payments.as_ref().and_then(Payments::mock).is_some() {
+        router = router.merge(payments::mock_router());
+    }
+    let router = router.layer(
         ServiceBuilder::new()
-            .layer(Extension(ApiContext {
-                config: Arc::new(config),
-                db,
-                template_env: env,
-            }))
+            .layer(Extension(ctx))
             .layer(TraceLayer::new_for_http())
             .layer(
                 CorsLayer::new()
@@ -44,12 +148,105 @@ pub async fn serve(config: Config, db: PgPool) -> anyhow::Result<()> {
                     .allow_headers(Any),
             ),
     );
-    axum::Server::bind(&"0.0.0.0:8000".parse()?)
-        .serve(app.into_make_service())
-        .await
-        .context("error running HTTP server")
+    // outside of the router, so a tenant's path prefix is gone before routing
+    tenants.layer(router)
+}
+
+/// Decode a urlencoded form body. Handlers take the raw body so a bad form can be reported
+/// alongside the form instead of as a bare extractor rejection.
+fn parse_form<T: serde::de::DeserializeOwned>(body: &str) -> Result<T> {
+    serde_urlencoded::from_str(body)
+        .map_err(|e| Error::unprocessable_entity([("form", e.to_string())]))
+}
+
+/// Render `completes/<name>` for a full page load, or only `fragments/<name>` when htmx asks.
+fn render_page(
+    ctx: &ApiContext,
+    headers: &HeaderMap,
+    name: &str,
+    context: minijinja::value::Value,
+) -> Result<Html<String>> {
+    let kind = if headers.get("hx-request").is_some() {
+        "fragments"
+    } else {
+        "completes"
+    };
+    render_template(ctx, &format!("{}/{}", kind, name), context)
+}
+
+/// Serve a PDF for the browser to show, or save as `filename`.
+fn pdf_response(filename: &str, pdf: Vec<u8>) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/pdf"));
+    if let Ok(disposition) = HeaderValue::from_str(&format!("inline; filename=\"{}\"", filename)) {
+        headers.insert(CONTENT_DISPOSITION, disposition);
+    }
+    (headers, pdf).into_response()
+}
+
+/// Serve a CSV for the browser to download as `filename`.
+fn csv_response(filename: &str, csv: Vec<u8>) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/csv; charset=utf-8"),
+    );
+    if let Ok(disposition) =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename))
+    {
+        headers.insert(CONTENT_DISPOSITION, disposition);
+    }
+    (headers, csv).into_response()
+}
+
+/// Render `name`, or the tenant's own copy of it under `tenants/<slug>/` when it has one.
+fn render_template(
+    ctx: &ApiContext,
+    name: &str,
+    context: minijinja::value::Value,
+) -> Result<Html<String>> {
+    let template = match tenant::current().and_then(|tenant| {
+        ctx.template_env
+            .get_template(&format!("tenants/{}/{}", tenant.slug, name))
+            .ok()
+    }) {
+        Some(template) => template,
+        None => ctx
+            .template_env
+            .get_template(name)
+            .map_err(anyhow::Error::from)?,
+    };
+    Ok(Html(template.render(context).map_err(anyhow::Error::from)?))
 }
 
 fn api_router() -> Router {
-    base::router().merge(admin::router())
+    base::router()
+        .merge(admin_router())
+        .merge(api::router())
+        .merge(articles::router())
+        .merge(auctions::router())
+        .merge(search::router())
+        .merge(dashboard::router())
+        .merge(deliveries::router())
+        .merge(donations::router())
+        .merge(feeds::router())
+        .merge(invoices::router())
+        .merge(organizations::router())
+        .merge(payments::router())
+        .merge(receipts::router())
+        .merge(users::router())
+}
+
+/// Everything under `/admin`, which only the tenant's admins may use.
+fn admin_router() -> Router {
+    admin::admin_router()
+        .merge(articles::admin_router())
+        .merge(donations::admin_router())
+        .merge(fulfillment::admin_router())
+        .merge(invoices::admin_router())
+        .merge(organizations::admin_router())
+        .merge(payouts::admin_router())
+        .merge(pickups::admin_router())
+        .merge(receipts::admin_router())
+        .route_layer(extractor_middleware::<AdminUser>())
 }
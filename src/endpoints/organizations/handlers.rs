@@ -0,0 +1,262 @@
+use axum::{
+    extract::{Extension, Path},
+    http::{header::HeaderMap, Uri},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{delete, get},
+    Router,
+};
+use minijinja::context;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::organizations::{self, OrgRole, OrganizationProfile};
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::{AuthUser, MaybeAuthUser};
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, MemberFromForm};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/organizations", get(list_organizations))
+        .route(
+            "/organizations/:organization_id",
+            get(get_organization).put(update_profile),
+        )
+}
+
+pub fn admin_router() -> Router {
+    Router::new()
+        .route(
+            "/admin/organizations/:organization_id/members",
+            get(get_members).post(add_member),
+        )
+        .route(
+            "/admin/organizations/:organization_id/members/:user_id",
+            delete(remove_member),
+        )
+}
+
+/// The organization's name, or `Error::NotFound` when it's another tenant's.
+async fn require_in_tenant(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    organization_id: Uuid,
+) -> Result<String> {
+    queries::get_organization_name(organization_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+fn login_redirect(next: &str) -> Response {
+    match format!("/login?next={}", next).parse::<Uri>() {
+        Ok(uri) => Redirect::to(uri).into_response(),
+        Err(_) => Error::Unauthorized.into_response(),
+    }
+}
+
+/// Straight to the portal for someone in one organization, otherwise a list to choose from.
+#[instrument(skip(ctx))]
+async fn list_organizations(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Result<Response> {
+    let user_id = match auth_user.user_id() {
+        Some(user_id) => user_id,
+        None => return Ok(login_redirect("/organizations")),
+    };
+    let memberships =
+        queries::list_memberships(user_id, tenant.tenant_id, None, 0, &ctx.db).await?;
+    if let [membership] = memberships.as_slice() {
+        let uri = format!("/organizations/{}", membership.organization_id);
+        return Ok(Redirect::to(uri.parse::<Uri>().map_err(anyhow::Error::from)?).into_response());
+    }
+    Ok(render_page(
+        &ctx,
+        &headers,
+        "organizations.html",
+        context!(
+            title => "My organizations",
+            logged_in => true,
+            memberships => memberships,
+        ),
+    )?
+    .into_response())
+}
+
+#[instrument(skip(ctx))]
+async fn get_organization(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Response> {
+    let user_id = match auth_user.user_id() {
+        Some(user_id) => user_id,
+        None => {
+            let next = format!("/organizations/{}", organization_id);
+            return Ok(login_redirect(&next));
+        }
+    };
+    require_in_tenant(&ctx, &tenant, organization_id).await?;
+    let role = organizations::require_member(organization_id, user_id, &ctx.db).await?;
+    let profile = organizations::get_profile(organization_id, &ctx.db).await?;
+    let (donated, benefits): (Vec<_>, Vec<_>) =
+        queries::list_items(organization_id, None, 0, &ctx.db)
+            .await?
+            .into_iter()
+            .partition(|item| item.donated);
+    // an item it donated to raise money for itself is only listed once, as a donation
+    let benefits: Vec<_> = benefits.into_iter().filter(|item| item.benefits).collect();
+    Ok(render_page(
+        &ctx,
+        &headers,
+        "organization.html",
+        context!(
+            title => profile.name.clone(),
+            logged_in => true,
+            organization_id => organization_id,
+            can_edit => role == OrgRole::Manager,
+            profile => profile,
+            donated => donated,
+            benefits => benefits,
+            proceeds => queries::get_proceeds(organization_id, &ctx.db).await?,
+            payouts => queries::list_payouts(organization_id, &ctx.db).await?,
+            errors => Vec::<String>::new(),
+        ),
+    )?
+    .into_response())
+}
+
+/// Problems are rendered into the form, since htmx won't swap in an error response.
+#[instrument(skip(ctx, body))]
+async fn update_profile(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    auth_user: AuthUser,
+    Path(organization_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    require_in_tenant(&ctx, &tenant, organization_id).await?;
+    let profile: OrganizationProfile = parse_form(&body)?;
+    let (errors, message) =
+        match organizations::update_profile(organization_id, auth_user.user_id, &profile, &ctx.db)
+            .await
+        {
+            Ok(()) => {
+                event!(
+                    Level::INFO,
+                    event_msg = "Updated organization profile",
+                    organization_id = %organization_id,
+                    user_id = %auth_user.user_id
+                );
+                (vec![], Some("Saved."))
+            }
+            Err(Error::UnprocessableEntity { errors }) => (
+                errors.into_values().flatten().map(String::from).collect(),
+                None,
+            ),
+            Err(e) => return Err(e),
+        };
+    render_template(
+        &ctx,
+        "fragments/organization_profile.html",
+        context!(
+            organization_id => organization_id,
+            can_edit => true,
+            profile => profile,
+            errors => errors,
+            message => message,
+        ),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn get_members(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(organization_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    render_members(&ctx, &tenant, Some(&headers), organization_id, vec![], None).await
+}
+
+#[instrument(skip(ctx, body))]
+async fn add_member(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(organization_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    require_in_tenant(&ctx, &tenant, organization_id).await?;
+    let added = match parse_form::<MemberFromForm>(&body) {
+        Ok(form) => organizations::add_member(organization_id, &form.email, form.role, &ctx.db)
+            .await
+            .map(|()| form),
+        Err(e) => Err(e),
+    };
+    match added {
+        Ok(form) => {
+            event!(
+                Level::INFO,
+                event_msg = "Added organization member",
+                organization_id = %organization_id,
+                role = form.role.as_str()
+            );
+            let message = format!("{} is now a {}.", form.email.trim(), form.role.as_str());
+            render_members(&ctx, &tenant, None, organization_id, vec![], Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_members(&ctx, &tenant, None, organization_id, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn remove_member(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path((organization_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<Html<String>> {
+    require_in_tenant(&ctx, &tenant, organization_id).await?;
+    if !organizations::remove_member(organization_id, user_id, &ctx.db).await? {
+        return Err(Error::NotFound);
+    }
+    event!(
+        Level::INFO,
+        event_msg = "Removed organization member",
+        organization_id = %organization_id,
+        user_id = %user_id
+    );
+    let message = "Removed them from the organization.".to_string();
+    render_members(&ctx, &tenant, None, organization_id, vec![], Some(message)).await
+}
+
+/// The full page when `headers` are given, otherwise only the members fragment.
+async fn render_members(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    organization_id: Uuid,
+    errors: Vec<String>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let name = require_in_tenant(ctx, tenant, organization_id).await?;
+    let context = context!(
+        organization_id => organization_id,
+        name => name,
+        members => queries::list_members(organization_id, &ctx.db).await?,
+        errors => errors,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_organization_members.html", context),
+        None => render_template(ctx, "fragments/admin_organization_members.html", context),
+    }
+}
@@ -0,0 +1,73 @@
+//! The organization portal, where the people who look after a donor or beneficiary see what
+//! it has given and raised, and edit its profile. Membership is managed by admins, see
+//! `crate::db::organizations`.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+pub(super) mod queries;
+
+use crate::db::tables::{serialize_dt, serialize_dt_opt};
+pub use handlers::{admin_router, router};
+
+/// An organization the user belongs to.
+#[derive(Debug, serde::Serialize)]
+pub struct Membership {
+    pub organization_id: Uuid,
+    pub name: String,
+    // "manager" or "member"
+    pub role: String,
+}
+
+/// An item the organization donated, or which raises money for it.
+#[derive(Debug, serde::Serialize)]
+pub struct OrganizationItem {
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub auction_title: String,
+    pub timezone: String,
+    pub title: String,
+    pub donated: bool,
+    pub benefits: bool,
+    #[serde(serialize_with = "serialize_dt")]
+    pub end_date: OffsetDateTime,
+    pub is_open: bool,
+    pub high_bid: Option<Decimal>,
+    // the winning bid, once there is one
+    pub sold_for: Option<Decimal>,
+    pub delivery_status: Option<String>,
+}
+
+/// What the organization has raised, as in the admin payouts page.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct Proceeds {
+    pub proceeds: Decimal,
+    pub refunds: Decimal,
+    pub fees: Decimal,
+    pub paid_out: Decimal,
+    pub owed: Decimal,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub last_paid_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct OrganizationPayout {
+    pub amount: Decimal,
+    pub reference: String,
+    #[serde(serialize_with = "serialize_dt")]
+    pub paid_at: OffsetDateTime,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct MemberRow {
+    pub user_id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+    pub role: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct MemberFromForm {
+    pub email: String,
+    pub role: crate::db::organizations::OrgRole,
+}
@@ -0,0 +1,227 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{MemberRow, Membership, OrganizationItem, OrganizationPayout, Proceeds};
+
+/// The organizations the user is a member of, by name. `limit` and `offset` read a page of
+/// them, and a `None` limit all of them.
+#[instrument(skip(db))]
+pub async fn list_memberships(
+    user_id: Uuid,
+    tenant_id: Uuid,
+    limit: Option<i64>,
+    offset: i64,
+    db: &PgPool,
+) -> Result<Vec<Membership>> {
+    sqlx::query_as!(
+        Membership,
+        r#"
+            select o.organization_id, o.name, m.role
+            from organization_member m
+            inner join organization o
+            on o.organization_id = m.organization_id
+            where m.user_id = $1
+            and o.tenant_id = $2
+            order by o.name, o.organization_id
+            limit $3
+            offset $4
+        "#,
+        user_id,
+        tenant_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn count_memberships(user_id: Uuid, tenant_id: Uuid, db: &PgPool) -> Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from organization_member m
+            inner join organization o
+            on o.organization_id = m.organization_id
+            where m.user_id = $1
+            and o.tenant_id = $2
+        "#,
+        user_id,
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Items the organization donated, and items whose proceeds go to it: its own, or its
+/// auction's when the item doesn't name a beneficiary. The latest auctions come first.
+/// `limit` and `offset` read a page of them, and a `None` limit all of them.
+#[instrument(skip(db))]
+pub async fn list_items(
+    organization_id: Uuid,
+    limit: Option<i64>,
+    offset: i64,
+    db: &PgPool,
+) -> Result<Vec<OrganizationItem>> {
+    sqlx::query_as!(
+        OrganizationItem,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                a.title auction_title,
+                a.timezone,
+                ai.title,
+                coalesce(ai.donated_by_organization_id = $1, false) "donated!",
+                coalesce(
+                    coalesce(ai.benefits_organization_id, a.benefits_organization_id) = $1,
+                    false
+                ) "benefits!",
+                least(ai.active_end_date, a.end_date) "end_date!",
+                now() >= greatest(ai.active_start_date, a.start_date)
+                    and now() < least(ai.active_end_date, a.end_date) "is_open!",
+                (
+                    select max(amount)
+                    from auction_item_bid aib
+                    where aib.auction_item_id = ai.auction_item_id
+                ) high_bid,
+                winner.amount "sold_for?",
+                d.status "delivery_status?"
+            from auction_item ai
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            left join auction_item_bid winner
+            on winner.auction_item_id = ai.auction_item_id
+            and winner.is_winning_bid
+            left join auction_item_delivery d
+            on d.auction_item_bid_id = winner.auction_item_bid_id
+            where ai.donated_by_organization_id = $1
+            or coalesce(ai.benefits_organization_id, a.benefits_organization_id) = $1
+            order by a.end_date desc, ai.title, ai.auction_item_id
+            limit $2
+            offset $3
+        "#,
+        organization_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// How many items `list_items` lists in all.
+#[instrument(skip(db))]
+pub async fn count_items(organization_id: Uuid, db: &PgPool) -> Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from auction_item ai
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            where ai.donated_by_organization_id = $1
+            or coalesce(ai.benefits_organization_id, a.benefits_organization_id) = $1
+        "#,
+        organization_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The organization's side of the ledger, which is all zeroes until it has raised something.
+#[instrument(skip(db))]
+pub async fn get_proceeds(organization_id: Uuid, db: &PgPool) -> Result<Proceeds> {
+    sqlx::query_as!(
+        Proceeds,
+        r#"
+            select
+                coalesce(-sum(e.amount) filter (
+                    where e.account = 'proceeds' and e.amount < 0
+                ), 0) "proceeds!",
+                coalesce(sum(e.amount) filter (
+                    where e.account = 'proceeds' and e.amount > 0
+                ), 0) "refunds!",
+                coalesce(sum(e.amount) filter (where e.account = 'fees'), 0) "fees!",
+                coalesce(sum(e.amount) filter (where e.account = 'payouts'), 0) "paid_out!",
+                coalesce(-sum(e.amount), 0) "owed!",
+                max(t.created_at) filter (where e.account = 'payouts') last_paid_at
+            from ledger_entry e
+            inner join ledger_transaction t
+            on t.ledger_transaction_id = e.ledger_transaction_id
+            where e.organization_id = $1
+            and e.account <> 'cash'
+        "#,
+        organization_id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_payouts(organization_id: Uuid, db: &PgPool) -> Result<Vec<OrganizationPayout>> {
+    sqlx::query_as!(
+        OrganizationPayout,
+        r#"
+            select amount, reference, paid_at
+            from payout
+            where organization_id = $1
+            order by paid_at desc
+        "#,
+        organization_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// `None` for an organization of another tenant, as well as one that doesn't exist.
+#[instrument(skip(db))]
+pub async fn get_organization_name(
+    organization_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<String>> {
+    sqlx::query_scalar!(
+        r#"
+            select name
+            from organization
+            where organization_id = $1
+            and tenant_id = $2
+        "#,
+        organization_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_members(organization_id: Uuid, db: &PgPool) -> Result<Vec<MemberRow>> {
+    sqlx::query_as!(
+        MemberRow,
+        r#"
+            select
+                u.user_id,
+                u.email,
+                nullif(concat_ws(' ', u.first_name, u.last_name), '') "name",
+                m.role
+            from organization_member m
+            inner join "user" u
+            on u.user_id = m.user_id
+            where m.organization_id = $1
+            order by m.role, u.email
+        "#,
+        organization_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,109 @@
+use axum::{
+    body::Bytes,
+    extract::{Extension, Path},
+    http::{header::HeaderMap, Uri},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use minijinja::context;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::endpoints::extractor::AuthUser;
+use crate::endpoints::{parse_form, render_page, ApiContext};
+use crate::error::{Error, Result};
+use crate::payments::Payments;
+
+use super::CheckoutFromForm;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/invoices/:invoice_id/pay", post(pay_invoice))
+        .route("/payments/webhook", post(payment_callback))
+}
+
+pub fn mock_router() -> Router {
+    Router::new().route(
+        "/payments/mock/:provider_ref",
+        get(get_mock_checkout).post(complete_mock_checkout),
+    )
+}
+
+/// The payment gateway, or `Error::NotFound` when there isn't one.
+fn payments(ctx: &ApiContext) -> Result<&Payments> {
+    ctx.payments.as_ref().ok_or(Error::NotFound)
+}
+
+fn redirect(location: &str) -> Result<Response> {
+    let uri: Uri = location.parse().map_err(anyhow::Error::from)?;
+    Ok(Redirect::to(uri).into_response())
+}
+
+#[instrument(skip(ctx))]
+async fn pay_invoice(
+    ctx: Extension<ApiContext>,
+    auth_user: AuthUser,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Response> {
+    let checkout_url = payments(&ctx)?
+        .checkout(invoice_id, auth_user.user_id, &ctx.db)
+        .await?;
+    redirect(&checkout_url)
+}
+
+/// Providers retry callbacks until they get a success, so a callback we've already handled is
+/// acknowledged like any other.
+#[instrument(skip(ctx, headers, body))]
+async fn payment_callback(
+    ctx: Extension<ApiContext>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<&'static str> {
+    payments(&ctx)?
+        .handle_callback(&headers, &body, &ctx.db)
+        .await
+        .map_err(|e| {
+            event!(Level::WARN, event_msg = "Rejected payment callback", err = ?e);
+            e
+        })?;
+    Ok("ok")
+}
+
+#[instrument(skip(ctx))]
+async fn get_mock_checkout(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    Path(provider_ref): Path<String>,
+) -> Result<Html<String>> {
+    let intent = payments(&ctx)?
+        .mock()
+        .and_then(|mock| mock.intent(&provider_ref))
+        .ok_or(Error::NotFound)?;
+    render_page(
+        &ctx,
+        &headers,
+        "mock_checkout.html",
+        context!(title => "Test checkout", intent => intent),
+    )
+}
+
+/// Finish a mock checkout and deliver its callback the way the provider would, before sending
+/// the bidder back to their invoice.
+#[instrument(skip(ctx, body))]
+async fn complete_mock_checkout(
+    ctx: Extension<ApiContext>,
+    Path(provider_ref): Path<String>,
+    body: String,
+) -> Result<Response> {
+    let form: CheckoutFromForm = parse_form(&body)?;
+    let payments = payments(&ctx)?;
+    let mock = payments.mock().ok_or(Error::NotFound)?;
+    let intent = mock.intent(&provider_ref).ok_or(Error::NotFound)?;
+    if let Some(callback) = mock.complete(&provider_ref, form.outcome)? {
+        payments
+            .handle_callback(&callback.headers(), &callback.body, &ctx.db)
+            .await?;
+    }
+    redirect(&intent.return_url)
+}
@@ -0,0 +1,11 @@
+//! Paying invoices: sending bidders to the payment provider's checkout, and the provider's
+//! callbacks. While the mock gateway is in use, `mock_router` serves its fake checkout page.
+mod handlers;
+
+use crate::payments::CheckoutOutcome;
+pub use handlers::{mock_router, router};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CheckoutFromForm {
+    pub outcome: CheckoutOutcome,
+}
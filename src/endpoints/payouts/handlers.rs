@@ -0,0 +1,86 @@
+use axum::{extract::Extension, http::header::HeaderMap, response::Html, routing::get, Router};
+use minijinja::context;
+use tracing::{event, instrument, Level};
+
+use crate::db::ledger;
+use crate::db::tenants::Tenant;
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, PayoutFromForm};
+
+pub fn admin_router() -> Router {
+    Router::new().route("/admin/payouts", get(get_payouts).post(record_payout))
+}
+
+#[instrument(skip(ctx))]
+async fn get_payouts(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+) -> Result<Html<String>> {
+    render_payouts(&ctx, &tenant, Some(&headers), vec![], None).await
+}
+
+/// A payout can't be for more than we owe, so a typo can't send the books negative.
+#[instrument(skip(ctx, body))]
+async fn record_payout(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    body: String,
+) -> Result<Html<String>> {
+    let recorded = match parse_form::<PayoutFromForm>(&body) {
+        Ok(form) => ledger::record_payout(
+            form.organization_id,
+            form.amount,
+            form.reference.trim(),
+            tenant.tenant_id,
+            &ctx.db,
+        )
+        .await
+        .map(|payout_id| (payout_id, form)),
+        Err(_) => Err(Error::unprocessable_entity([(
+            "amount",
+            "enter the payout in dollars, e.g. 125.00",
+        )])),
+    };
+    match recorded {
+        Ok((payout_id, form)) => {
+            event!(
+                Level::INFO,
+                event_msg = "Recorded payout",
+                payout_id = %payout_id,
+                organization_id = %form.organization_id
+            );
+            let message = format!("Recorded a payout of ${:.2}.", form.amount);
+            render_payouts(&ctx, &tenant, None, vec![], Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_payouts(&ctx, &tenant, None, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The full page when `headers` are given, otherwise only the report fragment.
+async fn render_payouts(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    errors: Vec<String>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let balances = queries::list_balances(tenant.tenant_id, &ctx.db).await?;
+    let payouts = queries::list_payouts(tenant.tenant_id, &ctx.db).await?;
+    let context = context!(
+        balances => balances,
+        payouts => payouts,
+        errors => errors,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_payouts.html", context),
+        None => render_template(ctx, "fragments/admin_payouts.html", context),
+    }
+}
@@ -0,0 +1,43 @@
+//! What we owe each organization from the ledger, and the payouts we've made to them.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables::{serialize_dt, serialize_dt_opt};
+pub use handlers::admin_router;
+
+/// An organization's side of the ledger. Money taken for items with no beneficiary is
+/// gathered under an organization of `None`.
+#[derive(Debug, serde::Serialize)]
+pub struct OrganizationBalance {
+    pub organization_id: Option<Uuid>,
+    pub name: String,
+    // what bidders paid for its items, before refunds
+    pub proceeds: Decimal,
+    pub refunds: Decimal,
+    pub fees: Decimal,
+    pub paid_out: Decimal,
+    pub owed: Decimal,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub last_paid_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PayoutRow {
+    pub payout_id: Uuid,
+    pub organization_name: String,
+    pub amount: Decimal,
+    pub reference: String,
+    #[serde(serialize_with = "serialize_dt")]
+    pub paid_at: OffsetDateTime,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PayoutFromForm {
+    pub organization_id: Uuid,
+    pub amount: Decimal,
+    #[serde(default)]
+    pub reference: String,
+}
@@ -0,0 +1,73 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{OrganizationBalance, PayoutRow};
+
+/// Every organization of the tenant with money through the ledger, the ones we owe the most
+/// first. Money with no beneficiary belongs to the tenant whose auction it was taken in.
+#[instrument(skip(db))]
+pub async fn list_balances(tenant_id: Uuid, db: &PgPool) -> Result<Vec<OrganizationBalance>> {
+    sqlx::query_as!(
+        OrganizationBalance,
+        r#"
+            select
+                e.organization_id,
+                coalesce(o.name, 'No beneficiary') "name!",
+                coalesce(-sum(e.amount) filter (
+                    where e.account = 'proceeds' and e.amount < 0
+                ), 0) "proceeds!",
+                coalesce(sum(e.amount) filter (
+                    where e.account = 'proceeds' and e.amount > 0
+                ), 0) "refunds!",
+                coalesce(sum(e.amount) filter (where e.account = 'fees'), 0) "fees!",
+                coalesce(sum(e.amount) filter (where e.account = 'payouts'), 0) "paid_out!",
+                -sum(e.amount) "owed!",
+                max(t.created_at) filter (where e.account = 'payouts') last_paid_at
+            from ledger_entry e
+            inner join ledger_transaction t
+            on t.ledger_transaction_id = e.ledger_transaction_id
+            left join organization o
+            on o.organization_id = e.organization_id
+            left join invoice i
+            on i.invoice_id = t.invoice_id
+            left join auction a
+            on a.auction_id = i.auction_id
+            where e.account <> 'cash'
+            and coalesce(o.tenant_id, a.tenant_id) = $1
+            group by e.organization_id, o.name
+            order by -sum(e.amount) desc, o.name
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_payouts(tenant_id: Uuid, db: &PgPool) -> Result<Vec<PayoutRow>> {
+    sqlx::query_as!(
+        PayoutRow,
+        r#"
+            select
+                p.payout_id,
+                o.name organization_name,
+                p.amount,
+                p.reference,
+                p.paid_at
+            from payout p
+            inner join organization o
+            on o.organization_id = p.organization_id
+            where o.tenant_id = $1
+            order by p.paid_at desc
+            limit 100
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,228 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::header::HeaderMap,
+    response::Html,
+    routing::{delete, get, post},
+    Router,
+};
+use minijinja::context;
+use sqlx::types::time::Date;
+use std::collections::HashMap;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+use crate::db::deliveries::{self, DeliveryStatus, Transition};
+use crate::db::pickups::{self, PickupWindowFromForm};
+use crate::db::tenants::Tenant;
+use crate::endpoints::{parse_form, render_page, render_template, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, CheckOffFromForm, DayEntry, DayParams, PickupRow};
+
+pub fn admin_router() -> Router {
+    Router::new()
+        .route("/admin/pickups", get(get_day))
+        .route("/admin/pickups/:auction_item_bid_id", post(check_off))
+        .route(
+            "/admin/auctions/:auction_id/pickup-windows",
+            get(get_windows).post(add_window),
+        )
+        .route(
+            "/admin/auctions/:auction_id/pickup-windows/:pickup_window_id",
+            delete(remove_window),
+        )
+}
+
+#[instrument(skip(ctx))]
+async fn get_day(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Query(params): Query<DayParams>,
+) -> Result<Html<String>> {
+    render_day(&ctx, &tenant, Some(&headers), params.date, vec![], None).await
+}
+
+/// Problems are rendered into the list, since htmx won't swap in an error response.
+#[instrument(skip(ctx, body))]
+async fn check_off(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_item_bid_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    let form: CheckOffFromForm = parse_form(&body)?;
+    let details = Transition {
+        signed_for_by: form.signed_for_by.clone(),
+        ..Transition::default()
+    };
+    let checked = deliveries::transition(
+        auction_item_bid_id,
+        DeliveryStatus::PickedUp,
+        &details,
+        tenant.tenant_id,
+        &ctx.db,
+    )
+    .await;
+    match checked {
+        Ok(()) => {
+            event!(
+                Level::INFO,
+                event_msg = "Checked off pickup",
+                auction_item_bid_id = %auction_item_bid_id
+            );
+            let message = format!(
+                "Checked off: collected by {}.",
+                form.signed_for_by.unwrap_or_default()
+            );
+            render_day(&ctx, &tenant, None, form.date, vec![], Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_day(&ctx, &tenant, None, form.date, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The full page when `headers` are given, otherwise only the list fragment.
+async fn render_day(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    date: Option<String>,
+    mut errors: Vec<String>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let day = match date.as_deref().map(|d| Date::parse(d, "%Y-%m-%d")) {
+        Some(Ok(day)) => Some(day),
+        Some(Err(_)) => {
+            errors.push("enter the day as YYYY-MM-DD".to_string());
+            None
+        }
+        None => None,
+    };
+    let windows = queries::list_day_windows(day, tenant.tenant_id, &ctx.db).await?;
+    let ids: Vec<Uuid> = windows.iter().map(|w| w.pickup_window_id).collect();
+    let mut pickups: HashMap<Uuid, Vec<PickupRow>> = HashMap::new();
+    for pickup in queries::list_pickups(&ids, &ctx.db).await? {
+        pickups
+            .entry(pickup.pickup_window_id)
+            .or_default()
+            .push(pickup);
+    }
+    let windows: Vec<DayEntry> = windows
+        .into_iter()
+        .map(|window| DayEntry {
+            pickups: pickups.remove(&window.pickup_window_id).unwrap_or_default(),
+            window,
+        })
+        .collect();
+    let context = context!(
+        windows => windows,
+        date => day.map(|d| d.format("%Y-%m-%d")),
+        auctions => queries::list_auctions(tenant.tenant_id, &ctx.db).await?,
+        errors => errors,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_pickups.html", context),
+        None => render_template(ctx, "fragments/admin_pickups.html", context),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_windows(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_id): Path<Uuid>,
+) -> Result<Html<String>> {
+    render_windows(&ctx, &tenant, Some(&headers), auction_id, vec![], None).await
+}
+
+#[instrument(skip(ctx, body))]
+async fn add_window(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(auction_id): Path<Uuid>,
+    body: String,
+) -> Result<Html<String>> {
+    queries::get_auction(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let added = match parse_form::<PickupWindowFromForm>(&body) {
+        Ok(form) => pickups::insert_window(auction_id, &form, &ctx.db).await,
+        Err(e) => Err(e),
+    };
+    match added {
+        Ok(window) => {
+            event!(
+                Level::INFO,
+                event_msg = "Added pickup window",
+                pickup_window_id = %window.pickup_window_id,
+                auction_id = %auction_id
+            );
+            let message = "Added the window.".to_string();
+            render_windows(&ctx, &tenant, None, auction_id, vec![], Some(message)).await
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_windows(&ctx, &tenant, None, auction_id, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn remove_window(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path((auction_id, pickup_window_id)): Path<(Uuid, Uuid)>,
+) -> Result<Html<String>> {
+    queries::get_auction(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    match pickups::delete_window(auction_id, pickup_window_id, &ctx.db).await {
+        Ok(true) => {
+            event!(
+                Level::INFO,
+                event_msg = "Removed pickup window",
+                pickup_window_id = %pickup_window_id
+            );
+            let message = "Removed the window.".to_string();
+            render_windows(&ctx, &tenant, None, auction_id, vec![], Some(message)).await
+        }
+        Ok(false) => Err(Error::NotFound),
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors = errors.into_values().flatten().map(String::from).collect();
+            render_windows(&ctx, &tenant, None, auction_id, errors, None).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The full page when `headers` are given, otherwise only the windows fragment.
+async fn render_windows(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    headers: Option<&HeaderMap>,
+    auction_id: Uuid,
+    errors: Vec<String>,
+    message: Option<String>,
+) -> Result<Html<String>> {
+    let auction = queries::get_auction(auction_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let context = context!(
+        windows => queries::list_windows(auction_id, &ctx.db).await?,
+        addresses => queries::list_addresses(tenant.tenant_id, &ctx.db).await?,
+        auction => auction,
+        errors => errors,
+        message => message,
+    );
+    match headers {
+        Some(headers) => render_page(ctx, headers, "admin_pickup_windows.html", context),
+        None => render_template(ctx, "fragments/admin_pickup_windows.html", context),
+    }
+}
@@ -0,0 +1,95 @@
+//! Local pickup for the clerks: setting up each auction's pickup windows, and the day-of list
+//! of who's coming to collect what, where each pickup is checked off as it's collected. See
+//! `crate::db::pickups` for the windows themselves.
+use sqlx::types::time::OffsetDateTime;
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables::{self, serialize_dt, serialize_dt_opt};
+pub use handlers::admin_router;
+
+#[derive(Debug, serde::Serialize)]
+pub struct AuctionSummary {
+    pub auction_id: Uuid,
+    pub title: String,
+    pub timezone: String,
+}
+
+/// One of an auction's windows, with how full it is.
+#[derive(Debug, serde::Serialize)]
+pub struct WindowRow {
+    pub pickup_window_id: Uuid,
+    #[serde(serialize_with = "serialize_dt")]
+    pub starts_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub ends_at: OffsetDateTime,
+    pub capacity: i32,
+    pub booked: i64,
+    pub address: String,
+}
+
+/// An address a window could be at: one of the organizations'.
+#[derive(Debug, serde::Serialize)]
+pub struct AddressOption {
+    pub address_id: Uuid,
+    pub label: String,
+}
+
+/// A window on the day list.
+#[derive(Debug, serde::Serialize)]
+pub struct DayWindow {
+    pub pickup_window_id: Uuid,
+    pub auction_id: Uuid,
+    pub auction_title: String,
+    pub timezone: String,
+    pub address: String,
+    #[serde(serialize_with = "serialize_dt")]
+    pub starts_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub ends_at: OffsetDateTime,
+    pub capacity: i32,
+}
+
+/// An item booked to be collected in a window.
+#[derive(Debug, serde::Serialize)]
+pub struct PickupRow {
+    pub pickup_window_id: Uuid,
+    pub auction_item_bid_id: Uuid,
+    pub auction_id: Uuid,
+    pub auction_item_id: Uuid,
+    pub title: String,
+    pub bidder_name: String,
+    pub bidder_email: String,
+    pub status: String,
+    pub signed_for_by: Option<String>,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub picked_up_at: Option<OffsetDateTime>,
+    // whether the bidder has paid for the item yet
+    pub paid: bool,
+}
+
+/// A window on the day list, with its pickups.
+#[derive(Debug, serde::Serialize)]
+pub struct DayEntry {
+    #[serde(flatten)]
+    pub window: DayWindow,
+    pub pickups: Vec<PickupRow>,
+}
+
+/// Which day to list, as `YYYY-MM-DD`. Without one each auction's own today is shown.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct DayParams {
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub date: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CheckOffFromForm {
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub signed_for_by: Option<String>,
+    // the list's day, to render the same list again
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub date: Option<String>,
+}
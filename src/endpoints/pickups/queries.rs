@@ -0,0 +1,195 @@
+use sqlx::types::time::Date;
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{AddressOption, AuctionSummary, DayWindow, PickupRow, WindowRow};
+
+#[instrument(skip(db))]
+pub async fn get_auction(
+    auction_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<AuctionSummary>> {
+    sqlx::query_as!(
+        AuctionSummary,
+        r#"
+            select auction_id, title, timezone
+            from auction
+            where auction_id = $1
+            and tenant_id = $2
+        "#,
+        auction_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Every auction of the tenant, the latest first, for picking one to set up windows for.
+#[instrument(skip(db))]
+pub async fn list_auctions(tenant_id: Uuid, db: &PgPool) -> Result<Vec<AuctionSummary>> {
+    sqlx::query_as!(
+        AuctionSummary,
+        r#"
+            select auction_id, title, timezone
+            from auction
+            where tenant_id = $1
+            order by end_date desc
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_windows(auction_id: Uuid, db: &PgPool) -> Result<Vec<WindowRow>> {
+    sqlx::query_as!(
+        WindowRow,
+        r#"
+            select
+                w.pickup_window_id,
+                w.starts_at,
+                w.ends_at,
+                w.capacity,
+                (
+                    select count(*)
+                    from auction_item_delivery d
+                    where d.pickup_window_id = w.pickup_window_id
+                ) "booked!",
+                concat_ws(', ',
+                    a.street_address1,
+                    a.city,
+                    a.state_province_county
+                ) "address!"
+            from pickup_window w
+            inner join address a
+            on a.address_id = w.address_id
+            where w.auction_id = $1
+            order by w.starts_at
+        "#,
+        auction_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn list_addresses(tenant_id: Uuid, db: &PgPool) -> Result<Vec<AddressOption>> {
+    sqlx::query_as!(
+        AddressOption,
+        r#"
+            select
+                a.address_id,
+                concat_ws(', ',
+                    o.name,
+                    a.street_address1,
+                    a.city,
+                    a.state_province_county
+                ) "label!"
+            from organization o
+            inner join address a
+            on a.address_id = o.primary_address_id
+            where o.tenant_id = $1
+            order by o.name
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// The tenant's windows starting on `date` in their auction's timezone, or on each auction's
+/// today.
+#[instrument(skip(db))]
+pub async fn list_day_windows(
+    date: Option<Date>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<DayWindow>> {
+    sqlx::query_as!(
+        DayWindow,
+        r#"
+            select
+                w.pickup_window_id,
+                w.auction_id,
+                au.title auction_title,
+                au.timezone,
+                concat_ws(', ',
+                    a.street_address1,
+                    a.city,
+                    a.state_province_county
+                ) "address!",
+                w.starts_at,
+                w.ends_at,
+                w.capacity
+            from pickup_window w
+            inner join auction au
+            on au.auction_id = w.auction_id
+            inner join address a
+            on a.address_id = w.address_id
+            where (w.starts_at at time zone au.timezone)::date
+                = coalesce($1::date, (now() at time zone au.timezone)::date)
+            and au.tenant_id = $2
+            order by w.starts_at, au.title
+        "#,
+        date,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Everything booked into the windows, by who's collecting it.
+#[instrument(skip(db))]
+pub async fn list_pickups(pickup_window_ids: &[Uuid], db: &PgPool) -> Result<Vec<PickupRow>> {
+    sqlx::query_as!(
+        PickupRow,
+        r#"
+            select
+                d.pickup_window_id "pickup_window_id!",
+                d.auction_item_bid_id,
+                ai.auction_id "auction_id!",
+                ai.auction_item_id,
+                ai.title,
+                coalesce(
+                    nullif(concat_ws(' ', u.first_name, u.last_name), ''),
+                    u.email
+                ) "bidder_name!",
+                u.email bidder_email,
+                d.status,
+                d.signed_for_by,
+                d.picked_up_at,
+                exists(
+                    select 1
+                    from invoice_line il
+                    inner join invoice i
+                    on i.invoice_id = il.invoice_id
+                    where il.auction_item_bid_id = d.auction_item_bid_id
+                    and il.kind = 'item'
+                    and i.status = 'paid'
+                ) "paid!"
+            from auction_item_delivery d
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = d.auction_item_bid_id
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            inner join "user" u
+            on u.user_id = aib.user_id
+            where d.pickup_window_id = any($1)
+            order by "bidder_name!", ai.title
+        "#,
+        pickup_window_ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
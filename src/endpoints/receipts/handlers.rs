@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Extension, Path},
+    http::Uri,
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::MaybeAuthUser;
+use crate::endpoints::{pdf_response, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{group_by_organization, queries, receipt_pdf};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/invoices/:invoice_id/receipt", get(get_invoice_receipt))
+        .route("/receipts/:year", get(get_yearly_statement))
+}
+
+pub fn admin_router() -> Router {
+    Router::new().route(
+        "/admin/invoices/:invoice_id/receipt",
+        get(get_admin_invoice_receipt),
+    )
+}
+
+fn login_redirect(next: &str) -> Response {
+    match format!("/login?next={}", next).parse::<Uri>() {
+        Ok(uri) => Redirect::to(uri).into_response(),
+        Err(_) => Error::Unauthorized.into_response(),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_invoice_receipt(
+    auth_user: MaybeAuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Response> {
+    match auth_user.user_id() {
+        Some(user_id) => render_invoice_receipt(&ctx, &tenant, user_id, invoice_id).await,
+        None => Ok(login_redirect(&format!("/invoices/{}", invoice_id))),
+    }
+}
+
+#[instrument(skip(ctx))]
+async fn get_admin_invoice_receipt(
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Response> {
+    let user_id = queries::get_invoice_user_id(invoice_id, tenant.tenant_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    render_invoice_receipt(&ctx, &tenant, user_id, invoice_id).await
+}
+
+/// Only paid invoices have receipts, so anything else is `Error::NotFound`.
+async fn render_invoice_receipt(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    user_id: Uuid,
+    invoice_id: Uuid,
+) -> Result<Response> {
+    let lines =
+        queries::list_receipt_lines(user_id, Some(invoice_id), None, tenant.tenant_id, &ctx.db)
+            .await?;
+    let first = lines.first().ok_or(Error::NotFound)?;
+    let reference = first.reference.clone();
+    let period = format!("Invoice {}", reference);
+    let donor = queries::get_donor(user_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let pdf = receipt_pdf(
+        &format!("Donation receipt {}", reference),
+        &tenant.name,
+        &donor,
+        &period,
+        &group_by_organization(lines),
+    )?;
+    Ok(pdf_response(&format!("{}-receipt.pdf", reference), pdf))
+}
+
+/// Everything a bidder paid in a year, for their taxes. A year with nothing paid is
+/// `Error::NotFound`.
+#[instrument(skip(ctx))]
+async fn get_yearly_statement(
+    auth_user: MaybeAuthUser,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Path(year): Path<i32>,
+) -> Result<Response> {
+    let user_id = match auth_user.user_id() {
+        Some(user_id) => user_id,
+        None => return Ok(login_redirect("/dashboard")),
+    };
+    let lines =
+        queries::list_receipt_lines(user_id, None, Some(year), tenant.tenant_id, &ctx.db).await?;
+    if lines.is_empty() {
+        return Err(Error::NotFound);
+    }
+    let donor = queries::get_donor(user_id, &ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let pdf = receipt_pdf(
+        &format!("Donation statement {}", year),
+        &tenant.name,
+        &donor,
+        &format!("Payments made in {}", year),
+        &group_by_organization(lines),
+    )?;
+    Ok(pdf_response(&format!("donations-{}.pdf", year), pdf))
+}
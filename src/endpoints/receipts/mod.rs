@@ -0,0 +1,223 @@
+//! Receipts for the tax-deductible part of what bidders pay.
+//!
+//! In the US only what a bidder pays beyond the fair market value of an item is a charitable
+//! contribution, so each item's `expected_retail_value` is shown as its fair market value and
+//! the difference, if any, as deductible. Shipping buys a service and isn't included. Receipts
+//! are made from paid invoices as they're asked for, one per invoice or one per calendar year.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables;
+use crate::pdf::{Cell, PdfBuilder, LEFT_EDGE, RIGHT_EDGE};
+pub use handlers::{admin_router, router};
+
+const PAID_AT: f32 = 130.0;
+const FAIR_MARKET_VALUE_AT: f32 = 162.0;
+// what fits to the left of the amounts
+const DESCRIPTION_CHARS: usize = 48;
+
+/// A paid item, with the organization it benefits.
+#[derive(Debug)]
+pub struct ReceiptLine {
+    pub reference: String,
+    pub description: String,
+    pub organization_id: Option<Uuid>,
+    pub organization_name: Option<String>,
+    pub paid: Decimal,
+    pub fair_market_value: Decimal,
+    pub paid_at: OffsetDateTime,
+    pub timezone: String,
+}
+
+#[derive(Debug)]
+pub struct Donor {
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ReceiptItem {
+    pub reference: String,
+    // e.g. October 18, 2026, in the auction's timezone
+    pub paid_on: String,
+    pub description: String,
+    pub paid: Decimal,
+    pub fair_market_value: Decimal,
+    pub deductible: Decimal,
+}
+
+/// Everything paid towards one organization. Items with no beneficiary are gathered under an
+/// organization of `None`, and nothing paid for them is deductible.
+#[derive(Debug, PartialEq)]
+pub struct OrganizationReceipt {
+    pub organization_id: Option<Uuid>,
+    pub name: String,
+    pub items: Vec<ReceiptItem>,
+    pub paid: Decimal,
+    pub fair_market_value: Decimal,
+    pub deductible: Decimal,
+}
+
+/// Group `lines` by organization, in the order each organization first appears.
+pub fn group_by_organization(lines: Vec<ReceiptLine>) -> Vec<OrganizationReceipt> {
+    let mut receipts: Vec<OrganizationReceipt> = vec![];
+    for line in lines {
+        let deductible = match line.organization_id {
+            Some(_) => (line.paid - line.fair_market_value).max(Decimal::ZERO),
+            None => Decimal::ZERO,
+        };
+        let paid_on = match tables::parse_timezone(&line.timezone) {
+            Ok(tz) => tables::format_dt_in_timezone(&line.paid_at, &tz, "%B %-d, %Y"),
+            Err(_) => line.paid_at.date().to_string(),
+        };
+        let item = ReceiptItem {
+            reference: line.reference,
+            paid_on,
+            description: line.description,
+            paid: line.paid,
+            fair_market_value: line.fair_market_value,
+            deductible,
+        };
+        let receipt = match receipts
+            .iter_mut()
+            .position(|receipt| receipt.organization_id == line.organization_id)
+        {
+            Some(i) => &mut receipts[i],
+            None => {
+                receipts.push(OrganizationReceipt {
+                    organization_id: line.organization_id,
+                    name: line
+                        .organization_name
+                        .unwrap_or_else(|| "No beneficiary organization".to_string()),
+                    items: vec![],
+                    paid: Decimal::ZERO,
+                    fair_market_value: Decimal::ZERO,
+                    deductible: Decimal::ZERO,
+                });
+                receipts.last_mut().unwrap()
+            }
+        };
+        receipt.paid += item.paid;
+        receipt.fair_market_value += item.fair_market_value;
+        receipt.deductible += item.deductible;
+        receipt.items.push(item);
+    }
+    receipts
+}
+
+/// A receipt for one invoice, or a statement covering many, with a section per organization.
+/// `issuer` is whoever ran the auctions, the tenant.
+pub fn receipt_pdf(
+    title: &str,
+    issuer: &str,
+    donor: &Donor,
+    period: &str,
+    receipts: &[OrganizationReceipt],
+) -> anyhow::Result<Vec<u8>> {
+    let mut pdf = PdfBuilder::new();
+    pdf.heading(title)
+        .text(issuer)
+        .blank()
+        .row(vec![
+            Cell::Left(LEFT_EDGE, "Received from".to_string()),
+            Cell::Left(60.0, format!("{} <{}>", donor.name, donor.email)),
+        ])
+        .row(vec![
+            Cell::Left(LEFT_EDGE, "For".to_string()),
+            Cell::Left(60.0, period.to_string()),
+        ]);
+    for receipt in receipts {
+        pdf.blank().subheading(&receipt.name).bold_row(vec![
+            Cell::Left(LEFT_EDGE, "Item".to_string()),
+            Cell::Right(PAID_AT, "Paid".to_string()),
+            Cell::Right(FAIR_MARKET_VALUE_AT, "Value".to_string()),
+            Cell::Right(RIGHT_EDGE, "Deductible".to_string()),
+        ]);
+        let mut reference = None;
+        for item in &receipt.items {
+            if reference != Some(&item.reference) {
+                reference = Some(&item.reference);
+                pdf.text(&format!("{}, paid {}", item.reference, item.paid_on));
+            }
+            pdf.row(vec![
+                Cell::Left(LEFT_EDGE + 5.0, clip(&item.description, DESCRIPTION_CHARS)),
+                Cell::Right(PAID_AT, format!("${:.2}", item.paid)),
+                Cell::Right(
+                    FAIR_MARKET_VALUE_AT,
+                    format!("${:.2}", item.fair_market_value),
+                ),
+                Cell::Right(RIGHT_EDGE, format!("${:.2}", item.deductible)),
+            ]);
+        }
+        pdf.bold_row(vec![
+            Cell::Left(LEFT_EDGE, "Total".to_string()),
+            Cell::Right(PAID_AT, format!("${:.2}", receipt.paid)),
+            Cell::Right(
+                FAIR_MARKET_VALUE_AT,
+                format!("${:.2}", receipt.fair_market_value),
+            ),
+            Cell::Right(RIGHT_EDGE, format!("${:.2}", receipt.deductible)),
+        ]);
+    }
+    let deductible: Decimal = receipts.iter().map(|receipt| receipt.deductible).sum();
+    pdf.blank()
+        .bold_row(vec![
+            Cell::Left(LEFT_EDGE, "Total deductible".to_string()),
+            Cell::Right(RIGHT_EDGE, format!("${:.2}", deductible)),
+        ])
+        .blank()
+        .text(
+            "Value is our estimate of the fair market value of the goods or services you \
+             received in return for your payment. Only the amount you paid beyond that value \
+             may be deductible as a charitable contribution. Shipping charges are not included. \
+             Please keep this receipt with your tax records.",
+        );
+    pdf.render(title)
+}
+
+fn clip(text: &str, chars: usize) -> String {
+    if text.chars().count() <= chars {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(chars - 3).collect();
+    clipped.push_str("...");
+    clipped
+}
+
+#[test]
+fn test_group_by_organization() {
+    let paid_at = OffsetDateTime::from_unix_timestamp(1_792_368_000);
+    let line = |reference: &str, organization: Option<u128>, paid: i64, value: i64| ReceiptLine {
+        reference: reference.to_string(),
+        description: "Goat yoga".to_string(),
+        organization_id: organization.map(Uuid::from_u128),
+        organization_name: organization.map(|_| "Farm Sanctuary".to_string()),
+        paid: Decimal::new(paid, 0),
+        fair_market_value: Decimal::new(value, 0),
+        paid_at,
+        timezone: "America/Los_Angeles".to_string(),
+    };
+    let receipts = group_by_organization(vec![
+        line("INV-000001", Some(1), 150, 100),
+        line("INV-000001", None, 150, 100),
+        // paying less than an item is worth leaves nothing to deduct
+        line("INV-000002", Some(1), 80, 100),
+    ]);
+    assert_eq!(receipts.len(), 2);
+    assert_eq!(receipts[0].items.len(), 2);
+    assert_eq!(receipts[0].paid, Decimal::new(230, 0));
+    assert_eq!(receipts[0].fair_market_value, Decimal::new(200, 0));
+    assert_eq!(receipts[0].deductible, Decimal::new(50, 0));
+    assert_eq!(receipts[0].items[0].paid_on, "October 18, 2026");
+    assert_eq!(receipts[1].name, "No beneficiary organization");
+    assert_eq!(receipts[1].deductible, Decimal::ZERO);
+}
+
+#[test]
+fn test_clip() {
+    assert_eq!(clip("Goat yoga", 9), "Goat yoga");
+    assert_eq!(clip("Goat yoga for two", 9), "Goat y...");
+}
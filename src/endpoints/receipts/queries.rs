@@ -0,0 +1,109 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{Donor, ReceiptLine};
+
+/// The items on a bidder's paid invoices from the tenant's auctions: one invoice, or all of
+/// those paid in a calendar year in the auction's timezone. Refunded invoices are left out.
+#[instrument(skip(db))]
+pub async fn list_receipt_lines(
+    user_id: Uuid,
+    invoice_id: Option<Uuid>,
+    year: Option<i32>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Vec<ReceiptLine>> {
+    sqlx::query_as!(
+        ReceiptLine,
+        r#"
+            select
+                'INV-' || lpad(i.invoice_number::text, 6, '0') "reference!",
+                il.description,
+                o.organization_id "organization_id?",
+                o.name "organization_name?",
+                il.amount paid,
+                -- without the item we can't say what it was worth, so claim nothing for it
+                coalesce(ai.expected_retail_value, il.amount) "fair_market_value!",
+                i.paid_at "paid_at!",
+                a.timezone
+            from invoice_line il
+            inner join invoice i
+            on i.invoice_id = il.invoice_id
+            inner join auction a
+            on a.auction_id = i.auction_id
+            left join auction_item ai
+            on ai.auction_item_id = il.auction_item_id
+            left join organization o
+            on o.organization_id = coalesce(
+                ai.benefits_organization_id,
+                a.benefits_organization_id
+            )
+            where i.user_id = $1
+            and i.status = 'paid'
+            and il.kind = 'item'
+            and ($2::uuid is null or i.invoice_id = $2)
+            and (
+                $3::int is null
+                or extract(year from i.paid_at at time zone a.timezone) = $3
+            )
+            and a.tenant_id = $4
+            order by i.paid_at, i.invoice_number, il.created_at
+        "#,
+        user_id,
+        invoice_id,
+        year,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[instrument(skip(db))]
+pub async fn get_donor(user_id: Uuid, db: &PgPool) -> Result<Option<Donor>> {
+    sqlx::query_as!(
+        Donor,
+        r#"
+            select
+                coalesce(
+                    nullif(concat_ws(' ', first_name, last_name), ''),
+                    email
+                ) "name!",
+                email
+            from "user"
+            where user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Who an invoice from one of the tenant's auctions belongs to, for admins fetching a
+/// bidder's receipt.
+#[instrument(skip(db))]
+pub async fn get_invoice_user_id(
+    invoice_id: Uuid,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+            select i.user_id
+            from invoice i
+            inner join auction a
+            on a.auction_id = i.auction_id
+            where i.invoice_id = $1
+            and a.tenant_id = $2
+        "#,
+        invoice_id,
+        tenant_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Extension, Query},
+    http::header::HeaderMap,
+    response::Html,
+    routing::get,
+    Router,
+};
+use minijinja::context;
+use tracing::instrument;
+
+use crate::db::tenants::Tenant;
+use crate::endpoints::extractor::MaybeAuthUser;
+use crate::endpoints::{render_page, ApiContext};
+use crate::error::Result;
+
+use super::{queries, SearchParams, SearchResult};
+
+pub fn router() -> Router {
+    Router::new().route("/search", get(search))
+}
+
+#[instrument(skip(ctx))]
+async fn search(
+    auth_user: MaybeAuthUser,
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    Query(params): Query<SearchParams>,
+) -> Result<Html<String>> {
+    let rows = queries::search(&params, tenant.tenant_id, &ctx.db).await?;
+    let total = rows.first().map(|row| row.total).unwrap_or(0);
+    let results: Vec<SearchResult> = rows.into_iter().map(SearchResult::from).collect();
+    let auctions = queries::list_auction_choices(tenant.tenant_id, &ctx.db).await?;
+
+    let page_url = |page: i64| {
+        let params = SearchParams {
+            page,
+            ..params.clone()
+        };
+        serde_urlencoded::to_string(&params)
+            .map(|query| format!("/search?{}", query))
+            .ok()
+    };
+    let previous_page_url = if params.page > 0 {
+        page_url(params.page - 1)
+    } else {
+        None
+    };
+    let next_page_url = if params.offset()? + (results.len() as i64) < total {
+        page_url(params.page + 1)
+    } else {
+        None
+    };
+
+    render_page(
+        &ctx,
+        &headers,
+        "search.html",
+        context!(
+            title => "Search",
+            logged_in => auth_user.0.is_some(),
+            params => params,
+            auctions => auctions,
+            results => results,
+            total => total,
+            previous_page_url => previous_page_url,
+            next_page_url => next_page_url,
+        ),
+    )
+}
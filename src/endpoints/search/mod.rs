@@ -0,0 +1,195 @@
+//! Full-text search over auction items and articles.
+//!
+//! Matching and ranking happen in Postgres against the `search_vector` columns, which weigh
+//! titles over tags over descriptions.
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use std::str::FromStr;
+use uuid::Uuid;
+
+mod handlers;
+mod queries;
+
+use crate::db::tables;
+use crate::error::Error;
+pub use handlers::router;
+
+// `ts_headline` marks matches with these, so that we can escape the text before turning
+// them into `<mark>` tags.
+const HIGHLIGHT_START: char = '\u{1}';
+const HIGHLIGHT_STOP: char = '\u{2}';
+
+const DEFAULT_PER_PAGE: i64 = 20;
+const MAX_PER_PAGE: i64 = 100;
+// far past the end of any real search, and small enough that the offset can't overflow
+const MAX_PAGE: i64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemStatus {
+    Open,
+    Closed,
+}
+
+impl FromStr for ItemStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(ItemStatus::Open),
+            "closed" => Ok(ItemStatus::Closed),
+            _ => Err(format!("unknown status: {}", s)),
+        }
+    }
+}
+
+impl ItemStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ItemStatus::Open => "open",
+            ItemStatus::Closed => "closed",
+        }
+    }
+}
+
+/// Query string for `/search`. Every filter is optional; price and status filters only
+/// apply to auction items, so setting either leaves articles out of the results.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct SearchParams {
+    #[serde(default)]
+    pub q: String,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub auction_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub tag: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub min_price: Option<Decimal>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub max_price: Option<Decimal>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub status: Option<ItemStatus>,
+    #[serde(default)]
+    pub page: i64,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub per_page: Option<i64>,
+}
+
+impl SearchParams {
+    pub fn limit(&self) -> i64 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+
+    /// Rows to skip to get to `page`, counting from 0. A page past `MAX_PAGE` is a 422.
+    pub fn offset(&self) -> Result<i64, Error> {
+        if self.page > MAX_PAGE {
+            return Err(Error::unprocessable_entity([(
+                "page",
+                format!("page must be at most {}", MAX_PAGE),
+            )]));
+        }
+        Ok(self.page.max(0) * self.limit())
+    }
+}
+
+/// A search hit as it comes out of the database, with match markers still in place.
+pub struct SearchRow {
+    pub kind: String,
+    pub id: Uuid,
+    pub auction_id: Option<Uuid>,
+    pub slug: Option<String>,
+    pub title: String,
+    pub title_highlight: String,
+    pub snippet: String,
+    pub rank: f32,
+    pub price: Option<Decimal>,
+    pub end_date: Option<OffsetDateTime>,
+    pub total: i64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SearchResult {
+    // "item" or "article"
+    pub kind: String,
+    pub id: Uuid,
+    pub auction_id: Option<Uuid>,
+    pub slug: Option<String>,
+    pub title: String,
+    // HTML: the title and snippet are escaped, with matches wrapped in `<mark>`
+    pub title_highlight: String,
+    pub snippet: String,
+    pub rank: f32,
+    // for items, the high bid or else the minimum bid
+    pub price: Option<Decimal>,
+    #[serde(serialize_with = "tables::serialize_dt_opt")]
+    pub end_date: Option<OffsetDateTime>,
+}
+
+impl From<SearchRow> for SearchResult {
+    fn from(row: SearchRow) -> Self {
+        Self {
+            kind: row.kind,
+            id: row.id,
+            auction_id: row.auction_id,
+            slug: row.slug,
+            title: row.title,
+            title_highlight: highlight(&row.title_highlight),
+            snippet: highlight(&row.snippet),
+            rank: row.rank,
+            price: row.price,
+            end_date: row.end_date,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct AuctionChoice {
+    pub auction_id: Uuid,
+    pub title: String,
+}
+
+/// Escape `text` for HTML and turn `ts_headline`'s markers into `<mark>` tags.
+fn highlight(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            HIGHLIGHT_START => out.push_str("<mark>"),
+            HIGHLIGHT_STOP => out.push_str("</mark>"),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[test]
+fn test_offset() {
+    let params = |page, per_page| SearchParams {
+        page,
+        per_page,
+        ..SearchParams::default()
+    };
+    assert_eq!(params(0, None).offset().unwrap(), 0);
+    assert_eq!(params(3, Some(10)).offset().unwrap(), 30);
+    assert_eq!(params(-2, Some(10)).offset().unwrap(), 0);
+    assert_eq!(
+        params(MAX_PAGE, Some(MAX_PER_PAGE)).offset().unwrap(),
+        MAX_PAGE * MAX_PER_PAGE
+    );
+    assert!(matches!(
+        params(i64::MAX, Some(MAX_PER_PAGE)).offset(),
+        Err(Error::UnprocessableEntity { .. })
+    ));
+}
+
+#[test]
+fn test_highlight_escapes_text() {
+    assert_eq!(
+        highlight("Big \u{1}planter\u{2} <script> & \"more\""),
+        "Big <mark>planter</mark> &lt;script&gt; &amp; &quot;more&quot;"
+    );
+}
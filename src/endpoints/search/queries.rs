@@ -0,0 +1,141 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error};
+
+use super::{AuctionChoice, SearchParams, SearchRow};
+
+/// Search items and published articles together, best matches first.
+///
+/// `q` uses `websearch_to_tsquery` syntax: `"quoted phrases"`, `or` and `-excluded` words.
+/// An empty `q` matches everything, so the filters can be used for browsing too.
+/// Every row carries the total number of matches for pagination.
+#[instrument(skip(db))]
+pub async fn search(params: &SearchParams, tenant_id: Uuid, db: &PgPool) -> Result<Vec<SearchRow>> {
+    let offset = params.offset()?;
+    sqlx::query_as!(
+        SearchRow,
+        r#"
+            with search as (
+                select
+                    websearch_to_tsquery('english', $1) query,
+                    'StartSel=' || chr(1) || ', StopSel=' || chr(2) as selectors
+            ),
+            item as (
+                select
+                    ai.*,
+                    coalesce(
+                        (
+                            select max(amount)
+                            from auction_item_bid aib
+                            where aib.auction_item_id = ai.auction_item_id
+                        ),
+                        ai.minimum_bid_amount
+                    ) price,
+                    least(ai.active_end_date, a.end_date) end_date,
+                    now() >= greatest(ai.active_start_date, a.start_date)
+                        and now() < least(ai.active_end_date, a.end_date) is_open
+                from auction_item ai
+                inner join auction a
+                on a.auction_id = ai.auction_id
+                where ai.tenant_id = $9
+            ),
+            result as (
+                select
+                    'item' kind,
+                    item.auction_item_id id,
+                    item.auction_id,
+                    null::text slug,
+                    item.title,
+                    ts_headline('english', item.title, search.query,
+                        search.selectors || ', HighlightAll=true') title_highlight,
+                    ts_headline('english', item.description, search.query,
+                        search.selectors || ', MaxFragments=2, MaxWords=30, MinWords=10') snippet,
+                    ts_rank(item.search_vector, search.query) rank,
+                    item.price,
+                    item.end_date
+                from item, search
+                where ($1 = '' or item.search_vector @@ search.query)
+                and ($2::uuid is null or item.auction_id = $2)
+                and ($3::text is null or $3 = any(item.tag_list))
+                and ($4::decimal is null or item.price >= $4)
+                and ($5::decimal is null or item.price <= $5)
+                and (
+                    $6::text is null
+                    or ($6 = 'open' and item.is_open)
+                    or ($6 = 'closed' and now() >= item.end_date)
+                )
+                union all
+                select
+                    'article' kind,
+                    ar.article_id id,
+                    ar.auction_id,
+                    ar.slug,
+                    ar.title,
+                    ts_headline('english', ar.title, search.query,
+                        search.selectors || ', HighlightAll=true') title_highlight,
+                    ts_headline('english', ar.description, search.query,
+                        search.selectors || ', MaxFragments=2, MaxWords=30, MinWords=10') snippet,
+                    ts_rank(ar.search_vector, search.query) rank,
+                    null::decimal price,
+                    null::timestamptz end_date
+                from article ar, search
+                where ar.tenant_id = $9
+                and ar.published_at is not null
+                and ($1 = '' or ar.search_vector @@ search.query)
+                and ($2::uuid is null or ar.auction_id = $2)
+                and ($3::text is null or $3 = any(ar.tag_list))
+                and $4::decimal is null
+                and $5::decimal is null
+                and $6::text is null
+            )
+            select
+                kind "kind!",
+                id "id!",
+                auction_id,
+                slug,
+                title "title!",
+                title_highlight "title_highlight!",
+                snippet "snippet!",
+                rank "rank!",
+                price,
+                end_date,
+                count(*) over () "total!"
+            from result
+            order by rank desc, end_date asc nulls last, title
+            limit $7
+            offset $8
+        "#,
+        params.q.trim(),
+        params.auction_id,
+        params.tag,
+        params.min_price,
+        params.max_price,
+        params.status.map(|status| status.as_str()),
+        params.limit(),
+        offset,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Auctions to offer in the search form's auction filter.
+#[instrument(skip(db))]
+pub async fn list_auction_choices(tenant_id: Uuid, db: &PgPool) -> Result<Vec<AuctionChoice>> {
+    sqlx::query_as!(
+        AuctionChoice,
+        r#"
+            select auction_id, title
+            from auction
+            where tenant_id = $1
+            order by start_date desc
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
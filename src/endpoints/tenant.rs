@@ -0,0 +1,316 @@
+//! Working out which tenant a request is for.
+//!
+//! A request under `/t/<slug>/` is for that tenant, and is routed as if the prefix wasn't
+//! there. Otherwise the tenant is the one whose `host` the request was made to, or else the
+//! default tenant. Handlers take the `Tenant` as an extractor, and templates see it as the
+//! `tenant` global.
+//!
+//! Links in pages served under a prefix have to keep it, so the absolute paths in their links,
+//! forms and htmx attributes, and in `Location` headers, are given the prefix on the way out.
+//! So are the paths of cookies, which keeps each tenant's session to its own pages.
+use std::convert::Infallible;
+use std::fmt;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use axum::body::{self, Body, Full};
+use axum::extract::{FromRequest, RequestParts};
+use axum::http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, HOST, LOCATION, SET_COOKIE};
+use axum::http::{Request, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use futures::future::BoxFuture;
+use minijinja::value::{Object, Value};
+use sqlx::PgPool;
+use tower::{Layer, Service};
+
+use crate::db::tenants::{self, Tenant};
+use crate::error::{Error, Result};
+
+// the attributes holding a path the browser will request
+const LINK_ATTRIBUTES: [&str; 8] = [
+    "href",
+    "src",
+    "data-src",
+    "action",
+    "hx-get",
+    "hx-post",
+    "hx-put",
+    "hx-delete",
+];
+
+tokio::task_local! {
+    static TENANT: Tenant;
+}
+
+/// The tenant of the request being handled, for code without access to the request.
+pub(crate) fn current() -> Option<Tenant> {
+    TENANT.try_with(Tenant::clone).ok()
+}
+
+/// The `tenant` template global, which is whichever tenant the page is being rendered for.
+#[derive(Debug)]
+pub(crate) struct CurrentTenant;
+
+impl fmt::Display for CurrentTenant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match current() {
+            Some(tenant) => write!(f, "{}", tenant.name),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Object for CurrentTenant {
+    fn get_attr(&self, name: &str) -> Option<Value> {
+        let tenant = current()?;
+        match name {
+            "slug" => Some(Value::from(tenant.slug)),
+            "name" => Some(Value::from(tenant.name)),
+            _ => None,
+        }
+    }
+
+    fn attributes(&self) -> &[&str] {
+        &["slug", "name"]
+    }
+}
+
+#[derive(Clone)]
+pub struct TenantLayer {
+    db: PgPool,
+}
+
+impl TenantLayer {
+    pub fn new(db: PgPool) -> Self {
+        TenantLayer { db }
+    }
+}
+
+impl<S> Layer<S> for TenantLayer {
+    type Service = Tenants<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Tenants {
+            inner,
+            db: self.db.clone(),
+        }
+    }
+}
+
+/// Wraps the whole router, since the prefix has to be gone before routing.
+#[derive(Clone)]
+pub struct Tenants<S> {
+    inner: S,
+    db: PgPool,
+}
+
+impl<S> Service<Request<Body>> for Tenants<S>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        // the clone hasn't been polled ready, so swap it for the one that has
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let db = self.db.clone();
+        Box::pin(async move {
+            let (tenant, prefix) = match resolve(&mut req, &db).await {
+                Ok(Some(resolved)) => resolved,
+                Ok(None) => return Ok(StatusCode::NOT_FOUND.into_response()),
+                Err(e) => return Ok(e.into_response()),
+            };
+            req.extensions_mut().insert(tenant.clone());
+            let response = TENANT.scope(tenant, inner.call(req)).await?;
+            Ok(match prefix {
+                Some(prefix) => add_prefix(response, &prefix).await,
+                None => response,
+            })
+        })
+    }
+}
+
+/// The request's tenant, and the path prefix it was found by, taking the prefix off the
+/// request. `None` for a prefix naming no tenant.
+async fn resolve(req: &mut Request<Body>, db: &PgPool) -> Result<Option<(Tenant, Option<String>)>> {
+    if let Some((slug, uri)) = split_prefix(req.uri()) {
+        return Ok(match tenants::get_by_slug(&slug, db).await? {
+            Some(tenant) => {
+                *req.uri_mut() = uri;
+                Some((tenant, Some(format!("/t/{}", slug))))
+            }
+            None => None,
+        });
+    }
+    let host = req
+        .headers()
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.split(':').next());
+    if let Some(host) = host {
+        if let Some(tenant) = tenants::get_by_host(host, db).await? {
+            return Ok(Some((tenant, None)));
+        }
+    }
+    Ok(Some((tenants::get_default(db).await?, None)))
+}
+
+/// The slug from a `/t/<slug>` prefix, and the request's path and query without it.
+fn split_prefix(uri: &Uri) -> Option<(String, Uri)> {
+    let rest = uri.path().strip_prefix("/t/")?;
+    let (slug, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    if slug.is_empty() {
+        return None;
+    }
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+    Some((slug.to_string(), path_and_query.parse().ok()?))
+}
+
+async fn add_prefix(mut response: Response, prefix: &str) -> Response {
+    if let Some(location) = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .and_then(|location| prefixed(location, prefix))
+        .and_then(|location| HeaderValue::from_str(&location).ok())
+    {
+        response.headers_mut().insert(LOCATION, location);
+    }
+    let cookies: Vec<HeaderValue> = response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .filter_map(|cookie| HeaderValue::from_str(&prefixed_cookie(cookie, prefix)).ok())
+        .collect();
+    if !cookies.is_empty() {
+        response.headers_mut().remove(SET_COOKIE);
+        for cookie in cookies {
+            response.headers_mut().append(SET_COOKIE, cookie);
+        }
+    }
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    if !is_html {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let html = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(e) => return Error::Anyhow(anyhow::anyhow!(e)).into_response(),
+    };
+    let html = match std::str::from_utf8(&html) {
+        Ok(html) => prefix_links(html, prefix).into_bytes(),
+        Err(_) => html.to_vec(),
+    };
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, body::boxed(Full::from(html)))
+}
+
+/// `path` under `prefix`, when it's an absolute path on this site.
+fn prefixed(path: &str, prefix: &str) -> Option<String> {
+    (path.starts_with('/') && !path.starts_with("//")).then(|| format!("{}{}", prefix, path))
+}
+
+/// A `Set-Cookie` value with its `Path` under `prefix`.
+fn prefixed_cookie(cookie: &str, prefix: &str) -> String {
+    cookie
+        .split("; ")
+        .map(|attribute| match attribute.strip_prefix("Path=") {
+            Some(path) => match prefixed(path, prefix) {
+                Some(path) => format!("Path={}", path.trim_end_matches('/')),
+                None => attribute.to_string(),
+            },
+            None => attribute.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn prefix_links(html: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut copied = 0;
+    for (i, _) in html.match_indices('=') {
+        let value = &html[i + 1..];
+        if !(value.starts_with("\"/") || value.starts_with("'/")) || value[2..].starts_with('/') {
+            continue;
+        }
+        let name_start = html[..i]
+            .rfind(char::is_whitespace)
+            .map_or(0, |start| start + 1);
+        if LINK_ATTRIBUTES.contains(&&html[name_start..i]) {
+            // up to and including the quote
+            out.push_str(&html[copied..i + 2]);
+            out.push_str(prefix);
+            copied = i + 2;
+        }
+    }
+    out.push_str(&html[copied..]);
+    out
+}
+
+#[async_trait]
+impl<B> FromRequest<B> for Tenant
+where
+    B: Send,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        req.extensions()
+            .and_then(|extensions| extensions.get::<Tenant>())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("the tenant layer isn't in front of the router").into())
+    }
+}
+
+#[test]
+fn test_prefix_links() {
+    let html = r##"<a href="/auctions">Auctions</a> <a href="//cdn.example.com/x.js">cdn</a>
+        <form method="post" action='/logout'></form> <img src="static/imgs/a.png">
+        <button hx-post="/donate" hx-target="#main">Donate</button> <p>1 = 1</p>
+        <a href="https://example.com/">elsewhere</a> <input value="/not-a-link">"##;
+    assert_eq!(
+        prefix_links(html, "/t/shore"),
+        r##"<a href="/t/shore/auctions">Auctions</a> <a href="//cdn.example.com/x.js">cdn</a>
+        <form method="post" action='/t/shore/logout'></form> <img src="static/imgs/a.png">
+        <button hx-post="/t/shore/donate" hx-target="#main">Donate</button> <p>1 = 1</p>
+        <a href="https://example.com/">elsewhere</a> <input value="/not-a-link">"##
+    );
+    let (slug, uri) = split_prefix(&"/t/shore/auctions?page=2".parse().unwrap()).unwrap();
+    assert_eq!(slug, "shore");
+    assert_eq!(uri, "/auctions?page=2");
+    let (_, uri) = split_prefix(&"/t/shore".parse().unwrap()).unwrap();
+    assert_eq!(uri, "/");
+    assert!(split_prefix(&"/auctions".parse().unwrap()).is_none());
+}
+
+#[test]
+fn test_prefixed_cookie() {
+    assert_eq!(
+        prefixed_cookie("session=abc; Path=/; HttpOnly", "/t/shore"),
+        "session=abc; Path=/t/shore; HttpOnly"
+    );
+    assert_eq!(
+        prefixed_cookie("session=abc; Path=/admin", "/t/shore"),
+        "session=abc; Path=/t/shore/admin"
+    );
+    assert_eq!(prefixed_cookie("theme=dark", "/t/shore"), "theme=dark");
+}
@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Extension, Query},
+    http::{
+        header::{HeaderMap, HeaderValue, LOCATION, SET_COOKIE},
+        StatusCode,
+    },
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use minijinja::context;
+use serde::Deserialize;
+use tracing::{event, instrument, Level};
+
+use crate::auth;
+use crate::db::tenants::{self, Tenant};
+use crate::endpoints::extractor::AuthUser;
+use crate::endpoints::{parse_form, render_page, ApiContext};
+use crate::error::{Error, Result};
+
+use super::{queries, safe_next, LoginFromForm, RegisterFromForm};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/login", get(get_login_form).post(login))
+        .route("/register", get(get_register_form).post(register))
+        .route("/logout", post(logout))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NextParams {
+    next: Option<String>,
+}
+
+#[instrument(skip(ctx))]
+async fn get_login_form(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    Query(params): Query<NextParams>,
+) -> Result<Html<String>> {
+    render_page(
+        &ctx,
+        &headers,
+        "login.html",
+        context!(title => "Log in", next => safe_next(params.next.as_deref())),
+    )
+}
+
+#[instrument(skip(ctx))]
+async fn get_register_form(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    Query(params): Query<NextParams>,
+) -> Result<Html<String>> {
+    render_page(
+        &ctx,
+        &headers,
+        "register.html",
+        context!(title => "Register", next => safe_next(params.next.as_deref())),
+    )
+}
+
+#[instrument(skip(ctx, body))]
+async fn login(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    body: String,
+) -> Result<Response> {
+    let form: LoginFromForm = parse_form(&body)?;
+    let next = safe_next(form.next.as_deref()).to_string();
+    let user = queries::get_user_login(&form.email, &ctx.db).await?;
+    let verified = match user {
+        Some(user) => auth::verify_password(form.password, user.password_hash)
+            .await
+            .map(|_| user.user_id),
+        None => Err(Error::Unauthorized),
+    };
+    match verified {
+        Ok(user_id) => {
+            // accounts are shared, so this may be their first time at this tenant
+            tenants::join(tenant.tenant_id, user_id, &ctx.db).await?;
+            Ok(logged_in_redirect(
+                &ctx,
+                &tenant,
+                AuthUser { user_id },
+                &next,
+            ))
+        }
+        Err(Error::Unauthorized) => {
+            let page = render_page(
+                &ctx,
+                &headers,
+                "login.html",
+                context!(
+                    title => "Log in",
+                    next => next,
+                    email => form.email,
+                    errors => vec!["Unknown email or wrong password."],
+                ),
+            )?;
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, page).into_response())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument(skip(ctx, body))]
+async fn register(
+    headers: HeaderMap,
+    ctx: Extension<ApiContext>,
+    tenant: Tenant,
+    body: String,
+) -> Result<Response> {
+    let form: RegisterFromForm = parse_form(&body)?;
+    let next = safe_next(form.next.as_deref()).to_string();
+    let created = if form.password.len() < 8 {
+        Err(Error::unprocessable_entity([(
+            "password",
+            "password must be at least 8 characters",
+        )]))
+    } else {
+        let password_hash = auth::hash_password(form.password.clone()).await?;
+        queries::create_user(
+            &form.email,
+            &password_hash,
+            form.first_name.as_deref(),
+            form.last_name.as_deref(),
+            tenant.tenant_id,
+            &ctx.db,
+        )
+        .await
+    };
+    match created {
+        Ok(user_id) => {
+            event!(Level::INFO, event_msg = "Registered new user", user_id=%user_id);
+            Ok(logged_in_redirect(
+                &ctx,
+                &tenant,
+                AuthUser { user_id },
+                &next,
+            ))
+        }
+        Err(Error::UnprocessableEntity { errors }) => {
+            let errors: Vec<String> = errors.into_values().flatten().map(String::from).collect();
+            let page = render_page(
+                &ctx,
+                &headers,
+                "register.html",
+                context!(
+                    title => "Register",
+                    next => next,
+                    email => form.email,
+                    first_name => form.first_name,
+                    last_name => form.last_name,
+                    errors => errors,
+                ),
+            )?;
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, page).into_response())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[instrument]
+async fn logout() -> Response {
+    redirect(
+        "/auctions",
+        HeaderValue::from_str(&AuthUser::clear_session_cookie()).ok(),
+    )
+}
+
+fn logged_in_redirect(
+    ctx: &ApiContext,
+    tenant: &Tenant,
+    auth_user: AuthUser,
+    next: &str,
+) -> Response {
+    redirect(
+        next,
+        HeaderValue::from_str(&auth_user.to_session_cookie(ctx, tenant.tenant_id)).ok(),
+    )
+}
+
+fn redirect(location: &str, cookie: Option<HeaderValue>) -> Response {
+    let mut headers = HeaderMap::new();
+    if let Ok(location) = HeaderValue::from_str(location) {
+        headers.insert(LOCATION, location);
+    }
+    if let Some(cookie) = cookie {
+        headers.insert(SET_COOKIE, cookie);
+    }
+    (StatusCode::SEE_OTHER, headers, ()).into_response()
+}
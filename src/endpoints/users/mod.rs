@@ -0,0 +1,48 @@
+//! Bidder accounts: registration and logging in and out of the public site.
+mod handlers;
+pub(super) mod queries;
+
+use crate::db::tables;
+pub use handlers::router;
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct LoginFromForm {
+    pub email: String,
+    pub password: String,
+    // where to send the user once they're logged in
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct RegisterFromForm {
+    pub email: String,
+    pub password: String,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub first_name: Option<String>,
+    #[serde(default, deserialize_with = "tables::empty_string_as_none")]
+    pub last_name: Option<String>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+pub struct UserLogin {
+    pub user_id: uuid::Uuid,
+    pub password_hash: String,
+}
+
+/// Only follow redirects back into this site.
+fn safe_next(next: Option<&str>) -> &str {
+    match next {
+        Some(next) if next.starts_with('/') && !next.starts_with("//") => next,
+        _ => "/auctions",
+    }
+}
+
+#[test]
+fn test_safe_next() {
+    assert_eq!(safe_next(Some("/auctions/abc")), "/auctions/abc");
+    assert_eq!(safe_next(Some("https://evil.example")), "/auctions");
+    assert_eq!(safe_next(Some("//evil.example")), "/auctions");
+    assert_eq!(safe_next(None), "/auctions");
+}
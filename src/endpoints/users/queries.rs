@@ -0,0 +1,58 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{error::Result, Error, ResultExt};
+
+use super::UserLogin;
+
+#[instrument(skip(db))]
+pub async fn get_user_login(email: &str, db: &PgPool) -> Result<Option<UserLogin>> {
+    sqlx::query_as!(
+        UserLogin,
+        r#"
+            select user_id, password_hash
+            from "user"
+            where email = $1
+        "#,
+        email
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// A new user, who is a member of the tenant they registered with.
+#[instrument(skip(password_hash, db))]
+pub async fn create_user(
+    email: &str,
+    password_hash: &str,
+    first_name: Option<&str>,
+    last_name: Option<&str>,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> Result<Uuid> {
+    sqlx::query_scalar!(
+        r#"
+            with new_user as (
+                insert into "user" (email, password_hash, first_name, last_name)
+                values ($1, $2, $3, $4)
+                returning user_id
+            )
+            insert into tenant_user (tenant_id, user_id)
+            select $5, user_id
+            from new_user
+            returning user_id
+        "#,
+        email,
+        password_hash,
+        first_name,
+        last_name,
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .on_constraint("user_email_key", |_| {
+        Error::unprocessable_entity([("email", "an account with that email already exists")])
+    })
+}
@@ -143,7 +143,7 @@ impl IntoResponse for Error {
                     //
                     // However, at Launchbadge we try to adhere to web standards wherever possible,
                     // if nothing else than to try to act as a vanguard of sanity on the web.
-                    [(WWW_AUTHENTICATE, HeaderValue::from_static("Token"))]
+                    [(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"))]
                         .into_iter()
                         .collect::<HeaderMap>(),
                     self.to_string(),
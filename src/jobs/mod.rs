@@ -0,0 +1,123 @@
+//! Work which happens after a request, reliably.
+//!
+//! A `Job` is enqueued in the same transaction as the change which calls for it, and a pool of
+//! workers in the server picks jobs up from the `job` table. A job which fails is retried with
+//! exponential backoff until it runs out of attempts, when it is marked `dead` and waits for an
+//! admin to retry it.
+use sqlx::types::time::OffsetDateTime;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+pub mod queries;
+mod worker;
+
+pub use worker::spawn_workers;
+
+use crate::db::deliveries::DeliveryStatus;
+use crate::db::donations::DonationStatus;
+use crate::error::Result;
+use crate::notify::Message;
+
+/// Attempts are spaced `BACKOFF_BASE * 2^(attempts - 1)` apart, up to `BACKOFF_MAX`.
+const BACKOFF_BASE: time::Duration = time::Duration::seconds(30);
+const BACKOFF_MAX: time::Duration = time::Duration::hours(6);
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    /// Tell a user they no longer hold the high bid on an item
+    Outbid {
+        user_id: Uuid,
+        auction_item_id: Uuid,
+    },
+    /// Tell the owner of a winning bid that they've won
+    Won { auction_item_bid_id: Uuid },
+    /// Send one rendered notification
+    SendMessage { message: Message },
+    /// Flag the winning bids of an auction whose items have closed
+    RecomputeWinners { auction_id: Uuid },
+    /// Rebuild an auction's draft invoices from its winning bids
+    GenerateInvoices { auction_id: Uuid },
+    /// Tell a bidder their invoice is ready to pay
+    InvoiceIssued { invoice_id: Uuid },
+    /// Ask the payment provider about a payment we haven't heard back on
+    CheckPayment { payment_id: Uuid },
+    /// Tell a bidder where the delivery of an item they won has got to
+    DeliveryStatusChanged {
+        auction_item_bid_id: Uuid,
+        status: DeliveryStatus,
+    },
+    /// Ask the carriers about everything in transit, then do it again in a while
+    TrackShipments,
+    /// Tell a donor how their donated item is getting on
+    DonationStatusChanged {
+        auction_item_id: Uuid,
+        status: DonationStatus,
+    },
+}
+
+impl Job {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Job::Outbid { .. } => "outbid",
+            Job::Won { .. } => "won",
+            Job::SendMessage { .. } => "send_message",
+            Job::RecomputeWinners { .. } => "recompute_winners",
+            Job::GenerateInvoices { .. } => "generate_invoices",
+            Job::InvoiceIssued { .. } => "invoice_issued",
+            Job::CheckPayment { .. } => "check_payment",
+            Job::DeliveryStatusChanged { .. } => "delivery_status_changed",
+            Job::TrackShipments => "track_shipments",
+            Job::DonationStatusChanged { .. } => "donation_status_changed",
+        }
+    }
+}
+
+/// Add a job to run as soon as a worker is free. Pass the transaction making the change the job
+/// is for, so the job is only committed along with it.
+pub async fn enqueue<'c, E: PgExecutor<'c>>(job: &Job, db: E) -> Result<Uuid> {
+    queries::insert_job(job, OffsetDateTime::now_utc(), db).await
+}
+
+/// Add a job which should not run before `run_at`.
+pub async fn enqueue_at<'c, E: PgExecutor<'c>>(
+    job: &Job,
+    run_at: OffsetDateTime,
+    db: E,
+) -> Result<Uuid> {
+    queries::insert_job(job, run_at, db).await
+}
+
+/// Add `job` unless one of its kind is already waiting or running, for jobs which keep
+/// themselves going by enqueuing their next run.
+pub async fn ensure_queued(job: &Job, db: &PgPool) -> Result<()> {
+    if !queries::has_queued_job(job.kind(), db).await? {
+        enqueue(job, db).await?;
+    }
+    Ok(())
+}
+
+/// How long to wait before trying a job again after its `attempts`th failure.
+fn backoff(attempts: i32) -> time::Duration {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    (BACKOFF_BASE * 2_i32.pow(exponent)).min(BACKOFF_MAX)
+}
+
+#[test]
+fn test_backoff() {
+    assert_eq!(backoff(1), time::Duration::seconds(30));
+    assert_eq!(backoff(2), time::Duration::minutes(1));
+    assert_eq!(backoff(4), time::Duration::minutes(4));
+    assert_eq!(backoff(12), BACKOFF_MAX);
+    assert_eq!(backoff(1000), BACKOFF_MAX);
+}
+
+#[test]
+fn test_job_payload() {
+    let job = Job::Won {
+        auction_item_bid_id: Uuid::nil(),
+    };
+    let payload = serde_json::to_value(&job).unwrap();
+    assert_eq!(payload["kind"], job.kind());
+    assert_eq!(serde_json::from_value::<Job>(payload).unwrap(), job);
+}
@@ -0,0 +1,199 @@
+use sqlx::types::{time::OffsetDateTime, Json};
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::tables::{serialize_dt, serialize_dt_opt};
+use crate::error::{Error, Result};
+
+use super::Job;
+
+/// A worker which hasn't finished a job after this long is assumed to have died with it.
+const LOCK_TIMEOUT_SECONDS: f64 = 15.0 * 60.0;
+
+/// A job as a worker sees it. `payload` is left as JSON so a job that no longer parses can be
+/// sent to the dead letters instead of blocking the queue.
+#[derive(Debug)]
+pub struct ClaimedJob {
+    pub job_id: Uuid,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+/// A job as shown in the admin view.
+#[derive(Debug, serde::Serialize)]
+pub struct JobRow {
+    pub job_id: Uuid,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    #[serde(serialize_with = "serialize_dt")]
+    pub run_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt_opt")]
+    pub locked_at: Option<OffsetDateTime>,
+    pub last_error: Option<String>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub created_at: OffsetDateTime,
+    #[serde(serialize_with = "serialize_dt")]
+    pub updated_at: OffsetDateTime,
+}
+
+pub async fn insert_job<'c, E: PgExecutor<'c>>(
+    job: &Job,
+    run_at: OffsetDateTime,
+    db: E,
+) -> Result<Uuid> {
+    sqlx::query_scalar!(
+        r#"
+            insert into job (kind, payload, run_at)
+            values ($1, $2, $3)
+            returning job_id
+        "#,
+        job.kind(),
+        Json(job) as _,
+        run_at
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+pub async fn has_queued_job(kind: &str, db: &PgPool) -> Result<bool> {
+    sqlx::query_scalar!(
+        r#"
+            select exists(
+                select 1
+                from job
+                where kind = $1
+                and status in ('pending', 'running')
+            ) "exists!"
+        "#,
+        kind
+    )
+    .fetch_one(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Claim the job which has been waiting longest, if any is ready.
+///
+/// `skip locked` lets any number of workers claim at once without ever picking the same job.
+pub async fn claim_job(db: &PgPool) -> Result<Option<ClaimedJob>> {
+    sqlx::query_as!(
+        ClaimedJob,
+        r#"
+            update job
+            set
+                status = 'running',
+                attempts = attempts + 1,
+                locked_at = now()
+            where job_id = (
+                select job_id
+                from job
+                where run_at <= now()
+                and (
+                    status = 'pending'
+                    or (
+                        status = 'running'
+                        and locked_at < now() - make_interval(secs => $1)
+                    )
+                )
+                order by run_at
+                limit 1
+                for update skip locked
+            )
+            returning job_id, payload, attempts, max_attempts
+        "#,
+        LOCK_TIMEOUT_SECONDS
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+pub async fn complete_job(job_id: Uuid, db: &PgPool) -> Result<()> {
+    sqlx::query!(
+        r#"
+            update job
+            set status = 'done', locked_at = null
+            where job_id = $1
+        "#,
+        job_id
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Record a failed attempt: the job runs again at `retry_at`, or is marked `dead` without one.
+pub async fn fail_job(
+    job_id: Uuid,
+    error: &str,
+    retry_at: Option<OffsetDateTime>,
+    db: &PgPool,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            update job
+            set
+                status = case when $3::timestamptz is null then 'dead' else 'pending' end,
+                run_at = coalesce($3, run_at),
+                locked_at = null,
+                last_error = $2
+            where job_id = $1
+        "#,
+        job_id,
+        error,
+        retry_at
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// The most recently updated jobs with a status, newest first.
+#[instrument(skip(db))]
+pub async fn list_jobs(status: &str, limit: i64, db: &PgPool) -> Result<Vec<JobRow>> {
+    sqlx::query_as!(
+        JobRow,
+        r#"
+            select
+                job_id, kind, payload, status, attempts, max_attempts,
+                run_at, locked_at, last_error, created_at, updated_at
+            from job
+            where status = $1
+            order by updated_at desc
+            limit $2
+        "#,
+        status,
+        limit
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Give a dead job a fresh set of attempts, starting now.
+///
+/// Returns `Error::NotFound` unless the job exists and is dead.
+#[instrument(skip(db))]
+pub async fn retry_job(job_id: Uuid, db: &PgPool) -> Result<()> {
+    let retried = sqlx::query!(
+        r#"
+            update job
+            set status = 'pending', attempts = 0, run_at = now()
+            where job_id = $1
+            and status = 'dead'
+        "#,
+        job_id
+    )
+    .execute(db)
+    .await?;
+    if retried.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use sqlx::types::time::OffsetDateTime;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{event, instrument, Level};
+
+use crate::db::{bidding, invoices};
+use crate::error::Result;
+use crate::notify::{Message, Notifications};
+use crate::payments::Payments;
+use crate::tracking::{self, CarrierTracker};
+
+use super::{backoff, enqueue, enqueue_at, queries, Job};
+
+/// How long an idle worker waits before looking for jobs again.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start `count` workers which run jobs for as long as the server does. Without a `tracker`,
+/// shipments are only moved along by hand, and without `payments` there are none to check on.
+pub fn spawn_workers(
+    count: usize,
+    notifications: Notifications,
+    payments: Option<Payments>,
+    tracker: Option<Arc<dyn CarrierTracker>>,
+    db: PgPool,
+) {
+    for worker in 0..count {
+        let (notifications, payments, tracker, db) = (
+            notifications.clone(),
+            payments.clone(),
+            tracker.clone(),
+            db.clone(),
+        );
+        tokio::spawn(async move {
+            loop {
+                match queries::claim_job(&db).await {
+                    Ok(Some(job)) => {
+                        run_claimed(
+                            job,
+                            &notifications,
+                            payments.as_ref(),
+                            tracker.as_deref(),
+                            &db,
+                        )
+                        .await
+                    }
+                    Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                    Err(e) => {
+                        event!(Level::ERROR, event_msg = "Error claiming job", worker, err = ?e);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[instrument(skip(notifications, payments, tracker, db), fields(job_id = %claimed.job_id))]
+async fn run_claimed(
+    claimed: queries::ClaimedJob,
+    notifications: &Notifications,
+    payments: Option<&Payments>,
+    tracker: Option<&dyn CarrierTracker>,
+    db: &PgPool,
+) {
+    let failure = match serde_json::from_value::<Job>(claimed.payload) {
+        Ok(job) => match run(job, notifications, payments, tracker, db).await {
+            Ok(()) => None,
+            Err(e) => Some((format!("{:?}", e), claimed.attempts < claimed.max_attempts)),
+        },
+        // retrying won't help a job we can't read
+        Err(e) => Some((format!("unreadable job: {}", e), false)),
+    };
+    let recorded = match failure {
+        None => queries::complete_job(claimed.job_id, db).await,
+        Some((error, retry)) => {
+            event!(Level::WARN, event_msg = "Job failed", %error, retry);
+            let retry_at = retry.then(|| OffsetDateTime::now_utc() + backoff(claimed.attempts));
+            queries::fail_job(claimed.job_id, &error, retry_at, db).await
+        }
+    };
+    // if this fails the job runs again once its lock times out
+    if let Err(e) = recorded {
+        event!(Level::ERROR, event_msg = "Error recording job result", err = ?e);
+    }
+}
+
+async fn run(
+    job: Job,
+    notifications: &Notifications,
+    payments: Option<&Payments>,
+    tracker: Option<&dyn CarrierTracker>,
+    db: &PgPool,
+) -> Result<()> {
+    match job {
+        Job::Outbid {
+            user_id,
+            auction_item_id,
+        } => {
+            let messages = notifications.outbid(user_id, auction_item_id, db).await?;
+            enqueue_messages(messages, db).await
+        }
+        Job::Won {
+            auction_item_bid_id,
+        } => {
+            let messages = notifications.won(auction_item_bid_id, db).await?;
+            enqueue_messages(messages, db).await
+        }
+        Job::SendMessage { message } => Ok(notifications.send(&message).await?),
+        Job::RecomputeWinners { auction_id } => {
+            bidding::recompute_winners(Some(auction_id), db).await?;
+            Ok(())
+        }
+        Job::GenerateInvoices { auction_id } => {
+            invoices::generate_invoices(auction_id, db).await?;
+            Ok(())
+        }
+        Job::InvoiceIssued { invoice_id } => {
+            let messages = notifications.invoice_issued(invoice_id, db).await?;
+            enqueue_messages(messages, db).await
+        }
+        Job::CheckPayment { payment_id } => match payments {
+            Some(payments) => payments.check(payment_id, db).await,
+            None => Ok(()),
+        },
+        Job::DeliveryStatusChanged {
+            auction_item_bid_id,
+            status,
+        } => {
+            let messages = notifications
+                .delivery_status(auction_item_bid_id, status, db)
+                .await?;
+            enqueue_messages(messages, db).await
+        }
+        // the next run is only queued once this one succeeds, so a failing run is retried
+        // instead of piling up behind another
+        Job::TrackShipments => match tracker {
+            Some(tracker) => {
+                tracking::poll(tracker, db).await?;
+                let next = OffsetDateTime::now_utc() + tracking::POLL_INTERVAL;
+                enqueue_at(&Job::TrackShipments, next, db).await?;
+                Ok(())
+            }
+            None => Ok(()),
+        },
+        Job::DonationStatusChanged {
+            auction_item_id,
+            status,
+        } => {
+            let messages = notifications
+                .donation_status(auction_item_id, status, db)
+                .await?;
+            enqueue_messages(messages, db).await
+        }
+    }
+}
+
+/// Each message is sent by its own job, so one failing channel is retried without resending
+/// the others.
+async fn enqueue_messages(messages: Vec<Message>, db: &PgPool) -> Result<()> {
+    let mut tx = db.begin().await?;
+    for message in messages {
+        enqueue(&Job::SendMessage { message }, &mut tx).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
@@ -1,5 +1,13 @@
+pub mod auth;
+pub mod cli;
 pub mod config;
 pub mod db;
 pub mod error;
 pub use crate::error::{Error, ResultExt};
 pub mod endpoints;
+pub mod jobs;
+pub mod notify;
+pub mod payments;
+pub mod pdf;
+pub mod shipping;
+pub mod tracking;
@@ -1,13 +1,10 @@
-use anyhow::Context;
 use clap::Parser;
-use sqlx::postgres::PgPoolOptions;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{EnvFilter, Registry};
 
-use hooksaurus_auctions::config::Config;
-use hooksaurus_auctions::endpoints;
+use hooksaurus_auctions::cli::{self, Cli, Command};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -16,8 +13,14 @@ async fn main() -> anyhow::Result<()> {
     }
     LogTracer::init().expect("Unable to setup log tracer!");
 
+    let cli = Cli::parse();
+
     let app_name = concat!(env!("CARGO_PKG_NAME"), "-", env!("CARGO_PKG_VERSION")).to_string();
-    let (non_blocking_writer, _guard) = tracing_appender::non_blocking(std::io::stdout());
+    let (non_blocking_writer, _guard) = match cli.command {
+        None | Some(Command::Serve(_)) => tracing_appender::non_blocking(std::io::stdout()),
+        // Management commands print their results to stdout, so keep logs out of the way.
+        Some(_) => tracing_appender::non_blocking(std::io::stderr()),
+    };
     let bunyan_formatting_layer = BunyanFormattingLayer::new(app_name, non_blocking_writer);
     let subscriber = Registry::default()
         .with(EnvFilter::new("INFO"))
@@ -25,16 +28,5 @@ async fn main() -> anyhow::Result<()> {
         .with(bunyan_formatting_layer);
     tracing::subscriber::set_global_default(subscriber).unwrap();
 
-    let config = Config::parse();
-    let db = PgPoolOptions::new()
-        .max_connections(50)
-        .connect(&config.database_url)
-        .await
-        .context("could not connect to database_url")?;
-
-    sqlx::migrate!().run(&db).await?;
-
-    endpoints::serve(config, db).await?;
-
-    Ok(())
+    cli::run(cli).await
 }
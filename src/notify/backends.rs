@@ -0,0 +1,142 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+
+use super::{Channel, Message, Notifier};
+
+/// Sends each message through the backend for its channel.
+pub struct ChannelNotifier {
+    pub email: Arc<dyn Notifier>,
+    pub sms: Arc<dyn Notifier>,
+}
+
+#[async_trait]
+impl Notifier for ChannelNotifier {
+    async fn send(&self, message: &Message) -> anyhow::Result<()> {
+        match message.channel {
+            Channel::Email => self.email.send(message).await,
+            Channel::Sms => self.sms.send(message).await,
+        }
+    }
+}
+
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpNotifier {
+    pub fn new(smtp_url: &str, from: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            transport: AsyncSmtpTransport::<Tokio1Executor>::from_url(smtp_url)
+                .context("smtp_url is not a valid SMTP URL")?
+                .build(),
+            from: from.parse().context("email_from is not a valid mailbox")?,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    async fn send(&self, message: &Message) -> anyhow::Result<()> {
+        let email = lettre::Message::builder()
+            .from(self.from.clone())
+            .to(message.to.parse()?)
+            .subject(&message.subject)
+            .body(message.body.clone())?;
+        self.transport.send(email).await?;
+        Ok(())
+    }
+}
+
+/// Sends texts through an HTTP messages API in the style of Twilio's.
+pub struct SmsNotifier {
+    client: reqwest::Client,
+    api_url: String,
+    user: String,
+    token: String,
+    from: String,
+}
+
+impl SmsNotifier {
+    pub fn new(api_url: &str, user: &str, token: &str, from: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_url: api_url.to_string(),
+            user: user.to_string(),
+            token: token.to_string(),
+            from: from.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SmsNotifier {
+    async fn send(&self, message: &Message) -> anyhow::Result<()> {
+        self.client
+            .post(&self.api_url)
+            .basic_auth(&self.user, Some(&self.token))
+            .form(&[
+                ("To", message.to.as_str()),
+                ("From", self.from.as_str()),
+                ("Body", message.body.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Appends each message to a file as a line of JSON, for development.
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn send(&self, message: &Message) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("could not open {}", self.path.display()))?;
+        file.write_all(&line).await?;
+        Ok(())
+    }
+}
+
+/// Keeps every message it is given, for tests.
+#[derive(Default)]
+pub struct MemoryNotifier {
+    sent: Mutex<Vec<Message>>,
+}
+
+impl MemoryNotifier {
+    pub fn sent(&self) -> Vec<Message> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Notifier for MemoryNotifier {
+    async fn send(&self, message: &Message) -> anyhow::Result<()> {
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+//! Telling bidders what happened while they weren't looking.
+//!
+//! A `Notifier` delivers a rendered `Message` over one channel. `Notifications` decides who
+//! should hear about an event, over which of their channels, and renders the message bodies
+//! from the `templates/notifications` templates. Messages are sent from background jobs, see
+//! `crate::jobs`.
+use anyhow::Context;
+use async_trait::async_trait;
+use minijinja::value::Value;
+use minijinja::Environment;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::instrument;
+use uuid::Uuid;
+
+mod backends;
+mod queries;
+
+pub use backends::{ChannelNotifier, FileNotifier, MemoryNotifier, SmsNotifier, SmtpNotifier};
+
+use crate::config::NotifyConfig;
+use crate::db::deliveries::DeliveryStatus;
+use crate::db::donations::DonationStatus;
+use crate::error::Result;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Email,
+    Sms,
+}
+
+impl Channel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Email => "email",
+            Channel::Sms => "sms",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Message {
+    pub channel: Channel,
+    // an email address or a phone number, depending on the channel
+    pub to: String,
+    // texts have no subject, so this is empty for them
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, message: &Message) -> anyhow::Result<()>;
+}
+
+/// Where and how a user wants to be told about their bids.
+#[derive(Debug)]
+pub struct Recipient {
+    pub user_id: Uuid,
+    pub first_name: Option<String>,
+    pub email: Option<String>,
+    pub sms_number: Option<String>,
+}
+
+impl Recipient {
+    fn addresses(&self) -> impl Iterator<Item = (Channel, &str)> {
+        let email = self.email.as_deref().map(|to| (Channel::Email, to));
+        let sms = self.sms_number.as_deref().map(|to| (Channel::Sms, to));
+        email.into_iter().chain(sms)
+    }
+}
+
+/// The variables a notification template is rendered with, besides `site_url` and `first_name`.
+type Vars = BTreeMap<&'static str, Value>;
+
+/// Build `Vars` from serializable values: `vars!(item)` or `vars!(item => bid)`.
+macro_rules! vars {
+    ($($name:ident $(=> $value:expr)?),*) => {{
+        let mut vars = Vars::new();
+        $(vars.insert(stringify!($name), vars!(@value $name $(, $value)?));)*
+        vars
+    }};
+    (@value $name:ident) => { Value::from_serializable(&$name) };
+    (@value $name:ident, $value:expr) => { Value::from_serializable(&$value) };
+}
+
+#[derive(Clone)]
+pub struct Notifications {
+    notifier: Arc<dyn Notifier>,
+    template_env: Environment<'static>,
+    site_url: String,
+}
+
+impl Notifications {
+    pub fn new(
+        notifier: Arc<dyn Notifier>,
+        template_env: Environment<'static>,
+        site_url: &str,
+    ) -> Self {
+        Self {
+            notifier,
+            template_env,
+            site_url: site_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Pick a backend for each channel from the configuration.
+    pub fn from_config(config: &NotifyConfig, template_env: Environment<'static>) -> Result<Self> {
+        let file: Arc<dyn Notifier> = Arc::new(FileNotifier::new(&config.notification_file));
+        let email: Arc<dyn Notifier> = match &config.smtp_url {
+            Some(url) => Arc::new(SmtpNotifier::new(url, &config.email_from)?),
+            None => file.clone(),
+        };
+        let sms: Arc<dyn Notifier> = match &config.sms_api_url {
+            Some(url) => Arc::new(SmsNotifier::new(
+                url,
+                config.sms_api_user.as_deref().unwrap_or_default(),
+                config.sms_api_token.as_deref().unwrap_or_default(),
+                config
+                    .sms_from
+                    .as_deref()
+                    .context("sms_from is required with sms_api_url")?,
+            )),
+            None => file,
+        };
+        Ok(Self::new(
+            Arc::new(ChannelNotifier { email, sms }),
+            template_env,
+            &config.site_url,
+        ))
+    }
+
+    pub async fn send(&self, message: &Message) -> anyhow::Result<()> {
+        self.notifier.send(message).await
+    }
+
+    /// The messages telling someone that their high bid on an item has been beaten.
+    #[instrument(skip(self, db))]
+    pub async fn outbid(
+        &self,
+        user_id: Uuid,
+        auction_item_id: Uuid,
+        db: &PgPool,
+    ) -> Result<Vec<Message>> {
+        let item = match queries::get_item(auction_item_id, db).await? {
+            Some(item) => item,
+            None => return Ok(vec![]),
+        };
+        self.render_for_user(user_id, "outbid", vars!(item), db)
+            .await
+    }
+
+    /// The messages telling the bidder behind a winning bid that they've won.
+    #[instrument(skip(self, db))]
+    pub async fn won(&self, auction_item_bid_id: Uuid, db: &PgPool) -> Result<Vec<Message>> {
+        let bid = match queries::get_winning_bid(auction_item_bid_id, db).await? {
+            Some(bid) => bid,
+            None => return Ok(vec![]),
+        };
+        self.render_for_user(bid.user_id, "won", vars!(item => bid), db)
+            .await
+    }
+
+    /// The messages telling a bidder that their invoice is ready to pay.
+    #[instrument(skip(self, db))]
+    pub async fn invoice_issued(&self, invoice_id: Uuid, db: &PgPool) -> Result<Vec<Message>> {
+        let invoice = match queries::get_invoice(invoice_id, db).await? {
+            Some(invoice) => invoice,
+            None => return Ok(vec![]),
+        };
+        self.render_for_user(invoice.user_id, "invoice_issued", vars!(invoice), db)
+            .await
+    }
+
+    /// The messages telling a bidder where the delivery of an item they won has got to. A number
+    /// given for updates on this delivery is texted, even if the bidder doesn't take texts about
+    /// anything else.
+    #[instrument(skip(self, db))]
+    pub async fn delivery_status(
+        &self,
+        auction_item_bid_id: Uuid,
+        status: DeliveryStatus,
+        db: &PgPool,
+    ) -> Result<Vec<Message>> {
+        let delivery = match queries::get_delivery(auction_item_bid_id, db).await? {
+            Some(delivery) => delivery,
+            None => return Ok(vec![]),
+        };
+        let mut recipient = match queries::get_recipient(delivery.user_id, db).await? {
+            Some(recipient) => recipient,
+            None => return Ok(vec![]),
+        };
+        if delivery.sms_updates_number.is_some() {
+            recipient.sms_number = delivery.sms_updates_number.clone();
+        }
+        self.render_all(&recipient, "delivery_status", vars!(delivery, status))
+    }
+
+    /// The email telling a donor what we've decided about the item they offered. Donors
+    /// needn't have an account, so it goes to the address they gave with the donation.
+    #[instrument(skip(self, db))]
+    pub async fn donation_status(
+        &self,
+        auction_item_id: Uuid,
+        status: DonationStatus,
+        db: &PgPool,
+    ) -> Result<Vec<Message>> {
+        let donation = match queries::get_donation(auction_item_id, db).await? {
+            Some(donation) => donation,
+            None => return Ok(vec![]),
+        };
+        let recipient = Recipient {
+            user_id: Uuid::nil(),
+            first_name: Some(donation.donor_name.clone()),
+            email: Some(donation.donor_email.clone()),
+            sms_number: None,
+        };
+        self.render_all(&recipient, "donation_status", vars!(donation, status))
+    }
+
+    async fn render_for_user(
+        &self,
+        user_id: Uuid,
+        template: &str,
+        vars: Vars,
+        db: &PgPool,
+    ) -> Result<Vec<Message>> {
+        match queries::get_recipient(user_id, db).await? {
+            Some(recipient) => self.render_all(&recipient, template, vars),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// One message for each of the recipient's channels.
+    fn render_all(
+        &self,
+        recipient: &Recipient,
+        template: &str,
+        mut vars: Vars,
+    ) -> Result<Vec<Message>> {
+        vars.insert("site_url", Value::from(self.site_url.as_str()));
+        vars.insert(
+            "first_name",
+            Value::from_serializable(&recipient.first_name),
+        );
+        let context = Value::from_serializable(&vars);
+        recipient
+            .addresses()
+            .map(|(channel, to)| self.render(template, channel, to, &context))
+            .collect()
+    }
+
+    /// Render `notifications/<template>_<channel>.txt`. The first line of an email template is
+    /// its subject.
+    fn render(
+        &self,
+        template: &str,
+        channel: Channel,
+        to: &str,
+        context: &Value,
+    ) -> Result<Message> {
+        let name = format!("notifications/{}_{}.txt", template, channel.as_str());
+        let rendered = self
+            .template_env
+            .get_template(&name)
+            .map_err(anyhow::Error::from)?
+            .render(context)
+            .map_err(anyhow::Error::from)?;
+        let (subject, body) = match channel {
+            Channel::Email => split_subject(&rendered),
+            Channel::Sms => ("", rendered.trim()),
+        };
+        Ok(Message {
+            channel,
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+        })
+    }
+}
+
+fn split_subject(rendered: &str) -> (&str, &str) {
+    let rendered = rendered.trim_start();
+    match rendered.split_once('\n') {
+        Some((subject, body)) => (subject.trim(), body.trim()),
+        None => (rendered.trim(), ""),
+    }
+}
+
+#[test]
+fn test_split_subject() {
+    assert_eq!(
+        split_subject("\nYou've been outbid\n\nHi Sam,\nsomeone bid more.\n"),
+        ("You've been outbid", "Hi Sam,\nsomeone bid more.")
+    );
+    assert_eq!(split_subject("Only a subject"), ("Only a subject", ""));
+}
+
+#[tokio::test]
+async fn test_outbid_messages() {
+    let notifier = Arc::new(MemoryNotifier::default());
+    let notifications = Notifications::new(
+        notifier.clone(),
+        crate::endpoints::template_env(),
+        "https://auctions.example.com/",
+    );
+    let recipient = Recipient {
+        user_id: Uuid::nil(),
+        first_name: Some("Sam".to_string()),
+        email: Some("sam@example.com".to_string()),
+        sms_number: Some("+15035550100".to_string()),
+    };
+    let item = queries::ItemSummary {
+        auction_item_id: Uuid::nil(),
+        auction_id: Uuid::nil(),
+        title: "Goat Yoga for Two".to_string(),
+        high_bid: Some(sqlx::types::Decimal::new(4200, 2)),
+        end_date: sqlx::types::time::OffsetDateTime::now_utc(),
+        timezone: "America/Los_Angeles".to_string(),
+    };
+    for message in notifications
+        .render_all(&recipient, "outbid", vars!(item))
+        .unwrap()
+    {
+        notifications.send(&message).await.unwrap();
+    }
+
+    let sent = notifier.sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].channel, Channel::Email);
+    assert_eq!(sent[0].to, "sam@example.com");
+    assert_eq!(sent[0].subject, "You've been outbid on Goat Yoga for Two");
+    assert!(sent[0].body.starts_with("Hi Sam,"));
+    assert!(sent[0].body.contains("$42.00"));
+    assert_eq!(sent[1].channel, Channel::Sms);
+    assert!(sent[1]
+        .body
+        .contains("https://auctions.example.com/auctions/"));
+}
+
+#[test]
+fn test_delivery_status_messages() {
+    let notifications = Notifications::new(
+        Arc::new(MemoryNotifier::default()),
+        crate::endpoints::template_env(),
+        "https://auctions.example.com",
+    );
+    let recipient = Recipient {
+        user_id: Uuid::nil(),
+        first_name: None,
+        email: Some("sam@example.com".to_string()),
+        sms_number: Some("+15035550100".to_string()),
+    };
+    let delivery = queries::DeliverySummary {
+        user_id: Uuid::nil(),
+        auction_id: Uuid::nil(),
+        auction_item_id: Uuid::nil(),
+        title: "Hand-thrown Mug Set".to_string(),
+        carrier: Some("USPS".to_string()),
+        tracking_number: Some("9400100000000000000000".to_string()),
+        shipping_exception: None,
+        sms_updates_number: None,
+    };
+    let status = DeliveryStatus::Shipped;
+    let messages = notifications
+        .render_all(&recipient, "delivery_status", vars!(delivery, status))
+        .unwrap();
+    assert_eq!(messages[0].subject, "Hand-thrown Mug Set is on its way");
+    assert!(messages[0]
+        .body
+        .starts_with("Hi,\n\nHand-thrown Mug Set has shipped with USPS."));
+    assert!(messages[1].body.contains("tracking 9400100000000000000000"));
+}
+
+#[test]
+fn test_donation_status_messages() {
+    let notifications = Notifications::new(
+        Arc::new(MemoryNotifier::default()),
+        crate::endpoints::template_env(),
+        "https://auctions.example.com",
+    );
+    let recipient = Recipient {
+        user_id: Uuid::nil(),
+        first_name: Some("Robin".to_string()),
+        email: Some("robin@example.com".to_string()),
+        sms_number: None,
+    };
+    let donation = queries::DonationSummary {
+        donor_name: "Robin".to_string(),
+        donor_email: "robin@example.com".to_string(),
+        auction_item_id: Uuid::nil(),
+        title: "Handmade Goat Milk Soap".to_string(),
+        auction_id: None,
+        auction_title: None,
+        decline_reason: Some("we can't auction food safely".to_string()),
+    };
+    let status = DonationStatus::Declined;
+    let messages = notifications
+        .render_all(&recipient, "donation_status", vars!(donation, status))
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        messages[0].subject,
+        "About your donation of Handmade Goat Milk Soap"
+    );
+    assert!(messages[0].body.starts_with("Hi Robin,"));
+    assert!(messages[0].body.contains("we can't auction food safely"));
+}
@@ -0,0 +1,240 @@
+use sqlx::types::{time::OffsetDateTime, Decimal};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::tables::serialize_dt;
+use crate::error::{Error, Result};
+
+use super::Recipient;
+
+#[derive(Debug, serde::Serialize)]
+pub struct ItemSummary {
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub title: String,
+    pub high_bid: Option<Decimal>,
+    #[serde(serialize_with = "serialize_dt")]
+    pub end_date: OffsetDateTime,
+    pub timezone: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct WinningBid {
+    pub user_id: Uuid,
+    pub auction_item_id: Uuid,
+    pub auction_id: Uuid,
+    pub title: String,
+    pub amount: Decimal,
+}
+
+/// Contact details from the user's delivery records win over the ones on their account, since
+/// those were given specifically for updates about their items. Channels the user has turned
+/// off are left empty.
+pub async fn get_recipient(user_id: Uuid, db: &PgPool) -> Result<Option<Recipient>> {
+    sqlx::query_as!(
+        Recipient,
+        r#"
+            select
+                u.user_id,
+                u.first_name,
+                case when u.notify_by_email then
+                    coalesce(
+                        (
+                            select d.email_contact
+                            from auction_item_delivery d
+                            where d.user_id = u.user_id
+                            and d.email_contact <> ''
+                            order by d.updated_at desc
+                            limit 1
+                        ),
+                        u.email
+                    )
+                end "email",
+                case when u.notify_by_sms then
+                    coalesce(
+                        (
+                            select d.sms_updates_number
+                            from auction_item_delivery d
+                            where d.user_id = u.user_id
+                            and d.sms_updates_number <> ''
+                            order by d.updated_at desc
+                            limit 1
+                        ),
+                        nullif(u.phone_number, '')
+                    )
+                end "sms_number"
+            from "user" u
+            where u.user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+pub async fn get_item(auction_item_id: Uuid, db: &PgPool) -> Result<Option<ItemSummary>> {
+    sqlx::query_as!(
+        ItemSummary,
+        r#"
+            select
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                ai.title,
+                (
+                    select max(amount)
+                    from auction_item_bid aib
+                    where aib.auction_item_id = ai.auction_item_id
+                ) high_bid,
+                least(ai.active_end_date, a.end_date) "end_date!",
+                a.timezone
+            from auction_item ai
+            inner join auction a
+            on a.auction_id = ai.auction_id
+            where ai.auction_item_id = $1
+        "#,
+        auction_item_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+pub async fn get_winning_bid(auction_item_bid_id: Uuid, db: &PgPool) -> Result<Option<WinningBid>> {
+    sqlx::query_as!(
+        WinningBid,
+        r#"
+            select
+                aib.user_id,
+                ai.auction_item_id,
+                ai.auction_id "auction_id!",
+                ai.title,
+                aib.amount
+            from auction_item_bid aib
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            where aib.auction_item_bid_id = $1
+            and aib.is_winning_bid
+        "#,
+        auction_item_bid_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct InvoiceSummary {
+    pub invoice_id: Uuid,
+    pub reference: String,
+    pub user_id: Uuid,
+    pub auction_title: String,
+    pub total: Decimal,
+}
+
+pub async fn get_invoice(invoice_id: Uuid, db: &PgPool) -> Result<Option<InvoiceSummary>> {
+    sqlx::query_as!(
+        InvoiceSummary,
+        r#"
+            select
+                i.invoice_id,
+                'INV-' || lpad(i.invoice_number::text, 6, '0') "reference!",
+                i.user_id,
+                a.title auction_title,
+                (
+                    select coalesce(sum(amount), 0)
+                    from invoice_line il
+                    where il.invoice_id = i.invoice_id
+                ) "total!"
+            from invoice i
+            inner join auction a
+            on a.auction_id = i.auction_id
+            where i.invoice_id = $1
+        "#,
+        invoice_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DeliverySummary {
+    pub user_id: Uuid,
+    pub auction_id: Uuid,
+    pub auction_item_id: Uuid,
+    pub title: String,
+    pub carrier: Option<String>,
+    pub tracking_number: Option<String>,
+    pub shipping_exception: Option<String>,
+    // where the winner asked for texts about this delivery
+    pub sms_updates_number: Option<String>,
+}
+
+pub async fn get_delivery(
+    auction_item_bid_id: Uuid,
+    db: &PgPool,
+) -> Result<Option<DeliverySummary>> {
+    sqlx::query_as!(
+        DeliverySummary,
+        r#"
+            select
+                aib.user_id,
+                ai.auction_id "auction_id!",
+                ai.auction_item_id,
+                ai.title,
+                d.carrier,
+                d.tracking_number,
+                d.shipping_exception,
+                nullif(d.sms_updates_number, '') sms_updates_number
+            from auction_item_delivery d
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = d.auction_item_bid_id
+            inner join auction_item ai
+            on ai.auction_item_id = aib.auction_item_id
+            where d.auction_item_bid_id = $1
+        "#,
+        auction_item_bid_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DonationSummary {
+    pub donor_name: String,
+    pub donor_email: String,
+    pub auction_item_id: Uuid,
+    pub title: String,
+    // the auction it was approved into
+    pub auction_id: Option<Uuid>,
+    pub auction_title: Option<String>,
+    pub decline_reason: Option<String>,
+}
+
+pub async fn get_donation(auction_item_id: Uuid, db: &PgPool) -> Result<Option<DonationSummary>> {
+    sqlx::query_as!(
+        DonationSummary,
+        r#"
+            select
+                d.donor_name,
+                d.donor_email "donor_email!",
+                ai.auction_item_id,
+                ai.title,
+                a.auction_id "auction_id?",
+                a.title "auction_title?",
+                d.decline_reason
+            from item_donation d
+            inner join auction_item ai
+            on ai.auction_item_id = d.auction_item_id
+            left join auction a
+            on a.auction_id = ai.auction_id
+            where d.auction_item_id = $1
+        "#,
+        auction_item_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(Error::Sqlx)
+}
@@ -0,0 +1,306 @@
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderValue};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use sqlx::types::Decimal;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::error::{Error, Result};
+
+use super::{IntentRequest, Outcome, PaymentEvent, PaymentGateway, PaymentIntent};
+
+/// The header carrying a callback's HMAC-SHA-256 signature, hex encoded.
+pub const SIGNATURE_HEADER: &str = "x-mock-signature";
+
+/// The mock charges what a typical card processor does: 2.9% plus 30 cents.
+const FEE_PERCENT: Decimal = Decimal::from_parts(29, 0, 0, false, 1);
+const FEE_FIXED: Decimal = Decimal::from_parts(30, 0, 0, false, 2);
+
+/// The buttons on the fake checkout page.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckoutOutcome {
+    Succeed,
+    Fail,
+    /// Walk away: the intent expires and no callback is sent
+    TimeOut,
+}
+
+/// A payment intent as the mock provider keeps it.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MockIntent {
+    pub provider_ref: String,
+    pub reference: String,
+    pub description: String,
+    pub amount: Decimal,
+    pub return_url: String,
+    // `None` until the bidder finishes checking out
+    pub outcome: Option<Outcome>,
+}
+
+/// A signed callback, as the provider would POST it to us.
+#[derive(Debug)]
+pub struct Callback {
+    pub body: Vec<u8>,
+    pub signature: String,
+}
+
+impl Callback {
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Ok(signature) = HeaderValue::from_str(&self.signature) {
+            headers.insert(SIGNATURE_HEADER, signature);
+        }
+        headers
+    }
+}
+
+/// An in-process payment provider for development and tests. Intents live in memory, so any
+/// left over from before a restart are reported as expired.
+pub struct MockGateway {
+    webhook_secret: String,
+    site_url: String,
+    intents: Mutex<HashMap<String, MockIntent>>,
+}
+
+impl MockGateway {
+    pub fn new(webhook_secret: &str, site_url: &str) -> Self {
+        Self {
+            webhook_secret: webhook_secret.to_string(),
+            site_url: site_url.trim_end_matches('/').to_string(),
+            intents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn intent(&self, provider_ref: &str) -> Option<MockIntent> {
+        self.intents.lock().unwrap().get(provider_ref).cloned()
+    }
+
+    /// Finish checking out, as the bidder would on the checkout page. Returns the callback the
+    /// provider sends, if any.
+    pub fn complete(
+        &self,
+        provider_ref: &str,
+        outcome: CheckoutOutcome,
+    ) -> Result<Option<Callback>> {
+        let mut intents = self.intents.lock().unwrap();
+        let intent = intents.get_mut(provider_ref).ok_or(Error::NotFound)?;
+        if intent.outcome.is_some() {
+            return Err(Error::unprocessable_entity([(
+                "checkout",
+                "this payment has already been completed",
+            )]));
+        }
+        let outcome = match outcome {
+            CheckoutOutcome::Succeed => Outcome::Succeeded {
+                fee: (intent.amount * FEE_PERCENT / Decimal::ONE_HUNDRED + FEE_FIXED).round_dp(2),
+            },
+            CheckoutOutcome::Fail => Outcome::Failed {
+                reason: "Your card was declined.".to_string(),
+            },
+            CheckoutOutcome::TimeOut => Outcome::Expired,
+        };
+        intent.outcome = Some(outcome.clone());
+        let event = event_for(provider_ref, outcome);
+        if event.outcome == Outcome::Expired {
+            return Ok(None);
+        }
+        let body = serde_json::to_vec(&event).map_err(anyhow::Error::from)?;
+        let signature = self.sign(&body);
+        Ok(Some(Callback { body, signature }))
+    }
+
+    fn sign(&self, body: &[u8]) -> String {
+        let mut mac = self.mac();
+        mac.update(body);
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(self.webhook_secret.as_bytes())
+            .expect("HMAC-SHA-256 can accept any key length")
+    }
+}
+
+/// Event ids are derived from what happened, so a callback and a later `confirm` agree.
+fn event_for(provider_ref: &str, outcome: Outcome) -> PaymentEvent {
+    let kind = match outcome {
+        Outcome::Succeeded { .. } => "succeeded",
+        Outcome::Failed { .. } => "failed",
+        Outcome::Expired => "expired",
+        Outcome::Refunded { .. } => "refunded",
+    };
+    PaymentEvent {
+        event_id: format!("evt_{}_{}", provider_ref, kind),
+        provider_ref: provider_ref.to_string(),
+        outcome,
+    }
+}
+
+// an odd length leaves a single digit at the end, which `get` turns down
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[async_trait]
+impl PaymentGateway for MockGateway {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn create_intent(&self, request: &IntentRequest<'_>) -> anyhow::Result<PaymentIntent> {
+        let provider_ref = format!("pi_mock_{:016x}", rand::thread_rng().gen::<u64>());
+        let intent = MockIntent {
+            provider_ref: provider_ref.clone(),
+            reference: request.reference.to_string(),
+            description: request.description.to_string(),
+            amount: request.amount,
+            return_url: request.return_url.to_string(),
+            outcome: None,
+        };
+        self.intents
+            .lock()
+            .unwrap()
+            .insert(provider_ref.clone(), intent);
+        Ok(PaymentIntent {
+            checkout_url: format!("{}/payments/mock/{}", self.site_url, provider_ref),
+            provider_ref,
+        })
+    }
+
+    async fn confirm(&self, provider_ref: &str) -> anyhow::Result<Option<PaymentEvent>> {
+        let outcome = match self.intents.lock().unwrap().get(provider_ref) {
+            Some(intent) => intent.outcome.clone(),
+            None => Some(Outcome::Expired),
+        };
+        Ok(outcome.map(|outcome| event_for(provider_ref, outcome)))
+    }
+
+    fn parse_callback(&self, headers: &HeaderMap, body: &[u8]) -> Result<PaymentEvent> {
+        let signature = headers
+            .get(SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(decode_hex)
+            .ok_or(Error::Unauthorized)?;
+        let mut mac = self.mac();
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| Error::Unauthorized)?;
+        serde_json::from_slice(body)
+            .map_err(|e| Error::unprocessable_entity([("callback", e.to_string())]))
+    }
+
+    async fn refund(&self, provider_ref: &str, amount: Decimal) -> anyhow::Result<PaymentEvent> {
+        let mut intents = self.intents.lock().unwrap();
+        let intent = match intents.get_mut(provider_ref) {
+            Some(intent) if matches!(intent.outcome, Some(Outcome::Succeeded { .. })) => intent,
+            _ => anyhow::bail!("{} has no successful payment to refund", provider_ref),
+        };
+        if amount != intent.amount {
+            anyhow::bail!("the mock only refunds payments in full");
+        }
+        intent.outcome = Some(Outcome::Refunded { amount });
+        Ok(event_for(provider_ref, Outcome::Refunded { amount }))
+    }
+}
+
+#[tokio::test]
+async fn test_mock_checkout() {
+    let gateway = MockGateway::new("secret", "http://localhost:8000/");
+    let request = IntentRequest {
+        reference: "INV-000001",
+        description: "INV-000001: Spring Auction",
+        amount: Decimal::new(10000, 2),
+        return_url: "http://localhost:8000/invoices/1",
+    };
+
+    let paid = gateway.create_intent(&request).await.unwrap();
+    assert!(paid
+        .checkout_url
+        .starts_with("http://localhost:8000/payments/mock/pi_mock_"));
+    assert_eq!(gateway.confirm(&paid.provider_ref).await.unwrap(), None);
+    let callback = gateway
+        .complete(&paid.provider_ref, CheckoutOutcome::Succeed)
+        .unwrap()
+        .unwrap();
+    let event = gateway
+        .parse_callback(&callback.headers(), &callback.body)
+        .unwrap();
+    assert_eq!(
+        event.outcome,
+        Outcome::Succeeded {
+            fee: Decimal::new(320, 2)
+        }
+    );
+    // a late confirm reports the same event, so it's only applied once
+    assert_eq!(
+        gateway.confirm(&paid.provider_ref).await.unwrap(),
+        Some(event)
+    );
+    assert!(gateway
+        .complete(&paid.provider_ref, CheckoutOutcome::Fail)
+        .is_err());
+
+    let declined = gateway.create_intent(&request).await.unwrap();
+    let callback = gateway
+        .complete(&declined.provider_ref, CheckoutOutcome::Fail)
+        .unwrap()
+        .unwrap();
+    let event = gateway
+        .parse_callback(&callback.headers(), &callback.body)
+        .unwrap();
+    assert!(matches!(event.outcome, Outcome::Failed { .. }));
+
+    let abandoned = gateway.create_intent(&request).await.unwrap();
+    assert!(gateway
+        .complete(&abandoned.provider_ref, CheckoutOutcome::TimeOut)
+        .unwrap()
+        .is_none());
+    let event = gateway.confirm(&abandoned.provider_ref).await.unwrap();
+    assert_eq!(event.unwrap().outcome, Outcome::Expired);
+
+    let refund = gateway
+        .refund(&paid.provider_ref, Decimal::new(10000, 2))
+        .await
+        .unwrap();
+    assert_eq!(
+        refund.outcome,
+        Outcome::Refunded {
+            amount: Decimal::new(10000, 2)
+        }
+    );
+    assert!(gateway
+        .refund(&declined.provider_ref, Decimal::new(10000, 2))
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_callback_signature() {
+    let gateway = MockGateway::new("secret", "http://localhost:8000");
+    let body = br#"{"event_id":"evt_1","provider_ref":"pi_1","type":"expired"}"#;
+    let callback = Callback {
+        body: body.to_vec(),
+        signature: gateway.sign(body),
+    };
+    let event = gateway
+        .parse_callback(&callback.headers(), &callback.body)
+        .unwrap();
+    assert_eq!(event.outcome, Outcome::Expired);
+
+    let forged = MockGateway::new("not the secret", "http://localhost:8000");
+    assert!(matches!(
+        forged.parse_callback(&callback.headers(), &callback.body),
+        Err(Error::Unauthorized)
+    ));
+    assert!(gateway.parse_callback(&HeaderMap::new(), body).is_err());
+}
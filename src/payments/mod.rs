@@ -0,0 +1,232 @@
+//! Taking payment for invoices.
+//!
+//! A `PaymentGateway` is a payment provider. It creates payment intents, which the bidder
+//! completes on the provider's checkout page. It tells us how they went in signed callbacks,
+//! and it issues refunds. `Payments` ties a gateway to our invoices: it records each intent as
+//! a `payment` and applies the provider's events to it exactly once, posting ledger entries as
+//! money comes in and goes back out.
+//!
+//! The only gateway so far is `MockGateway`, which runs in process behind a fake checkout page.
+//! It's only used when `--mock-payments` is given.
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use sqlx::types::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{event, instrument, Level};
+use uuid::Uuid;
+
+mod mock;
+
+pub use mock::{Callback, CheckoutOutcome, MockGateway, MockIntent, SIGNATURE_HEADER};
+
+use crate::config::PaymentConfig;
+use crate::db::payments;
+use crate::error::{Error, Result};
+
+/// What we ask a provider to collect.
+#[derive(Debug)]
+pub struct IntentRequest<'a> {
+    // the invoice reference, shown to the bidder at checkout
+    pub reference: &'a str,
+    pub description: &'a str,
+    pub amount: Decimal,
+    // where the checkout page sends the bidder afterwards
+    pub return_url: &'a str,
+}
+
+#[derive(Debug)]
+pub struct PaymentIntent {
+    pub provider_ref: String,
+    pub checkout_url: String,
+}
+
+/// Something which happened to a payment intent, as the provider reports it.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PaymentEvent {
+    // unique per event: providers may send the same event more than once
+    pub event_id: String,
+    pub provider_ref: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Outcome {
+    Succeeded {
+        fee: Decimal,
+    },
+    Failed {
+        reason: String,
+    },
+    /// The bidder never finished checking out
+    Expired,
+    Refunded {
+        amount: Decimal,
+    },
+}
+
+#[async_trait]
+pub trait PaymentGateway: Send + Sync {
+    /// Stored with each payment, since provider references are only unique per provider.
+    fn name(&self) -> &'static str;
+
+    async fn create_intent(&self, request: &IntentRequest<'_>) -> anyhow::Result<PaymentIntent>;
+
+    /// Ask where an intent stands, for when a callback doesn't arrive. `None` means the bidder
+    /// hasn't finished checking out yet.
+    async fn confirm(&self, provider_ref: &str) -> anyhow::Result<Option<PaymentEvent>>;
+
+    /// Check a callback's signature and read its event.
+    fn parse_callback(&self, headers: &HeaderMap, body: &[u8]) -> Result<PaymentEvent>;
+
+    /// Return the whole of a successful payment.
+    async fn refund(&self, provider_ref: &str, amount: Decimal) -> anyhow::Result<PaymentEvent>;
+}
+
+#[derive(Clone)]
+pub struct Payments {
+    gateway: Arc<dyn PaymentGateway>,
+    // the same gateway when it's the mock, so the fake checkout page can drive it
+    mock: Option<Arc<MockGateway>>,
+    site_url: String,
+}
+
+impl Payments {
+    pub fn new(gateway: Arc<dyn PaymentGateway>, site_url: &str) -> Self {
+        Self {
+            gateway,
+            mock: None,
+            site_url: site_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn with_mock(mock: Arc<MockGateway>, site_url: &str) -> Self {
+        Self {
+            mock: Some(mock.clone()),
+            ..Self::new(mock, site_url)
+        }
+    }
+
+    /// The configured gateway, or `None` when there isn't one.
+    pub fn from_config(config: &PaymentConfig, site_url: &str) -> Option<Self> {
+        if !config.mock_payments {
+            return None;
+        }
+        let mock = MockGateway::new(&config.payment_webhook_secret, site_url);
+        Some(Self::with_mock(Arc::new(mock), site_url))
+    }
+
+    pub fn mock(&self) -> Option<&MockGateway> {
+        self.mock.as_deref()
+    }
+
+    /// Where to send a bidder who wants to pay an invoice: the provider's checkout page, or
+    /// back to the invoice if there turns out to be nothing to pay.
+    ///
+    /// A checkout which is still in progress is picked up again rather than starting another,
+    /// so an invoice can't be paid twice.
+    #[instrument(skip(self, db))]
+    pub async fn checkout(&self, invoice_id: Uuid, user_id: Uuid, db: &PgPool) -> Result<String> {
+        let invoice = payments::get_payable_invoice(invoice_id, db)
+            .await?
+            .filter(|invoice| invoice.user_id == user_id && invoice.status != "draft")
+            .ok_or(Error::NotFound)?;
+        let return_url = format!("{}/invoices/{}", self.site_url, invoice_id);
+        if invoice.status != "issued" {
+            return Ok(return_url);
+        }
+        if let Some(pending) = payments::get_pending_payment(invoice_id, db).await? {
+            match self.gateway.confirm(&pending.provider_ref).await? {
+                None => return Ok(pending.checkout_url),
+                Some(event) => {
+                    let retry = matches!(event.outcome, Outcome::Failed { .. } | Outcome::Expired);
+                    self.apply(&event, db).await?;
+                    if !retry {
+                        return Ok(return_url);
+                    }
+                }
+            }
+        }
+        let description = format!("{}: {}", invoice.reference, invoice.auction_title);
+        let intent = self
+            .gateway
+            .create_intent(&IntentRequest {
+                reference: &invoice.reference,
+                description: &description,
+                amount: invoice.total,
+                return_url: &return_url,
+            })
+            .await?;
+        payments::insert_payment(invoice_id, self.gateway.name(), &intent, invoice.total, db)
+            .await?;
+        Ok(intent.checkout_url)
+    }
+
+    /// Act on a callback from the provider. Repeats of an event are accepted and ignored.
+    #[instrument(skip(self, headers, body, db))]
+    pub async fn handle_callback(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        db: &PgPool,
+    ) -> Result<()> {
+        let event = self.gateway.parse_callback(headers, body)?;
+        self.apply(&event, db).await?;
+        Ok(())
+    }
+
+    /// Ask the provider about a payment we haven't heard back on, and check again later if
+    /// the bidder is still at the checkout.
+    #[instrument(skip(self, db))]
+    pub async fn check(&self, payment_id: Uuid, db: &PgPool) -> Result<()> {
+        let pending = match payments::get_payment(payment_id, db).await? {
+            Some(payment) if payment.status == "pending" => payment,
+            _ => return Ok(()),
+        };
+        match self.gateway.confirm(&pending.provider_ref).await? {
+            Some(event) => self.apply(&event, db).await.map(|_| ()),
+            None => payments::schedule_check(payment_id, db).await,
+        }
+    }
+
+    /// Refund a paid invoice in full.
+    ///
+    /// Returns `Error::NotFound` unless the invoice has been paid.
+    #[instrument(skip(self, db))]
+    pub async fn refund(&self, invoice_id: Uuid, db: &PgPool) -> Result<()> {
+        // the payment stays locked until the refund is recorded, so a second refund of the
+        // same invoice waits for this one and then finds nothing left to refund
+        let mut tx = db.begin().await?;
+        let payment = payments::lock_refundable_payment(invoice_id, &mut tx)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let event = self
+            .gateway
+            .refund(&payment.provider_ref, payment.amount)
+            .await?;
+        let applied = payments::apply_event(self.gateway.name(), &event, &mut tx).await?;
+        tx.commit().await?;
+        log_event(&event, applied);
+        Ok(())
+    }
+
+    async fn apply(&self, payment_event: &PaymentEvent, db: &PgPool) -> Result<bool> {
+        let mut tx = db.begin().await?;
+        let applied = payments::apply_event(self.gateway.name(), payment_event, &mut tx).await?;
+        tx.commit().await?;
+        log_event(payment_event, applied);
+        Ok(applied)
+    }
+}
+
+fn log_event(payment_event: &PaymentEvent, applied: bool) {
+    event!(
+        Level::INFO,
+        event_msg = "Payment event",
+        event_id = %payment_event.event_id,
+        provider_ref = %payment_event.provider_ref,
+        applied
+    );
+}
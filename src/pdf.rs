@@ -0,0 +1,201 @@
+//! Plain, printable PDFs built up a line at a time: invoices, receipts and the like.
+//!
+//! Only the PDF built-in fonts are used, so nothing needs to be embedded. Amounts are set in
+//! Courier, whose fixed width lets us right-align them without font metrics.
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference};
+
+const PAGE_WIDTH: f32 = 215.9;
+const PAGE_HEIGHT: f32 = 279.4;
+const MARGIN: f32 = 20.0;
+// millimetres per point
+const PT: f32 = 0.3528;
+// Courier glyphs are all 0.6em wide
+const COURIER_WIDTH: f32 = 0.6;
+const BODY_SIZE: f32 = 10.0;
+const WRAP_AT: usize = 100;
+
+/// The right edge of the printable area, for right-aligned amounts.
+pub const RIGHT_EDGE: f32 = PAGE_WIDTH - MARGIN;
+pub const LEFT_EDGE: f32 = MARGIN;
+
+#[derive(Clone, Debug)]
+pub enum Cell {
+    /// Text starting this many millimetres from the left of the page
+    Left(f32, String),
+    /// A fixed-width amount ending this many millimetres from the left of the page
+    Right(f32, String),
+}
+
+#[derive(Clone, Debug)]
+struct Line {
+    cells: Vec<Cell>,
+    size: f32,
+    bold: bool,
+    // starts a new page instead of drawing anything
+    page_break: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct PdfBuilder {
+    lines: Vec<Line>,
+}
+
+impl PdfBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn heading(&mut self, text: &str) -> &mut Self {
+        self.push(vec![Cell::Left(LEFT_EDGE, text.to_string())], 16.0, true)
+    }
+
+    pub fn subheading(&mut self, text: &str) -> &mut Self {
+        self.push(vec![Cell::Left(LEFT_EDGE, text.to_string())], 12.0, true)
+    }
+
+    /// A paragraph, wrapped to fit the page.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        for line in wrap(text, WRAP_AT) {
+            self.push(vec![Cell::Left(LEFT_EDGE, line)], BODY_SIZE, false);
+        }
+        self
+    }
+
+    pub fn blank(&mut self) -> &mut Self {
+        self.push(vec![], BODY_SIZE, false)
+    }
+
+    pub fn row(&mut self, cells: Vec<Cell>) -> &mut Self {
+        self.push(cells, BODY_SIZE, false)
+    }
+
+    pub fn bold_row(&mut self, cells: Vec<Cell>) -> &mut Self {
+        self.push(cells, BODY_SIZE, true)
+    }
+
+    /// Carry on at the top of a new page, unless nothing has been drawn on this one yet.
+    pub fn page_break(&mut self) -> &mut Self {
+        self.lines.push(Line {
+            cells: vec![],
+            size: 0.0,
+            bold: false,
+            page_break: true,
+        });
+        self
+    }
+
+    fn push(&mut self, cells: Vec<Cell>, size: f32, bold: bool) -> &mut Self {
+        self.lines.push(Line {
+            cells,
+            size,
+            bold,
+            page_break: false,
+        });
+        self
+    }
+
+    /// Lay the lines out top to bottom, starting new pages as needed.
+    pub fn render(&self, title: &str) -> anyhow::Result<Vec<u8>> {
+        let (doc, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        let fonts = Fonts {
+            regular: doc.add_builtin_font(BuiltinFont::Helvetica)?,
+            bold: doc.add_builtin_font(BuiltinFont::HelveticaBold)?,
+            fixed: doc.add_builtin_font(BuiltinFont::Courier)?,
+            fixed_bold: doc.add_builtin_font(BuiltinFont::CourierBold)?,
+        };
+        let mut layer = doc.get_page(page).get_layer(layer);
+        let top = PAGE_HEIGHT - MARGIN;
+        let mut y = top;
+        for line in &self.lines {
+            let height = line.size * PT * 1.5;
+            if (line.page_break && y < top) || y - height < MARGIN {
+                let (page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+                layer = doc.get_page(page).get_layer(new_layer);
+                y = top;
+            }
+            if line.page_break {
+                continue;
+            }
+            y -= height;
+            draw_line(&layer, &fonts, line, y);
+        }
+        Ok(doc.save_to_bytes()?)
+    }
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    fixed: IndirectFontRef,
+    fixed_bold: IndirectFontRef,
+}
+
+fn draw_line(layer: &PdfLayerReference, fonts: &Fonts, line: &Line, y: f32) {
+    for cell in &line.cells {
+        match cell {
+            Cell::Left(x, text) => {
+                let font = if line.bold {
+                    &fonts.bold
+                } else {
+                    &fonts.regular
+                };
+                layer.use_text(text.as_str(), line.size, Mm(*x), Mm(y), font);
+            }
+            Cell::Right(x, text) => {
+                let font = if line.bold {
+                    &fonts.fixed_bold
+                } else {
+                    &fonts.fixed
+                };
+                let width = text.chars().count() as f32 * line.size * COURIER_WIDTH * PT;
+                layer.use_text(text.as_str(), line.size, Mm(x - width), Mm(y), font);
+            }
+        }
+    }
+}
+
+/// Break text into lines of at most `width` characters, at spaces where possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[test]
+fn test_wrap() {
+    assert_eq!(
+        wrap("one two three four\nfive", 9),
+        vec!["one two", "three", "four", "five"]
+    );
+    assert!(wrap("", 9).is_empty());
+}
+
+#[test]
+fn test_render() {
+    let mut pdf = PdfBuilder::new();
+    pdf.heading("Invoice").row(vec![
+        Cell::Left(LEFT_EDGE, "Goat yoga".to_string()),
+        Cell::Right(RIGHT_EDGE, "$42.00".to_string()),
+    ]);
+    for _ in 0..100 {
+        pdf.text("Enough lines to need a second page.");
+    }
+    pdf.page_break()
+        .page_break()
+        .text("A third page, and no blank one before it.");
+    let bytes = pdf.render("Invoice").unwrap();
+    assert!(bytes.starts_with(b"%PDF"));
+}
@@ -0,0 +1,206 @@
+//! Working out what it costs to send a won item to its winner.
+//!
+//! A `ShippingRateCalculator` prices one item's trip from the organization it benefits to the
+//! winner's shipping address. The default, `DistanceRates`, charges by the great-circle
+//! distance between the two and by the item's weight and size classes. An item the winner
+//! collects in person is always free.
+use async_trait::async_trait;
+use sqlx::types::Decimal;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightClass {
+    /// Up to 1kg
+    Light,
+    /// Up to 5kg
+    Medium,
+    /// Up to 30kg
+    Heavy,
+    /// Anything heavier goes by freight
+    Freight,
+}
+
+impl WeightClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WeightClass::Light => "light",
+            WeightClass::Medium => "medium",
+            WeightClass::Heavy => "heavy",
+            WeightClass::Freight => "freight",
+        }
+    }
+
+    pub fn parse(class: &str) -> Option<Self> {
+        [
+            WeightClass::Light,
+            WeightClass::Medium,
+            WeightClass::Heavy,
+            WeightClass::Freight,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == class)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeClass {
+    /// Fits in a padded envelope
+    Small,
+    /// Fits in a shoebox
+    Medium,
+    /// Needs a big box
+    Large,
+    /// Needs a crate or a pallet
+    Oversized,
+}
+
+impl SizeClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SizeClass::Small => "small",
+            SizeClass::Medium => "medium",
+            SizeClass::Large => "large",
+            SizeClass::Oversized => "oversized",
+        }
+    }
+
+    pub fn parse(class: &str) -> Option<Self> {
+        [
+            SizeClass::Small,
+            SizeClass::Medium,
+            SizeClass::Large,
+            SizeClass::Oversized,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == class)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// Addresses don't always have coordinates, and need both to be any use.
+    pub fn new(latitude: Option<f64>, longitude: Option<f64>) -> Option<Self> {
+        Some(Coordinates {
+            latitude: latitude?,
+            longitude: longitude?,
+        })
+    }
+}
+
+/// The distance between two points along the surface of the earth, by the haversine formula.
+pub fn great_circle_km(from: &Coordinates, to: &Coordinates) -> f64 {
+    let (lat1, lat2) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (to.longitude - from.longitude).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// One item on its way to a winner.
+#[derive(Debug)]
+pub struct Shipment {
+    // the beneficiary organization's address, when we know where that is
+    pub origin: Option<Coordinates>,
+    pub destination: Option<Coordinates>,
+    pub weight_class: WeightClass,
+    pub size_class: SizeClass,
+    pub local_pickup: bool,
+}
+
+#[async_trait]
+pub trait ShippingRateCalculator: Send + Sync {
+    /// The fee for a shipment, in dollars and cents.
+    async fn rate(&self, shipment: &Shipment) -> anyhow::Result<Decimal>;
+}
+
+/// A fee for packing an item by its size, plus a charge per 100km by its weight.
+///
+/// When either end has no coordinates the item is charged as if it went `UNKNOWN_DISTANCE_KM`,
+/// which is about as far as anything goes within the continental US.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DistanceRates;
+
+impl DistanceRates {
+    pub const UNKNOWN_DISTANCE_KM: i64 = 4000;
+
+    fn packing(size_class: SizeClass) -> Decimal {
+        match size_class {
+            SizeClass::Small => Decimal::new(4_00, 2),
+            SizeClass::Medium => Decimal::new(8_00, 2),
+            SizeClass::Large => Decimal::new(15_00, 2),
+            SizeClass::Oversized => Decimal::new(40_00, 2),
+        }
+    }
+
+    fn per_100_km(weight_class: WeightClass) -> Decimal {
+        match weight_class {
+            WeightClass::Light => Decimal::new(10, 2),
+            WeightClass::Medium => Decimal::new(25, 2),
+            WeightClass::Heavy => Decimal::new(75, 2),
+            WeightClass::Freight => Decimal::new(2_50, 2),
+        }
+    }
+}
+
+#[async_trait]
+impl ShippingRateCalculator for DistanceRates {
+    async fn rate(&self, shipment: &Shipment) -> anyhow::Result<Decimal> {
+        if shipment.local_pickup {
+            return Ok(Decimal::ZERO);
+        }
+        let km = match (&shipment.origin, &shipment.destination) {
+            (Some(origin), Some(destination)) => {
+                great_circle_km(origin, destination).round() as i64
+            }
+            _ => Self::UNKNOWN_DISTANCE_KM,
+        };
+        let fee = Self::packing(shipment.size_class)
+            + Self::per_100_km(shipment.weight_class) * Decimal::new(km, 2);
+        Ok(fee.round_dp(2))
+    }
+}
+
+#[test]
+fn test_great_circle_km() {
+    let portland = Coordinates::new(Some(45.5152), Some(-122.6784)).unwrap();
+    let seattle = Coordinates::new(Some(47.6062), Some(-122.3321)).unwrap();
+    let km = great_circle_km(&portland, &seattle);
+    assert!((km - 233.5).abs() < 1.0, "{}", km);
+    assert_eq!(great_circle_km(&portland, &portland), 0.0);
+    assert_eq!(Coordinates::new(Some(45.5), None), None);
+}
+
+#[tokio::test]
+async fn test_distance_rates() {
+    let portland = Coordinates::new(Some(45.5152), Some(-122.6784));
+    let seattle = Coordinates::new(Some(47.6062), Some(-122.3321));
+    let mut shipment = Shipment {
+        origin: portland,
+        destination: seattle,
+        weight_class: WeightClass::Light,
+        size_class: SizeClass::Medium,
+        local_pickup: false,
+    };
+    // $8 to pack, and 234km at 10 cents per 100km
+    assert_eq!(
+        DistanceRates.rate(&shipment).await.unwrap(),
+        Decimal::new(8_23, 2)
+    );
+    shipment.destination = None;
+    assert_eq!(
+        DistanceRates.rate(&shipment).await.unwrap(),
+        Decimal::new(12_00, 2)
+    );
+    shipment.local_pickup = true;
+    assert_eq!(DistanceRates.rate(&shipment).await.unwrap(), Decimal::ZERO);
+    assert_eq!(WeightClass::parse("freight"), Some(WeightClass::Freight));
+    assert_eq!(SizeClass::parse("huge"), None);
+}
@@ -0,0 +1,188 @@
+//! Following shipped items with the carriers who have them.
+//!
+//! A `CarrierTracker` asks where a parcel has got to, given the free-text `carrier` and
+//! `tracking_number` a clerk entered when it shipped, and reports it as a `TrackingUpdate`
+//! which maps onto our `DeliveryStatus`es. `HttpTracker` talks to a carrier-neutral tracking
+//! API, of the kind which puts every carrier behind one endpoint.
+//!
+//! The `TrackShipments` job polls the tracker for everything in transit and records what it
+//! hears, see `crate::db::deliveries::record_tracking`.
+use async_trait::async_trait;
+use reqwest::{StatusCode, Url};
+use sqlx::types::time::OffsetDateTime;
+use sqlx::PgPool;
+use tracing::{event, instrument, Level};
+
+use crate::db::deliveries::{self, DeliveryStatus};
+use crate::db::tables;
+use crate::error::Result;
+
+/// How long the `TrackShipments` job waits between looking everything up.
+pub const POLL_INTERVAL: time::Duration = time::Duration::hours(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackingStatus {
+    /// A label exists, but the carrier doesn't have the parcel yet
+    PreTransit,
+    InTransit,
+    OutForDelivery,
+    Delivered,
+    /// Returned, damaged, lost, or waiting for something from us
+    Exception,
+}
+
+impl TrackingStatus {
+    /// Where a delivery stands when its carrier reports this. A parcel the carrier doesn't
+    /// have yet leaves the delivery where it is.
+    pub fn delivery_status(&self) -> Option<DeliveryStatus> {
+        match self {
+            TrackingStatus::PreTransit => None,
+            TrackingStatus::InTransit | TrackingStatus::OutForDelivery => {
+                Some(DeliveryStatus::Shipped)
+            }
+            TrackingStatus::Delivered => Some(DeliveryStatus::Delivered),
+            TrackingStatus::Exception => Some(DeliveryStatus::Exception),
+        }
+    }
+}
+
+/// What a carrier says about a parcel.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct TrackingUpdate {
+    pub status: TrackingStatus,
+    // when the carrier took the parcel
+    #[serde(default, deserialize_with = "tables::deserialize_optional_datetime")]
+    pub shipped_at: Option<OffsetDateTime>,
+    #[serde(default, deserialize_with = "tables::deserialize_optional_datetime")]
+    pub delivered_at: Option<OffsetDateTime>,
+    // the carrier's own words, for an exception
+    #[serde(default)]
+    pub exception: Option<String>,
+}
+
+#[async_trait]
+pub trait CarrierTracker: Send + Sync {
+    /// `None` means the carrier doesn't know the tracking number, or doesn't yet.
+    async fn track(
+        &self,
+        carrier: &str,
+        tracking_number: &str,
+    ) -> anyhow::Result<Option<TrackingUpdate>>;
+}
+
+/// Looks parcels up at `GET {api_url}/trackers/{carrier}/{tracking_number}`, authenticated
+/// with a bearer token. The response is a `TrackingUpdate` as JSON, or a 404 for a tracking
+/// number the carrier doesn't know.
+pub struct HttpTracker {
+    client: reqwest::Client,
+    api_url: Url,
+    api_key: String,
+}
+
+impl HttpTracker {
+    pub fn new(api_url: &str, api_key: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::new(),
+            api_url: Url::parse(api_url)?,
+            api_key: api_key.to_string(),
+        })
+    }
+
+    /// Carriers are typed in by hand, so "USPS " and "usps" are the same carrier.
+    fn url(&self, carrier: &str, tracking_number: &str) -> anyhow::Result<Url> {
+        let mut url = self.api_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("{} can't be a base URL", self.api_url))?
+            .pop_if_empty()
+            .extend([
+                "trackers",
+                &carrier.trim().to_lowercase(),
+                tracking_number.trim(),
+            ]);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl CarrierTracker for HttpTracker {
+    async fn track(
+        &self,
+        carrier: &str,
+        tracking_number: &str,
+    ) -> anyhow::Result<Option<TrackingUpdate>> {
+        let response = self
+            .client
+            .get(self.url(carrier, tracking_number)?)
+            .bearer_auth(&self.api_key)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let body = response.error_for_status()?.bytes().await?;
+        Ok(Some(serde_json::from_slice(&body)?))
+    }
+}
+
+/// Look up every delivery in transit and record what its carrier says. A parcel which can't be
+/// looked up doesn't hold up the rest: it's logged, and tried again next time. Returns how many
+/// deliveries changed status.
+#[instrument(skip(tracker, db))]
+pub async fn poll(tracker: &dyn CarrierTracker, db: &PgPool) -> Result<usize> {
+    let mut changed = 0;
+    for parcel in deliveries::list_in_transit(db).await? {
+        let update = match tracker
+            .track(&parcel.carrier, &parcel.tracking_number)
+            .await
+        {
+            Ok(Some(update)) => update,
+            Ok(None) => continue,
+            Err(e) => {
+                event!(
+                    Level::WARN,
+                    event_msg = "Error tracking shipment",
+                    auction_item_bid_id = %parcel.auction_item_bid_id,
+                    err = ?e
+                );
+                continue;
+            }
+        };
+        if let Some(status) =
+            deliveries::record_tracking(parcel.auction_item_bid_id, &update, db).await?
+        {
+            event!(
+                Level::INFO,
+                event_msg = "Carrier moved delivery",
+                auction_item_bid_id = %parcel.auction_item_bid_id,
+                status = status.as_str()
+            );
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+#[test]
+fn test_tracking_statuses() {
+    assert_eq!(TrackingStatus::PreTransit.delivery_status(), None);
+    assert_eq!(
+        TrackingStatus::OutForDelivery.delivery_status(),
+        Some(DeliveryStatus::Shipped)
+    );
+    let update: TrackingUpdate =
+        serde_json::from_str(r#"{"status": "delivered", "delivered_at": "2026-10-18T15:04:00Z"}"#)
+            .unwrap();
+    assert_eq!(update.status, TrackingStatus::Delivered);
+    assert_eq!(update.shipped_at, None);
+    assert_eq!(update.delivered_at.unwrap().unix_timestamp(), 1792335840);
+}
+
+#[test]
+fn test_tracker_urls() {
+    let tracker = HttpTracker::new("https://tracking.example.com/v1/", "key").unwrap();
+    assert_eq!(
+        tracker.url(" USPS", "9400 1000").unwrap().as_str(),
+        "https://tracking.example.com/v1/trackers/usps/9400%201000"
+    );
+}
@@ -0,0 +1,272 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, HOST, WWW_AUTHENTICATE};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use sqlx::types::Decimal;
+use uuid::Uuid;
+
+use hooksaurus_auctions::db::organizations::{self, OrgRole};
+use hooksaurus_auctions::db::tenants;
+use hooksaurus_auctions::endpoints::Tenants;
+
+async fn api(
+    app: &Tenants<Router>,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(HOST, "localhost");
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = common::send(app, request.body(body).unwrap()).await;
+    let json = serde_json::from_str(&response.body).unwrap_or(Value::Null);
+    (response.status, json)
+}
+
+/// Amounts are strings, whose scale depends on how they were stored.
+fn money(value: &Value) -> Decimal {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+/// An open auction of three items, each starting at $10.
+async fn open_auction(db: &sqlx::PgPool) -> (Uuid, Vec<Uuid>) {
+    let auction_id = sqlx::query_scalar!(
+        r#"
+            insert into auction (title, start_date, end_date, etag)
+            values ('Harvest Gala', now() - interval '1 day', now() + interval '7 days',
+                uuid_generate_v1mc())
+            returning auction_id
+        "#
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let item_ids = sqlx::query_scalar!(
+        r#"
+            insert into auction_item (
+                auction_id, title, featured_image_filepath, image_dir, tag_list,
+                minimum_bid_amount, active_end_date, etag
+            )
+            select $1, title, '', '', '{}', 10, now() + interval '7 days', uuid_generate_v1mc()
+            from unnest(array['Barn tour', 'Hay ride', 'Pie basket']) title
+            returning auction_item_id
+        "#,
+        auction_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+    (auction_id, item_ids)
+}
+
+#[tokio::test]
+async fn test_api_login_and_bidding() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let (user_id, email) = common::user_with_password(db).await;
+    let (auction_id, item_ids) = open_auction(db).await;
+
+    let (status, _) = api(
+        &app,
+        "POST",
+        "/api/v1/users/login",
+        None,
+        Some(json!({"email": email, "password": "wrong"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, session) = api(
+        &app,
+        "POST",
+        "/api/v1/users/login",
+        None,
+        Some(json!({"email": email, "password": "a-good-password"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(session["user"]["email"], email.as_str());
+    let token = session["token"].as_str().unwrap();
+
+    let response = common::get(&app, "localhost", "/api/v1/users/me").await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers[WWW_AUTHENTICATE], "Bearer");
+    let (status, me) = api(&app, "GET", "/api/v1/users/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["user_id"], user_id.to_string());
+
+    // items come a page at a time
+    let items_uri = format!("/api/v1/auctions/{}/items", auction_id);
+    let (status, page) = api(
+        &app,
+        "GET",
+        &format!("{}?per_page=2", items_uri),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["data"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        page["pagination"],
+        json!({"page": 1, "per_page": 2, "total": 3, "total_pages": 2})
+    );
+    let (_, page) = api(
+        &app,
+        "GET",
+        &format!("{}?per_page=2&page=2", items_uri),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(page["data"][0]["title"], "Pie basket");
+    let (status, errors) = api(
+        &app,
+        "GET",
+        &format!("{}?per_page=0", items_uri),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(errors["errors"]["per_page"].is_array());
+    let (status, errors) = api(&app, "GET", &format!("{}?pgae=2", items_uri), None, None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(errors["errors"]["query"].is_array());
+
+    // bidding needs a token, and a bid that's too low is explained as the bid panel would
+    let bids_uri = format!("{}/{}/bids", items_uri, item_ids[0]);
+    let (status, _) = api(
+        &app,
+        "POST",
+        &bids_uri,
+        None,
+        Some(json!({"amount": "30.00"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, errors) = api(
+        &app,
+        "POST",
+        &bids_uri,
+        Some(token),
+        Some(json!({"amount": "5.00"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(errors["errors"]["amount"][0], "bid must be at least $10.00");
+    let (status, placed) = api(
+        &app,
+        "POST",
+        &bids_uri,
+        Some(token),
+        Some(json!({"amount": "30.00", "max_bid_amount": "45.00"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(placed["outcome"], "leading");
+    assert_eq!(money(&placed["item"]["high_bid"]), Decimal::from(30));
+
+    let (_, bids) = api(&app, "GET", &bids_uri, Some(token), None).await;
+    assert_eq!(bids["pagination"]["total"], 1);
+    assert_eq!(bids["data"][0]["is_viewer"], true);
+    let (_, my_bids) = api(&app, "GET", "/api/v1/users/me/bids", Some(token), None).await;
+    assert_eq!(my_bids["data"][0]["status"], "winning");
+    assert_eq!(
+        money(&my_bids["data"][0]["max_bid_amount"]),
+        Decimal::from(45)
+    );
+
+    // another tenant has none of it
+    tenants::create("shore", "Shore Sanctuary", None, db)
+        .await
+        .unwrap();
+    let (status, _) = api(
+        &app,
+        "GET",
+        &format!("/t/shore/api/v1/auctions/{}", auction_id),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, auctions) = api(&app, "GET", "/t/shore/api/v1/auctions", None, None).await;
+    assert_eq!(auctions["pagination"]["total"], 0);
+    // and tokens are only good at the tenant which issued them
+    let (status, _) = api(&app, "GET", "/t/shore/api/v1/users/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let shore_token = common::login(&app, "/t/shore", &email).await;
+    let (status, _) = api(
+        &app,
+        "GET",
+        "/t/shore/api/v1/users/me",
+        Some(&shore_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = api(&app, "GET", "/api/v1/users/me", Some(&shore_token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_api_organizations_are_for_members() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let (_, email) = common::user_with_password(db).await;
+    let token = common::login(&app, "", &email).await;
+    let goats = common::organization("Goat Rescue", db).await;
+    let cats = common::organization("Cat Rescue", db).await;
+    organizations::add_member(goats, &email, OrgRole::Member, db)
+        .await
+        .unwrap();
+
+    let (_, page) = api(&app, "GET", "/api/v1/organizations", Some(&token), None).await;
+    assert_eq!(page["pagination"]["total"], 1);
+    assert_eq!(page["data"][0]["name"], "Goat Rescue");
+    let (status, detail) = api(
+        &app,
+        "GET",
+        &format!("/api/v1/organizations/{}", goats),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(detail["role"], "member");
+    assert_eq!(detail["profile"]["name"], "Goat Rescue");
+    assert_eq!(money(&detail["proceeds"]["owed"]), Decimal::ZERO);
+    let (status, items) = api(
+        &app,
+        "GET",
+        &format!("/api/v1/organizations/{}/items", goats),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(items["pagination"]["total"], 0);
+    let (status, _) = api(
+        &app,
+        "GET",
+        &format!("/api/v1/organizations/{}", cats),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    test_db.cleanup().await;
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use axum::http::StatusCode;
+
+use hooksaurus_auctions::db::articles;
+use hooksaurus_auctions::db::tables::article::ArticleFromForm;
+use hooksaurus_auctions::db::tenants;
+use hooksaurus_auctions::Error;
+
+fn article(title: &str, body: &str, tags: &str) -> ArticleFromForm {
+    ArticleFromForm {
+        title: title.to_string(),
+        slug: None,
+        description: format!("All about {}", title.to_lowercase()),
+        body: body.to_string(),
+        tags: tags.to_string(),
+        auction_id: None,
+        featured_image_filepath: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_article_slugs() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let (user_id, _) = common::user(db).await;
+    let main = tenants::get_default(db).await.unwrap();
+    let shore = tenants::create("shore", "Shore Sanctuary", None, db)
+        .await
+        .unwrap();
+
+    let form = article("Open House!", "Come meet the goats.", "events");
+    let first = articles::create(&form, user_id, main.tenant_id, db)
+        .await
+        .unwrap();
+    assert_eq!(first.slug, "open-house");
+    assert_eq!(first.published_at, None);
+    let second = articles::create(&form, user_id, main.tenant_id, db)
+        .await
+        .unwrap();
+    assert_eq!(second.slug, "open-house-2");
+    // slugs only have to be unique within a tenant
+    let elsewhere = articles::create(&form, user_id, shore.tenant_id, db)
+        .await
+        .unwrap();
+    assert_eq!(elsewhere.slug, "open-house");
+
+    // a slug which is asked for has to be free
+    let mut taken = article("Another Open House", "Again!", "");
+    taken.slug = Some("Open House".to_string());
+    assert!(matches!(
+        articles::create(&taken, user_id, main.tenant_id, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    assert!(matches!(
+        articles::update(second.article_id, &taken, main.tenant_id, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    // and editing keeps the slug unless told otherwise
+    let edited = articles::update(
+        first.article_id,
+        &article("Open House, Rescheduled", "Next week now.", "events"),
+        main.tenant_id,
+        db,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(edited.slug, "open-house");
+    assert_eq!(edited.title, "Open House, Rescheduled");
+    assert!(matches!(
+        articles::update(first.article_id, &form, shore.tenant_id, db).await,
+        Ok(None)
+    ));
+
+    assert!(matches!(
+        articles::create(&article(" ", "", ""), user_id, main.tenant_id, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_only_published_articles_are_public() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let (user_id, _) = common::user(db).await;
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+    let body = "Meet **Clover**.\n\n<script>alert('hi')</script>";
+    let clover = articles::create(
+        &article("Clover the Goat", body, "goats, new arrivals"),
+        user_id,
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+    let hay = articles::create(
+        &article("Hay Drive", "We need hay.", "appeals"),
+        user_id,
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+
+    let page = common::get(&app, "localhost", "/articles/clover-the-goat").await;
+    assert_eq!(page.status, StatusCode::NOT_FOUND);
+    assert!(!common::get(&app, "localhost", "/articles")
+        .await
+        .body
+        .contains("Clover"));
+    assert!(!common::get(&app, "localhost", "/search?q=clover")
+        .await
+        .body
+        .contains("Clover"));
+
+    for article in [&clover, &hay] {
+        articles::publish(article.article_id, true, tenant_id, db)
+            .await
+            .unwrap();
+    }
+    let page = common::get(&app, "localhost", "/articles/clover-the-goat").await;
+    assert_eq!(page.status, StatusCode::OK);
+    assert!(page.body.contains("Meet <strong>Clover</strong>."));
+    assert!(!page.body.contains("alert('hi')"));
+    let listing = common::get(&app, "localhost", "/articles").await.body;
+    assert!(listing.contains("Clover the Goat"));
+    assert!(listing.contains("Hay Drive"));
+    let listing = common::get(&app, "localhost", "/articles?tag=new+arrivals")
+        .await
+        .body;
+    assert!(listing.contains("Clover the Goat"));
+    assert!(!listing.contains("Hay Drive"));
+    let results = common::get(&app, "localhost", "/search?q=clover")
+        .await
+        .body;
+    assert!(results.contains(r#"href="/articles/clover-the-goat""#));
+
+    // back to a draft
+    articles::publish(clover.article_id, false, tenant_id, db)
+        .await
+        .unwrap();
+    let page = common::get(&app, "localhost", "/articles/clover-the-goat").await;
+    assert_eq!(page.status, StatusCode::NOT_FOUND);
+    let (_, email) = common::admin(tenant_id, db).await;
+    let token = common::login(&app, "", &email).await;
+    let admin = common::get_as(&app, "localhost", "/admin/articles", &token)
+        .await
+        .body;
+    assert!(admin.contains("Clover the Goat"));
+    assert!(admin.contains("Draft"));
+
+    test_db.cleanup().await;
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use hooksaurus_auctions::db::bidding;
+
+#[tokio::test]
+async fn test_recompute_keeps_a_winner_chosen_by_hand() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+    let (user_id, _) = common::user(db).await;
+    let higher_bid_id = sqlx::query_scalar!(
+        r#"
+            insert into auction_item_bid (auction_item_id, user_id, amount, etag)
+            values ($1, $2, 200, uuid_generate_v1mc())
+            returning auction_item_bid_id
+        "#,
+        won.auction_item_id,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    bidding::recompute_winners(Some(won.auction_id), db)
+        .await
+        .unwrap();
+    assert_eq!(winner(db, won.auction_item_id).await, higher_bid_id);
+
+    bidding::choose_winner(won.auction_item_bid_id, db)
+        .await
+        .unwrap();
+    bidding::recompute_winners(Some(won.auction_id), db)
+        .await
+        .unwrap();
+    assert_eq!(
+        winner(db, won.auction_item_id).await,
+        won.auction_item_bid_id
+    );
+
+    test_db.cleanup().await;
+}
+
+async fn winner(db: &sqlx::PgPool, auction_item_id: uuid::Uuid) -> uuid::Uuid {
+    sqlx::query_scalar!(
+        "select auction_item_bid_id from auction_item_bid where auction_item_id = $1 and is_winning_bid",
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
@@ -0,0 +1,269 @@
+//! Helpers shared by the integration tests.
+//!
+//! Each test gets a database of its own, made next to the one `DATABASE_URL` points at and
+//! migrated from scratch, so tests can run side by side without seeing each other's rows.
+// every test file builds its own copy of this module, and none of them uses all of it
+#![allow(dead_code)]
+use std::str::FromStr;
+
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, HOST, LOCATION};
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::Router;
+use clap::Parser;
+use sqlx::postgres::{PgConnectOptions, PgPool};
+use sqlx::{ConnectOptions, Connection};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use hooksaurus_auctions::auth;
+use hooksaurus_auctions::config::Config;
+use hooksaurus_auctions::db::{users, MIGRATOR};
+use hooksaurus_auctions::endpoints::{self, Tenants};
+
+pub struct TestDb {
+    pub db: PgPool,
+    name: String,
+    options: PgConnectOptions,
+}
+
+impl TestDb {
+    pub async fn new() -> TestDb {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set to run tests");
+        let options = PgConnectOptions::from_str(&url).expect("DATABASE_URL isn't a database url");
+        let name = format!("hooksaurus_test_{:016x}", rand::random::<u64>());
+        let mut conn = options
+            .connect()
+            .await
+            .expect("can't connect to DATABASE_URL");
+        // the case-insensitive collation needs UTF-8, whatever the server's default is
+        sqlx::query(&format!(
+            r#"create database "{}" encoding 'UTF8' template template0"#,
+            name
+        ))
+        .execute(&mut conn)
+        .await
+        .expect("can't create a test database");
+        conn.close().await.ok();
+
+        let db = PgPool::connect_with(options.clone().database(&name))
+            .await
+            .expect("can't connect to the test database");
+        MIGRATOR.run(&db).await.expect("migrations failed");
+        TestDb { db, name, options }
+    }
+
+    /// Drop the test database. A test which fails before getting here leaves its database
+    /// behind, to be looked at.
+    pub async fn cleanup(self) {
+        self.db.close().await;
+        let mut conn = self.options.connect().await.unwrap();
+        sqlx::query(&format!(r#"drop database if exists "{}""#, self.name))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+    }
+}
+
+/// Ids for an auction with one item, and a bidder's winning bid on it.
+pub struct WonItem {
+    pub user_id: Uuid,
+    pub address_id: Uuid,
+    pub auction_id: Uuid,
+    pub auction_item_id: Uuid,
+    pub auction_item_bid_id: Uuid,
+}
+
+pub async fn won_item(db: &PgPool) -> WonItem {
+    let user_id = sqlx::query_scalar!(
+        r#"
+            insert into "user" (email, password_hash)
+            values ($1, 'not a real hash')
+            returning user_id
+        "#,
+        format!("bidder-{:016x}@example.com", rand::random::<u64>())
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let address_id = sqlx::query_scalar!(
+        r#"
+            insert into address (street_address1, city, state_province_county, postal_code)
+            values ('1 Barnyard Lane', 'Petaluma', 'CA', '94952')
+            returning address_id
+        "#
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let item = sqlx::query!(
+        r#"
+            with auction as (
+                insert into auction (title, start_date, end_date, etag)
+                values ('Spring Fling', now() - interval '7 days', now() - interval '1 day', uuid_generate_v1mc())
+                returning auction_id
+            )
+            insert into auction_item (
+                auction_id, title, featured_image_filepath, image_dir, tag_list,
+                active_end_date, etag
+            )
+            select auction_id, 'Goat yoga for two', '', '', '{}', now() - interval '1 day',
+                uuid_generate_v1mc()
+            from auction
+            returning auction_id "auction_id!", auction_item_id
+        "#
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let auction_item_bid_id = sqlx::query_scalar!(
+        r#"
+            insert into auction_item_bid (auction_item_id, user_id, amount, is_winning_bid, etag)
+            values ($1, $2, 150, true, uuid_generate_v1mc())
+            returning auction_item_bid_id
+        "#,
+        item.auction_item_id,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    WonItem {
+        user_id,
+        address_id,
+        auction_id: item.auction_id,
+        auction_item_id: item.auction_item_id,
+        auction_item_bid_id,
+    }
+}
+
+/// A user who hasn't done anything yet.
+pub async fn user(db: &PgPool) -> (Uuid, String) {
+    let email = format!("user-{:016x}@example.com", rand::random::<u64>());
+    let user_id = sqlx::query_scalar!(
+        r#"
+            insert into "user" (email, password_hash)
+            values ($1, 'not a real hash')
+            returning user_id
+        "#,
+        email
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    (user_id, email)
+}
+
+/// A user who logs in with "a-good-password".
+pub async fn user_with_password(db: &PgPool) -> (Uuid, String) {
+    let (user_id, email) = user(db).await;
+    let password_hash = auth::hash_password("a-good-password".to_string())
+        .await
+        .unwrap();
+    sqlx::query!(
+        r#"update "user" set password_hash = $1 where user_id = $2"#,
+        password_hash,
+        user_id
+    )
+    .execute(db)
+    .await
+    .unwrap();
+    (user_id, email)
+}
+
+/// A new admin of the tenant, who logs in with "a-good-password".
+pub async fn admin(tenant_id: Uuid, db: &PgPool) -> (Uuid, String) {
+    let email = format!("admin-{:016x}@example.com", rand::random::<u64>());
+    let password_hash = auth::hash_password("a-good-password".to_string())
+        .await
+        .unwrap();
+    let user_id = users::upsert_admin(&email, &password_hash, None, None, tenant_id, db)
+        .await
+        .unwrap();
+    (user_id, email)
+}
+
+/// Log in through the JSON API of the tenant at `prefix`, e.g. `/t/shore` or `""`, for a token.
+pub async fn login(app: &Tenants<Router>, prefix: &str, email: &str) -> String {
+    let body = serde_json::json!({"email": email, "password": "a-good-password"});
+    let request = Request::post(format!("{}/api/v1/users/login", prefix))
+        .header(HOST, "localhost")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = send(app, request).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let session: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    session["token"].as_str().unwrap().to_string()
+}
+
+pub async fn organization(name: &str, db: &PgPool) -> Uuid {
+    sqlx::query_scalar!(
+        r#"
+            insert into organization (name, email, website)
+            values ($1, 'hello@example.org', 'https://example.org')
+            returning organization_id
+        "#,
+        name
+    )
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+/// The app as `serve` runs it, for sending requests to with `send` or `get`.
+pub fn app(db: &PgPool) -> Tenants<Router> {
+    let config = Config::parse_from([
+        "hooksaurus-auctions",
+        "--version",
+        "test",
+        "--database-url",
+        "unused",
+        "--hmac-key",
+        "test",
+        "--payment-webhook-secret",
+        "test",
+    ]);
+    endpoints::app(config, db.clone())
+}
+
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub location: Option<String>,
+    pub body: String,
+}
+
+pub async fn send(app: &Tenants<Router>, request: Request<Body>) -> Response {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .map(|location| location.to_str().unwrap().to_string());
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    Response {
+        status,
+        headers,
+        location,
+        body: String::from_utf8(body.to_vec()).unwrap(),
+    }
+}
+
+pub async fn get(app: &Tenants<Router>, host: &str, uri: &str) -> Response {
+    let request = Request::get(uri)
+        .header(HOST, host)
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+/// `get` with `Authorization: Bearer <token>`.
+pub async fn get_as(app: &Tenants<Router>, host: &str, uri: &str, token: &str) -> Response {
+    let request = Request::get(uri)
+        .header(HOST, host)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
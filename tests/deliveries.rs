@@ -0,0 +1,233 @@
+mod common;
+
+use hooksaurus_auctions::db::deliveries::{self, DeliveryChoice, DeliveryStatus};
+use hooksaurus_auctions::db::tables::address::AddressId;
+use hooksaurus_auctions::db::tables::auction::{AuctionItemBidId, AuctionItemDeliveryFromForm};
+use hooksaurus_auctions::shipping::DistanceRates;
+use hooksaurus_auctions::Error;
+use sqlx::types::Decimal;
+use uuid::Uuid;
+
+fn delivery_form(
+    auction_item_bid_id: Uuid,
+    address_id: Option<Uuid>,
+) -> AuctionItemDeliveryFromForm {
+    AuctionItemDeliveryFromForm {
+        auction_item_bid_id: AuctionItemBidId(auction_item_bid_id),
+        shipping_address: address_id.map(AddressId),
+        shipping_fee: None,
+        sms_updates_number: None,
+        email_contact: Some("bidder@example.com".to_string()),
+        signature_name: None,
+        carrier: None,
+        tracking_number: None,
+    }
+}
+
+#[tokio::test]
+async fn test_delivery_crud() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+
+    let inserted = deliveries::insert_delivery(&delivery_form(won.auction_item_bid_id, None), db)
+        .await
+        .unwrap();
+    // the delivery belongs to the bidder, who hasn't said where to send it yet
+    assert_eq!(inserted.user_id, won.user_id);
+    assert_eq!(inserted.status, DeliveryStatus::PendingAddress.as_str());
+    assert_eq!(inserted.shipping_fee, Some(Decimal::ZERO));
+
+    let delivery_id = inserted.delivery_id.0;
+    let found = deliveries::get_delivery(delivery_id, db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.auction_item_bid_id.0, won.auction_item_bid_id);
+    assert_eq!(found.email_contact.as_deref(), Some("bidder@example.com"));
+
+    let mut form = delivery_form(won.auction_item_bid_id, Some(won.address_id));
+    form.shipping_fee = Some(Decimal::new(1250, 2));
+    let updated = deliveries::update_delivery(delivery_id, &form, db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.shipping_address.map(|a| a.0), Some(won.address_id));
+    assert_eq!(updated.shipping_fee, Some(Decimal::new(1250, 2)));
+    // corrections leave the status to the fulfillment queue
+    assert_eq!(updated.status, DeliveryStatus::PendingAddress.as_str());
+
+    assert!(deliveries::delete_delivery(delivery_id, db).await.unwrap());
+    assert!(deliveries::get_delivery(delivery_id, db)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(!deliveries::delete_delivery(delivery_id, db).await.unwrap());
+    assert!(deliveries::update_delivery(delivery_id, &form, db)
+        .await
+        .unwrap()
+        .is_none());
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_one_delivery_per_winning_bid() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+
+    let form = delivery_form(won.auction_item_bid_id, Some(won.address_id));
+    let inserted = deliveries::insert_delivery(&form, db).await.unwrap();
+    assert_eq!(inserted.status, DeliveryStatus::ReadyToShip.as_str());
+    assert!(inserted.ready_at.is_some());
+    assert!(matches!(
+        deliveries::insert_delivery(&form, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    assert!(matches!(
+        deliveries::insert_delivery(&delivery_form(Uuid::from_u128(1), None), db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    assert!(matches!(
+        deliveries::insert_delivery(
+            &delivery_form(won.auction_item_bid_id, Some(Uuid::from_u128(1))),
+            db
+        )
+        .await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_delivery_keeps_its_address_once_ready() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+
+    let inserted = deliveries::insert_delivery(
+        &delivery_form(won.auction_item_bid_id, Some(won.address_id)),
+        db,
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        deliveries::update_delivery(
+            inserted.delivery_id.0,
+            &delivery_form(won.auction_item_bid_id, None),
+            db
+        )
+        .await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_deliveries_go_with_the_bidder() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+
+    let inserted = deliveries::insert_delivery(&delivery_form(won.auction_item_bid_id, None), db)
+        .await
+        .unwrap();
+    sqlx::query!(r#"delete from "user" where user_id = $1"#, won.user_id)
+        .execute(db)
+        .await
+        .unwrap();
+    assert!(deliveries::get_delivery(inserted.delivery_id.0, db)
+        .await
+        .unwrap()
+        .is_none());
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_choosing_a_delivery_fills_in_the_fee() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+    let ship = DeliveryChoice::Ship(AddressId(won.address_id));
+
+    // nobody knows where the item is coming from, so it's charged as going a long way
+    let quoted = deliveries::quote(won.user_id, won.auction_item_id, &ship, &DistanceRates, db)
+        .await
+        .unwrap();
+    assert_eq!(quoted, Decimal::new(18_00, 2));
+    let fee =
+        deliveries::choose_delivery(won.user_id, won.auction_item_id, &ship, &DistanceRates, db)
+            .await
+            .unwrap();
+    assert_eq!(fee, quoted);
+    let delivery = sqlx::query!(
+        r#"
+            select delivery_id, status, shipping_fee, local_pickup
+            from auction_item_delivery
+            where auction_item_bid_id = $1
+        "#,
+        won.auction_item_bid_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::ReadyToShip.as_str());
+    assert_eq!(delivery.shipping_fee, Some(fee));
+    assert!(!delivery.local_pickup);
+
+    // changing their mind is free, and the draft invoice is rebuilt each time
+    let pickup_window_id = sqlx::query_scalar!(
+        r#"
+            insert into pickup_window (auction_id, address_id, starts_at, ends_at, capacity)
+            values ($1, $2, now() + interval '1 day', now() + interval '25 hours', 10)
+            returning pickup_window_id
+        "#,
+        won.auction_id,
+        won.address_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let fee = deliveries::choose_delivery(
+        won.user_id,
+        won.auction_item_id,
+        &DeliveryChoice::Pickup { pickup_window_id },
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+    assert_eq!(fee, Decimal::ZERO);
+    let found = deliveries::get_delivery(delivery.delivery_id, db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(found.local_pickup && found.shipping_address.is_none());
+    let invoice_jobs = sqlx::query_scalar!(
+        r#"select count(*) "count!" from job where kind = 'generate_invoices'"#
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(invoice_jobs, 2);
+
+    // only the winner can choose
+    let other = common::won_item(db).await;
+    assert!(matches!(
+        deliveries::choose_delivery(
+            other.user_id,
+            won.auction_item_id,
+            &ship,
+            &DistanceRates,
+            db
+        )
+        .await,
+        Err(Error::NotFound)
+    ));
+
+    test_db.cleanup().await;
+}
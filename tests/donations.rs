@@ -0,0 +1,296 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, HOST};
+use axum::http::{Request, StatusCode};
+use hooksaurus_auctions::db::donations::{self, ApprovalFromForm, DonationFromForm};
+use hooksaurus_auctions::db::tables::address::AddressFromForm;
+use hooksaurus_auctions::db::tenants;
+use hooksaurus_auctions::Error;
+use sqlx::types::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+fn donation(organization_name: Option<&str>) -> DonationFromForm {
+    DonationFromForm {
+        donor_name: "Robin Ortiz".to_string(),
+        donor_email: "robin@example.com".to_string(),
+        donor_phone: None,
+        organization_name: organization_name.map(String::from),
+        organization_website: None,
+        title: "Handmade goat milk soap".to_string(),
+        description: "A dozen bars, lavender and oat.".to_string(),
+        expected_retail_value: Some(Decimal::new(60, 0)),
+        tags: "Soap, handmade, ".to_string(),
+    }
+}
+
+async fn auction(ends_in_days: i32, db: &PgPool) -> Uuid {
+    sqlx::query_scalar!(
+        r#"
+            insert into auction (title, start_date, end_date, etag)
+            values ('Fall Fling', now() - interval '1 day', now() + make_interval(days => $1), uuid_generate_v1mc())
+            returning auction_id
+        "#,
+        ends_in_days
+    )
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+async fn queued_emails(auction_item_id: Uuid, db: &PgPool) -> Vec<String> {
+    sqlx::query_scalar!(
+        r#"
+            select payload->>'status' "status!"
+            from job
+            where kind = 'donation_status_changed'
+            and payload->>'auction_item_id' = $1
+            order by created_at
+        "#,
+        auction_item_id.to_string()
+    )
+    .fetch_all(db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_donation_approved_into_auction() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+    let auction_id = auction(7, db).await;
+    let ended_id = auction(-1, db).await;
+    let address = AddressFromForm {
+        street_address1: "12 Mill Road".to_string(),
+        street_address2: None,
+        street_address3: None,
+        city: "Sebastopol".to_string(),
+        state_province_county: "CA".to_string(),
+        postal_code: Some("95472".to_string()),
+        country_code: None,
+        latitude: None,
+        longitude: None,
+    };
+
+    let auction_item_id = donations::submit(
+        &donation(Some("Oat & Lavender")),
+        Some(&address),
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+    let draft = sqlx::query!(
+        r#"
+            select ai.auction_id, ai.approval_status, ai.tag_list, o.name, a.city
+            from auction_item ai
+            inner join organization o
+            on o.organization_id = ai.donated_by_organization_id
+            inner join address a
+            on a.address_id = o.primary_address_id
+            where ai.auction_item_id = $1
+        "#,
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert!(draft.auction_id.is_none());
+    assert_eq!(draft.approval_status, "pending");
+    assert_eq!(draft.tag_list, vec!["soap", "handmade"]);
+    assert_eq!(
+        (draft.name.as_str(), draft.city.as_str()),
+        ("Oat & Lavender", "Sebastopol")
+    );
+
+    // a second donation from the same organization doesn't add it again
+    donations::submit(&donation(Some("oat & lavender")), None, tenant_id, db)
+        .await
+        .unwrap();
+    let organizations = sqlx::query_scalar!(r#"select count(*) "count!" from organization"#)
+        .fetch_one(db)
+        .await
+        .unwrap();
+    assert_eq!(organizations, 1);
+
+    let approval = |auction_id, minimum_bid_amount| ApprovalFromForm {
+        auction_id,
+        minimum_bid_amount,
+    };
+    assert!(matches!(
+        donations::approve(
+            auction_item_id,
+            &approval(ended_id, Decimal::new(20, 0)),
+            tenant_id,
+            db
+        )
+        .await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    assert!(matches!(
+        donations::approve(
+            auction_item_id,
+            &approval(auction_id, Decimal::new(-1, 0)),
+            tenant_id,
+            db
+        )
+        .await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    donations::approve(
+        auction_item_id,
+        &approval(auction_id, Decimal::new(20, 0)),
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+    let approved = sqlx::query!(
+        r#"
+            select ai.auction_id, ai.approval_status, ai.minimum_bid_amount, d.decided_at
+            from auction_item ai
+            inner join item_donation d
+            on d.auction_item_id = ai.auction_item_id
+            where ai.auction_item_id = $1
+        "#,
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(approved.auction_id, Some(auction_id));
+    assert_eq!(approved.approval_status, "approved");
+    assert_eq!(approved.minimum_bid_amount, Decimal::new(20, 0));
+    assert!(approved.decided_at.is_some());
+    assert_eq!(
+        queued_emails(auction_item_id, db).await,
+        vec!["pending", "approved"]
+    );
+
+    // it's been decided, so it can't be decided again
+    assert!(matches!(
+        donations::decline(auction_item_id, "We have plenty of soap", tenant_id, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    assert!(matches!(
+        donations::decline(Uuid::nil(), "We have plenty of soap", tenant_id, db).await,
+        Err(Error::NotFound)
+    ));
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_donation_declined() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+
+    let incomplete = DonationFromForm {
+        donor_email: "robin".to_string(),
+        title: " ".to_string(),
+        ..donation(None)
+    };
+    match donations::submit(&incomplete, None, tenant_id, db).await {
+        Err(Error::UnprocessableEntity { errors }) => assert_eq!(errors.len(), 2),
+        other => panic!("expected a 422, got {:?}", other),
+    }
+
+    let auction_item_id = donations::submit(&donation(None), None, tenant_id, db)
+        .await
+        .unwrap();
+    assert!(matches!(
+        donations::decline(auction_item_id, "", tenant_id, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    donations::decline(
+        auction_item_id,
+        "We can't auction food safely",
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+    let declined = sqlx::query!(
+        r#"
+            select ai.auction_id, ai.approval_status, ai.donated_by_organization_id, d.decline_reason
+            from auction_item ai
+            inner join item_donation d
+            on d.auction_item_id = ai.auction_item_id
+            where ai.auction_item_id = $1
+        "#,
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert!(declined.auction_id.is_none() && declined.donated_by_organization_id.is_none());
+    assert_eq!(declined.approval_status, "declined");
+    assert_eq!(
+        declined.decline_reason.as_deref(),
+        Some("We can't auction food safely")
+    );
+    assert_eq!(
+        queued_emails(auction_item_id, db).await,
+        vec!["pending", "declined"]
+    );
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_only_admins_review_donations() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+    let auction_id = auction(7, db).await;
+    let auction_item_id = donations::submit(&donation(None), None, tenant_id, db)
+        .await
+        .unwrap();
+    let (_, member) = common::user_with_password(db).await;
+    let member = common::login(&app, "", &member).await;
+    let (_, admin) = common::admin(tenant_id, db).await;
+    let admin = common::login(&app, "", &admin).await;
+
+    let review = |action: &str, token: Option<&str>| {
+        let mut request = Request::post(format!("/admin/donations/{}/{}", auction_item_id, action))
+            .header(HOST, "localhost:8000")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded");
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let form = match action {
+            "approve" => format!("auction_id={}&minimum_bid_amount=10", auction_id),
+            _ => "decline_reason=Not for us".to_string(),
+        };
+        request.body(Body::from(form)).unwrap()
+    };
+    for action in ["approve", "decline"] {
+        let response = common::send(&app, review(action, None)).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        let response = common::send(&app, review(action, Some(&member))).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+    }
+    let status = sqlx::query_scalar!(
+        "select approval_status from auction_item where auction_item_id = $1",
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(status, "pending");
+    assert_eq!(queued_emails(auction_item_id, db).await, vec!["pending"]);
+
+    let response = common::send(&app, review("approve", Some(&admin))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let status = sqlx::query_scalar!(
+        "select approval_status from auction_item where auction_item_id = $1",
+        auction_item_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(status, "approved");
+    test_db.cleanup().await;
+}
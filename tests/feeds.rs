@@ -0,0 +1,215 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::header::{
+    CONTENT_TYPE, ETAG, HOST, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+
+use hooksaurus_auctions::db::articles;
+use hooksaurus_auctions::db::tables::article::ArticleFromForm;
+use hooksaurus_auctions::db::tenants;
+use hooksaurus_auctions::endpoints::Tenants;
+
+async fn get_if(app: &Tenants<Router>, uri: &str, header: &str, value: &str) -> common::Response {
+    let request = Request::get(uri)
+        .header(HOST, "localhost")
+        .header(header, value)
+        .body(Body::empty())
+        .unwrap();
+    common::send(app, request).await
+}
+
+fn article(title: &str, tags: &str) -> ArticleFromForm {
+    ArticleFromForm {
+        title: title.to_string(),
+        slug: None,
+        description: "What's new & exciting".to_string(),
+        body: "Meet **Clover**.".to_string(),
+        tags: tags.to_string(),
+        auction_id: None,
+        featured_image_filepath: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_article_feeds() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let (user_id, _) = common::user(db).await;
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+    let clover = articles::create(&article("Clover Arrives", "goats"), user_id, tenant_id, db)
+        .await
+        .unwrap();
+    articles::publish(clover.article_id, true, tenant_id, db)
+        .await
+        .unwrap();
+    let hay = articles::create(&article("Hay Drive", "appeals"), user_id, tenant_id, db)
+        .await
+        .unwrap();
+    articles::publish(hay.article_id, true, tenant_id, db)
+        .await
+        .unwrap();
+    articles::create(&article("Secret Plans", "goats"), user_id, tenant_id, db)
+        .await
+        .unwrap();
+
+    let atom = common::get(&app, "localhost", "/feeds/articles.atom").await;
+    assert_eq!(atom.status, StatusCode::OK);
+    assert_eq!(
+        atom.headers[CONTENT_TYPE],
+        "application/atom+xml; charset=utf-8"
+    );
+    assert!(atom.body.contains("<title>Clover Arrives</title>"));
+    assert!(atom.body.contains("<title>Hay Drive</title>"));
+    assert!(atom.body.contains("&lt;strong&gt;Clover&lt;/strong&gt;"));
+    assert!(atom
+        .body
+        .contains("<summary>What&apos;s new &amp; exciting</summary>"));
+    assert!(atom
+        .body
+        .contains("href=\"http://localhost:8000/articles/clover-arrives\""));
+    assert!(!atom.body.contains("Secret Plans"));
+    let rss = common::get(&app, "localhost", "/feeds/articles.rss").await;
+    assert!(rss.body.contains("<title>Clover Arrives</title>"));
+    assert_ne!(rss.headers[ETAG], atom.headers[ETAG]);
+
+    let tagged = common::get(&app, "localhost", "/feeds/tags/goats/articles.rss").await;
+    assert!(tagged.body.contains("Clover Arrives"));
+    assert!(!tagged.body.contains("Hay Drive"));
+    assert!(!tagged.body.contains("Secret Plans"));
+    assert_eq!(
+        common::get(&app, "localhost", "/feeds/articles.json")
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+
+    // nothing has changed since the reader last looked
+    let etag = atom.headers[ETAG].to_str().unwrap();
+    let last_modified = atom.headers[LAST_MODIFIED].to_str().unwrap();
+    let unchanged = get_if(&app, "/feeds/articles.atom", IF_NONE_MATCH.as_str(), etag).await;
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+    assert!(unchanged.body.is_empty());
+    let unchanged = get_if(
+        &app,
+        "/feeds/articles.atom",
+        IF_MODIFIED_SINCE.as_str(),
+        last_modified,
+    )
+    .await;
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+
+    // until an article is edited, which Postgres notices within the second
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    articles::update(
+        hay.article_id,
+        &article("Hay Drive: We Did It!", "appeals"),
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+    let changed = get_if(&app, "/feeds/articles.atom", IF_NONE_MATCH.as_str(), etag).await;
+    assert_eq!(changed.status, StatusCode::OK);
+    assert!(changed.body.contains("We Did It!"));
+    let changed = get_if(
+        &app,
+        "/feeds/articles.atom",
+        IF_MODIFIED_SINCE.as_str(),
+        last_modified,
+    )
+    .await;
+    assert_eq!(changed.status, StatusCode::OK);
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_item_and_auction_feeds() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let (user_id, _) = common::user(db).await;
+    let shore = tenants::create("shore", "Shore Sanctuary", None, db)
+        .await
+        .unwrap();
+    let auction_id = sqlx::query_scalar!(
+        r#"
+            insert into auction (title, start_date, end_date, tenant_id, etag)
+            values ('Tidepool Benefit', now() - interval '1 day', now() + interval '7 days', $1,
+                uuid_generate_v1mc())
+            returning auction_id
+        "#,
+        shore.tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    sqlx::query!(
+        r#"
+            insert into auction_item (
+                auction_id, title, featured_image_filepath, image_dir, tag_list,
+                active_start_date, active_end_date, tenant_id, etag
+            )
+            values
+                ($1, 'Driftwood sculpture', '', '', '{art}', now() - interval '1 hour',
+                    now() + interval '7 days', $2, uuid_generate_v1mc()),
+                ($1, 'Sea glass mosaic', '', '', '{art}', now() + interval '1 day',
+                    now() + interval '7 days', $2, uuid_generate_v1mc())
+        "#,
+        auction_id,
+        shore.tenant_id
+    )
+    .execute(db)
+    .await
+    .unwrap();
+    let mut about = article("Tidepool Benefit Opens", "events");
+    about.auction_id = Some(auction_id);
+    let about = articles::create(&about, user_id, shore.tenant_id, db)
+        .await
+        .unwrap();
+    articles::publish(about.article_id, true, shore.tenant_id, db)
+        .await
+        .unwrap();
+    let other = articles::create(
+        &article("Volunteer Day", "events"),
+        user_id,
+        shore.tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+    articles::publish(other.article_id, true, shore.tenant_id, db)
+        .await
+        .unwrap();
+
+    let items = common::get(&app, "localhost", "/t/shore/feeds/items.atom").await;
+    assert_eq!(items.status, StatusCode::OK);
+    assert!(items.body.contains("<title>Driftwood sculpture</title>"));
+    assert!(items.body.contains("In Tidepool Benefit."));
+    assert!(!items.body.contains("Sea glass"));
+    // links to another tenant's pages keep its prefix
+    assert!(items.body.contains(&format!(
+        "href=\"http://localhost:8000/t/shore/auctions/{}/items/",
+        auction_id
+    )));
+    // and the default tenant has none of it
+    let items = common::get(&app, "localhost", "/feeds/items.atom").await;
+    assert!(!items.body.contains("Driftwood"));
+    assert!(items.headers.get(LAST_MODIFIED).is_none());
+
+    let uri = format!("/t/shore/feeds/auctions/{}/articles.rss", auction_id);
+    let auction = common::get(&app, "localhost", &uri).await;
+    assert!(auction.body.contains("Tidepool Benefit Opens"));
+    assert!(!auction.body.contains("Volunteer Day"));
+    let uri = format!("/feeds/auctions/{}/articles.rss", auction_id);
+    assert_eq!(
+        common::get(&app, "localhost", &uri).await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    test_db.cleanup().await;
+}
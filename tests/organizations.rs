@@ -0,0 +1,245 @@
+mod common;
+
+use hooksaurus_auctions::db::organizations::{self, OrgRole, OrganizationProfile};
+use hooksaurus_auctions::db::tables::organization::OrgType;
+use hooksaurus_auctions::Error;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+fn profile(name: &str) -> OrganizationProfile {
+    OrganizationProfile {
+        name: name.to_string(),
+        description: Some("Home to 40 rescued goats.".to_string()),
+        email: "hello@goathaven.org".to_string(),
+        website: "https://goathaven.org".to_string(),
+        contact_name: None,
+        phone_number: None,
+        alt_phone_number: None,
+    }
+}
+
+#[tokio::test]
+async fn test_organization_membership() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let haven = common::organization("Goat Haven", db).await;
+    let bakery = common::organization("Corner Bakery", db).await;
+    let (manager_id, manager_email) = common::user(db).await;
+    let (member_id, member_email) = common::user(db).await;
+    let (baker_id, baker_email) = common::user(db).await;
+
+    organizations::add_member(haven, &manager_email, OrgRole::Manager, db)
+        .await
+        .unwrap();
+    organizations::add_member(haven, &member_email, OrgRole::Member, db)
+        .await
+        .unwrap();
+    organizations::add_member(bakery, &baker_email, OrgRole::Manager, db)
+        .await
+        .unwrap();
+    assert!(matches!(
+        organizations::add_member(haven, "nobody@example.com", OrgRole::Member, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    assert!(matches!(
+        organizations::add_member(Uuid::nil(), &member_email, OrgRole::Member, db).await,
+        Err(Error::NotFound)
+    ));
+
+    assert_eq!(
+        organizations::require_member(haven, manager_id, db)
+            .await
+            .unwrap(),
+        OrgRole::Manager
+    );
+    assert_eq!(
+        organizations::require_member(haven, member_id, db)
+            .await
+            .unwrap(),
+        OrgRole::Member
+    );
+    // another organization's people can't tell it apart from one which doesn't exist
+    assert!(matches!(
+        organizations::require_member(haven, baker_id, db).await,
+        Err(Error::NotFound)
+    ));
+    assert!(matches!(
+        organizations::require_member(Uuid::nil(), baker_id, db).await,
+        Err(Error::NotFound)
+    ));
+
+    // adding someone again changes their role
+    organizations::add_member(haven, &member_email, OrgRole::Manager, db)
+        .await
+        .unwrap();
+    assert_eq!(
+        organizations::require_member(haven, member_id, db)
+            .await
+            .unwrap(),
+        OrgRole::Manager
+    );
+    assert!(organizations::remove_member(haven, member_id, db)
+        .await
+        .unwrap());
+    assert!(!organizations::remove_member(haven, member_id, db)
+        .await
+        .unwrap());
+    assert!(matches!(
+        organizations::require_member(haven, member_id, db).await,
+        Err(Error::NotFound)
+    ));
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_only_managers_edit_profiles() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let haven = common::organization("Goat Haven", db).await;
+    let bakery = common::organization("Corner Bakery", db).await;
+    let (manager_id, manager_email) = common::user(db).await;
+    let (member_id, member_email) = common::user(db).await;
+    let (baker_id, baker_email) = common::user(db).await;
+    organizations::add_member(haven, &manager_email, OrgRole::Manager, db)
+        .await
+        .unwrap();
+    organizations::add_member(haven, &member_email, OrgRole::Member, db)
+        .await
+        .unwrap();
+    organizations::add_member(bakery, &baker_email, OrgRole::Manager, db)
+        .await
+        .unwrap();
+
+    let renamed = profile("Goat Haven Sanctuary");
+    assert!(matches!(
+        organizations::update_profile(haven, member_id, &renamed, db).await,
+        Err(Error::Forbidden)
+    ));
+    // a manager elsewhere is nobody here
+    assert!(matches!(
+        organizations::update_profile(haven, baker_id, &renamed, db).await,
+        Err(Error::NotFound)
+    ));
+    assert!(matches!(
+        organizations::update_profile(haven, manager_id, &profile(" "), db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    assert_eq!(
+        organizations::get_profile(haven, db).await.unwrap().name,
+        "Goat Haven"
+    );
+
+    organizations::update_profile(haven, manager_id, &renamed, db)
+        .await
+        .unwrap();
+    let saved = organizations::get_profile(haven, db).await.unwrap();
+    assert_eq!(saved.name, "Goat Haven Sanctuary");
+    assert_eq!(saved.website, "https://goathaven.org");
+    assert_eq!(
+        organizations::get_profile(bakery, db).await.unwrap().name,
+        "Corner Bakery"
+    );
+
+    test_db.cleanup().await;
+}
+
+async fn set_org_type(organization_id: Uuid, org_type: OrgType, db: &PgPool) -> sqlx::Result<()> {
+    sqlx::query!(
+        "update organization set org_type = $2 where organization_id = $1",
+        organization_id,
+        org_type as _
+    )
+    .execute(db)
+    .await
+    .map(|_| ())
+}
+
+/// The constraint named by a failed write, as `ResultExt::on_constraint` sees it.
+fn constraint<T>(result: sqlx::Result<T>) -> Option<String> {
+    match result {
+        Err(sqlx::Error::Database(e)) => e.constraint().map(String::from),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn test_org_type_beneficiaries() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    // organizations start out as businesses
+    let bakery = common::organization("Corner Bakery", db).await;
+    let haven = common::organization("Goat Haven", db).await;
+    set_org_type(haven, OrgType::FarmAnimalSanctuary, db)
+        .await
+        .unwrap();
+    let org_type = |organization_id: Uuid| {
+        sqlx::query_scalar!(
+            r#"select org_type "org_type: OrgType" from organization where organization_id = $1"#,
+            organization_id
+        )
+        .fetch_one(db)
+    };
+    assert_eq!(org_type(bakery).await.unwrap(), OrgType::Business);
+    assert_eq!(org_type(haven).await.unwrap(), OrgType::FarmAnimalSanctuary);
+    assert!(!OrgType::Business.can_benefit() && OrgType::NonProfit.can_benefit());
+    assert_eq!(
+        constraint(
+            sqlx::query!(
+                "update organization set org_type = 'Business' where organization_id = $1",
+                bakery
+            )
+            .execute(db)
+            .await
+        )
+        .as_deref(),
+        Some("organization_org_type")
+    );
+
+    let auction = |benefits_organization_id: Uuid| {
+        sqlx::query_scalar!(
+            r#"
+                insert into auction (title, start_date, end_date, benefits_organization_id, etag)
+                values ('Fall Fling', now(), now() + interval '7 days', $1, uuid_generate_v1mc())
+                returning auction_id
+            "#,
+            benefits_organization_id
+        )
+        .fetch_one(db)
+    };
+    assert_eq!(
+        constraint(auction(bakery).await).as_deref(),
+        Some("auction_beneficiary_type")
+    );
+    let auction_id = auction(haven).await.unwrap();
+    // a business can donate items, but an item can't raise money for it either
+    let item = |benefits_organization_id: Uuid| {
+        sqlx::query_scalar!(
+            r#"
+                insert into auction_item (
+                    auction_id, title, featured_image_filepath, image_dir, tag_list,
+                    donated_by_organization_id, benefits_organization_id, active_end_date, etag
+                )
+                values ($1, 'Sourdough for a year', '', '', '{}', $2, $3, now(), uuid_generate_v1mc())
+                returning auction_item_id
+            "#,
+            auction_id,
+            bakery,
+            benefits_organization_id
+        )
+        .fetch_one(db)
+    };
+    assert_eq!(
+        constraint(item(bakery).await).as_deref(),
+        Some("auction_item_beneficiary_type")
+    );
+    item(haven).await.unwrap();
+    // and a beneficiary can't turn into a business
+    assert_eq!(
+        constraint(set_org_type(haven, OrgType::Business, db).await).as_deref(),
+        Some("organization_beneficiary_type")
+    );
+    set_org_type(haven, OrgType::NonProfit, db).await.unwrap();
+
+    test_db.cleanup().await;
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::header::{CONTENT_TYPE, HOST};
+use axum::http::{Request, StatusCode};
+
+use hooksaurus_auctions::db::deliveries::{self, DeliveryChoice};
+use hooksaurus_auctions::db::invoices;
+use hooksaurus_auctions::db::tables::address::AddressId;
+use hooksaurus_auctions::payments::{CheckoutOutcome, MockGateway, Payments};
+use hooksaurus_auctions::shipping::DistanceRates;
+use hooksaurus_auctions::Error;
+
+#[tokio::test]
+async fn test_the_mock_checkout_is_off_unless_asked_for() {
+    let test_db = common::TestDb::new().await;
+    let app = common::app(&test_db.db);
+
+    let page = common::get(&app, "localhost", "/payments/mock/anything").await;
+    assert_eq!(page.status, StatusCode::NOT_FOUND);
+    let request = Request::post("/payments/mock/anything")
+        .header(HOST, "localhost")
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from("outcome=succeed"))
+        .unwrap();
+    assert_eq!(
+        common::send(&app, request).await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_an_invoice_is_only_refunded_once() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let mock = Arc::new(MockGateway::new("test", "http://localhost"));
+    let payments = Payments::with_mock(mock.clone(), "http://localhost");
+
+    // a paid invoice
+    let won = common::won_item(db).await;
+    deliveries::choose_delivery(
+        won.user_id,
+        won.auction_item_id,
+        &DeliveryChoice::Ship(AddressId(won.address_id)),
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+    invoices::generate_invoices(won.auction_id, db)
+        .await
+        .unwrap();
+    let invoice_id = sqlx::query_scalar!(
+        "select invoice_id from invoice where auction_id = $1",
+        won.auction_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    invoices::issue_invoice(invoice_id, db).await.unwrap();
+    payments
+        .checkout(invoice_id, won.user_id, db)
+        .await
+        .unwrap();
+    let provider_ref = sqlx::query_scalar!(
+        "select provider_ref from payment where invoice_id = $1",
+        invoice_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let callback = mock
+        .complete(&provider_ref, CheckoutOutcome::Succeed)
+        .unwrap()
+        .unwrap();
+    payments
+        .handle_callback(&callback.headers(), &callback.body, db)
+        .await
+        .unwrap();
+
+    // refunded twice at once: the second finds nothing left to refund
+    let (first, second) = tokio::join!(
+        payments.refund(invoice_id, db),
+        payments.refund(invoice_id, db)
+    );
+    let mut results = [first, second];
+    results.sort_by_key(|result| result.is_err());
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::NotFound)));
+    let refunds = sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from ledger_transaction
+            where payment_id = (select payment_id from payment where invoice_id = $1)
+            and memo = 'Payment refunded'
+        "#,
+        invoice_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(refunds, 1);
+
+    test_db.cleanup().await;
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use sqlx::types::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use hooksaurus_auctions::db::{ledger, tenants};
+use hooksaurus_auctions::Error;
+
+/// Proceeds of `amount` taken for the organization.
+async fn proceeds(organization_id: Uuid, amount: i32, db: &PgPool) {
+    sqlx::query!(
+        r#"
+            with t as (
+                insert into ledger_transaction (memo)
+                values ('Auction proceeds')
+                returning ledger_transaction_id
+            )
+            insert into ledger_entry (ledger_transaction_id, account, amount, organization_id)
+            select ledger_transaction_id, account, amount * $2, $1
+            from t, (values ('cash', 1), ('proceeds', -1)) e (account, amount)
+        "#,
+        organization_id,
+        amount
+    )
+    .execute(db)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_payouts_are_never_more_than_we_owe() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+    let goats = common::organization("Goat Rescue", db).await;
+    proceeds(goats, 100, db).await;
+
+    let too_much = ledger::record_payout(goats, Decimal::from(101), "", tenant_id, db).await;
+    assert!(matches!(too_much, Err(Error::UnprocessableEntity { .. })));
+
+    // two clerks paying out at once can't both be paid from the same $100
+    let sixty = Decimal::from(60);
+    let (first, second) = tokio::join!(
+        ledger::record_payout(goats, sixty, "cheque 101", tenant_id, db),
+        ledger::record_payout(goats, sixty, "cheque 102", tenant_id, db),
+    );
+    assert_eq!(
+        [&first, &second].iter().filter(|paid| paid.is_ok()).count(),
+        1
+    );
+    let paid_out = sqlx::query_scalar!(
+        r#"select coalesce(sum(amount), 0) "paid_out!" from payout where organization_id = $1"#,
+        goats
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(paid_out, sixty);
+    ledger::record_payout(goats, Decimal::from(40), "cheque 103", tenant_id, db)
+        .await
+        .unwrap();
+
+    // and only the tenant's own organizations are paid
+    let shore = tenants::create("shore", "Shore Sanctuary", None, db)
+        .await
+        .unwrap();
+    let cats = common::organization("Cat Rescue", db).await;
+    proceeds(cats, 50, db).await;
+    let elsewhere = ledger::record_payout(cats, Decimal::from(10), "", shore.tenant_id, db).await;
+    assert!(matches!(elsewhere, Err(Error::UnprocessableEntity { .. })));
+
+    test_db.cleanup().await;
+}
@@ -0,0 +1,217 @@
+mod common;
+
+use hooksaurus_auctions::db::deliveries::{self, DeliveryChoice, DeliveryStatus, Transition};
+use hooksaurus_auctions::db::pickups::{self, PickupWindowFromForm};
+use hooksaurus_auctions::db::tables::address::AddressId;
+use hooksaurus_auctions::db::tables::LocalDateTime;
+use hooksaurus_auctions::db::tenants;
+use hooksaurus_auctions::shipping::DistanceRates;
+use hooksaurus_auctions::Error;
+use sqlx::types::time::OffsetDateTime;
+use sqlx::types::Decimal;
+use sqlx::PgPool;
+use time::Duration;
+use uuid::Uuid;
+
+/// A window an hour long, starting `starts_in` from now.
+async fn pickup_window(
+    auction_id: Uuid,
+    address_id: Uuid,
+    starts_in: Duration,
+    capacity: i32,
+    db: &PgPool,
+) -> Uuid {
+    let starts_at = OffsetDateTime::now_utc() + starts_in;
+    let window = PickupWindowFromForm {
+        address_id,
+        starts_at: LocalDateTime::Offset(starts_at),
+        ends_at: LocalDateTime::Offset(starts_at + Duration::hour()),
+        capacity,
+    };
+    pickups::insert_window(auction_id, &window, db)
+        .await
+        .unwrap()
+        .pickup_window_id
+}
+
+#[tokio::test]
+async fn test_pickup_windows_fill_up() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+    let window_id = pickup_window(won.auction_id, won.address_id, Duration::day(), 1, db).await;
+    let pickup = DeliveryChoice::Pickup {
+        pickup_window_id: window_id,
+    };
+
+    let fee = deliveries::choose_delivery(
+        won.user_id,
+        won.auction_item_id,
+        &pickup,
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+    assert_eq!(fee, Decimal::ZERO);
+    // choosing the same window again doesn't count twice
+    deliveries::choose_delivery(
+        won.user_id,
+        won.auction_item_id,
+        &pickup,
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+
+    // the same winner's second item doesn't fit
+    let second_item_id = sqlx::query_scalar!(
+        r#"
+            with item as (
+                insert into auction_item (
+                    auction_id, title, featured_image_filepath, image_dir, tag_list,
+                    active_end_date, etag
+                )
+                values ($1, 'Hay bale maze', '', '', '{}', now() - interval '1 day',
+                    uuid_generate_v1mc())
+                returning auction_item_id
+            )
+            insert into auction_item_bid (auction_item_id, user_id, amount, is_winning_bid, etag)
+            select auction_item_id, $2, 40, true, uuid_generate_v1mc()
+            from item
+            returning auction_item_id
+        "#,
+        won.auction_id,
+        won.user_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let full =
+        deliveries::choose_delivery(won.user_id, second_item_id, &pickup, &DistanceRates, db).await;
+    assert!(matches!(full, Err(Error::UnprocessableEntity { .. })));
+
+    // nor can anyone book a window which is over, or belongs to another auction
+    let past = pickup_window(won.auction_id, won.address_id, -Duration::day(), 5, db).await;
+    let other = common::won_item(db).await;
+    let elsewhere = pickup_window(other.auction_id, other.address_id, Duration::day(), 5, db).await;
+    for pickup_window_id in [past, elsewhere] {
+        let booked = deliveries::choose_delivery(
+            won.user_id,
+            second_item_id,
+            &DeliveryChoice::Pickup { pickup_window_id },
+            &DistanceRates,
+            db,
+        )
+        .await;
+        assert!(matches!(booked, Err(Error::UnprocessableEntity { .. })));
+    }
+
+    // a booked window stays until the winner goes elsewhere
+    assert!(matches!(
+        pickups::delete_window(won.auction_id, window_id, db).await,
+        Err(Error::UnprocessableEntity { .. })
+    ));
+    deliveries::choose_delivery(
+        won.user_id,
+        won.auction_item_id,
+        &DeliveryChoice::Ship(AddressId(won.address_id)),
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+    deliveries::choose_delivery(won.user_id, second_item_id, &pickup, &DistanceRates, db)
+        .await
+        .unwrap();
+    deliveries::choose_delivery(
+        won.user_id,
+        second_item_id,
+        &DeliveryChoice::Ship(AddressId(won.address_id)),
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+    assert!(pickups::delete_window(won.auction_id, window_id, db)
+        .await
+        .unwrap());
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_checking_off_a_pickup() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+    let window_id = pickup_window(won.auction_id, won.address_id, Duration::hour(), 3, db).await;
+    deliveries::choose_delivery(
+        won.user_id,
+        won.auction_item_id,
+        &DeliveryChoice::Pickup {
+            pickup_window_id: window_id,
+        },
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+
+    // the clerk has to say who took it
+    let unsigned = deliveries::transition(
+        won.auction_item_bid_id,
+        DeliveryStatus::PickedUp,
+        &Transition::default(),
+        tenant_id,
+        db,
+    )
+    .await;
+    assert!(matches!(unsigned, Err(Error::UnprocessableEntity { .. })));
+    let signed = Transition {
+        signed_for_by: Some("Robin Farmer".to_string()),
+        ..Transition::default()
+    };
+    // and can only check off their own tenant's pickups
+    let shore = tenants::create("shore", "Shore Sanctuary", None, db)
+        .await
+        .unwrap();
+    let elsewhere = deliveries::transition(
+        won.auction_item_bid_id,
+        DeliveryStatus::PickedUp,
+        &signed,
+        shore.tenant_id,
+        db,
+    )
+    .await;
+    assert!(matches!(elsewhere, Err(Error::NotFound)));
+    deliveries::transition(
+        won.auction_item_bid_id,
+        DeliveryStatus::PickedUp,
+        &signed,
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+    let delivery = sqlx::query!(
+        r#"
+            select status, signed_for_by, picked_up_at, pickup_window_id
+            from auction_item_delivery
+            where auction_item_bid_id = $1
+        "#,
+        won.auction_item_bid_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::PickedUp.as_str());
+    assert_eq!(delivery.signed_for_by.as_deref(), Some("Robin Farmer"));
+    assert!(delivery.picked_up_at.is_some());
+    assert_eq!(delivery.pickup_window_id, Some(window_id));
+
+    test_db.cleanup().await;
+}
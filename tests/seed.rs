@@ -0,0 +1,48 @@
+mod common;
+
+use hooksaurus_auctions::db::seed::SeedPlan;
+
+#[tokio::test]
+async fn test_seed_plan_inserts_into_a_migrated_database() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let plan = SeedPlan::generate(42);
+
+    let summary = plan.insert(db).await.unwrap();
+    assert_eq!(summary.auctions, plan.auctions.len());
+    assert_eq!(summary.deliveries, plan.deliveries.len());
+    let counts = sqlx::query!(
+        r#"
+            select
+                (select count(*) from auction) "auctions!",
+                (select count(*) from auction_item) "items!",
+                (select count(*) from auction_item_bid) "bids!",
+                (select count(*) from auction_item_delivery) "deliveries!"
+        "#
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(counts.auctions as usize, plan.auctions.len());
+    assert_eq!(counts.items as usize, plan.items.len());
+    assert_eq!(counts.bids as usize, plan.bids.len());
+    assert_eq!(counts.deliveries as usize, plan.deliveries.len());
+
+    // each delivery goes to whoever won the item
+    let strays = sqlx::query_scalar!(
+        r#"
+            select count(*) "count!"
+            from auction_item_delivery d
+            inner join auction_item_bid aib
+            on aib.auction_item_bid_id = d.auction_item_bid_id
+            where d.user_id <> aib.user_id
+            or not aib.is_winning_bid
+        "#
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(strays, 0);
+
+    test_db.cleanup().await;
+}
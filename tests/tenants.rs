@@ -0,0 +1,560 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, COOKIE, HOST, SET_COOKIE};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use hooksaurus_auctions::db::deliveries::{self, DeliveryChoice};
+use hooksaurus_auctions::db::donations::{self, DonationFromForm};
+use hooksaurus_auctions::db::tables::address::AddressId;
+use hooksaurus_auctions::db::{invoices, tenants};
+use hooksaurus_auctions::endpoints::Tenants;
+use hooksaurus_auctions::shipping::DistanceRates;
+
+const SHORE_HOST: &str = "shore.example.com";
+
+async fn post_form(
+    app: &Tenants<Router>,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    form: &str,
+) -> common::Response {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(HOST, "localhost:8000")
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded");
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+    common::send(app, request.body(Body::from(form.to_string())).unwrap()).await
+}
+
+/// An open auction of the tenant, with one item. Returns their ids.
+async fn auction_with_item(
+    title: &str,
+    item_title: &str,
+    tenant_id: Uuid,
+    db: &PgPool,
+) -> (Uuid, Uuid) {
+    let row = sqlx::query!(
+        r#"
+            with auction as (
+                insert into auction (title, start_date, end_date, tenant_id, etag)
+                values ($1, now() - interval '1 day', now() + interval '7 days', $3, uuid_generate_v1mc())
+                returning auction_id
+            )
+            insert into auction_item (
+                auction_id, title, featured_image_filepath, image_dir, tag_list,
+                active_end_date, tenant_id, etag
+            )
+            select auction_id, $2, '', '', '{}', now() + interval '7 days', $3,
+                uuid_generate_v1mc()
+            from auction
+            returning auction_id "auction_id!", auction_item_id
+        "#,
+        title,
+        item_title,
+        tenant_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    (row.auction_id, row.auction_item_id)
+}
+
+async fn organization(name: &str, tenant_id: Uuid, db: &PgPool) -> Uuid {
+    let organization_id = common::organization(name, db).await;
+    sqlx::query!(
+        "update organization set tenant_id = $2 where organization_id = $1",
+        organization_id,
+        tenant_id
+    )
+    .execute(db)
+    .await
+    .unwrap();
+    organization_id
+}
+
+#[tokio::test]
+async fn test_tenant_pages_only_show_their_own_data() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let main = tenants::get_default(db).await.unwrap();
+    let shore = tenants::create("shore", "Shore Sanctuary", Some(SHORE_HOST), db)
+        .await
+        .unwrap();
+    let (main_auction, main_item) =
+        auction_with_item("Barnyard Gala", "Haybale maze tickets", main.tenant_id, db).await;
+    let (shore_auction, shore_item) = auction_with_item(
+        "Tidepool Benefit",
+        "Driftwood sculpture",
+        shore.tenant_id,
+        db,
+    )
+    .await;
+    let (_, main_admin) = common::admin(main.tenant_id, db).await;
+    let main_token = common::login(&app, "", &main_admin).await;
+    let (_, shore_admin) = common::admin(shore.tenant_id, db).await;
+    let shore_token = common::login(&app, "/t/shore", &shore_admin).await;
+
+    // the host, a path prefix, or neither
+    let page = common::get(&app, SHORE_HOST, "/auctions").await;
+    assert!(page.body.contains("Tidepool Benefit"));
+    assert!(page.body.contains("Shore Sanctuary"));
+    assert!(!page.body.contains("Barnyard Gala"));
+    let page = common::get(&app, "localhost:8000", "/t/shore/auctions").await;
+    assert!(page.body.contains("Tidepool Benefit"));
+    assert!(!page.body.contains("Barnyard Gala"));
+    // and links keep the prefix
+    assert!(page
+        .body
+        .contains(&format!(r#"href="/t/shore/auctions/{}""#, shore_auction)));
+    let page = common::get(&app, "localhost:8000", "/auctions").await;
+    assert!(page.body.contains("Barnyard Gala"));
+    assert!(!page.body.contains("Tidepool Benefit"));
+    assert_eq!(
+        common::get(&app, "localhost", "/t/nowhere/auctions")
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+
+    // another tenant's auctions and items are as good as missing
+    let main_item_uri = format!("/auctions/{}/items/{}", main_auction, main_item);
+    assert_eq!(
+        common::get(&app, "localhost", &main_item_uri).await.status,
+        StatusCode::OK
+    );
+    assert_eq!(
+        common::get(&app, SHORE_HOST, &main_item_uri).await.status,
+        StatusCode::NOT_FOUND
+    );
+    let shore_auction_uri = format!("/auctions/{}", shore_auction);
+    assert_eq!(
+        common::get(&app, "localhost", &shore_auction_uri)
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+
+    let results = common::get(&app, SHORE_HOST, "/search").await.body;
+    assert!(results.contains("Driftwood"));
+    assert!(!results.contains("Haybale"));
+    let results = common::get(&app, "localhost", "/search").await.body;
+    assert!(results.contains("Haybale"));
+    assert!(!results.contains("Driftwood"));
+
+    // the admin pages too
+    let rows = common::get_as(&app, SHORE_HOST, "/admin/tables/auction-item", &shore_token)
+        .await
+        .body;
+    assert!(rows.contains(&shore_item.to_string()));
+    assert!(!rows.contains(&main_item.to_string()));
+    let main_auction_uri = format!("/admin/tables/auction/{}", main_auction);
+    assert_eq!(
+        common::get_as(&app, SHORE_HOST, &main_auction_uri, &shore_token)
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+    let form = "title=Renamed&description=&start_date=2026-11-01T10%3A00\
+        &end_date=2026-11-08T10%3A00&benefits_organization_id=&timezone=UTC";
+    let renamed = post_form(
+        &app,
+        "PUT",
+        &format!("/t/shore{}", main_auction_uri),
+        Some(&shore_token),
+        form,
+    )
+    .await;
+    assert_eq!(renamed.status, StatusCode::NOT_FOUND);
+    let title = sqlx::query_scalar!(
+        "select title from auction where auction_id = $1",
+        main_auction
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(title, "Barnyard Gala");
+
+    // which can't give an auction's proceeds to another tenant's organization
+    let main_org = organization("Barnyard Friends", main.tenant_id, db).await;
+    let form = format!(
+        "title=Beach+Cleanup&description=&start_date=2026-11-01T10%3A00\
+         &end_date=2026-11-08T10%3A00&benefits_organization_id={}&timezone=UTC",
+        main_org
+    );
+    let inserted = post_form(
+        &app,
+        "POST",
+        "/t/shore/admin/tables/auction/insert",
+        Some(&shore_token),
+        &form,
+    )
+    .await;
+    assert_eq!(inserted.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let rows = common::get_as(&app, SHORE_HOST, "/admin/tables/organization", &shore_token)
+        .await
+        .body;
+    assert!(!rows.contains("Barnyard Friends"));
+
+    // donations wait for the tenant they were made to
+    let donation = DonationFromForm {
+        donor_name: "Robin Ortiz".to_string(),
+        donor_email: "robin@example.com".to_string(),
+        donor_phone: None,
+        organization_name: None,
+        organization_website: None,
+        title: "Hand-knit alpaca scarf".to_string(),
+        description: String::new(),
+        expected_retail_value: None,
+        tags: String::new(),
+    };
+    let donated = donations::submit(&donation, None, main.tenant_id, db)
+        .await
+        .unwrap();
+    assert!(
+        common::get_as(&app, "localhost", "/admin/donations", &main_token)
+            .await
+            .body
+            .contains("alpaca")
+    );
+    assert!(
+        !common::get_as(&app, SHORE_HOST, "/admin/donations", &shore_token)
+            .await
+            .body
+            .contains("alpaca")
+    );
+    let approve = format!("auction_id={}&minimum_bid_amount=10", shore_auction);
+    let approved = post_form(
+        &app,
+        "POST",
+        &format!("/t/shore/admin/donations/{}/approve", donated),
+        Some(&shore_token),
+        &approve,
+    )
+    .await;
+    assert_eq!(approved.status, StatusCode::NOT_FOUND);
+    let approved = post_form(
+        &app,
+        "POST",
+        &format!("/admin/donations/{}/approve", donated),
+        Some(&main_token),
+        &approve,
+    )
+    .await;
+    assert!(approved.body.contains("choose an auction"));
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_users_join_the_tenant_they_use() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let main = tenants::get_default(db).await.unwrap();
+    let shore = tenants::create("shore", "Shore Sanctuary", None, db)
+        .await
+        .unwrap();
+    assert!(matches!(
+        tenants::create("shore", "Another Shore", None, db).await,
+        Err(hooksaurus_auctions::Error::UnprocessableEntity { .. })
+    ));
+
+    let registered = post_form(
+        &app,
+        "POST",
+        "/t/shore/register",
+        None,
+        "email=sam%40example.com&password=correct+horse&next=%2Fdashboard",
+    )
+    .await;
+    assert_eq!(registered.status, StatusCode::SEE_OTHER);
+    assert_eq!(registered.location.as_deref(), Some("/t/shore/dashboard"));
+    // the session is the tenant's own, and the browser only sends it back to its pages
+    let set_cookie = registered.headers[SET_COOKIE].to_str().unwrap();
+    assert!(set_cookie.contains("; Path=/t/shore;"), "{}", set_cookie);
+    let session = set_cookie.split(';').next().unwrap();
+    let with_session = |uri: &str| {
+        Request::get(uri)
+            .header(HOST, "localhost:8000")
+            .header(COOKIE, session)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let dashboard = common::send(&app, with_session("/t/shore/dashboard")).await;
+    assert_eq!(dashboard.status, StatusCode::OK);
+    let dashboard = common::send(&app, with_session("/dashboard")).await;
+    assert_eq!(dashboard.status, StatusCode::SEE_OTHER);
+    let user_id =
+        sqlx::query_scalar!(r#"select user_id from "user" where email = 'sam@example.com'"#)
+            .fetch_one(db)
+            .await
+            .unwrap();
+    assert_eq!(
+        tenants::get_role(shore.tenant_id, user_id, db)
+            .await
+            .unwrap(),
+        Some("member".to_string())
+    );
+    assert_eq!(
+        tenants::get_role(main.tenant_id, user_id, db)
+            .await
+            .unwrap(),
+        None
+    );
+
+    // the same account works everywhere, and logging in joins the tenant
+    let logged_in = post_form(
+        &app,
+        "POST",
+        "/login",
+        None,
+        "email=sam%40example.com&password=correct+horse",
+    )
+    .await;
+    assert_eq!(logged_in.status, StatusCode::SEE_OTHER);
+    assert_eq!(
+        tenants::get_role(main.tenant_id, user_id, db)
+            .await
+            .unwrap(),
+        Some("member".to_string())
+    );
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_admin_pages_are_for_the_tenants_admins() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let main = tenants::get_default(db).await.unwrap();
+    let (_, member) = common::user_with_password(db).await;
+    let member_token = common::login(&app, "", &member).await;
+    let (_, admin) = common::admin(main.tenant_id, db).await;
+    let admin_token = common::login(&app, "", &admin).await;
+
+    for uri in [
+        "/admin",
+        "/admin/tables/auction",
+        "/admin/invoices",
+        "/admin/payouts",
+        "/admin/fulfillment",
+        "/admin/fulfillment/labels",
+        "/admin/pickups",
+        "/admin/donations",
+        "/admin/articles",
+    ] {
+        assert_eq!(
+            common::get(&app, "localhost", uri).await.status,
+            StatusCode::UNAUTHORIZED,
+            "{}",
+            uri
+        );
+        assert_eq!(
+            common::get_as(&app, "localhost", uri, &member_token)
+                .await
+                .status,
+            StatusCode::FORBIDDEN,
+            "{}",
+            uri
+        );
+        assert_eq!(
+            common::get_as(&app, "localhost", uri, &admin_token)
+                .await
+                .status,
+            StatusCode::OK,
+            "{}",
+            uri
+        );
+    }
+    let paid = post_form(&app, "POST", "/admin/payouts", None, "amount=1").await;
+    assert_eq!(paid.status, StatusCode::UNAUTHORIZED);
+    let paid = post_form(
+        &app,
+        "POST",
+        "/admin/payouts",
+        Some(&member_token),
+        "amount=1",
+    )
+    .await;
+    assert_eq!(paid.status, StatusCode::FORBIDDEN);
+    // jobs are the deployment's, so only the default tenant's admins see them
+    let shore = tenants::create("shore", "Shore Sanctuary", None, db)
+        .await
+        .unwrap();
+    let (_, shore_admin) = common::admin(shore.tenant_id, db).await;
+    let shore_token = common::login(&app, "/t/shore", &shore_admin).await;
+    assert_eq!(
+        common::get_as(&app, "localhost", "/admin/jobs", &admin_token)
+            .await
+            .status,
+        StatusCode::OK
+    );
+    assert_eq!(
+        common::get_as(&app, "localhost", "/t/shore/admin/jobs", &shore_token)
+            .await
+            .status,
+        StatusCode::FORBIDDEN
+    );
+    let retried = post_form(
+        &app,
+        "POST",
+        &format!("/t/shore/admin/jobs/{}/retry", Uuid::nil()),
+        Some(&shore_token),
+        "",
+    )
+    .await;
+    assert_eq!(retried.status, StatusCode::FORBIDDEN);
+    // routes which aren't there are still a 404
+    assert_eq!(
+        common::get(&app, "localhost", "/nowhere").await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_back_office_pages_only_show_their_tenants_data() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let app = common::app(db);
+    let main = tenants::get_default(db).await.unwrap();
+    let shore = tenants::create("shore", "Shore Sanctuary", Some(SHORE_HOST), db)
+        .await
+        .unwrap();
+    let (_, main_admin) = common::admin(main.tenant_id, db).await;
+    let main_token = common::login(&app, "", &main_admin).await;
+    let (_, shore_admin) = common::admin(shore.tenant_id, db).await;
+    let shore_token = common::login(&app, "/t/shore", &shore_admin).await;
+
+    // an item won at the main tenant, waiting to be shipped and invoiced
+    let won = common::won_item(db).await;
+    deliveries::choose_delivery(
+        won.user_id,
+        won.auction_item_id,
+        &DeliveryChoice::Ship(AddressId(won.address_id)),
+        &DistanceRates,
+        db,
+    )
+    .await
+    .unwrap();
+    invoices::generate_invoices(won.auction_id, db)
+        .await
+        .unwrap();
+    let invoice_id = sqlx::query_scalar!(
+        "select invoice_id from invoice where auction_id = $1",
+        won.auction_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    // and money taken for one of its organizations
+    let main_org = organization("Barnyard Friends", main.tenant_id, db).await;
+    sqlx::query!(
+        r#"
+            with t as (
+                insert into ledger_transaction (memo)
+                values ('Auction proceeds')
+                returning ledger_transaction_id
+            )
+            insert into ledger_entry (ledger_transaction_id, account, amount, organization_id)
+            select ledger_transaction_id, account, amount, $1
+            from t, (values ('cash', 100), ('proceeds', -100)) e (account, amount)
+        "#,
+        main_org
+    )
+    .execute(db)
+    .await
+    .unwrap();
+
+    for (uri, shows) in [
+        ("/admin/fulfillment", "Goat yoga for two"),
+        ("/admin/fulfillment/labels", "1 Barnyard Lane"),
+        ("/admin/invoices", "Spring Fling"),
+        ("/admin/payouts", "Barnyard Friends"),
+        ("/admin/pickups", "Spring Fling"),
+    ] {
+        let page = common::get_as(&app, "localhost", uri, &main_token).await;
+        assert!(page.body.contains(shows), "{}", uri);
+        let page = common::get_as(&app, SHORE_HOST, uri, &shore_token).await;
+        assert_eq!(page.status, StatusCode::OK, "{}", uri);
+        assert!(!page.body.contains(shows), "{}", uri);
+    }
+    for uri in [
+        format!("/admin/invoices/{}", invoice_id),
+        format!("/admin/invoices/{}/pdf", invoice_id),
+        format!("/admin/invoices/{}/receipt", invoice_id),
+        format!("/admin/auctions/{}/pickup-windows", won.auction_id),
+    ] {
+        assert_eq!(
+            common::get_as(&app, SHORE_HOST, &uri, &shore_token)
+                .await
+                .status,
+            StatusCode::NOT_FOUND,
+            "{}",
+            uri
+        );
+    }
+    assert_eq!(
+        common::get_as(
+            &app,
+            "localhost",
+            &format!("/admin/invoices/{}", invoice_id),
+            &main_token
+        )
+        .await
+        .status,
+        StatusCode::OK
+    );
+
+    // nor can another tenant's admins move its deliveries along
+    let uri = format!("/t/shore/admin/pickups/{}", won.auction_item_bid_id);
+    let checked = post_form(&app, "POST", &uri, Some(&shore_token), "signed_for_by=Sam").await;
+    assert_eq!(checked.status, StatusCode::NOT_FOUND);
+    let uri = format!("/t/shore/admin/fulfillment/{}", won.auction_item_bid_id);
+    let form = "status=shipped&carrier=USPS&tracking_number=9400";
+    let shipped = post_form(&app, "POST", &uri, Some(&shore_token), form).await;
+    assert_eq!(shipped.status, StatusCode::NOT_FOUND);
+    let delivery_status = sqlx::query_scalar!(
+        "select status from auction_item_delivery where auction_item_bid_id = $1",
+        won.auction_item_bid_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(delivery_status, "ready_to_ship");
+
+    // or act on its invoices
+    for action in ["issue", "void", "refund"] {
+        let uri = format!("/t/shore/admin/invoices/{}/{}", invoice_id, action);
+        let acted = post_form(&app, "POST", &uri, Some(&shore_token), "").await;
+        assert_eq!(acted.status, StatusCode::NOT_FOUND, "{}", action);
+    }
+    let uri = format!("/t/shore/admin/auctions/{}/invoices", won.auction_id);
+    let generated = post_form(&app, "POST", &uri, Some(&shore_token), "").await;
+    assert_eq!(generated.status, StatusCode::NOT_FOUND);
+    let status = || {
+        sqlx::query_scalar!(
+            "select status from invoice where invoice_id = $1",
+            invoice_id
+        )
+        .fetch_one(db)
+    };
+    assert_eq!(status().await.unwrap(), "draft");
+    let uri = format!("/admin/invoices/{}/issue", invoice_id);
+    let issued = post_form(&app, "POST", &uri, Some(&main_token), "").await;
+    assert_eq!(issued.status, StatusCode::OK);
+    assert_eq!(status().await.unwrap(), "issued");
+
+    test_db.cleanup().await;
+}
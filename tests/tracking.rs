@@ -0,0 +1,185 @@
+mod common;
+
+use std::sync::Arc;
+
+use hooksaurus_auctions::db::deliveries::{self, DeliveryStatus, Transition};
+use hooksaurus_auctions::db::tables::address::AddressId;
+use hooksaurus_auctions::db::tables::auction::{AuctionItemBidId, AuctionItemDeliveryFromForm};
+use hooksaurus_auctions::db::tenants;
+use hooksaurus_auctions::notify::{Channel, MemoryNotifier, Notifications};
+use hooksaurus_auctions::tracking::{self, HttpTracker};
+use sqlx::PgPool;
+use uuid::Uuid;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Ship the winner's item with a USPS tracking number, with texts to `sms_updates_number`.
+async fn ship(won: &common::WonItem, tracking_number: &str, db: &PgPool) {
+    let form = AuctionItemDeliveryFromForm {
+        auction_item_bid_id: AuctionItemBidId(won.auction_item_bid_id),
+        shipping_address: Some(AddressId(won.address_id)),
+        shipping_fee: None,
+        sms_updates_number: Some("+15035550142".to_string()),
+        email_contact: None,
+        signature_name: None,
+        carrier: None,
+        tracking_number: None,
+    };
+    // with an address, it's ready to ship straight away
+    deliveries::insert_delivery(&form, db).await.unwrap();
+    let details = Transition {
+        carrier: Some("USPS".to_string()),
+        tracking_number: Some(tracking_number.to_string()),
+        ..Transition::default()
+    };
+    let tenant_id = tenants::get_default(db).await.unwrap().tenant_id;
+    deliveries::transition(
+        won.auction_item_bid_id,
+        DeliveryStatus::Shipped,
+        &details,
+        tenant_id,
+        db,
+    )
+    .await
+    .unwrap();
+}
+
+async fn queued_statuses(auction_item_bid_id: Uuid, db: &PgPool) -> Vec<String> {
+    sqlx::query_scalar!(
+        r#"
+            select payload->>'status' "status!"
+            from job
+            where kind = 'delivery_status_changed'
+            and payload->>'auction_item_bid_id' = $1
+            order by created_at
+        "#,
+        auction_item_bid_id.to_string()
+    )
+    .fetch_all(db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_tracking_a_delivered_parcel() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+    ship(&won, "9400100000000000000001", db).await;
+
+    let carrier = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/trackers/usps/9400100000000000000001"))
+        .and(header("authorization", "Bearer test-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "delivered",
+            "shipped_at": "2026-10-16T09:30:00Z",
+            "delivered_at": "2026-10-18T15:04:00Z",
+        })))
+        .expect(1)
+        .mount(&carrier)
+        .await;
+    let tracker = HttpTracker::new(&carrier.uri(), "test-key").unwrap();
+
+    assert_eq!(tracking::poll(&tracker, db).await.unwrap(), 1);
+    let delivery = sqlx::query!(
+        r#"
+            select status, shipped_datetime, delivered
+            from auction_item_delivery
+            where auction_item_bid_id = $1
+        "#,
+        won.auction_item_bid_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::Delivered.as_str());
+    assert_eq!(
+        delivery.shipped_datetime.unwrap().unix_timestamp(),
+        1792143000
+    );
+    assert_eq!(delivery.delivered.unwrap().unix_timestamp(), 1792335840);
+    assert_eq!(
+        queued_statuses(won.auction_item_bid_id, db).await.last(),
+        Some(&"delivered".to_string())
+    );
+
+    // the winner hasn't turned on texts, but asked for them about this delivery
+    let notifications = Notifications::new(
+        Arc::new(MemoryNotifier::default()),
+        hooksaurus_auctions::endpoints::template_env(),
+        "https://auctions.example.com",
+    );
+    let messages = notifications
+        .delivery_status(won.auction_item_bid_id, DeliveryStatus::Delivered, db)
+        .await
+        .unwrap();
+    let sms = messages.iter().find(|m| m.channel == Channel::Sms).unwrap();
+    assert_eq!(sms.to, "+15035550142");
+    assert!(sms
+        .body
+        .starts_with("Goat yoga for two has been delivered."));
+
+    // nothing's in transit any more, so the carrier isn't asked again
+    assert_eq!(tracking::poll(&tracker, db).await.unwrap(), 0);
+
+    test_db.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_tracking_exceptions_and_unknown_parcels() {
+    let test_db = common::TestDb::new().await;
+    let db = &test_db.db;
+    let won = common::won_item(db).await;
+    ship(&won, "9400100000000000000002", db).await;
+
+    let carrier = MockServer::start().await;
+    // a carrier which hasn't scanned the parcel yet doesn't know the number
+    Mock::given(method("GET"))
+        .and(path("/trackers/usps/9400100000000000000002"))
+        .respond_with(ResponseTemplate::new(404))
+        .up_to_n_times(1)
+        .mount(&carrier)
+        .await;
+    let tracker = HttpTracker::new(&carrier.uri(), "test-key").unwrap();
+    assert_eq!(tracking::poll(&tracker, db).await.unwrap(), 0);
+    let shipped = deliveries::list_in_transit(db).await.unwrap();
+    assert_eq!(shipped.len(), 1);
+
+    Mock::given(method("GET"))
+        .and(path("/trackers/usps/9400100000000000000002"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "exception",
+            "exception": "Addressee unknown",
+        })))
+        .mount(&carrier)
+        .await;
+    assert_eq!(tracking::poll(&tracker, db).await.unwrap(), 1);
+    let delivery = sqlx::query!(
+        r#"
+            select status, shipping_exception, exception_at
+            from auction_item_delivery
+            where auction_item_bid_id = $1
+        "#,
+        won.auction_item_bid_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::Exception.as_str());
+    assert_eq!(
+        delivery.shipping_exception.as_deref(),
+        Some("Addressee unknown")
+    );
+    assert!(delivery.exception_at.is_some());
+
+    // an exception is still followed, in case the carrier sorts it out
+    assert_eq!(deliveries::list_in_transit(db).await.unwrap().len(), 1);
+    assert_eq!(tracking::poll(&tracker, db).await.unwrap(), 0);
+    assert_eq!(
+        queued_statuses(won.auction_item_bid_id, db).await,
+        vec!["shipped".to_string(), "exception".to_string()]
+    );
+
+    test_db.cleanup().await;
+}